@@ -0,0 +1,107 @@
+//! Serialization and deserialization of representative values in Full and Slim.
+//!
+//! Deserialization is measured from a slice and from a reader.
+//!
+//! Run with `cargo bench --bench codec`.
+
+use std::{collections::HashMap, hint::black_box};
+
+use criterion::{BenchmarkGroup, Criterion, Throughput, criterion_group, criterion_main, measurement::WallTime};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use postbag::{
+    cfg::{Cfg, Full, Slim},
+    deserialize, from_full_slice, from_slim_slice, serialize,
+};
+
+#[derive(Serialize, Deserialize)]
+struct Person {
+    name: String,
+    age: u32,
+    email: Option<String>,
+    address: Address,
+    tags: Vec<String>,
+    scores: Vec<f64>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Address {
+    street: String,
+    city: String,
+    zip: u32,
+    location: (f64, f64),
+}
+
+#[derive(Serialize, Deserialize)]
+enum Nested {
+    Leaf(u64),
+    Pair(Box<Nested>, Box<Nested>),
+    Wrap { inner: Box<Nested>, label: String },
+}
+
+fn person() -> Person {
+    Person {
+        name: "Alice Example".to_string(),
+        age: 42,
+        email: Some("alice@example.com".to_string()),
+        address: Address {
+            street: "1 Main Street".to_string(),
+            city: "Springfield".to_string(),
+            zip: 12345,
+            location: (52.52, 13.405),
+        },
+        tags: ["admin", "editor", "reviewer"].map(String::from).to_vec(),
+        scores: vec![1.5, 2.25, 99.0, -3.75],
+    }
+}
+
+fn nested(depth: u32) -> Nested {
+    match depth {
+        0 => Nested::Leaf(u64::from(depth) << 40),
+        _ if depth.is_multiple_of(2) => {
+            Nested::Pair(Box::new(nested(depth - 1)), Box::new(Nested::Leaf(depth.into())))
+        }
+        _ => Nested::Wrap { inner: Box::new(nested(depth - 1)), label: format!("level {depth}") },
+    }
+}
+
+/// Benchmarks serialization and deserialization of `value` using the configuration `CFG`.
+fn bench_cfg<CFG: Cfg, T: Serialize + DeserializeOwned>(
+    group: &mut BenchmarkGroup<WallTime>, cfg: &str, value: &T, from_slice: fn(&[u8]) -> postbag::Result<T>,
+) {
+    let mut data = Vec::new();
+    serialize::<CFG, _, _>(&mut data, value).unwrap();
+    group.throughput(Throughput::Bytes(data.len() as u64));
+
+    group.bench_function(format!("{cfg}/serialize"), |b| {
+        let mut buf = Vec::with_capacity(data.len());
+        b.iter(|| {
+            buf.clear();
+            serialize::<CFG, _, _>(&mut buf, black_box(value)).unwrap();
+        })
+    });
+    group.bench_function(format!("{cfg}/deserialize_slice"), |b| {
+        b.iter(|| black_box(from_slice(black_box(&data)).unwrap()))
+    });
+    group.bench_function(format!("{cfg}/deserialize_reader"), |b| {
+        b.iter(|| black_box(deserialize::<CFG, _, T>(black_box(data.as_slice())).unwrap()))
+    });
+}
+
+fn bench_value<T: Serialize + DeserializeOwned>(c: &mut Criterion, name: &str, value: T) {
+    let mut group = c.benchmark_group(name);
+    bench_cfg::<Full, _>(&mut group, "full", &value, from_full_slice);
+    bench_cfg::<Slim, _>(&mut group, "slim", &value, from_slim_slice);
+    group.finish();
+}
+
+fn benches(c: &mut Criterion) {
+    bench_value(c, "struct", person());
+    bench_value(c, "vec_u8", (0..100_000u32).map(|i| i as u8).collect::<Vec<u8>>());
+    bench_value(c, "vec_u32", (0..100_000u32).map(|i| i.wrapping_mul(2_654_435_761)).collect::<Vec<u32>>());
+    bench_value(c, "hash_map", (0..10_000u32).map(|i| (format!("key {i}"), i)).collect::<HashMap<_, _>>());
+    bench_value(c, "nested_enum", nested(100));
+}
+
+criterion_group!(codec, benches);
+criterion_main!(codec);
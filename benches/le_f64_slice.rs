@@ -0,0 +1,62 @@
+//! Compares deserializing a `Vec<f64>` using `le_f64_slice` with the generic sequence path.
+//!
+//! Run with `cargo bench --bench le_f64_slice`.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use postbag::{deserialize_slim, from_slim_slice, to_slim_vec};
+
+#[derive(Serialize, Deserialize)]
+struct Generic(Vec<f64>);
+
+#[derive(Serialize, Deserialize)]
+struct Fast(#[serde(with = "postbag::le_f64_slice")] Vec<f64>);
+
+const LEN: usize = 10_000;
+const ITERATIONS: u32 = 2_000;
+
+/// Returns the average duration of `f`.
+fn measure(mut f: impl FnMut()) -> Duration {
+    f();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    let values: Vec<f64> = (0..LEN).map(|i| i as f64 * 0.001 - 5.0).collect();
+    let data = to_slim_vec(&Generic(values)).unwrap();
+
+    let slice_generic = measure(|| {
+        black_box(from_slim_slice::<Generic>(black_box(&data)).unwrap());
+    });
+    let slice_fast = measure(|| {
+        black_box(from_slim_slice::<Fast>(black_box(&data)).unwrap());
+    });
+    let reader_generic = measure(|| {
+        black_box(deserialize_slim::<_, Generic>(black_box(data.as_slice())).unwrap());
+    });
+    let reader_fast = measure(|| {
+        black_box(deserialize_slim::<_, Fast>(black_box(data.as_slice())).unwrap());
+    });
+
+    println!("deserialize Vec<f64> of {LEN} elements from slice");
+    println!("  generic:      {slice_generic:>10.2?}");
+    println!(
+        "  le_f64_slice: {slice_fast:>10.2?} ({:.1}x)",
+        slice_generic.as_secs_f64() / slice_fast.as_secs_f64()
+    );
+    println!("deserialize Vec<f64> of {LEN} elements from reader");
+    println!("  generic:      {reader_generic:>10.2?}");
+    println!(
+        "  le_f64_slice: {reader_fast:>10.2?} ({:.1}x)",
+        reader_generic.as_secs_f64() / reader_fast.as_secs_f64()
+    );
+}
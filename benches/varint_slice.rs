@@ -0,0 +1,60 @@
+//! Compares serializing a `Vec<u64>` using `varint_slice` with the generic sequence path.
+//!
+//! Run with `cargo bench --bench varint_slice`.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use postbag::{from_slim_slice, to_slim_vec};
+
+#[derive(Serialize, Deserialize)]
+struct Generic(Vec<u64>);
+
+#[derive(Serialize, Deserialize)]
+struct Fast(#[serde(with = "postbag::varint_slice")] Vec<u64>);
+
+const LEN: u64 = 100_000;
+const ITERATIONS: u32 = 200;
+
+/// Returns the average duration of `f`.
+fn measure(mut f: impl FnMut()) -> Duration {
+    f();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    let values: Vec<u64> = (0..LEN).map(|i| i.wrapping_mul(0x9e37_79b9_7f4a_7c15) >> (i % 64)).collect();
+    let generic = Generic(values.clone());
+    let fast = Fast(values);
+
+    let data = to_slim_vec(&generic).unwrap();
+    assert_eq!(data, to_slim_vec(&fast).unwrap());
+
+    let ser_generic = measure(|| {
+        black_box(to_slim_vec(black_box(&generic)).unwrap());
+    });
+    let ser_fast = measure(|| {
+        black_box(to_slim_vec(black_box(&fast)).unwrap());
+    });
+    let de_generic = measure(|| {
+        black_box(from_slim_slice::<Generic>(black_box(&data)).unwrap());
+    });
+    let de_fast = measure(|| {
+        black_box(from_slim_slice::<Fast>(black_box(&data)).unwrap());
+    });
+
+    println!("serialize Vec<u64> of {LEN} elements");
+    println!("  generic:      {ser_generic:>10.2?}");
+    println!("  varint_slice: {ser_fast:>10.2?} ({:.1}x)", ser_generic.as_secs_f64() / ser_fast.as_secs_f64());
+    println!("deserialize Vec<u64> of {LEN} elements");
+    println!("  generic:      {de_generic:>10.2?}");
+    println!("  varint_slice: {de_fast:>10.2?} ({:.1}x)", de_generic.as_secs_f64() / de_fast.as_secs_f64());
+}
@@ -0,0 +1,282 @@
+//! Derive macros for [postbag](https://docs.rs/postbag).
+//!
+//! Use the macros through their re-exports from the `postbag` crate,
+//! which requires its `derive` feature.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{
+    Attribute, Data, DeriveInput, Expr, ExprLit, Fields, Lit, LitInt, LitStr, Meta, Token, parse_macro_input,
+    parse_quote, punctuated::Punctuated,
+};
+
+/// Derives `Serialize` and `Deserialize` for a newtype struct, prefixing its
+/// inner value by the version byte given by `#[postbag(version = N)]`.
+///
+/// See `postbag::versioned` for details.
+#[proc_macro_derive(Versioned, attributes(postbag))]
+pub fn derive_versioned(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match versioned(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn versioned(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let version = version(&input)?;
+
+    let field_ty = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => fields.unnamed[0].ty.clone(),
+            _ => return Err(syn::Error::new_spanned(&input.ident, "Versioned requires a newtype struct")),
+        },
+        _ => return Err(syn::Error::new_spanned(&input.ident, "Versioned requires a newtype struct")),
+    };
+
+    let name = &input.ident;
+
+    let mut ser_generics = input.generics.clone();
+    ser_generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote!(#field_ty: ::postbag::__private::serde::Serialize));
+    let (impl_generics, ty_generics, where_clause) = ser_generics.split_for_impl();
+    let serialize = quote! {
+        impl #impl_generics ::postbag::__private::serde::Serialize for #name #ty_generics #where_clause {
+            fn serialize<__S>(&self, serializer: __S) -> ::core::result::Result<__S::Ok, __S::Error>
+            where
+                __S: ::postbag::__private::serde::Serializer,
+            {
+                ::postbag::versioned::serialize(#version, &self.0, serializer)
+            }
+        }
+    };
+
+    let mut de_generics = input.generics.clone();
+    de_generics.params.insert(0, parse_quote!('de));
+    de_generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote!(#field_ty: ::postbag::__private::serde::Deserialize<'de>));
+    let (impl_generics, _, where_clause) = de_generics.split_for_impl();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let deserialize = quote! {
+        impl #impl_generics ::postbag::__private::serde::Deserialize<'de> for #name #ty_generics #where_clause {
+            fn deserialize<__D>(deserializer: __D) -> ::core::result::Result<Self, __D::Error>
+            where
+                __D: ::postbag::__private::serde::Deserializer<'de>,
+            {
+                ::postbag::versioned::deserialize(#version, deserializer).map(Self)
+            }
+        }
+    };
+
+    Ok(quote! {
+        #serialize
+        #deserialize
+    })
+}
+
+/// Parses the version from the `#[postbag(version = N)]` attribute.
+fn version(input: &DeriveInput) -> syn::Result<u8> {
+    let mut version = None;
+
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("postbag")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("version") {
+                let lit: LitInt = meta.value()?.parse()?;
+                version = Some(lit.base10_parse::<u8>()?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported postbag attribute"))
+            }
+        })?;
+    }
+
+    version.ok_or_else(|| syn::Error::new(Span::call_site(), "missing #[postbag(version = N)] attribute"))
+}
+
+/// Encodes the variants of an enum as numeric identifiers `_0`, `_1`, ...
+/// by adding `#[serde(rename = "_N")]` to each variant, while keeping their
+/// human-readable names as aliases and in the `NumericVariants` implementation.
+///
+/// Must be placed before `#[derive(Serialize, Deserialize)]`.
+/// See `postbag::numeric_variants` for details.
+#[proc_macro_attribute]
+pub fn numeric_variants(args: TokenStream, input: TokenStream) -> TokenStream {
+    if !args.is_empty() {
+        let err = syn::Error::new(Span::call_site(), "numeric_variants takes no arguments");
+        return err.to_compile_error().into();
+    }
+
+    let input = parse_macro_input!(input as DeriveInput);
+    match numeric(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn numeric(mut input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let mut rename_all = None;
+    for meta in serde_metas(&input.attrs)? {
+        if meta.path().is_ident("rename_all") {
+            let rule = str_value(&meta)?;
+            rename_all = Some(
+                rename_rule(&rule.value())
+                    .ok_or_else(|| syn::Error::new_spanned(&rule, "unknown rename rule"))?,
+            );
+        }
+    }
+
+    let Data::Enum(data) = &mut input.data else {
+        return Err(syn::Error::new_spanned(&input.ident, "numeric_variants requires an enum"));
+    };
+
+    let mut names = Vec::new();
+    for (index, variant) in data.variants.iter_mut().enumerate() {
+        if let Some(meta) = serde_metas(&variant.attrs)?.into_iter().find(|meta| meta.path().is_ident("rename")) {
+            return Err(syn::Error::new_spanned(meta, "numeric_variants assigns the names of variants"));
+        }
+
+        let ident = variant.ident.to_string();
+        let name = match &rename_all {
+            Some(rule) => rule(&ident),
+            None => ident,
+        };
+
+        let numeric = format!("_{index}");
+        variant.attrs.push(parse_quote!(#[serde(rename = #numeric, alias = #name)]));
+        names.push(name);
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        #input
+
+        impl #impl_generics ::postbag::numeric_variants::NumericVariants for #name #ty_generics #where_clause {
+            const VARIANT_NAMES: &'static [&'static str] = &[#(#names),*];
+        }
+    })
+}
+
+/// Assigns stable numeric identifiers to struct fields, given by `#[postbag(id = N)]`,
+/// by adding `#[serde(rename = "_N")]` to each such field.
+///
+/// Must be placed before `#[derive(Serialize, Deserialize)]`.
+/// See `postbag::field_ids` for details.
+#[proc_macro_attribute]
+pub fn field_ids(args: TokenStream, input: TokenStream) -> TokenStream {
+    if !args.is_empty() {
+        let err = syn::Error::new(Span::call_site(), "field_ids takes no arguments");
+        return err.to_compile_error().into();
+    }
+
+    let input = parse_macro_input!(input as DeriveInput);
+    match ids(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn ids(mut input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let Data::Struct(data) = &mut input.data else {
+        return Err(syn::Error::new_spanned(&input.ident, "field_ids requires a struct with named fields"));
+    };
+    let Fields::Named(fields) = &mut data.fields else {
+        return Err(syn::Error::new_spanned(&input.ident, "field_ids requires a struct with named fields"));
+    };
+
+    let mut names: Vec<(String, Span)> = Vec::new();
+    for field in fields.named.iter_mut() {
+        let mut id = None;
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("postbag")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("id") {
+                    let lit: LitInt = meta.value()?.parse()?;
+                    id = Some((lit.base10_parse::<u32>()?, lit.span()));
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported postbag attribute"))
+                }
+            })?;
+        }
+        field.attrs.retain(|attr| !attr.path().is_ident("postbag"));
+
+        let rename = serde_metas(&field.attrs)?.into_iter().find(|meta| meta.path().is_ident("rename"));
+        let name = match (id, rename) {
+            (Some((id, span)), None) => {
+                let numeric = format!("_{id}");
+                field.attrs.push(parse_quote!(#[serde(rename = #numeric)]));
+                (numeric, span)
+            }
+            (Some(_), Some(meta)) => {
+                return Err(syn::Error::new_spanned(meta, "field_ids assigns the names of fields with an id"));
+            }
+            (None, Some(meta)) => {
+                let lit = str_value(&meta)?;
+                (lit.value(), lit.span())
+            }
+            (None, None) => {
+                let ident = field.ident.as_ref().unwrap();
+                (ident.to_string(), ident.span())
+            }
+        };
+
+        if names.iter().any(|(other, _)| *other == name.0) {
+            return Err(syn::Error::new(name.1, format!("duplicate field identifier `{}`", name.0)));
+        }
+        names.push(name);
+    }
+
+    Ok(quote! { #input })
+}
+
+/// Parses the contents of all `#[serde(...)]` attributes.
+fn serde_metas(attrs: &[Attribute]) -> syn::Result<Vec<Meta>> {
+    let mut metas = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+        metas.extend(attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?);
+    }
+    Ok(metas)
+}
+
+/// Returns the string value of a `name = "value"` attribute.
+fn str_value(meta: &Meta) -> syn::Result<LitStr> {
+    match meta {
+        Meta::NameValue(nv) => match &nv.value {
+            Expr::Lit(ExprLit { lit: Lit::Str(lit), .. }) => Ok(lit.clone()),
+            value => Err(syn::Error::new_spanned(value, "expected a string")),
+        },
+        _ => Err(syn::Error::new_spanned(meta, "expected `name = \"value\"`")),
+    }
+}
+
+/// Returns the conversion of a serde rename rule for variant names, which are in `PascalCase`.
+fn rename_rule(rule: &str) -> Option<fn(&str) -> String> {
+    Some(match rule {
+        "lowercase" => |name| name.to_ascii_lowercase(),
+        "UPPERCASE" => |name| name.to_ascii_uppercase(),
+        "PascalCase" => |name| name.to_string(),
+        "camelCase" => |name| name[..1].to_ascii_lowercase() + &name[1..],
+        "snake_case" => |name| separated(name, '_'),
+        "SCREAMING_SNAKE_CASE" => |name| separated(name, '_').to_ascii_uppercase(),
+        "kebab-case" => |name| separated(name, '-'),
+        "SCREAMING-KEBAB-CASE" => |name| separated(name, '-').to_ascii_uppercase(),
+        _ => return None,
+    })
+}
+
+/// Converts a `PascalCase` name into lowercase words separated by `sep`.
+fn separated(name: &str, sep: char) -> String {
+    let mut out = String::new();
+    for (i, ch) in name.char_indices() {
+        if i > 0 && ch.is_uppercase() {
+            out.push(sep);
+        }
+        out.push(ch.to_ascii_lowercase());
+    }
+    out
+}
@@ -0,0 +1,58 @@
+//! # Measuring Types
+//!
+//! Helpers for measuring the encoded size and the serialization performance
+//! of your own types, for example to compare configurations or type layouts.
+//!
+//! ```rust
+//! use serde::{Serialize, Deserialize};
+//! use postbag::{bench::roundtrip_bytes, cfg::{Full, Slim}};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Person {
+//!     name: String,
+//!     age: u32,
+//! }
+//!
+//! let person = Person { name: "Alice".to_string(), age: 30 };
+//! let (full_len, _full_time) = roundtrip_bytes::<Full, _>(&person);
+//! let (slim_len, _slim_time) = roundtrip_bytes::<Slim, _>(&person);
+//! assert!(slim_len < full_len);
+//! ```
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{cfg::Cfg, de::from_slice, ser::serialize};
+
+/// Serializes `value` using the configuration `CFG` and deserializes it again from the
+/// serialized bytes.
+///
+/// Returns the number of serialized bytes and the time taken by the round trip.
+/// The time of a single round trip is subject to noise, thus repeat it and
+/// aggregate the results when comparing small values.
+///
+/// # Panics
+///
+/// Panics if serialization or deserialization fails.
+pub fn roundtrip_bytes<CFG, T>(value: &T) -> (usize, Duration)
+where
+    CFG: Cfg,
+    T: Serialize + DeserializeOwned,
+{
+    let start = Instant::now();
+
+    let mut data = Vec::new();
+    if let Err(err) = serialize::<CFG, _, _>(&mut data, black_box(value)) {
+        panic!("serialization failed: {err}");
+    }
+    match from_slice::<CFG, T>(black_box(&data)) {
+        Ok(value) => drop(black_box(value)),
+        Err(err) => panic!("deserialization failed: {err}"),
+    }
+
+    (data.len(), start.elapsed())
+}
@@ -0,0 +1,94 @@
+//! # Bit-Packed Boolean Sequences
+//!
+//! By default each `bool` of a sequence is serialized as one byte.
+//! This module, for use with `#[serde(with = "postbag::bits")]`, packs
+//! a boolean sequence into bits instead.
+//!
+//! The number of booleans is serialized as a varint, followed by
+//! `ceil(len / 8)` bytes. The first boolean is stored in the least
+//! significant bit of the first byte. Unused bits of the last byte
+//! are written as zero and ignored when reading.
+//!
+//! Supported field types are `Vec<bool>` and `[bool; N]`.
+//!
+//! ```rust
+//! # use serde::{Serialize, Deserialize};
+//! #[derive(Serialize, Deserialize)]
+//! pub struct Bitmap {
+//!     #[serde(with = "postbag::bits")]
+//!     used: Vec<bool>,
+//!     #[serde(with = "postbag::bits")]
+//!     flags: [bool; 12],
+//! }
+//! ```
+
+use std::fmt;
+
+use serde::{
+    Deserializer, Serializer,
+    de::{self, SeqAccess, Visitor},
+    ser::SerializeTupleStruct,
+};
+
+/// Tuple struct name recognized by the serializer and deserializer.
+pub(crate) const NAME: &str = "$postbag::Bits";
+
+/// Maximum number of booleans allocated up front for a length read from the input.
+const PREALLOC_MAX: usize = 64 * 1024;
+
+/// Serialize the boolean sequence packed into bits.
+pub fn serialize<S, T>(val: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: AsRef<[bool]> + ?Sized,
+{
+    let bits = val.as_ref();
+    let bytes = bits
+        .chunks(8)
+        .map(|chunk| chunk.iter().enumerate().fold(0u8, |byte, (i, &bit)| byte | (u8::from(bit) << i)));
+
+    let mut tuple = serializer.serialize_tuple_struct(NAME, 1 + bits.len().div_ceil(8))?;
+    tuple.serialize_field(&(bits.len() as u64))?;
+    for byte in bytes {
+        tuple.serialize_field(&byte)?;
+    }
+    tuple.end()
+}
+
+/// Deserialize the boolean sequence from packed bits.
+pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: TryFrom<Vec<bool>>,
+{
+    let bits = deserializer.deserialize_tuple_struct(NAME, usize::MAX, BitsVisitor)?;
+    let len = bits.len();
+    T::try_from(bits).map_err(|_| de::Error::invalid_length(len, &"a boolean sequence of matching length"))
+}
+
+struct BitsVisitor;
+
+impl<'de> Visitor<'de> for BitsVisitor {
+    type Value = Vec<bool>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a bit-packed boolean sequence")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Vec<bool>, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let len: u64 = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let len = usize::try_from(len).map_err(|_| de::Error::custom("boolean sequence too long"))?;
+
+        let mut bits = Vec::with_capacity(len.min(PREALLOC_MAX));
+        while bits.len() < len {
+            let byte: u8 = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(bits.len(), &self))?;
+            let n = (len - bits.len()).min(8);
+            bits.extend((0..n).map(|i| byte & (1 << i) != 0));
+        }
+
+        Ok(bits)
+    }
+}
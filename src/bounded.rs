@@ -0,0 +1,305 @@
+//! # Bounded Collections
+//!
+//! [`BoundedVec<T, N>`] and [`BoundedString<N>`] hold at most `N` elements or bytes,
+//! expressing the maximum length of a field in its type. They are serialized like
+//! `Vec<T>` and `String`, thus the bound can be added to or removed from a field
+//! without breaking compatibility.
+//!
+//! When deserializing, the length prefix is checked against the bound before
+//! any data is read or allocated. A length exceeding the bound fails with
+//! [`Error::BadLen`](crate::Error::BadLen). Serializing a value exceeding the bound fails.
+//!
+//! ```rust
+//! use serde::{Serialize, Deserialize};
+//! use postbag::{BoundedString, BoundedVec, Error, from_slim_slice, to_slim_vec};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Message {
+//!     subject: BoundedString<64>,
+//!     recipients: BoundedVec<u32, 16>,
+//! }
+//!
+//! let data = to_slim_vec(&("x".repeat(65), vec![1u32])).unwrap();
+//! let res: Result<Message, _> = from_slim_slice(&data);
+//! assert!(matches!(res, Err(Error::BadLen)));
+//! ```
+
+use std::{
+    fmt,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+};
+
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{self, DeserializeSeed, SeqAccess, Visitor},
+    ser::{self, SerializeTupleStruct},
+};
+
+/// Tuple struct name recognized by the serializer.
+pub(crate) const NAME: &str = "$postbag::Bounded";
+
+/// Number of decimal digits of the bound in the tuple struct names recognized by the deserializer.
+const BOUND_DIGITS: usize = 20;
+
+/// Length of the tuple struct names recognized by the deserializer.
+const BOUND_NAME_LEN: usize = NAME.len() + BOUND_DIGITS;
+
+/// Tuple struct name passing the bound `N` to the deserializer.
+///
+/// Consists of [`NAME`] followed by the bound as decimal digits, padded with leading zeros.
+/// The name is ignored by other formats, thus the tuple struct keeps its actual arity.
+struct BoundName<const N: usize>;
+
+impl<const N: usize> BoundName<N> {
+    const BYTES: &'static [u8; BOUND_NAME_LEN] = &bound_name(N);
+
+    const NAME: &'static str = match std::str::from_utf8(Self::BYTES) {
+        Ok(name) => name,
+        Err(_) => panic!("name is not ASCII"),
+    };
+}
+
+const fn bound_name(bound: usize) -> [u8; BOUND_NAME_LEN] {
+    let mut name = [b'0'; BOUND_NAME_LEN];
+    let mut i = 0;
+    while i < NAME.len() {
+        name[i] = NAME.as_bytes()[i];
+        i += 1;
+    }
+    let mut rest = bound;
+    let mut i = BOUND_NAME_LEN;
+    while rest > 0 {
+        i -= 1;
+        name[i] = b'0' + (rest % 10) as u8;
+        rest /= 10;
+    }
+    name
+}
+
+/// Bound passed by the tuple struct name, if it is the name of a bounded collection.
+pub(crate) fn expected_bound(name: &str) -> Option<usize> {
+    name.strip_prefix(NAME)?.parse().ok()
+}
+
+/// Maximum number of elements allocated up front for a length read from the input.
+const PREALLOC_MAX: usize = 4096;
+
+/// Vector of at most `N` elements.
+///
+/// See the [module documentation](self) for details.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct BoundedVec<T, const N: usize>(pub Vec<T>);
+
+/// String of at most `N` bytes.
+///
+/// See the [module documentation](self) for details.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct BoundedString<const N: usize>(pub String);
+
+impl<T, const N: usize> BoundedVec<T, N> {
+    /// Returns the vector.
+    pub fn into_inner(self) -> Vec<T> {
+        self.0
+    }
+}
+
+impl<const N: usize> BoundedString<N> {
+    /// Returns the string.
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl<T, const N: usize> Deref for BoundedVec<T, N> {
+    type Target = Vec<T>;
+
+    fn deref(&self) -> &Vec<T> {
+        &self.0
+    }
+}
+
+impl<T, const N: usize> DerefMut for BoundedVec<T, N> {
+    fn deref_mut(&mut self) -> &mut Vec<T> {
+        &mut self.0
+    }
+}
+
+impl<const N: usize> Deref for BoundedString<N> {
+    type Target = String;
+
+    fn deref(&self) -> &String {
+        &self.0
+    }
+}
+
+impl<const N: usize> DerefMut for BoundedString<N> {
+    fn deref_mut(&mut self) -> &mut String {
+        &mut self.0
+    }
+}
+
+impl<T, const N: usize> From<Vec<T>> for BoundedVec<T, N> {
+    fn from(value: Vec<T>) -> Self {
+        Self(value)
+    }
+}
+
+impl<const N: usize> From<String> for BoundedString<N> {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl<const N: usize> From<&str> for BoundedString<N> {
+    fn from(value: &str) -> Self {
+        Self(value.to_string())
+    }
+}
+
+/// Serializes the value as a tuple struct carrying the bound.
+fn serialize_bounded<S, T>(value: &T, len: usize, bound: usize, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Serialize + ?Sized,
+{
+    if len > bound {
+        return Err(ser::Error::custom(format_args!("length {len} exceeds bound {bound}")));
+    }
+
+    let mut tuple = serializer.serialize_tuple_struct(NAME, 1)?;
+    tuple.serialize_field(value)?;
+    tuple.end()
+}
+
+impl<T: Serialize, const N: usize> Serialize for BoundedVec<T, N> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize_bounded(&self.0, self.0.len(), N, serializer)
+    }
+}
+
+impl<const N: usize> Serialize for BoundedString<N> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize_bounded(&self.0, self.0.len(), N, serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>, const N: usize> Deserialize<'de> for BoundedVec<T, N> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer
+            .deserialize_tuple_struct(BoundName::<N>::NAME, 1, BoundedVisitor(VecSeed::<T, N>(PhantomData)))
+            .map(Self)
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for BoundedString<N> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_tuple_struct(BoundName::<N>::NAME, 1, BoundedVisitor(StringSeed::<N>)).map(Self)
+    }
+}
+
+/// Visits the tuple struct containing the bounded value.
+struct BoundedVisitor<S>(S);
+
+impl<'de, S: DeserializeSeed<'de>> Visitor<'de> for BoundedVisitor<S> {
+    type Value = S::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a bounded value")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<S::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        seq.next_element_seed(self.0)?.ok_or_else(|| de::Error::invalid_length(0, &"a bounded value"))
+    }
+}
+
+/// Deserializes a vector of at most `N` elements.
+struct VecSeed<T, const N: usize>(PhantomData<fn() -> T>);
+
+impl<'de, T: Deserialize<'de>, const N: usize> DeserializeSeed<'de> for VecSeed<T, N> {
+    type Value = Vec<T>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Vec<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, T: Deserialize<'de>, const N: usize> Visitor<'de> for VecSeed<T, N> {
+    type Value = Vec<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a sequence of at most {N} elements")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Vec<T>, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        if let Some(len) = seq.size_hint().filter(|len| *len > N) {
+            return Err(de::Error::invalid_length(len, &self));
+        }
+
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or_default().min(N).min(PREALLOC_MAX));
+        while let Some(value) = seq.next_element()? {
+            if values.len() == N {
+                return Err(de::Error::invalid_length(N + 1, &self));
+            }
+            values.push(value);
+        }
+        Ok(values)
+    }
+}
+
+/// Deserializes a string of at most `N` bytes.
+struct StringSeed<const N: usize>;
+
+impl<'de, const N: usize> DeserializeSeed<'de> for StringSeed<N> {
+    type Value = String;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<String, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_string(self)
+    }
+}
+
+impl<'de, const N: usize> Visitor<'de> for StringSeed<N> {
+    type Value = String;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a string of at most {N} bytes")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<String, E> {
+        if v.len() > N {
+            return Err(de::Error::invalid_length(v.len(), &self));
+        }
+        Ok(v.to_string())
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<String, E> {
+        if v.len() > N {
+            return Err(de::Error::invalid_length(v.len(), &self));
+        }
+        Ok(v)
+    }
+}
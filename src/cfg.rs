@@ -7,6 +7,216 @@ pub trait Cfg {
     /// Whether struct field identifiers and enum variant identifiers
     /// are serialized.
     fn with_idents() -> bool;
+
+    /// Whether map entries are sorted by their serialized key bytes.
+    ///
+    /// This makes serialization of maps with unspecified iteration order,
+    /// such as `HashMap`, deterministic. All entries of a map are buffered
+    /// in memory before being written.
+    ///
+    /// Enabled by default if [`Self::canonical`] is enabled.
+    fn sort_map_keys() -> bool {
+        Self::canonical()
+    }
+
+    /// Whether sequences and maps of unknown length can be serialized.
+    ///
+    /// If disabled, serializing a sequence or map whose length is not known
+    /// in advance fails with [`Error::SerializeSeqLengthUnknown`](crate::Error::SerializeSeqLengthUnknown)
+    /// or [`Error::SerializeMapLengthUnknown`](crate::Error::SerializeMapLengthUnknown).
+    ///
+    /// Disabled by default if [`Self::canonical`] is enabled.
+    fn unknown_len() -> bool {
+        !Self::canonical()
+    }
+
+    /// Whether sequences and maps of unknown length are serialized in terminated mode.
+    ///
+    /// By default the elements of a sequence or map of unknown length are enclosed
+    /// in a skippable block. In terminated mode each element is preceded by a
+    /// continuation flag byte of `1` instead and the last element is followed by
+    /// a `0` byte. This is less compact, but simple to parse for other implementations.
+    ///
+    /// Deserialization supports both modes regardless of this setting.
+    fn terminated_unknown_len() -> bool {
+        false
+    }
+
+    /// Whether sequences and maps of unknown length are rejected when deserializing.
+    ///
+    /// If enabled, deserializing a sequence or map of unknown length, in either mode,
+    /// fails with [`Error::UnknownLengthForbidden`](crate::Error::UnknownLengthForbidden).
+    /// This reduces the encodings accepted from untrusted producers, which are known
+    /// to always serialize sequences and maps of known length.
+    ///
+    /// Serialization is unaffected. Disabled by default.
+    fn forbid_unknown_len() -> bool {
+        false
+    }
+
+    /// Whether the canonical encoding is used.
+    ///
+    /// The canonical encoding has exactly one valid representation per value.
+    /// During serialization NaN floats are written with a single bit pattern.
+    /// During deserialization data not in canonical form is rejected with
+    /// [`Error::NonCanonical`](crate::Error::NonCanonical), for example varints
+    /// with redundant bytes, map keys not in ascending order, unknown struct fields
+    /// or struct fields not in declaration order. Fields using
+    /// [`PaddedVarint`](crate::PaddedVarint) cannot be serialized.
+    ///
+    /// Together with [`Self::sort_map_keys`] and disabled [`Self::unknown_len`],
+    /// which are the defaults if enabled, this guarantees that serialization is
+    /// deterministic.
+    fn canonical() -> bool {
+        false
+    }
+
+    /// Whether struct fields must be in declaration order when deserializing.
+    ///
+    /// If enabled and [`Self::with_idents`] is enabled, each struct field identifier
+    /// must name a known field following the previous field in declaration order.
+    /// Otherwise deserialization fails with [`Error::FieldOrderMismatch`](crate::Error::FieldOrderMismatch).
+    /// Fields missing from the data are still permitted.
+    ///
+    /// This detects producers using a different field order or schema.
+    /// By default fields are matched by their identifiers regardless of their order.
+    fn strict_field_order() -> bool {
+        false
+    }
+
+    /// Whether identifiers must be plausible names when deserializing.
+    ///
+    /// If enabled, struct field identifiers and enum variant identifiers must be
+    /// non-empty and must not contain control or whitespace characters.
+    /// Otherwise deserialization fails with [`Error::BadIdentifier`](crate::Error::BadIdentifier).
+    ///
+    /// This hardens parsing of data from untrusted producers, for example to keep
+    /// control characters out of logged identifiers. It has no effect unless
+    /// [`Self::with_idents`] is enabled.
+    fn strict_identifiers() -> bool {
+        false
+    }
+
+    /// Whether the value of each `Some` is enclosed in a skippable block.
+    ///
+    /// This allows the type within an `Option` to evolve independently, for example
+    /// a tuple gaining trailing elements, even if the `Option` is not a struct field
+    /// and thus not enclosed by the skippable block of the field. Data not read when
+    /// deserializing the value is skipped.
+    ///
+    /// Both sides must use the same setting. Disabled by default.
+    fn skippable_options() -> bool {
+        false
+    }
+
+    /// Whether tuples, tuple structs and tuple variants are enclosed in a skippable block
+    /// preceded by their number of elements, like structs without identifiers.
+    ///
+    /// This allows changing the arity of a tuple: elements not read when deserializing
+    /// are skipped and missing trailing elements of a tuple struct receive their serde
+    /// defaults, if marked `#[serde(default)]`. Fixed-size arrays are serialized as
+    /// tuples by serde and thus are enclosed as well. The encodings of helpers, such
+    /// as [`fixint`](crate::fixint), are unaffected.
+    ///
+    /// Both sides must use the same setting. Disabled by default.
+    fn skippable_tuples() -> bool {
+        false
+    }
+
+    /// Whether structs without serialized fields omit their skippable block when
+    /// serialized without identifiers.
+    ///
+    /// Such a struct is then encoded as its number of fields, zero, only, saving one byte.
+    /// Consequently fields cannot be added to a struct that had no fields.
+    /// Has no effect if [`Self::with_idents`] or [`Self::positional_ids`] is enabled.
+    ///
+    /// Both sides must use the same setting. Disabled by default.
+    fn omit_empty_struct_block() -> bool {
+        false
+    }
+
+    /// Whether struct fields are preceded by their position when serialized without identifiers.
+    ///
+    /// Each field is written as its position within the struct, as a varint, followed by
+    /// its value enclosed in a skippable block, like a field with a numeric identifier
+    /// in [`Full`]. This costs two bytes per field, but fields can be omitted anywhere:
+    /// fields of unknown positions are skipped and missing fields receive their serde
+    /// defaults, if marked `#[serde(default)]`. Thus fields skipped using
+    /// `#[serde(skip_serializing_if)]` are supported.
+    ///
+    /// Positions are assigned in declaration order, counting fields skipped using
+    /// `#[serde(skip_serializing_if)]` but not fields marked `#[serde(skip)]`.
+    /// To remove a field from the middle of a struct, keep its position by replacing it
+    /// with a placeholder field, for example of type `()`, marked
+    /// `#[serde(default, skip_serializing_if = "...")]` with a predicate always returning true.
+    ///
+    /// Has no effect if [`Self::with_idents`] is enabled.
+    /// Both sides must use the same setting. Disabled by default.
+    fn positional_ids() -> bool {
+        false
+    }
+
+    /// Whether any nonzero byte is deserialized as `true`.
+    ///
+    /// By default only [`TRUE`](crate::format::TRUE) and [`FALSE`](crate::format::FALSE)
+    /// are accepted and other bytes are rejected with [`Error::BadBool`](crate::Error::BadBool).
+    /// Enable this to read data of producers encoding `true` as an arbitrary nonzero byte.
+    /// Serialization is unaffected.
+    ///
+    /// Has no effect if [`Self::canonical`] is enabled. Disabled by default.
+    fn lenient_bool() -> bool {
+        false
+    }
+
+    /// Whether enum variant indices are written as a single byte instead of a varint.
+    ///
+    /// This gives enums of up to 256 variants a fixed-width discriminant. Serializing
+    /// a variant with an index of 256 or more fails with [`Error::BadEnum`](crate::Error::BadEnum).
+    ///
+    /// Has no effect on variants written using their identifier.
+    /// Both sides must use the same setting. Disabled by default.
+    fn fixed_variant_index() -> bool {
+        false
+    }
+
+    /// Whether the encoding is compatible with [postcard](https://docs.rs/postcard).
+    ///
+    /// Structs are encoded as their fields in declaration order, without the number of
+    /// fields and without a skippable block, and lengths of sequences and maps are
+    /// plain varints. Together with the other settings at their defaults, values are
+    /// encoded exactly like postcard encodes them, thus data can be exchanged with
+    /// postcard in both directions.
+    ///
+    /// This gives up the schema evolution of structs: fields cannot be added or removed.
+    /// Skipping a field using `#[serde(skip_serializing_if)]` fails with
+    /// [`Error::SkippedField`](crate::Error::SkippedField) and sequences and maps of unknown
+    /// length are unsupported.
+    ///
+    /// Has no effect if [`Self::with_idents`] is enabled.
+    /// Both sides must use the same setting. Disabled by default.
+    fn postcard_compat() -> bool {
+        false
+    }
+
+    /// Byte order of fixed-width values.
+    ///
+    /// This applies to floats, as well as integers serialized using [`fixint`](crate::fixint)
+    /// and chars serialized using [`fixchar`](crate::fixchar). Varints have no byte order.
+    ///
+    /// Little-endian by default.
+    fn endian() -> Endian {
+        Endian::Little
+    }
+}
+
+/// Byte order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Endian {
+    /// Least significant byte first.
+    #[default]
+    Little,
+    /// Most significant byte first.
+    Big,
 }
 
 /// Static (compile-time) configuration.
@@ -36,3 +246,87 @@ pub type Full = StaticCfg<true>;
 /// Struct field identifiers are not serialized.
 /// Enum variants are serialized using their index.
 pub type Slim = StaticCfg<false>;
+
+/// Serialize with identifiers like [`Full`] using big-endian fixed-width values.
+///
+/// See [`Cfg::endian`] for details.
+#[derive(Clone, Copy, Debug)]
+pub struct BigEndianFull;
+
+impl Cfg for BigEndianFull {
+    fn with_idents() -> bool {
+        true
+    }
+
+    fn endian() -> Endian {
+        Endian::Big
+    }
+}
+
+/// Serialize without identifiers like [`Slim`] using big-endian fixed-width values.
+///
+/// See [`Cfg::endian`] for details.
+#[derive(Clone, Copy, Debug)]
+pub struct BigEndianSlim;
+
+impl Cfg for BigEndianSlim {
+    fn with_idents() -> bool {
+        false
+    }
+
+    fn endian() -> Endian {
+        Endian::Big
+    }
+}
+
+/// Canonical configuration.
+///
+/// Serializes without identifiers like [`Slim`] using the canonical encoding,
+/// thus each value has exactly one valid serialized representation.
+/// This is suitable for signing or hashing serialized data.
+///
+/// See [`Cfg::canonical`] for details.
+#[derive(Clone, Copy, Debug)]
+pub struct Canonical;
+
+impl Cfg for Canonical {
+    fn with_idents() -> bool {
+        false
+    }
+
+    fn canonical() -> bool {
+        true
+    }
+}
+
+/// Serialize without identifiers like [`Slim`], but with each struct field preceded by its position.
+///
+/// See [`Cfg::positional_ids`] for details.
+#[derive(Clone, Copy, Debug)]
+pub struct PositionalSlim;
+
+impl Cfg for PositionalSlim {
+    fn with_idents() -> bool {
+        false
+    }
+
+    fn positional_ids() -> bool {
+        true
+    }
+}
+
+/// Serialize compatibly with [postcard](https://docs.rs/postcard).
+///
+/// See [`Cfg::postcard_compat`] for details.
+#[derive(Clone, Copy, Debug)]
+pub struct Postcard;
+
+impl Cfg for Postcard {
+    fn with_idents() -> bool {
+        false
+    }
+
+    fn postcard_compat() -> bool {
+        true
+    }
+}
@@ -0,0 +1,128 @@
+//! # Strings Prefixed by Char Count
+//!
+//! By default a string is serialized as its length in bytes followed by its UTF-8 bytes.
+//! This module, for use with `#[serde(with = "postbag::char_len_string")]`, serializes
+//! a string as its number of chars, i.e. Unicode scalar values, as a varint instead,
+//! followed by its UTF-8 bytes. This is intended for interoperability with protocols
+//! using this encoding.
+//!
+//! Since the number of bytes differs from the number of chars for non-ASCII strings,
+//! the bytes are decoded char by char during deserialization. Invalid UTF-8 fails
+//! with [`Error::BadString`](crate::Error::BadString).
+//!
+//! ```rust
+//! # use serde::{Serialize, Deserialize};
+//! #[derive(Serialize, Deserialize)]
+//! pub struct Label {
+//!     #[serde(with = "postbag::char_len_string")]
+//!     text: String,
+//! }
+//! ```
+
+use std::fmt;
+
+use serde::{
+    Deserializer, Serialize, Serializer,
+    de::{self, SeqAccess, Unexpected, Visitor},
+    ser::SerializeTuple,
+};
+
+/// Newtype struct name recognized by the deserializer.
+pub(crate) const NAME: &str = "$postbag::CharLenString";
+
+/// Maximum number of bytes allocated up front for a char count read from the input.
+const PREALLOC_MAX: usize = 64 * 1024;
+
+/// Serialize the string prefixed by its number of chars.
+pub fn serialize<S, T>(val: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: AsRef<str> + ?Sized,
+{
+    serializer.serialize_newtype_struct(NAME, &CharLen(val.as_ref()))
+}
+
+/// Deserialize the string prefixed by its number of chars.
+pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: From<String>,
+{
+    deserializer.deserialize_newtype_struct(NAME, CharLenVisitor).map(T::from)
+}
+
+/// Returns the number of bytes of a UTF-8 encoded char from its first byte,
+/// or `None` if the byte cannot start a char.
+pub(crate) fn utf8_width(first: u8) -> Option<usize> {
+    match first {
+        0x00..=0x7f => Some(1),
+        0xc2..=0xdf => Some(2),
+        0xe0..=0xef => Some(3),
+        0xf0..=0xf4 => Some(4),
+        _ => None,
+    }
+}
+
+struct CharLen<'a>(&'a str);
+
+impl Serialize for CharLen<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let bytes = self.0.as_bytes();
+        let mut tuple = serializer.serialize_tuple(1 + bytes.len())?;
+        tuple.serialize_element(&(self.0.chars().count() as u64))?;
+        for byte in bytes {
+            tuple.serialize_element(byte)?;
+        }
+        tuple.end()
+    }
+}
+
+struct CharLenVisitor;
+
+impl<'de> Visitor<'de> for CharLenVisitor {
+    type Value = String;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string prefixed by its number of chars")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<String, E> {
+        Ok(v.to_string())
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<String, E> {
+        Ok(v)
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<String, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_tuple(usize::MAX, self)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<String, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let count: u64 = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let count = usize::try_from(count).map_err(|_| de::Error::custom("string too long"))?;
+
+        let mut bytes = Vec::with_capacity(count.min(PREALLOC_MAX));
+        for n in 0..count {
+            let first: u8 = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(n, &self))?;
+            let width = utf8_width(first).ok_or_else(|| {
+                de::Error::invalid_value(Unexpected::Unsigned(first.into()), &"a UTF-8 start byte")
+            })?;
+            bytes.push(first);
+            for _ in 1..width {
+                bytes.push(seq.next_element()?.ok_or_else(|| de::Error::invalid_length(n, &self))?);
+            }
+        }
+
+        String::from_utf8(bytes).map_err(|err| de::Error::invalid_value(Unexpected::Bytes(err.as_bytes()), &self))
+    }
+}
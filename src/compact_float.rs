@@ -0,0 +1,100 @@
+//! # Compact Floats
+//!
+//! **Experimental:** the encoding may change in future versions.
+//!
+//! By default an `f64` is serialized as its eight byte IEEE 754 representation.
+//! This module, for use with `#[serde(with = "postbag::compact_float")]`, serializes
+//! an `f64` that is exactly representable as an integer of magnitude at most 2<sup>53</sup>
+//! as the tag byte [`INTEGER`] followed by the zig-zag varint of the integer instead,
+//! occupying between two and nine bytes. Other values, including `-0.0`, infinities and NaN,
+//! are serialized as the tag byte [`RAW`] followed by the IEEE 754 representation.
+//!
+//! This saves space for values that are mostly small integers, such as counters
+//! or measurements of coarse resolution, while all values round-trip losslessly.
+//!
+//! ```rust
+//! use serde::{Serialize, Deserialize};
+//! use postbag::{from_slim_slice, to_slim_vec};
+//!
+//! #[derive(Serialize, Deserialize, Debug, PartialEq)]
+//! pub struct Sample {
+//!     #[serde(with = "postbag::compact_float")]
+//!     value: f64,
+//! }
+//!
+//! let data = to_slim_vec(&Sample { value: 3.0 }).unwrap();
+//! assert_eq!(data.len(), 4);
+//! assert_eq!(from_slim_slice::<Sample>(&data).unwrap(), Sample { value: 3.0 });
+//! ```
+
+use std::fmt;
+
+use serde::{
+    Deserializer, Serializer,
+    de::{self, SeqAccess, Unexpected, Visitor},
+    ser::SerializeTupleStruct,
+};
+
+/// Tuple struct name recognized by the serializer and deserializer.
+pub(crate) const NAME: &str = "$postbag::CompactFloat";
+
+/// Tag of a value serialized as an integer.
+pub const INTEGER: u8 = 0;
+
+/// Tag of a value serialized as its IEEE 754 representation.
+pub const RAW: u8 = 1;
+
+/// Largest magnitude of an integer serialized in integer form.
+const INTEGER_MAX: f64 = (1u64 << f64::MANTISSA_DIGITS) as f64;
+
+/// Serialize the float in integer form, if possible.
+pub fn serialize<S>(val: &f64, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut tuple = serializer.serialize_tuple_struct(NAME, 2)?;
+    if val.fract() == 0.0 && val.abs() <= INTEGER_MAX && !(*val == 0.0 && val.is_sign_negative()) {
+        tuple.serialize_field(&INTEGER)?;
+        tuple.serialize_field(&(*val as i64))?;
+    } else {
+        tuple.serialize_field(&RAW)?;
+        tuple.serialize_field(val)?;
+    }
+    tuple.end()
+}
+
+/// Deserialize the float from integer or raw form.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_tuple_struct(NAME, 2, CompactFloatVisitor)
+}
+
+struct CompactFloatVisitor;
+
+impl<'de> Visitor<'de> for CompactFloatVisitor {
+    type Value = f64;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a compact float")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<f64, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let tag: u8 = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        match tag {
+            INTEGER => {
+                let int: i64 = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                if int.unsigned_abs() > INTEGER_MAX as u64 {
+                    return Err(de::Error::invalid_value(Unexpected::Signed(int), &self));
+                }
+                Ok(int as f64)
+            }
+            RAW => seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self)),
+            _ => Err(de::Error::invalid_value(Unexpected::Unsigned(tag.into()), &self)),
+        }
+    }
+}
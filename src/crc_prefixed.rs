@@ -0,0 +1,221 @@
+//! # CRC-Prefixed Messages
+//!
+//! A message serialized by [`to_full_vec_crc_prefixed`] or [`serialize_crc_prefixed`] starts
+//! with the length of the body as a little-endian `u64`. The body follows, split into chunks
+//! of [`CHUNK_LEN`] bytes, the last one possibly shorter. Each chunk is preceded by the
+//! CRC-32 checksum of the body up to the end of the chunk as a little-endian `u32`.
+//! The checksum uses the same parameters as the [`Crc32`](crate::ser_flavors::Crc32) flavor,
+//! thus the checksum preceding the last chunk is the checksum of the whole body.
+//!
+//! Since the length precedes the body, the value is serialized twice: once to determine
+//! the length of the body and once to write it.
+//!
+//! [`CrcReader`] verifies the body while it is being read, buffering one chunk at a time.
+//! Each chunk is verified before any of its bytes are returned, thus corrupted data is
+//! rejected with [`Error::BadChecksum`] as soon as the chunk containing it has been read,
+//! without reading the remainder of the body. A truncated body is detected by the length.
+//! [`deserialize_crc_prefixed`] deserializes a value through a [`CrcReader`].
+//!
+//! This format is not compatible with the serialization of a value using [`serialize`].
+//!
+//! ```rust
+//! use postbag::{Error, cfg::Full, crc_prefixed::deserialize_crc_prefixed, to_full_vec_crc_prefixed};
+//!
+//! let mut data = to_full_vec_crc_prefixed(&vec!["large".to_string(); 1000]).unwrap();
+//! let value: Vec<String> = deserialize_crc_prefixed::<Full, _, _>(data.as_slice()).unwrap();
+//! assert_eq!(value.len(), 1000);
+//!
+//! data[100] ^= 0x01;
+//! let res = deserialize_crc_prefixed::<Full, _, Vec<String>>(data.as_slice());
+//! assert!(matches!(res, Err(Error::BadChecksum)));
+//! ```
+
+use std::{
+    io::{self, Read, Write},
+    mem,
+};
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{
+    cfg::{Cfg, Full},
+    de::deserialize,
+    error::{Error, Result},
+    ser::{SizeCounter, serialize},
+    ser_flavors::crc32_update,
+};
+
+/// Number of body bytes covered by each checksum, except for the last chunk.
+pub const CHUNK_LEN: usize = 4096;
+
+/// Serialize a value using the specified configuration prefixed by its length,
+/// with a checksum preceding each chunk of the body.
+///
+/// See the [module documentation](self) for the format.
+pub fn serialize_crc_prefixed<CFG, W, T>(mut writer: W, value: &T) -> Result<()>
+where
+    CFG: Cfg,
+    W: Write,
+    T: Serialize + ?Sized,
+{
+    let mut sizer = SizeCounter(0);
+    serialize::<CFG, _, _>(&mut sizer, value)?;
+    writer.write_all(&(sizer.0 as u64).to_le_bytes())?;
+
+    let mut chunks = ChunkWriter { inner: writer, chunk: Vec::with_capacity(CHUNK_LEN.min(sizer.0)), crc: !0 };
+    serialize::<CFG, _, _>(&mut chunks, value)?;
+    Ok(chunks.write_chunk()?)
+}
+
+/// Serialize a value using the [`Full`] configuration prefixed by its length,
+/// with a checksum preceding each chunk of the body, and return a `Vec<u8>`.
+///
+/// See the [module documentation](self) for the format.
+pub fn to_full_vec_crc_prefixed<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: Serialize + ?Sized,
+{
+    let mut buffer = Vec::new();
+    serialize_crc_prefixed::<Full, _, _>(&mut buffer, value)?;
+    Ok(buffer)
+}
+
+/// Deserialize a value prefixed by its length and checksummed in chunks from a [`std::io::Read`].
+///
+/// Deserialization fails with [`Error::BadChecksum`] as soon as a chunk does not match
+/// its checksum. Only chunks read by the deserializer are verified, thus the remainder
+/// of the body after the value is neither read nor verified.
+pub fn deserialize_crc_prefixed<CFG, R, T>(reader: R) -> Result<T>
+where
+    CFG: Cfg,
+    R: Read,
+    T: DeserializeOwned,
+{
+    let mut reader = CrcReader::new(reader)?;
+    deserialize::<CFG, _, T>(&mut reader).map_err(checksum_error)
+}
+
+/// Recovers [`Error::BadChecksum`] passed through an I/O error.
+fn checksum_error(err: Error) -> Error {
+    match err {
+        Error::Io(err)
+            if err
+                .get_ref()
+                .and_then(|err| err.downcast_ref::<Error>())
+                .is_some_and(|err| matches!(err, Error::BadChecksum)) =>
+        {
+            Error::BadChecksum
+        }
+        err => err,
+    }
+}
+
+/// Writes the body in chunks, each preceded by the checksum of the body up to its end.
+struct ChunkWriter<W> {
+    inner: W,
+    chunk: Vec<u8>,
+    crc: u32,
+}
+
+impl<W: Write> ChunkWriter<W> {
+    /// Writes the buffered chunk preceded by its checksum, if it is not empty.
+    fn write_chunk(&mut self) -> io::Result<()> {
+        if self.chunk.is_empty() {
+            return Ok(());
+        }
+
+        self.crc = self.chunk.iter().fold(self.crc, |crc, byte| crc32_update(crc, *byte));
+        self.inner.write_all(&(!self.crc).to_le_bytes())?;
+        self.inner.write_all(&self.chunk)?;
+        self.chunk.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for ChunkWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = buf.len().min(CHUNK_LEN - self.chunk.len());
+        self.chunk.extend_from_slice(&buf[..n]);
+        if self.chunk.len() == CHUNK_LEN {
+            self.write_chunk()?;
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Reader verifying the checksums of a body prefixed by its length.
+///
+/// Each chunk of the body is read and verified before any of its bytes are returned.
+/// A read fails with an I/O error wrapping [`Error::BadChecksum`] if a chunk does not
+/// match its checksum. Reading stops at the end of the body.
+///
+/// See the [module documentation](self) for the format.
+#[derive(Debug)]
+pub struct CrcReader<R> {
+    inner: R,
+    remaining: u64,
+    crc: u32,
+    chunk: Vec<u8>,
+    pos: usize,
+}
+
+impl<R: Read> CrcReader<R> {
+    /// Reads the length from `inner` and returns a reader for the body.
+    pub fn new(mut inner: R) -> Result<Self> {
+        let mut len = [0; 8];
+        inner.read_exact(&mut len)?;
+
+        Ok(Self { inner, remaining: u64::from_le_bytes(len), crc: !0, chunk: Vec::new(), pos: 0 })
+    }
+
+    /// Number of bytes of the body not returned yet.
+    pub fn remaining(&self) -> u64 {
+        self.remaining + (self.chunk.len() - self.pos) as u64
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Reads and verifies the next chunk.
+    fn read_chunk(&mut self) -> io::Result<()> {
+        let mut chunk = mem::take(&mut self.chunk);
+        chunk.clear();
+        self.pos = 0;
+
+        let mut expected = [0; 4];
+        self.inner.read_exact(&mut expected)?;
+
+        let len = CHUNK_LEN.min(usize::try_from(self.remaining).unwrap_or(usize::MAX));
+        chunk.resize(len, 0);
+        self.inner.read_exact(&mut chunk)?;
+        self.remaining -= len as u64;
+
+        self.crc = chunk.iter().fold(self.crc, |crc, byte| crc32_update(crc, *byte));
+        if !self.crc != u32::from_le_bytes(expected) {
+            self.remaining = 0;
+            return Err(Error::BadChecksum.into());
+        }
+
+        self.chunk = chunk;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for CrcReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.chunk.len() && self.remaining > 0 {
+            self.read_chunk()?;
+        }
+
+        let n = buf.len().min(self.chunk.len() - self.pos);
+        buf[..n].copy_from_slice(&self.chunk[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
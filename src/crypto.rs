@@ -0,0 +1,123 @@
+//! # Encrypted Messages
+//!
+//! Serialization of values encrypted and authenticated using an AEAD cipher
+//! (authenticated encryption with associated data), for example for a secure channel.
+//! It requires the `crypto` feature.
+//!
+//! No cipher is bundled. Instead a cipher is plugged in by implementing the [`Aead`]
+//! trait, usually by wrapping the in-place API of a cipher crate.
+//!
+//! [`to_sealed_vec`] serializes a value and encrypts it in place, appending the
+//! authentication tag. [`from_sealed_slice`] copies the data once into the buffer the
+//! value is deserialized from, decrypts and verifies it in place and deserializes the value.
+//! [`from_sealed_vec`] avoids the copy by decrypting the provided vector in place.
+//! Data failing verification is rejected with [`Error::AuthFailed`] before deserializing.
+//!
+//! ```rust
+//! use postbag::{cfg::Full, crypto::{Aead, AeadError, from_sealed_slice, to_sealed_vec}};
+//!
+//! /// Toy cipher for illustration only; it provides no security.
+//! struct Xor(u8);
+//!
+//! impl Aead for Xor {
+//!     fn seal_in_place(&self, nonce: &[u8], buffer: &mut Vec<u8>) -> Result<(), AeadError> {
+//!         let tag = buffer.iter().fold(nonce.len() as u8, |acc, b| acc.wrapping_add(*b));
+//!         buffer.iter_mut().for_each(|b| *b ^= self.0);
+//!         buffer.push(tag);
+//!         Ok(())
+//!     }
+//!
+//!     fn open_in_place(&self, nonce: &[u8], buffer: &mut Vec<u8>) -> Result<(), AeadError> {
+//!         let tag = buffer.pop().ok_or(AeadError)?;
+//!         buffer.iter_mut().for_each(|b| *b ^= self.0);
+//!         match buffer.iter().fold(nonce.len() as u8, |acc, b| acc.wrapping_add(*b)) == tag {
+//!             true => Ok(()),
+//!             false => Err(AeadError),
+//!         }
+//!     }
+//! }
+//!
+//! let sealed = to_sealed_vec::<Full, _, _>(&"secret", &Xor(0x5a), b"nonce").unwrap();
+//! let value: String = from_sealed_slice::<Full, _, _>(&sealed, &Xor(0x5a), b"nonce").unwrap();
+//! assert_eq!(value, "secret");
+//! ```
+
+use std::fmt;
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{
+    cfg::Cfg,
+    de::from_slice,
+    error::{Error, Result},
+    ser::serialize,
+};
+
+/// AEAD cipher encrypting and decrypting in place.
+///
+/// An implementation holds the key. The nonce is passed for each message
+/// and must never be reused with the same key.
+pub trait Aead {
+    /// Encrypts the plaintext in `buffer` in place and appends the authentication tag.
+    fn seal_in_place(&self, nonce: &[u8], buffer: &mut Vec<u8>) -> std::result::Result<(), AeadError>;
+
+    /// Verifies the authentication tag at the end of `buffer`, removes it
+    /// and decrypts the ciphertext in place.
+    ///
+    /// Fails if the data has not been sealed using the same key and nonce.
+    fn open_in_place(&self, nonce: &[u8], buffer: &mut Vec<u8>) -> std::result::Result<(), AeadError>;
+}
+
+/// Encryption or decryption failure of an [`Aead`] cipher.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AeadError;
+
+impl fmt::Display for AeadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "AEAD error")
+    }
+}
+
+impl std::error::Error for AeadError {}
+
+/// Serialize a value using the specified configuration, encrypt it using `aead`
+/// and return the ciphertext followed by the authentication tag.
+pub fn to_sealed_vec<CFG, T, A>(value: &T, aead: &A, nonce: &[u8]) -> Result<Vec<u8>>
+where
+    CFG: Cfg,
+    T: Serialize + ?Sized,
+    A: Aead + ?Sized,
+{
+    let mut buffer = Vec::new();
+    serialize::<CFG, _, _>(&mut buffer, value)?;
+    aead.seal_in_place(nonce, &mut buffer).map_err(|err| Error::Custom(format!("sealing failed: {err}")))?;
+    Ok(buffer)
+}
+
+/// Decrypt and verify data sealed by [`to_sealed_vec`] and deserialize the value
+/// using the specified configuration.
+///
+/// The data is copied once into the buffer it is decrypted in.
+/// Verification failure is reported as [`Error::AuthFailed`].
+pub fn from_sealed_slice<CFG, T, A>(slice: &[u8], aead: &A, nonce: &[u8]) -> Result<T>
+where
+    CFG: Cfg,
+    T: DeserializeOwned,
+    A: Aead + ?Sized,
+{
+    from_sealed_vec::<CFG, T, A>(slice.to_vec(), aead, nonce)
+}
+
+/// Decrypt and verify data sealed by [`to_sealed_vec`] in place and deserialize the value
+/// using the specified configuration.
+///
+/// Verification failure is reported as [`Error::AuthFailed`].
+pub fn from_sealed_vec<CFG, T, A>(mut data: Vec<u8>, aead: &A, nonce: &[u8]) -> Result<T>
+where
+    CFG: Cfg,
+    T: DeserializeOwned,
+    A: Aead + ?Sized,
+{
+    aead.open_in_place(nonce, &mut data).map_err(|_| Error::AuthFailed)?;
+    from_slice::<CFG, T>(&data)
+}
@@ -0,0 +1,80 @@
+//! # Date and Time
+//!
+//! This module, for use with `#[serde(with = "postbag::datetime")]`, serializes
+//! an [`OffsetDateTime`] losslessly as nanoseconds since the Unix epoch together
+//! with its UTC offset.
+//! It requires the `time` feature.
+//!
+//! The value is written as two varints without length prefix:
+//!
+//! 1. the number of nanoseconds since 1970-01-01 00:00:00 UTC as a zigzag-encoded
+//!    `i128` varint, negative for points in time before the epoch,
+//! 2. the UTC offset in seconds as a zigzag-encoded `i32` varint, positive
+//!    for offsets east of UTC.
+//!
+//! The point in time is independent of the offset, i.e. the local date and time
+//! is obtained by adding the offset to the point in time.
+//!
+//! ```rust
+//! # use serde::{Serialize, Deserialize};
+//! use time::OffsetDateTime;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! pub struct Trade {
+//!     #[serde(with = "postbag::datetime")]
+//!     executed: OffsetDateTime,
+//! }
+//! ```
+
+use std::fmt;
+
+use serde::{
+    Deserializer, Serializer,
+    de::{self, SeqAccess, Visitor},
+    ser::SerializeTupleStruct,
+};
+use time::{OffsetDateTime, UtcOffset};
+
+/// Tuple struct name recognized by the serializer and deserializer.
+pub(crate) const NAME: &str = "$postbag::DateTime";
+
+/// Serialize the date and time as nanoseconds since the Unix epoch and UTC offset.
+pub fn serialize<S>(val: &OffsetDateTime, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut tuple = serializer.serialize_tuple_struct(NAME, 2)?;
+    tuple.serialize_field(&val.unix_timestamp_nanos())?;
+    tuple.serialize_field(&val.offset().whole_seconds())?;
+    tuple.end()
+}
+
+/// Deserialize the date and time from nanoseconds since the Unix epoch and UTC offset.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<OffsetDateTime, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_tuple_struct(NAME, 2, DateTimeVisitor)
+}
+
+struct DateTimeVisitor;
+
+impl<'de> Visitor<'de> for DateTimeVisitor {
+    type Value = OffsetDateTime;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("nanoseconds since the Unix epoch and a UTC offset")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<OffsetDateTime, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let nanos: i128 = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let offset: i32 = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
+
+        let utc = OffsetDateTime::from_unix_timestamp_nanos(nanos).map_err(de::Error::custom)?;
+        let offset = UtcOffset::from_whole_seconds(offset).map_err(de::Error::custom)?;
+        utc.checked_to_offset(offset).ok_or_else(|| de::Error::custom("date and time out of range for offset"))
+    }
+}
@@ -0,0 +1,146 @@
+//! Allocators of deserialization buffers.
+
+use std::{io::Write, ops::DerefMut};
+
+/// Allocator of the internal buffers of a [`Deserializer`](crate::Deserializer).
+///
+/// The deserializer reuses internal buffers for strings, byte arrays and identifiers
+/// provided to visitors by reference, and for data captured while reading, such as the
+/// serialized map keys verified by the [`Canonical`](crate::cfg::Canonical) configuration.
+///
+/// [`Global`] allocates these buffers using the global allocator. With the `allocator-api2`
+/// feature enabled, a reference to any `allocator_api2::alloc::Allocator`, for example
+/// a bump arena scoped to a request, can be passed to
+/// [`Deserializer::new_in`](crate::Deserializer::new_in) instead.
+///
+/// Owned strings and byte arrays, as well as the data of
+/// [unknown fields](crate::UnknownFields), are always allocated using the global
+/// allocator, since they are passed to visitors as a [`String`] or [`Vec<u8>`].
+pub trait BufferAlloc: Clone + private::Sealed {
+    #[doc(hidden)]
+    type Buf: Buffer;
+
+    #[doc(hidden)]
+    fn buffer(&self) -> Self::Buf;
+}
+
+/// Global allocator, used by default.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Global;
+
+impl private::Sealed for Global {}
+
+impl BufferAlloc for Global {
+    type Buf = Vec<u8>;
+
+    fn buffer(&self) -> Vec<u8> {
+        Vec::new()
+    }
+}
+
+#[cfg(feature = "allocator-api2")]
+impl<A: allocator_api2::alloc::Allocator + ?Sized> private::Sealed for &A {}
+
+#[cfg(feature = "allocator-api2")]
+impl<'a, A: allocator_api2::alloc::Allocator + ?Sized> BufferAlloc for &'a A {
+    type Buf = allocator_api2::vec::Vec<u8, &'a A>;
+
+    fn buffer(&self) -> Self::Buf {
+        allocator_api2::vec::Vec::new_in(*self)
+    }
+}
+
+/// Byte buffer allocated by a [`BufferAlloc`].
+#[doc(hidden)]
+pub trait Buffer: DerefMut<Target = [u8]> + Write {
+    /// Creates an empty buffer using the same allocator.
+    fn new_like(&self) -> Self;
+
+    fn capacity(&self) -> usize;
+
+    fn clear(&mut self);
+
+    fn truncate(&mut self, len: usize);
+
+    /// Resizes the buffer, filling new bytes with zeros.
+    fn resize(&mut self, len: usize);
+
+    fn reserve(&mut self, additional: usize);
+
+    fn reserve_exact(&mut self, additional: usize);
+
+    fn extend_from_slice(&mut self, data: &[u8]);
+}
+
+impl Buffer for Vec<u8> {
+    fn new_like(&self) -> Self {
+        Vec::new()
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity()
+    }
+
+    fn clear(&mut self) {
+        self.clear()
+    }
+
+    fn truncate(&mut self, len: usize) {
+        self.truncate(len)
+    }
+
+    fn resize(&mut self, len: usize) {
+        self.resize(len, 0)
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.reserve(additional)
+    }
+
+    fn reserve_exact(&mut self, additional: usize) {
+        self.reserve_exact(additional)
+    }
+
+    fn extend_from_slice(&mut self, data: &[u8]) {
+        self.extend_from_slice(data)
+    }
+}
+
+#[cfg(feature = "allocator-api2")]
+impl<A: allocator_api2::alloc::Allocator + Clone> Buffer for allocator_api2::vec::Vec<u8, A> {
+    fn new_like(&self) -> Self {
+        allocator_api2::vec::Vec::new_in(self.allocator().clone())
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity()
+    }
+
+    fn clear(&mut self) {
+        self.clear()
+    }
+
+    fn truncate(&mut self, len: usize) {
+        self.truncate(len)
+    }
+
+    fn resize(&mut self, len: usize) {
+        self.resize(len, 0)
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.reserve(additional)
+    }
+
+    fn reserve_exact(&mut self, additional: usize) {
+        self.reserve_exact(additional)
+    }
+
+    fn extend_from_slice(&mut self, data: &[u8]) {
+        self.extend_from_slice(data)
+    }
+}
+
+mod private {
+    pub trait Sealed {}
+}
@@ -0,0 +1,204 @@
+//! Deserializer builder.
+
+use std::{
+    any::type_name,
+    io::Read,
+    sync::{Arc, Mutex},
+};
+
+use serde::de::DeserializeOwned;
+
+use crate::{
+    cfg::Cfg,
+    de::deserializer::{Deserializer, IdentifierHook, Limits},
+    dump::{DecodeDiagnostic, RecentRead},
+    error::{Error, Result},
+};
+
+/// Builder for a [`Deserializer`] with limits for untrusted input.
+///
+/// By default no limits are applied.
+///
+/// # Example
+///
+/// ```rust
+/// use postbag::{cfg::Full, DeserializerBuilder, Error, to_full_vec};
+///
+/// let builder = DeserializerBuilder::new().max_depth(16).max_alloc(1024).max_total(64 * 1024);
+///
+/// let bytes = to_full_vec(&vec!["a".repeat(2048)]).unwrap();
+/// let res = builder.deserialize::<Full, _, Vec<String>>(bytes.as_slice());
+/// assert!(matches!(res, Err(Error::AllocLimitExceeded)));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DeserializerBuilder {
+    limits: Limits,
+    max_total: Option<u64>,
+    on_identifier: Option<IdentifierHook>,
+}
+
+impl DeserializerBuilder {
+    /// Creates a new builder without limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum nesting depth of sequences, maps, structs and enums.
+    ///
+    /// Exceeding it fails with [`Error::DepthLimitExceeded`](crate::Error::DepthLimitExceeded).
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.limits.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Sets the maximum length of a single string, byte array or identifier.
+    ///
+    /// Exceeding it fails with [`Error::AllocLimitExceeded`](crate::Error::AllocLimitExceeded).
+    pub fn max_alloc(mut self, max_alloc: usize) -> Self {
+        self.limits.max_alloc = Some(max_alloc);
+        self
+    }
+
+    /// Sets the maximum length of a single string in bytes, in addition to [`max_alloc`](Self::max_alloc).
+    ///
+    /// This allows limiting strings, such as names and map keys, more strictly than
+    /// byte arrays. Identifiers are not affected.
+    /// Exceeding it fails with [`Error::AllocLimitExceeded`](crate::Error::AllocLimitExceeded).
+    pub fn max_string(mut self, max_string: usize) -> Self {
+        self.limits.max_string = Some(max_string);
+        self
+    }
+
+    /// Sets the maximum length of a single byte array, in addition to [`max_alloc`](Self::max_alloc).
+    ///
+    /// Exceeding it fails with [`Error::AllocLimitExceeded`](crate::Error::AllocLimitExceeded).
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.limits.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Sets the maximum number of bytes read from the reader, including skipped data.
+    ///
+    /// Exceeding it fails with [`Error::ReadLimitExceeded`](crate::Error::ReadLimitExceeded).
+    pub fn max_total(mut self, max_total: u64) -> Self {
+        self.max_total = Some(max_total);
+        self
+    }
+
+    /// Sets whether integer varints with redundant bytes are rejected.
+    ///
+    /// Such varints fail with [`Error::NonCanonical`](crate::Error::NonCanonical).
+    /// This is always enabled if the configuration is [canonical](Cfg::canonical).
+    pub fn canonical_varints(mut self, canonical_varints: bool) -> Self {
+        self.limits.canonical_varints = canonical_varints;
+        self
+    }
+
+    /// Sets a callback invoked with each struct field identifier and enum variant identifier read.
+    ///
+    /// The callback is invoked for known and unknown identifiers alike, for example to
+    /// determine which fields occur in real data. It does not affect deserialization.
+    /// Identifiers are only read in configurations with identifiers, such as
+    /// [`Full`](crate::cfg::Full), or for enums using [`named_variant`](crate::named_variant).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::sync::{Arc, Mutex};
+    /// use serde::{Serialize, Deserialize};
+    /// use postbag::{cfg::Full, DeserializerBuilder, to_full_vec};
+    ///
+    /// #[derive(Serialize)]
+    /// struct Current {
+    ///     id: u32,
+    ///     legacy: bool,
+    /// }
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Reader {
+    ///     id: u32,
+    /// }
+    ///
+    /// let seen = Arc::new(Mutex::new(Vec::new()));
+    /// let seen_by_hook = seen.clone();
+    /// let builder = DeserializerBuilder::new()
+    ///     .on_identifier(move |ident| seen_by_hook.lock().unwrap().push(ident.to_string()));
+    ///
+    /// let bytes = to_full_vec(&Current { id: 1, legacy: false }).unwrap();
+    /// builder.deserialize::<Full, _, Reader>(bytes.as_slice()).unwrap();
+    /// assert_eq!(*seen.lock().unwrap(), ["id", "legacy"]);
+    /// ```
+    pub fn on_identifier(mut self, on_identifier: impl FnMut(&str) + Send + 'static) -> Self {
+        self.on_identifier = Some(IdentifierHook(Arc::new(Mutex::new(on_identifier))));
+        self
+    }
+
+    /// Builds a deserializer reading from the specified reader.
+    pub fn build<'de, CFG, R>(&self, read: R) -> Deserializer<'de, R, CFG>
+    where
+        CFG: Cfg,
+        R: Read,
+    {
+        let mut deserializer = Deserializer::new(read);
+        deserializer.set_limits(self.limits.clone(), self.max_total);
+        deserializer.set_on_identifier(self.on_identifier.clone());
+        deserializer
+    }
+
+    /// Deserialize a value of type `T` from a [`std::io::Read`] applying the limits.
+    pub fn deserialize<CFG, R, T>(&self, read: R) -> Result<T>
+    where
+        CFG: Cfg,
+        R: Read,
+        T: DeserializeOwned,
+    {
+        let mut deserializer = self.build::<CFG, R>(read);
+        let t = T::deserialize(&mut deserializer)?;
+        deserializer.finalize();
+        Ok(t)
+    }
+
+    /// Deserialize a value of type `T` from a [`std::io::Read`] applying the limits,
+    /// capturing a diagnostic on failure.
+    ///
+    /// On failure the error is returned together with a [`DecodeDiagnostic`] containing
+    /// the number of bytes read when the failure was detected, the bytes around this position and
+    /// a description of the error. To capture the bytes, the most recently read bytes
+    /// are retained and the bytes following the position are read from the reader.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use postbag::{cfg::Slim, DeserializerBuilder, to_slim_vec};
+    ///
+    /// let mut bytes = to_slim_vec(&(1u16, true)).unwrap();
+    /// bytes[1] = 7;
+    ///
+    /// let (_err, diagnostic) =
+    ///     DeserializerBuilder::new().deserialize_with_diagnostic::<Slim, _, (u16, bool)>(bytes.as_slice()).unwrap_err();
+    /// assert_eq!(diagnostic.offset, 2);
+    /// assert_eq!(diagnostic.window, [1, 7]);
+    /// assert_eq!(diagnostic.message, "invalid bool");
+    /// ```
+    pub fn deserialize_with_diagnostic<CFG, R, T>(
+        &self, read: R,
+    ) -> std::result::Result<T, (Error, DecodeDiagnostic)>
+    where
+        CFG: Cfg,
+        R: Read,
+        T: DeserializeOwned,
+    {
+        let mut deserializer = self.build::<CFG, _>(RecentRead::new(read));
+        match T::deserialize(&mut deserializer) {
+            Ok(t) => {
+                deserializer.finalize();
+                Ok(t)
+            }
+            Err(err) => {
+                let offset = deserializer.position();
+                let diagnostic = deserializer.finalize().diagnostic(offset, type_name::<T>(), err.to_string());
+                Err((err, diagnostic))
+            }
+        }
+    }
+}
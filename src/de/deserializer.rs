@@ -1,21 +1,135 @@
-use std::{collections::HashMap, io::Read, marker::PhantomData};
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{Read, Seek, Write},
+    marker::PhantomData,
+    mem,
+    sync::{Arc, Mutex, PoisonError},
+};
 
 use serde::de::{
     self, DeserializeSeed, IntoDeserializer, Visitor,
-    value::{StringDeserializer, U32Deserializer},
+    value::{
+        BytesDeserializer, SeqDeserializer, StrDeserializer, U8Deserializer, U32Deserializer, U64Deserializer,
+        UnitDeserializer,
+    },
 };
 
 use crate::{
-    FALSE, ID_COUNT, ID_LEN, ID_LEN_NAME, NONE, SOME, SPECIAL_LEN, TRUE, UNKNOWN_LEN,
-    cfg::Cfg,
-    de::skippable::SkipRead,
+    CONTINUE, FALSE, ID_COUNT, ID_LEN, ID_LEN_NAME, NONE, SOME, SPECIAL_LEN, STOP, TERMINATED_LEN, TRUE,
+    UNKNOWN_LEN, bits, bounded,
+    cfg::{Cfg, Endian},
+    char_len_string, compact_float,
+    de::{
+        UnknownFields,
+        alloc::{Buffer, BufferAlloc, Global},
+        intern::Interner,
+        skippable::SkipRead,
+    },
+    dump::Annotation,
     error::{Error, Result},
+    feature_helper_tuple, fixchar, fixint, le_f64_slice, named_variant, numeric_identifier, padded_varint, raw,
     varint::{max_of_last_byte, varint_max},
+    versioned,
 };
 
+/// Maximum capacity of the scratch buffer kept between reads.
+const SCRATCH_MAX: usize = 64 * 1024;
+
+/// Maximum number of elements of a sequence or map consuming no input.
+const EMPTY_ELEMENTS_MAX: usize = 1024 * 1024;
+
+/// Conservative minimum serialized size of a sequence element or map entry in bytes,
+/// used to bound the size hint of a sequence or map by the remaining input.
+const MIN_ELEMENT_SIZE: u64 = 1;
+
+/// Maximum number of entries announced by the size hint of a map if the remaining input is unknown.
+const MAP_PREALLOC_MAX: usize = 4096;
+
+/// Limits of a [`Deserializer`] set at runtime.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Limits {
+    /// Maximum nesting depth of sequences, maps, structs and enums.
+    pub max_depth: Option<usize>,
+    /// Maximum length of a single string or byte array.
+    pub max_alloc: Option<usize>,
+    /// Maximum length of a single string.
+    pub max_string: Option<usize>,
+    /// Maximum length of a single byte array.
+    pub max_bytes: Option<usize>,
+    /// Whether varints with redundant bytes are rejected.
+    pub canonical_varints: bool,
+}
+
+/// Kind of a value preceded by its length, which is subject to the allocation limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LenKind {
+    String,
+    Bytes,
+    Identifier,
+}
+
+/// Callback invoked with each identifier read.
+type IdentifierFn = dyn FnMut(&str) + Send;
+
+/// Shared callback invoked with each identifier read.
+#[derive(Clone)]
+pub(crate) struct IdentifierHook(pub Arc<Mutex<IdentifierFn>>);
+
+impl fmt::Debug for IdentifierHook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("IdentifierHook")
+    }
+}
+
+/// Enum variant tag.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Variant {
+    /// Variant index, used when identifiers are not serialized.
+    Index(u32),
+    /// Variant identifier, used when identifiers are serialized.
+    Name(Arc<str>),
+}
+
 /// Deserializer.
-pub struct Deserializer<'de, R, CFG> {
-    input: SkipRead<R>,
+///
+/// Internal buffers are allocated using the [`BufferAlloc`] `A`, which defaults to
+/// the global allocator.
+pub struct Deserializer<'de, R, CFG, A: BufferAlloc = Global> {
+    input: SkipRead<R, A::Buf>,
+    peeked_variant: Option<Variant>,
+    /// Whether the next enum variant is read as an identifier.
+    named_variant: bool,
+    /// Whether the next tuple is a fixed-size integer with reversed byte order.
+    reversed_fixint: bool,
+    /// Whether the next tuple is the encoding of a helper and thus not enclosed in a skippable block.
+    plain_tuple: bool,
+    interner: Option<Interner>,
+    scratch: A::Buf,
+    alloc: A,
+    /// Input being read, if deserialized values can borrow from it.
+    borrowed: Option<&'de [u8]>,
+    limits: Limits,
+    /// Current nesting depth.
+    depth: usize,
+    /// Descriptions of the values read, when annotating.
+    annotations: Option<Vec<Annotation>>,
+    /// Variant names by enum name, for resolving variant indices.
+    variant_names: Arc<HashMap<&'static str, &'static [&'static str]>>,
+    /// Variant names of the enum being deserialized.
+    enum_variant_names: Option<&'static [&'static str]>,
+    /// Variants of the enum being deserialized, as given to serde.
+    enum_variants: &'static [&'static str],
+    /// Number of unknown enum variants read.
+    unknown_variants: usize,
+    /// Maximum length of the next sequence or string.
+    len_bound: Option<usize>,
+    /// Whether a value has been ignored, i.e. deserialized as [`IgnoredAny`](serde::de::IgnoredAny).
+    ignored: bool,
+    /// Unknown fields of the outermost struct, when collecting them.
+    unknown_fields: Option<UnknownFields>,
+    /// Callback invoked with each identifier read.
+    on_identifier: Option<IdentifierHook>,
     _de: PhantomData<&'de ()>,
     _cfg: PhantomData<CFG>,
 }
@@ -26,16 +140,361 @@ where
 {
     /// Obtain a Deserializer from a reader.
     pub fn new(read: R) -> Self {
-        Deserializer { input: SkipRead::new(read), _de: PhantomData, _cfg: PhantomData }
+        Self::new_in(read, Global)
+    }
+
+    /// Obtain a Deserializer from a reader with a known number of remaining bytes.
+    ///
+    /// The number of remaining bytes is used to allocate buffers for strings
+    /// and byte arrays with a single allocation, while rejecting lengths
+    /// exceeding the remaining input before allocating.
+    pub fn with_remaining(read: R, remaining: u64) -> Self {
+        Self::with_input(SkipRead::with_remaining(read, remaining, Vec::new()), Global)
+    }
+
+    /// Obtain a Deserializer from a seekable reader.
+    ///
+    /// Skipped data, such as unknown struct fields, is seeked over instead of being read.
+    pub fn with_seek(read: R) -> Self
+    where
+        R: Seek,
+    {
+        Self::with_input(SkipRead::with_seek(read, Vec::new()), Global)
+    }
+}
+
+impl<'de, R, CFG: Cfg, A: BufferAlloc> Deserializer<'de, R, CFG, A>
+where
+    R: Read,
+{
+    /// Obtain a Deserializer from a reader, allocating its internal buffers using `alloc`.
+    ///
+    /// See [`BufferAlloc`] for the buffers affected.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "allocator-api2")]
+    /// # {
+    /// use allocator_api2::alloc::Global;
+    /// use postbag::{Deserializer, cfg::Full, to_full_vec};
+    ///
+    /// let data = to_full_vec(&("first", 2u8)).unwrap();
+    ///
+    /// let mut deserializer = Deserializer::<_, Full, _>::new_in(data.as_slice(), &Global);
+    /// assert_eq!(deserializer.deserialize_value::<(String, u8)>().unwrap(), ("first".to_string(), 2));
+    /// # }
+    /// ```
+    pub fn new_in(read: R, alloc: A) -> Self {
+        Self::with_input(SkipRead::new(read, alloc.buffer()), alloc)
+    }
+
+    fn with_input(mut input: SkipRead<R, A::Buf>, alloc: A) -> Self {
+        input.set_canonical(CFG::canonical());
+        Deserializer {
+            input,
+            peeked_variant: None,
+            named_variant: false,
+            reversed_fixint: false,
+            plain_tuple: false,
+            interner: None,
+            scratch: alloc.buffer(),
+            alloc,
+            borrowed: None,
+            limits: Limits::default(),
+            depth: 0,
+            annotations: None,
+            variant_names: Arc::default(),
+            enum_variant_names: None,
+            enum_variants: &[],
+            unknown_variants: 0,
+            len_bound: None,
+            ignored: false,
+            unknown_fields: None,
+            on_identifier: None,
+            _de: PhantomData,
+            _cfg: PhantomData,
+        }
+    }
+
+    /// Applies the limits of a builder.
+    pub(crate) fn set_limits(&mut self, limits: Limits, max_total: Option<u64>) {
+        self.limits = limits;
+        if let Some(max_total) = max_total {
+            self.input.set_read_limit(max_total);
+        }
+    }
+
+    /// Sets the callback invoked with each identifier read.
+    pub(crate) fn set_on_identifier(&mut self, on_identifier: Option<IdentifierHook>) {
+        self.on_identifier = on_identifier;
+    }
+
+    /// Starts recording a description of each value read.
+    pub(crate) fn start_annotating(&mut self) {
+        self.annotations = Some(Vec::new());
+    }
+
+    /// Returns the recorded descriptions.
+    pub(crate) fn take_annotations(&mut self) -> Vec<Annotation> {
+        self.annotations.take().unwrap_or_default()
+    }
+
+    /// Starts collecting the unknown fields of the outermost struct.
+    pub(crate) fn start_collecting_unknown_fields(&mut self) {
+        self.unknown_fields = Some(Vec::new());
+    }
+
+    /// Returns the collected unknown fields.
+    pub(crate) fn take_unknown_fields(&mut self) -> UnknownFields {
+        self.unknown_fields.take().unwrap_or_default()
+    }
+
+    /// Whether unknown fields of the struct being deserialized are collected.
+    fn collecting_unknown_fields(&self) -> bool {
+        self.unknown_fields.is_some() && self.depth == 1
+    }
+
+    /// Number of bytes read from the reader so far.
+    ///
+    /// This includes bytes of skipped data, such as unknown struct fields.
+    pub fn position(&self) -> u64 {
+        self.input.position()
+    }
+
+    /// Sets the interner used for identifiers.
+    pub fn set_interner(&mut self, interner: Interner) {
+        self.interner = Some(interner);
+    }
+
+    /// Removes and returns the interner used for identifiers.
+    pub fn take_interner(&mut self) -> Option<Interner> {
+        self.interner.take()
+    }
+
+    /// Sets the variant names of the enum with the specified name, as given to serde,
+    /// in the order of their indices.
+    ///
+    /// When the variant of this enum is serialized by index, as in the
+    /// [`Slim`](crate::cfg::Slim) configuration, the index is translated into the
+    /// variant name using this table before the variant is resolved by name.
+    /// This allows deserializing data serialized before the variants of the enum
+    /// were reordered, by providing the variant names in their previous order.
+    /// An index beyond the table fails with [`Error::BadEnum`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use serde::Deserialize;
+    /// use postbag::{Deserializer, cfg::Slim};
+    ///
+    /// // Previously declared as `enum Color { Red, Green, Blue }`.
+    /// #[derive(Deserialize, Debug, PartialEq)]
+    /// enum Color { Blue, Red, Green }
+    ///
+    /// // Serialized `Color::Green`.
+    /// let data = [1u8];
+    ///
+    /// let mut deserializer = Deserializer::<_, Slim>::new(data.as_slice());
+    /// deserializer.set_variant_names("Color", &["Red", "Green", "Blue"]);
+    /// assert_eq!(deserializer.deserialize_value::<Color>().unwrap(), Color::Green);
+    /// ```
+    pub fn set_variant_names(&mut self, name: &'static str, variants: &'static [&'static str]) {
+        Arc::make_mut(&mut self.variant_names).insert(name, variants);
+    }
+
+    /// Number of enum variants read so far that are not among the variants of their enum.
+    ///
+    /// Such a variant is usually absorbed by a variant marked `#[serde(other)]`,
+    /// indicating that the data was serialized using a newer version of the enum.
+    /// A variant is unknown if its name or, when serialized by index, its index
+    /// is not among the variants passed by serde to
+    /// [`deserialize_enum`](serde::Deserializer::deserialize_enum).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use serde::{Serialize, Deserialize};
+    /// use postbag::{Deserializer, cfg::Full, to_full_vec};
+    ///
+    /// #[derive(Serialize)]
+    /// enum NewColor { Red, Green, Blue }
+    ///
+    /// #[derive(Deserialize, Debug, PartialEq)]
+    /// enum Color {
+    ///     Red,
+    ///     Green,
+    ///     #[serde(other)]
+    ///     Unknown,
+    /// }
+    ///
+    /// let data = to_full_vec(&[NewColor::Red, NewColor::Blue]).unwrap();
+    ///
+    /// let mut deserializer = Deserializer::<_, Full>::new(data.as_slice());
+    /// let colors: [Color; 2] = deserializer.deserialize_value().unwrap();
+    /// assert_eq!(colors, [Color::Red, Color::Unknown]);
+    /// assert_eq!(deserializer.unknown_variants(), 1);
+    /// assert!(deserializer.saw_unknown_variant());
+    /// ```
+    pub fn unknown_variants(&self) -> usize {
+        self.unknown_variants
+    }
+
+    /// Whether an enum variant not among the variants of its enum has been read.
+    ///
+    /// See [`unknown_variants`](Self::unknown_variants) for details.
+    pub fn saw_unknown_variant(&self) -> bool {
+        self.unknown_variants > 0
+    }
+
+    /// Replaces the buffer used for strings, byte arrays and identifiers provided to
+    /// visitors by reference and returns the previous buffer.
+    ///
+    /// This allows supplying a preallocated buffer, for example taken from a pool shared
+    /// by request handlers, and recovering it afterwards. The contents of both buffers
+    /// are cleared, so that the returned buffer holds no data of previous values.
+    /// A buffer whose capacity exceeds 64 KiB after reading a value is freed
+    /// and replaced by an empty buffer.
+    ///
+    /// Owned strings and byte arrays are allocated individually, since they are passed
+    /// to visitors as a [`String`] or [`Vec<u8>`]. To allocate all internal buffers
+    /// using a custom allocator, see [`new_in`](Self::new_in).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use postbag::{Deserializer, cfg::Full, to_full_vec};
+    ///
+    /// let pool = Vec::with_capacity(1024);
+    /// let data = to_full_vec(&(1u8, 2u8)).unwrap();
+    ///
+    /// let mut deserializer = Deserializer::<_, Full>::new(data.as_slice());
+    /// deserializer.replace_scratch(pool);
+    /// assert_eq!(deserializer.deserialize_value::<(u8, u8)>().unwrap(), (1, 2));
+    /// let pool = deserializer.replace_scratch(Vec::new());
+    /// assert!(pool.capacity() >= 1024 && pool.is_empty());
+    /// ```
+    pub fn replace_scratch(&mut self, mut scratch: A::Buf) -> A::Buf {
+        scratch.clear();
+        let mut previous = mem::replace(&mut self.scratch, scratch);
+        previous.clear();
+        previous
     }
 
     /// Returns the reader.
     pub fn finalize(self) -> R {
         self.input.into_inner()
     }
+
+    /// Replaces the reader, for example to read the next message of a connection,
+    /// and returns the previous reader.
+    ///
+    /// The state of a partially read value is discarded and the position and
+    /// the [number of unknown variants](Self::unknown_variants) are reset.
+    /// The interner, variant names, limits and internal buffers are kept, so that a
+    /// long-lived deserializer avoids reallocating them for each message.
+    /// The number of remaining bytes given to [`with_remaining`](Self::with_remaining)
+    /// is not known for the new reader, and data is no longer borrowed from a slice
+    /// given to [`from_slice`](Deserializer::from_slice).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use postbag::{Deserializer, cfg::Full, to_full_vec};
+    ///
+    /// let messages = [to_full_vec(&"first").unwrap(), to_full_vec(&"second").unwrap()];
+    ///
+    /// let mut deserializer = Deserializer::<_, Full>::new(messages[0].as_slice());
+    /// assert_eq!(deserializer.deserialize_value::<String>().unwrap(), "first");
+    ///
+    /// deserializer.reset(messages[1].as_slice());
+    /// assert_eq!(deserializer.deserialize_value::<String>().unwrap(), "second");
+    /// ```
+    pub fn reset(&mut self, read: R) -> R {
+        self.borrowed = None;
+        self.peeked_variant = None;
+        self.named_variant = false;
+        self.reversed_fixint = false;
+        self.plain_tuple = false;
+        self.scratch.clear();
+        self.depth = 0;
+        self.enum_variant_names = None;
+        self.enum_variants = &[];
+        self.unknown_variants = 0;
+        self.len_bound = None;
+        self.ignored = false;
+        self.input.reset(read)
+    }
+
+    /// Deserializes a value.
+    ///
+    /// This can be called repeatedly to read multiple values written by
+    /// [`Serializer::serialize_value`](crate::Serializer::serialize_value).
+    /// The values must be read with exactly the same sequence of types
+    /// as they were written.
+    pub fn deserialize_value<T>(&mut self) -> Result<T>
+    where
+        T: de::Deserialize<'de>,
+    {
+        if self.peeked_variant.is_none() {
+            self.input.start_message();
+        }
+        T::deserialize(self)
+    }
+
+    /// Reads the variant tag of the next value, which must be an enum,
+    /// without consuming its payload.
+    ///
+    /// The enum can subsequently be deserialized from this deserializer
+    /// as if the tag had not been read. Deserializing any other value
+    /// fails with [`Error::PeekedVariant`].
+    pub fn peek_variant(&mut self) -> Result<&Variant> {
+        if self.peeked_variant.is_none() {
+            self.input.start_message();
+            self.peeked_variant = Some(self.read_variant()?);
+        }
+        Ok(self.peeked_variant.as_ref().unwrap())
+    }
 }
 
-impl<'de, R: Read, CFG: Cfg> Deserializer<'de, R, CFG> {
+impl<'de, CFG: Cfg> Deserializer<'de, &'de [u8], CFG> {
+    /// Obtain a Deserializer from a byte slice.
+    ///
+    /// Strings and byte arrays are borrowed from the slice, allowing
+    /// values such as `&'de str` or `BTreeMap<&'de str, V>` to be deserialized.
+    /// Data interrupted by the chunk framing of a skippable block cannot be
+    /// borrowed and is provided as a copy instead.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::collections::BTreeMap;
+    /// use postbag::{Deserializer, cfg::Slim, to_slim_vec};
+    ///
+    /// let data = to_slim_vec(&BTreeMap::from([("alpha", 1u32), ("beta", 2)])).unwrap();
+    ///
+    /// let mut deserializer = Deserializer::<_, Slim>::from_slice(&data);
+    /// let map: BTreeMap<&str, u32> = deserializer.deserialize_value().unwrap();
+    /// assert_eq!(map["beta"], 2);
+    /// ```
+    pub fn from_slice(slice: &'de [u8]) -> Self {
+        Self::from_slice_in(slice, Global)
+    }
+}
+
+impl<'de, CFG: Cfg, A: BufferAlloc> Deserializer<'de, &'de [u8], CFG, A> {
+    /// Obtain a Deserializer from a byte slice, allocating its internal buffers using `alloc`.
+    ///
+    /// Data is borrowed from the slice as by [`from_slice`](Deserializer::from_slice).
+    pub fn from_slice_in(slice: &'de [u8], alloc: A) -> Self {
+        let input = SkipRead::with_remaining(slice, slice.len() as u64, alloc.buffer());
+        let mut this = Self::with_input(input, alloc);
+        this.borrowed = Some(slice);
+        this
+    }
+}
+
+impl<'de, R: Read, CFG: Cfg, A: BufferAlloc> Deserializer<'de, R, CFG, A> {
     fn read_varint_usize(&mut self) -> Result<usize> {
         let value = self.read_varint_u64()?;
         usize::try_from(value).map_err(|_| Error::UsizeOverflow)
@@ -51,6 +510,8 @@ impl<'de, R: Read, CFG: Cfg> Deserializer<'de, R, CFG> {
             if (val & 0x80) == 0 {
                 if i == varint_max::<u16>() - 1 && val > max_of_last_byte::<u16>() {
                     return Err(Error::BadVarint);
+                } else if (CFG::canonical() || self.limits.canonical_varints) && i > 0 && val == 0 {
+                    return Err(Error::NonCanonical);
                 } else {
                     return Ok(out);
                 }
@@ -69,6 +530,8 @@ impl<'de, R: Read, CFG: Cfg> Deserializer<'de, R, CFG> {
             if (val & 0x80) == 0 {
                 if i == varint_max::<u32>() - 1 && val > max_of_last_byte::<u32>() {
                     return Err(Error::BadVarint);
+                } else if (CFG::canonical() || self.limits.canonical_varints) && i > 0 && val == 0 {
+                    return Err(Error::NonCanonical);
                 } else {
                     return Ok(out);
                 }
@@ -87,6 +550,8 @@ impl<'de, R: Read, CFG: Cfg> Deserializer<'de, R, CFG> {
             if (val & 0x80) == 0 {
                 if i == varint_max::<u64>() - 1 && val > max_of_last_byte::<u64>() {
                     return Err(Error::BadVarint);
+                } else if (CFG::canonical() || self.limits.canonical_varints) && i > 0 && val == 0 {
+                    return Err(Error::NonCanonical);
                 } else {
                     return Ok(out);
                 }
@@ -105,6 +570,8 @@ impl<'de, R: Read, CFG: Cfg> Deserializer<'de, R, CFG> {
             if (val & 0x80) == 0 {
                 if i == varint_max::<u128>() - 1 && val > max_of_last_byte::<u128>() {
                     return Err(Error::BadVarint);
+                } else if (CFG::canonical() || self.limits.canonical_varints) && i > 0 && val == 0 {
+                    return Err(Error::NonCanonical);
                 } else {
                     return Ok(out);
                 }
@@ -113,71 +580,424 @@ impl<'de, R: Read, CFG: Cfg> Deserializer<'de, R, CFG> {
         Err(Error::BadVarint)
     }
 
-    fn read_identifier(&mut self) -> Result<String> {
+    /// Returns the current position, if annotating.
+    ///
+    /// Pending framing of the innermost block is read first, so that it is not
+    /// attributed to the following value.
+    fn mark(&mut self) -> Result<u64> {
+        if self.annotations.is_none() {
+            return Ok(0);
+        }
+        self.input.at_block_end()?;
+        Ok(self.input.position())
+    }
+
+    /// Fails if the variant of an enum has been peeked, since only an enum can consume it.
+    fn check_peeked(&self) -> Result<()> {
+        match self.peeked_variant {
+            Some(_) => Err(Error::PeekedVariant),
+            None => Ok(()),
+        }
+    }
+
+    /// Records a description of the data read since `start`, if annotating.
+    fn annotate(&mut self, start: u64, desc: impl FnOnce() -> String) {
+        if let Some(annotations) = &mut self.annotations {
+            annotations.push(Annotation { start, end: self.input.position(), desc: desc() });
+        }
+    }
+
+    /// Reads a fixed-width value of `N` bytes and returns its bytes in little-endian order.
+    fn read_fixed<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut bytes: [u8; N] = self.input.read(N)?.try_into().unwrap();
+        if CFG::endian() == Endian::Big {
+            bytes.reverse();
+        }
+        Ok(bytes)
+    }
+
+    /// Whether the encoding is compatible with postcard.
+    fn postcard() -> bool {
+        CFG::postcard_compat() && !CFG::with_idents()
+    }
+
+    /// Reads the length of a sequence or map, verifying it against the length bound.
+    fn read_len(&mut self, kind: &str) -> Result<Len> {
+        let bound = self.len_bound.take();
+        let start = self.mark()?;
+        let len = match self.read_varint_usize()? {
+            len if Self::postcard() => Len::Known(len),
+            SPECIAL_LEN => match self.read_varint_usize()? {
+                SPECIAL_LEN => Len::Known(SPECIAL_LEN),
+                UNKNOWN_LEN | TERMINATED_LEN if CFG::canonical() => return Err(Error::NonCanonical),
+                UNKNOWN_LEN | TERMINATED_LEN if CFG::forbid_unknown_len() => {
+                    return Err(Error::UnknownLengthForbidden);
+                }
+                UNKNOWN_LEN => {
+                    self.input.start_skippable();
+                    Len::Block
+                }
+                TERMINATED_LEN => Len::Terminated,
+                _ => return Err(Error::BadLen),
+            },
+            len => Len::Known(len),
+        };
+        if bound.is_some_and(|bound| len.known().is_some_and(|len| len > bound)) {
+            return Err(Error::BadLen);
+        }
+        self.annotate(start, || match len {
+            Len::Known(len) => format!("{kind} len={len}"),
+            Len::Block => format!("{kind} of unknown length"),
+            Len::Terminated => format!("{kind} of unknown length, terminated"),
+        });
+        Ok(len)
+    }
+
+    /// Reads the flag preceding an element of a sequence or map in terminated mode.
+    ///
+    /// Returns whether an element follows.
+    fn read_continuation(&mut self) -> Result<bool> {
+        let start = self.mark()?;
+        let more = match self.input.read_u8()? {
+            CONTINUE => true,
+            STOP => false,
+            _ => return Err(Error::BadLen),
+        };
+        self.annotate(start, || if more { "continue" } else { "stop" }.to_string());
+        Ok(more)
+    }
+
+    /// Reads the length of a string, byte array or identifier, verifying it against
+    /// the length bound and allocation limits.
+    fn read_alloc_len(&mut self, kind: LenKind) -> Result<usize> {
+        let bound = self.len_bound.take();
+        let len = self.read_varint_usize()?;
+        if bound.is_some_and(|bound| len > bound) {
+            return Err(Error::BadLen);
+        }
+        self.check_alloc_len(kind, len)?;
+        Ok(len)
+    }
+
+    /// Verifies the length in bytes of a string, byte array or identifier against the allocation limits.
+    fn check_alloc_len(&self, kind: LenKind, len: usize) -> Result<()> {
+        let max = match kind {
+            LenKind::String => self.limits.max_string,
+            LenKind::Bytes => self.limits.max_bytes,
+            LenKind::Identifier => None,
+        };
+        if self.limits.max_alloc.is_some_and(|max| len > max) || max.is_some_and(|max| len > max) {
+            return Err(Error::AllocLimitExceeded);
+        }
+        Ok(())
+    }
+
+    /// Reads a sequence of floats in bulk and provides their little-endian bytes to the visitor.
+    ///
+    /// A sequence of unknown length is provided element by element.
+    fn deserialize_f64_slice<V: Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value> {
+        self.enter()?;
+        let len = self.read_len("seq")?;
+
+        let value = match len {
+            Len::Known(len) => {
+                let size = len.checked_mul(8).ok_or(Error::UsizeOverflow)?;
+                if self.limits.max_alloc.is_some_and(|max| size > max) {
+                    return Err(Error::AllocLimitExceeded);
+                }
+
+                let start = self.mark()?;
+                let mut bytes = self.input.read(size)?;
+                for chunk in bytes.chunks_exact_mut(8) {
+                    if CFG::endian() == Endian::Big {
+                        chunk.reverse();
+                    }
+                    if CFG::canonical() {
+                        let v = f64::from_le_bytes(chunk.try_into().unwrap());
+                        if v.is_nan() && v.to_bits() != f64::NAN.to_bits() {
+                            return Err(Error::NonCanonical);
+                        }
+                    }
+                }
+                self.annotate(start, || format!("{len} f64"));
+                visitor.visit_byte_buf::<Error>(bytes)?
+            }
+            Len::Block | Len::Terminated => {
+                let value = visitor.visit_seq(SeqAccess { deserializer: self, len, empty_check: None })?;
+                if let Len::Block = len {
+                    self.input.end_skippable()?;
+                }
+                value
+            }
+        };
+
+        self.leave();
+        Ok(value)
+    }
+
+    /// Enters a nested value, verifying the depth limit.
+    fn enter(&mut self) -> Result<()> {
+        self.depth += 1;
+        if self.limits.max_depth.is_some_and(|max| self.depth > max) {
+            return Err(Error::DepthLimitExceeded);
+        }
+        Ok(())
+    }
+
+    /// Leaves a nested value.
+    fn leave(&mut self) {
+        self.depth -= 1;
+    }
+
+    /// Reads `sz` bytes into the scratch buffer, replacing its contents.
+    fn read_scratch(&mut self, sz: usize) -> Result<()> {
+        self.scratch.clear();
+        self.input.read_to(sz, &mut self.scratch)
+    }
+
+    /// Reads `len` bytes borrowed from the input, if deserializing from a slice.
+    ///
+    /// Returns `None` without reading the bytes if they cannot be borrowed, since they
+    /// are interrupted by the chunk framing of a skippable block.
+    fn read_borrowed(&mut self, len: usize) -> Result<Option<&'de [u8]>> {
+        let Some(input) = self.borrowed else { return Ok(None) };
+        let Some(start) = self.input.skip_contiguous(len)? else { return Ok(None) };
+        let start = start as usize;
+        Ok(Some(&input[start..start + len]))
+    }
+
+    /// Frees the scratch buffer if it has grown large.
+    fn release_scratch(&mut self) {
+        if self.scratch.capacity() > SCRATCH_MAX {
+            self.scratch = self.alloc.buffer();
+        }
+    }
+
+    /// Deserializes a tuple consisting of its elements only.
+    fn deserialize_plain_tuple<V: Visitor<'de>>(&mut self, len: usize, visitor: V) -> Result<V::Value> {
+        self.enter()?;
+        let value =
+            visitor.visit_seq(SeqAccess { deserializer: self, len: Len::Known(len), empty_check: None })?;
+        self.leave();
+        Ok(value)
+    }
+
+    /// Reads the bytes of a padded varint of at most `len` bytes, rejecting padding.
+    fn deserialize_canonical_padded<V: Visitor<'de>>(&mut self, len: usize, visitor: V) -> Result<V::Value> {
+        let start = self.mark()?;
+        let mut bytes = Vec::new();
+        while bytes.len() < len {
+            let byte = self.input.read_u8()?;
+            bytes.push(byte);
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        if bytes.len() > 1 && bytes.last() == Some(&0) {
+            return Err(Error::NonCanonical);
+        }
+        self.annotate(start, || format!("varint {bytes:02x?}"));
+        visitor.visit_seq(SeqDeserializer::<_, Error>::new(bytes.into_iter()))
+    }
+
+    /// Reads a string prefixed by its number of chars.
+    fn read_char_len_string(&mut self) -> Result<String> {
+        let count = self.read_alloc_len(LenKind::String)?;
+
+        let mut bytes = Vec::new();
+        for _ in 0..count {
+            let first = self.input.read_u8()?;
+            let width = char_len_string::utf8_width(first).ok_or(Error::BadString)?;
+            bytes.push(first);
+            self.input.read_to(width - 1, &mut bytes)?;
+        }
+
+        self.check_alloc_len(LenKind::String, bytes.len())?;
+        String::from_utf8(bytes).map_err(|_| Error::BadString)
+    }
+
+    fn read_variant(&mut self) -> Result<Variant> {
+        let named = mem::take(&mut self.named_variant);
+        if let Some(variant) = self.peeked_variant.take() {
+            return Ok(variant);
+        }
+
+        if CFG::with_idents() || named {
+            Ok(Variant::Name(self.read_identifier()?))
+        } else {
+            let start = self.mark()?;
+            let index = if CFG::fixed_variant_index() {
+                self.input.read_u8()?.into()
+            } else {
+                // An index beyond u32 is a valid varint, but not a valid discriminant.
+                u32::try_from(self.read_varint_u64()?).map_err(|_| Error::BadEnum)?
+            };
+            self.annotate(start, || format!("variant {index}"));
+            Ok(Variant::Index(index))
+        }
+    }
+
+    fn read_identifier(&mut self) -> Result<Arc<str>> {
+        let start = self.mark()?;
         let v = self.read_varint_usize()?;
 
         if v >= ID_LEN_NAME + ID_COUNT {
             return Err(Error::BadIdentifier);
         }
 
+        self.scratch.clear();
         if v >= ID_LEN_NAME {
             let id = v - ID_LEN_NAME;
-            return Ok(format!("_{id}"));
+            write!(self.scratch, "_{id}")?;
+        } else {
+            let len = if v == ID_LEN { self.read_alloc_len(LenKind::Identifier)? } else { v };
+            if CFG::canonical() && v == ID_LEN && len < ID_LEN {
+                return Err(Error::NonCanonical);
+            }
+            self.input.read_to(len, &mut self.scratch)?;
         }
 
-        let len = if v == ID_LEN { self.read_varint_usize()? } else { v };
+        let ident = str::from_utf8(&self.scratch).map_err(|_| Error::BadIdentifier)?;
+        if CFG::strict_identifiers()
+            && (ident.is_empty() || ident.chars().any(|c| c.is_control() || c.is_whitespace()))
+        {
+            return Err(Error::BadIdentifier);
+        }
+        if CFG::canonical() && v < ID_LEN_NAME && numeric_identifier(ident).is_some_and(|id| id < ID_COUNT) {
+            return Err(Error::NonCanonical);
+        }
+        if let Some(annotations) = &mut self.annotations {
+            let desc = format!("identifier {ident:?}");
+            annotations.push(Annotation { start, end: self.input.position(), desc });
+        }
+        if let Some(IdentifierHook(hook)) = &self.on_identifier {
+            (hook.lock().unwrap_or_else(PoisonError::into_inner))(ident);
+        }
+        match &mut self.interner {
+            Some(interner) => Ok(interner.intern(ident)),
+            None => Ok(ident.into()),
+        }
+    }
+}
 
-        let bytes = self.input.read(len)?;
-        String::from_utf8(bytes).map_err(|_| Error::BadIdentifier)
+/// Length of a sequence or map.
+#[derive(Debug, Clone, Copy)]
+enum Len {
+    /// Number of remaining elements.
+    Known(usize),
+    /// Unknown, elements are enclosed in a skippable block.
+    Block,
+    /// Unknown, each element is preceded by a continuation flag.
+    Terminated,
+}
+
+impl Len {
+    /// Number of remaining elements, if known.
+    fn known(self) -> Option<usize> {
+        match self {
+            Len::Known(len) => Some(len),
+            Len::Block | Len::Terminated => None,
+        }
+    }
+}
+
+/// Detects sequences and maps with a large number of elements consuming no input.
+///
+/// Elements of zero-sized types are deserialized without reading any input,
+/// thus a bogus length would make the deserializer loop without reaching the
+/// end of the input. If the first [`EMPTY_ELEMENTS_MAX`] elements consume no
+/// input, the length is rejected.
+struct EmptyCheck {
+    start: u64,
+    count: usize,
+}
+
+impl EmptyCheck {
+    /// Starts checking a sequence or map of the specified length, if it exceeds the limit.
+    fn new(len: Option<usize>, position: impl FnOnce() -> u64) -> Option<Self> {
+        len.is_some_and(|len| len > EMPTY_ELEMENTS_MAX).then(|| Self { start: position(), count: 0 })
+    }
+
+    /// Verifies that input has been consumed after the limit of elements has been read.
+    fn element(check: &mut Option<Self>, position: impl FnOnce() -> u64) -> Result<()> {
+        if let Some(this) = check {
+            this.count += 1;
+            if this.count == EMPTY_ELEMENTS_MAX {
+                if position() == this.start {
+                    return Err(Error::BadLen);
+                }
+                *check = None;
+            }
+        }
+        Ok(())
     }
 }
 
-struct SeqAccess<'a, 'b, R, CFG> {
-    deserializer: &'a mut Deserializer<'b, R, CFG>,
-    len: Option<usize>,
+struct SeqAccess<'a, 'b, R, CFG, A: BufferAlloc> {
+    deserializer: &'a mut Deserializer<'b, R, CFG, A>,
+    len: Len,
+    empty_check: Option<EmptyCheck>,
 }
 
-impl<'a, 'b: 'a, R: Read, CFG: Cfg> serde::de::SeqAccess<'b> for SeqAccess<'a, 'b, R, CFG> {
+impl<'a, 'b: 'a, R: Read, CFG: Cfg, A: BufferAlloc> serde::de::SeqAccess<'b> for SeqAccess<'a, 'b, R, CFG, A> {
     type Error = Error;
 
     #[inline(never)]
     fn next_element_seed<V: DeserializeSeed<'b>>(&mut self, seed: V) -> Result<Option<V::Value>> {
         match &mut self.len {
-            Some(0) => Ok(None),
-            Some(len) => {
+            Len::Known(0) => Ok(None),
+            Len::Known(len) => {
                 *len -= 1;
                 let data = DeserializeSeed::deserialize(seed, &mut *self.deserializer)?;
+                EmptyCheck::element(&mut self.empty_check, || self.deserializer.input.position())?;
                 Ok(Some(data))
             }
-            None => match DeserializeSeed::deserialize(seed, &mut *self.deserializer) {
-                Ok(data) => Ok(Some(data)),
-                Err(Error::EndOfBlock) => Ok(None),
-                Err(err) => Err(err),
-            },
+            Len::Block if self.deserializer.input.at_block_end()? => Ok(None),
+            Len::Terminated if !self.deserializer.read_continuation()? => {
+                self.len = Len::Known(0);
+                Ok(None)
+            }
+            Len::Block | Len::Terminated => {
+                Ok(Some(DeserializeSeed::deserialize(seed, &mut *self.deserializer)?))
+            }
         }
     }
 
+    /// Declared length, limited by the number of elements the remaining input
+    /// can hold if it is known, so that a bogus length cannot cause over-reservation.
     fn size_hint(&self) -> Option<usize> {
-        self.len
+        let len = self.len.known()?;
+        match self.deserializer.input.remaining() {
+            Some(remaining) => Some(len.min(usize::try_from(remaining / MIN_ELEMENT_SIZE).unwrap_or(usize::MAX))),
+            None => Some(len),
+        }
     }
 }
 
-struct StructSeqAccess<'a, 'b, R, CFG> {
-    deserializer: &'a mut Deserializer<'b, R, CFG>,
+/// SeqAccess for struct fields in Slim mode and for tuples enclosed in a skippable block.
+///
+/// Provides at most as many fields as were serialized. Fields missing from the
+/// end receive their serde defaults, while fields not read by the visitor remain
+/// in the struct's skippable block and are skipped when the block is ended.
+struct StructSeqAccess<'a, 'b, R, CFG, A: BufferAlloc> {
+    deserializer: &'a mut Deserializer<'b, R, CFG, A>,
+    /// Number of serialized fields not yet read.
     len: usize,
 }
 
-impl<'a, 'b: 'a, R: Read, CFG: Cfg> serde::de::SeqAccess<'b> for StructSeqAccess<'a, 'b, R, CFG> {
+impl<'a, 'b: 'a, R: Read, CFG: Cfg, A: BufferAlloc> serde::de::SeqAccess<'b>
+    for StructSeqAccess<'a, 'b, R, CFG, A>
+{
     type Error = Error;
 
     #[inline(never)]
     fn next_element_seed<V: DeserializeSeed<'b>>(&mut self, seed: V) -> Result<Option<V::Value>> {
-        assert!(!CFG::with_idents());
-
         if self.len > 0 {
             self.len -= 1;
             let data = DeserializeSeed::deserialize(seed, &mut *self.deserializer)?;
             Ok(Some(data))
+        } else if CFG::canonical() {
+            Err(Error::NonCanonical)
         } else {
             Ok(None)
         }
@@ -192,19 +1012,39 @@ impl<'a, 'b: 'a, R: Read, CFG: Cfg> serde::de::SeqAccess<'b> for StructSeqAccess
 ///
 /// Reads field identifiers and values directly from the wire without
 /// buffering, using skippable blocks for forward compatibility.
-struct StructFieldAccess<'a, 'b, R, CFG> {
-    deserializer: &'a mut Deserializer<'b, R, CFG>,
+struct StructFieldAccess<'a, 'b, R, CFG, A: BufferAlloc> {
+    deserializer: &'a mut Deserializer<'b, R, CFG, A>,
+    fields: &'static [&'static str],
     len: usize,
+    /// Index of the previous field, for verifying field order.
+    last_field: Option<usize>,
+    /// Identifier of the current field, if it is unknown and collected.
+    unknown: Option<Arc<str>>,
 }
 
-impl<'a, 'b: 'a, R: Read, CFG: Cfg> serde::de::MapAccess<'b> for StructFieldAccess<'a, 'b, R, CFG> {
+impl<'a, 'b: 'a, R: Read, CFG: Cfg, A: BufferAlloc> serde::de::MapAccess<'b>
+    for StructFieldAccess<'a, 'b, R, CFG, A>
+{
     type Error = Error;
 
     #[inline(never)]
     fn next_key_seed<K: DeserializeSeed<'b>>(&mut self, seed: K) -> Result<Option<K::Value>> {
         if self.len > 0 {
             self.len -= 1;
-            let value = DeserializeSeed::deserialize(seed, &mut *self.deserializer)?;
+            let collect = self.deserializer.collecting_unknown_fields();
+            let value = if CFG::canonical() || CFG::strict_field_order() || collect {
+                let ident = self.deserializer.read_identifier()?;
+                if CFG::canonical() || CFG::strict_field_order() {
+                    self.last_field = Some(ordered_field_index::<CFG>(self.fields, &ident, self.last_field)?);
+                }
+                if collect && !self.fields.contains(&&*ident) {
+                    self.unknown = Some(ident.clone());
+                }
+                let deserializer: StrDeserializer<Error> = (*ident).into_deserializer();
+                DeserializeSeed::deserialize(seed, deserializer)?
+            } else {
+                DeserializeSeed::deserialize(seed, &mut *self.deserializer)?
+            };
             Ok(Some(value))
         } else {
             Ok(None)
@@ -215,6 +1055,12 @@ impl<'a, 'b: 'a, R: Read, CFG: Cfg> serde::de::MapAccess<'b> for StructFieldAcce
     fn next_value_seed<V: DeserializeSeed<'b>>(&mut self, seed: V) -> Result<V::Value> {
         assert!(CFG::with_idents());
 
+        if let Some(ident) = self.unknown.take() {
+            let data = self.deserializer.input.read_skippable_block()?;
+            self.deserializer.unknown_fields.as_mut().unwrap().push((ident.to_string(), data));
+            return DeserializeSeed::deserialize(seed, UnitDeserializer::new());
+        }
+
         self.deserializer.input.start_skippable();
         let value = DeserializeSeed::deserialize(seed, &mut *self.deserializer)?;
         self.deserializer.input.end_skippable()?;
@@ -227,6 +1073,73 @@ impl<'a, 'b: 'a, R: Read, CFG: Cfg> serde::de::MapAccess<'b> for StructFieldAcce
     }
 }
 
+/// MapAccess for struct fields preceded by their position in Slim mode.
+///
+/// Fields of unknown positions are passed to the visitor by their position,
+/// which derived implementations ignore, and their values are skipped.
+struct PositionalFieldAccess<'a, 'b, R, CFG, A: BufferAlloc> {
+    deserializer: &'a mut Deserializer<'b, R, CFG, A>,
+    len: usize,
+    /// Position of the previous field, for verifying field order in canonical mode.
+    last_position: Option<usize>,
+}
+
+impl<'a, 'b: 'a, R: Read, CFG: Cfg, A: BufferAlloc> serde::de::MapAccess<'b>
+    for PositionalFieldAccess<'a, 'b, R, CFG, A>
+{
+    type Error = Error;
+
+    #[inline(never)]
+    fn next_key_seed<K: DeserializeSeed<'b>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        if self.len == 0 {
+            return Ok(None);
+        }
+        self.len -= 1;
+
+        let start = self.deserializer.mark()?;
+        let position = self.deserializer.read_varint_usize()?;
+        self.deserializer.annotate(start, || format!("field position {position}"));
+        if CFG::canonical() {
+            if self.last_position.is_some_and(|last| position <= last) {
+                return Err(Error::NonCanonical);
+            }
+            self.last_position = Some(position);
+        }
+
+        // Serde's field names include aliases, thus the position is passed as an index,
+        // which the derived field visitor resolves among the actual fields only.
+        let value = DeserializeSeed::deserialize(seed, U64Deserializer::<Error>::new(position as u64))?;
+        Ok(Some(value))
+    }
+
+    #[inline(never)]
+    fn next_value_seed<V: DeserializeSeed<'b>>(&mut self, seed: V) -> Result<V::Value> {
+        self.deserializer.ignored = false;
+        self.deserializer.input.start_skippable();
+        let value = DeserializeSeed::deserialize(seed, &mut *self.deserializer)?;
+        if CFG::canonical() && self.deserializer.ignored {
+            // Field of unknown position.
+            return Err(Error::NonCanonical);
+        }
+        self.deserializer.input.end_skippable()?;
+        Ok(value)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
+/// Returns the index of a struct field, verifying that it is known
+/// and follows the previous field in declaration order.
+fn ordered_field_index<CFG: Cfg>(fields: &[&str], ident: &str, last_field: Option<usize>) -> Result<usize> {
+    match fields.iter().position(|field| *field == ident) {
+        Some(idx) if last_field.is_none_or(|last| idx > last) => Ok(idx),
+        _ if CFG::canonical() => Err(Error::NonCanonical),
+        _ => Err(Error::FieldOrderMismatch),
+    }
+}
+
 /// SeqAccess that provides pre-buffered field data in the expected order.
 ///
 /// This allows using `visit_seq` instead of `visit_map` for struct
@@ -234,13 +1147,19 @@ impl<'a, 'b: 'a, R: Read, CFG: Cfg> serde::de::MapAccess<'b> for StructFieldAcce
 /// monomorphized code at the cost of buffering all field data in memory.
 ///
 /// Activate with `RUSTFLAGS="--cfg postbag_fast_compile"`.
-struct BufferedFieldSeqAccess<'de, CFG> {
+struct BufferedFieldSeqAccess<'a, 'de, CFG> {
     field_data: Vec<Option<Vec<u8>>>,
     index: usize,
+    limits: Limits,
+    depth: usize,
+    variant_names: Arc<HashMap<&'static str, &'static [&'static str]>>,
+    on_identifier: Option<IdentifierHook>,
+    /// Number of unknown enum variants read by the parent deserializer.
+    unknown_variants: &'a mut usize,
     _phantom: PhantomData<(&'de (), CFG)>,
 }
 
-impl<'de, CFG: Cfg> BufferedFieldSeqAccess<'de, CFG> {
+impl<'a, 'de, CFG: Cfg> BufferedFieldSeqAccess<'a, 'de, CFG> {
     /// Reads all wire fields from the deserializer and reorders them to
     /// match the expected field declaration order. Unknown fields are
     /// silently dropped (forward compatibility).
@@ -249,8 +1168,8 @@ impl<'de, CFG: Cfg> BufferedFieldSeqAccess<'de, CFG> {
     /// so that it is monomorphized only once per (R, CFG) pair, avoiding
     /// code duplication across the many `deserialize_struct` instantiations.
     #[inline(never)]
-    fn new<R: Read>(
-        deser: &mut Deserializer<'_, R, CFG>, fields: &'static [&'static str], len: usize,
+    fn new<R: Read, A: BufferAlloc>(
+        deser: &'a mut Deserializer<'_, R, CFG, A>, fields: &'static [&'static str], len: usize,
     ) -> Result<Self> {
         // Build index: field name -> position in expected order.
         let field_index: HashMap<&'static str, usize> =
@@ -258,20 +1177,35 @@ impl<'de, CFG: Cfg> BufferedFieldSeqAccess<'de, CFG> {
 
         // Read wire fields and place directly into the right slot.
         let mut field_data: Vec<Option<Vec<u8>>> = vec![None; fields.len()];
+        let mut last_field = None;
         for _ in 0..len {
             let ident = deser.read_identifier()?;
+            if CFG::canonical() || CFG::strict_field_order() {
+                last_field = Some(ordered_field_index::<CFG>(fields, &ident, last_field)?);
+            }
             let raw = deser.input.read_skippable_block()?;
-            if let Some(&idx) = field_index.get(ident.as_str()) {
+            if let Some(&idx) = field_index.get(&*ident) {
                 field_data[idx] = Some(raw);
+            } else if deser.collecting_unknown_fields() {
+                deser.unknown_fields.as_mut().unwrap().push((ident.to_string(), raw));
             }
-            // Unknown fields (forward compat) are silently dropped.
+            // Other unknown fields (forward compat) are silently dropped.
         }
 
-        Ok(Self { field_data, index: 0, _phantom: PhantomData })
+        Ok(Self {
+            field_data,
+            index: 0,
+            limits: deser.limits.clone(),
+            depth: deser.depth,
+            variant_names: deser.variant_names.clone(),
+            on_identifier: deser.on_identifier.clone(),
+            unknown_variants: &mut deser.unknown_variants,
+            _phantom: PhantomData,
+        })
     }
 }
 
-impl<'de, CFG: Cfg> serde::de::SeqAccess<'de> for BufferedFieldSeqAccess<'de, CFG> {
+impl<'de, CFG: Cfg> serde::de::SeqAccess<'de> for BufferedFieldSeqAccess<'_, 'de, CFG> {
     type Error = Error;
 
     #[inline(never)]
@@ -287,8 +1221,17 @@ impl<'de, CFG: Cfg> serde::de::SeqAccess<'de> for BufferedFieldSeqAccess<'de, CF
             self.index += 1;
 
             if let Some(raw) = self.field_data[idx].take() {
-                let mut deser = Deserializer::<&[u8], CFG>::new(raw.as_slice());
-                let value = DeserializeSeed::deserialize(seed, &mut deser)?;
+                let mut deser = Deserializer::<&[u8], CFG>::with_remaining(raw.as_slice(), raw.len() as u64);
+                deser.limits = self.limits.clone();
+                deser.depth = self.depth;
+                deser.variant_names = self.variant_names.clone();
+                deser.on_identifier = self.on_identifier.clone();
+                let value = DeserializeSeed::deserialize(seed, &mut deser);
+                *self.unknown_variants += deser.unknown_variants;
+                let value = value?;
+                if CFG::canonical() && deser.position() != raw.len() as u64 {
+                    return Err(Error::NonCanonical);
+                }
                 return Ok(Some(value));
             }
         }
@@ -302,42 +1245,151 @@ impl<'de, CFG: Cfg> serde::de::SeqAccess<'de> for BufferedFieldSeqAccess<'de, CF
     }
 }
 
-struct MapAccess<'a, 'b, R, CFG> {
-    deserializer: &'a mut Deserializer<'b, R, CFG>,
-    len: Option<usize>,
+/// Provides a value followed by the bytes it was deserialized from.
+struct RawAccess<'a, 'b, R, CFG, A: BufferAlloc> {
+    deserializer: &'a mut Deserializer<'b, R, CFG, A>,
+    state: RawState<A::Buf>,
+}
+
+enum RawState<B> {
+    /// The value is next.
+    Value,
+    /// The captured bytes of the value are next.
+    Bytes(B),
+    /// All elements have been provided.
+    Done,
 }
 
-impl<'a, 'b: 'a, R: Read, CFG: Cfg> serde::de::MapAccess<'b> for MapAccess<'a, 'b, R, CFG> {
+impl<'a, 'b: 'a, R: Read, CFG: Cfg, A: BufferAlloc> serde::de::SeqAccess<'b> for RawAccess<'a, 'b, R, CFG, A> {
+    type Error = Error;
+
+    #[inline(never)]
+    fn next_element_seed<V: DeserializeSeed<'b>>(&mut self, seed: V) -> Result<Option<V::Value>> {
+        match mem::replace(&mut self.state, RawState::Done) {
+            RawState::Value => {
+                let start = self.deserializer.input.start_capture();
+                let res = DeserializeSeed::deserialize(seed, &mut *self.deserializer);
+                self.state = RawState::Bytes(self.deserializer.input.end_capture(start));
+                Ok(Some(res?))
+            }
+            RawState::Bytes(bytes) => {
+                let deserializer = BytesDeserializer::<Error>::new(&bytes);
+                Ok(Some(DeserializeSeed::deserialize(seed, deserializer)?))
+            }
+            RawState::Done => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        match self.state {
+            RawState::Value => Some(2),
+            RawState::Bytes(_) => Some(1),
+            RawState::Done => Some(0),
+        }
+    }
+}
+
+/// Provides an already read and verified tag byte followed by the tagged value.
+///
+/// Used for the width of checked fixed-size integers and the version of versioned newtypes.
+struct TagAccess<'a, 'b, R, CFG, A: BufferAlloc> {
+    deserializer: &'a mut Deserializer<'b, R, CFG, A>,
+    tag: Option<u8>,
+    done: bool,
+}
+
+impl<'a, 'b: 'a, R: Read, CFG: Cfg, A: BufferAlloc> serde::de::SeqAccess<'b> for TagAccess<'a, 'b, R, CFG, A> {
+    type Error = Error;
+
+    fn next_element_seed<V: DeserializeSeed<'b>>(&mut self, seed: V) -> Result<Option<V::Value>> {
+        if let Some(tag) = self.tag.take() {
+            let deserializer: U8Deserializer<Error> = tag.into_deserializer();
+            return Ok(Some(DeserializeSeed::deserialize(seed, deserializer)?));
+        }
+
+        if mem::replace(&mut self.done, true) {
+            return Ok(None);
+        }
+        Ok(Some(DeserializeSeed::deserialize(seed, &mut *self.deserializer)?))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(usize::from(self.tag.is_some()) + usize::from(!self.done))
+    }
+}
+
+struct MapAccess<'a, 'b, R, CFG, A: BufferAlloc> {
+    deserializer: &'a mut Deserializer<'b, R, CFG, A>,
+    len: Len,
+    empty_check: Option<EmptyCheck>,
+    /// Serialized previous key, for verifying key order in canonical mode.
+    last_key: Option<A::Buf>,
+}
+
+impl<'a, 'b: 'a, R: Read, CFG: Cfg, A: BufferAlloc> MapAccess<'a, 'b, R, CFG, A> {
+    /// Deserializes a key and verifies that it is greater than the previous key.
+    fn deserialize_canonical_key<K: DeserializeSeed<'b>>(&mut self, seed: K) -> Result<K::Value> {
+        let start = self.deserializer.input.start_capture();
+        let res = DeserializeSeed::deserialize(seed, &mut *self.deserializer);
+        let key = self.deserializer.input.end_capture(start);
+        let data = res?;
+
+        if self.last_key.as_ref().is_some_and(|last| **last >= *key) {
+            return Err(Error::NonCanonical);
+        }
+        self.last_key = Some(key);
+
+        Ok(data)
+    }
+}
+
+impl<'a, 'b: 'a, R: Read, CFG: Cfg, A: BufferAlloc> serde::de::MapAccess<'b> for MapAccess<'a, 'b, R, CFG, A> {
     type Error = Error;
 
     #[inline(never)]
     fn next_key_seed<K: DeserializeSeed<'b>>(&mut self, seed: K) -> Result<Option<K::Value>> {
         match &mut self.len {
-            Some(0) => Ok(None),
-            Some(len) => {
+            Len::Known(0) => Ok(None),
+            Len::Known(len) => {
                 *len -= 1;
-                let data = DeserializeSeed::deserialize(seed, &mut *self.deserializer)?;
+                let data = if CFG::canonical() {
+                    self.deserialize_canonical_key(seed)?
+                } else {
+                    DeserializeSeed::deserialize(seed, &mut *self.deserializer)?
+                };
                 Ok(Some(data))
             }
-            None => match DeserializeSeed::deserialize(seed, &mut *self.deserializer) {
-                Ok(data) => Ok(Some(data)),
-                Err(Error::EndOfBlock) => Ok(None),
-                Err(err) => Err(err),
-            },
+            Len::Block if self.deserializer.input.at_block_end()? => Ok(None),
+            Len::Terminated if !self.deserializer.read_continuation()? => {
+                self.len = Len::Known(0);
+                Ok(None)
+            }
+            Len::Block | Len::Terminated => {
+                Ok(Some(DeserializeSeed::deserialize(seed, &mut *self.deserializer)?))
+            }
         }
     }
 
     #[inline(never)]
     fn next_value_seed<V: DeserializeSeed<'b>>(&mut self, seed: V) -> Result<V::Value> {
-        DeserializeSeed::deserialize(seed, &mut *self.deserializer)
+        let data = DeserializeSeed::deserialize(seed, &mut *self.deserializer)?;
+        EmptyCheck::element(&mut self.empty_check, || self.deserializer.input.position())?;
+        Ok(data)
     }
 
+    /// Declared length, limited by the number of entries the remaining input can hold
+    /// if it is known and by a fixed maximum otherwise, so that a bogus length cannot
+    /// cause over-reservation.
     fn size_hint(&self) -> Option<usize> {
-        self.len
+        let len = self.len.known()?;
+        match self.deserializer.input.remaining() {
+            Some(remaining) => Some(len.min(usize::try_from(remaining / MIN_ELEMENT_SIZE).unwrap_or(usize::MAX))),
+            None => Some(len.min(MAP_PREALLOC_MAX)),
+        }
     }
 }
 
-impl<'de, R: Read, CFG: Cfg> de::Deserializer<'de> for &mut Deserializer<'de, R, CFG> {
+impl<'de, R: Read, CFG: Cfg, A: BufferAlloc> de::Deserializer<'de> for &mut Deserializer<'de, R, CFG, A> {
     type Error = Error;
 
     fn is_human_readable(&self) -> bool {
@@ -355,11 +1407,15 @@ impl<'de, R: Read, CFG: Cfg> de::Deserializer<'de> for &mut Deserializer<'de, R,
     where
         V: Visitor<'de>,
     {
+        self.check_peeked()?;
+        let start = self.mark()?;
         let val = match self.input.read_u8()? {
             FALSE => false,
             TRUE => true,
+            _ if CFG::lenient_bool() && !CFG::canonical() => true,
             _ => return Err(Error::BadBool),
         };
+        self.annotate(start, || format!("bool {val}"));
         visitor.visit_bool(val)
     }
 
@@ -367,53 +1423,76 @@ impl<'de, R: Read, CFG: Cfg> de::Deserializer<'de> for &mut Deserializer<'de, R,
     where
         V: Visitor<'de>,
     {
-        visitor.visit_i8(self.input.read_u8()? as i8)
+        self.check_peeked()?;
+        let start = self.mark()?;
+        let v = self.input.read_u8()? as i8;
+        self.annotate(start, || format!("i8 {v}"));
+        visitor.visit_i8(v)
     }
 
     fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        let v = self.read_varint_u16()?;
-        visitor.visit_i16(de_zig_zag_i16(v))
+        self.check_peeked()?;
+        let start = self.mark()?;
+        let v = de_zig_zag_i16(self.read_varint_u16()?);
+        self.annotate(start, || format!("i16 {v}"));
+        visitor.visit_i16(v)
     }
 
     fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        let v = self.read_varint_u32()?;
-        visitor.visit_i32(de_zig_zag_i32(v))
+        self.check_peeked()?;
+        let start = self.mark()?;
+        let v = de_zig_zag_i32(self.read_varint_u32()?);
+        self.annotate(start, || format!("i32 {v}"));
+        visitor.visit_i32(v)
     }
 
     fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        let v = self.read_varint_u64()?;
-        visitor.visit_i64(de_zig_zag_i64(v))
+        self.check_peeked()?;
+        let start = self.mark()?;
+        let v = de_zig_zag_i64(self.read_varint_u64()?);
+        self.annotate(start, || format!("i64 {v}"));
+        visitor.visit_i64(v)
     }
 
     fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        let v = self.read_varint_u128()?;
-        visitor.visit_i128(de_zig_zag_i128(v))
+        self.check_peeked()?;
+        let start = self.mark()?;
+        let v = de_zig_zag_i128(self.read_varint_u128()?);
+        self.annotate(start, || format!("i128 {v}"));
+        visitor.visit_i128(v)
     }
 
     fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u8(self.input.read_u8()?)
+        self.check_peeked()?;
+        let start = self.mark()?;
+        let v = self.input.read_u8()?;
+        self.annotate(start, || format!("u8 {v}"));
+        visitor.visit_u8(v)
     }
 
     fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
+        self.check_peeked()?;
+        let start = self.mark()?;
         let v = self.read_varint_u16()?;
+        self.annotate(start, || format!("u16 {v}"));
         visitor.visit_u16(v)
     }
 
@@ -421,7 +1500,10 @@ impl<'de, R: Read, CFG: Cfg> de::Deserializer<'de> for &mut Deserializer<'de, R,
     where
         V: Visitor<'de>,
     {
+        self.check_peeked()?;
+        let start = self.mark()?;
         let v = self.read_varint_u32()?;
+        self.annotate(start, || format!("u32 {v}"));
         visitor.visit_u32(v)
     }
 
@@ -429,7 +1511,10 @@ impl<'de, R: Read, CFG: Cfg> de::Deserializer<'de> for &mut Deserializer<'de, R,
     where
         V: Visitor<'de>,
     {
+        self.check_peeked()?;
+        let start = self.mark()?;
         let v = self.read_varint_u64()?;
+        self.annotate(start, || format!("u64 {v}"));
         visitor.visit_u64(v)
     }
 
@@ -437,7 +1522,10 @@ impl<'de, R: Read, CFG: Cfg> de::Deserializer<'de> for &mut Deserializer<'de, R,
     where
         V: Visitor<'de>,
     {
+        self.check_peeked()?;
+        let start = self.mark()?;
         let v = self.read_varint_u128()?;
+        self.annotate(start, || format!("u128 {v}"));
         visitor.visit_u128(v)
     }
 
@@ -445,22 +1533,36 @@ impl<'de, R: Read, CFG: Cfg> de::Deserializer<'de> for &mut Deserializer<'de, R,
     where
         V: Visitor<'de>,
     {
-        let bytes = self.input.read(4)?;
-        visitor.visit_f32(f32::from_bits(u32::from_le_bytes(bytes.try_into().unwrap())))
+        self.check_peeked()?;
+        let start = self.mark()?;
+        let v = f32::from_bits(u32::from_le_bytes(self.read_fixed()?));
+        if CFG::canonical() && v.is_nan() && v.to_bits() != f32::NAN.to_bits() {
+            return Err(Error::NonCanonical);
+        }
+        self.annotate(start, || format!("f32 {v}"));
+        visitor.visit_f32(v)
     }
 
     fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        let bytes = self.input.read(8)?;
-        visitor.visit_f64(f64::from_bits(u64::from_le_bytes(bytes.try_into().unwrap())))
+        self.check_peeked()?;
+        let start = self.mark()?;
+        let v = f64::from_bits(u64::from_le_bytes(self.read_fixed()?));
+        if CFG::canonical() && v.is_nan() && v.to_bits() != f64::NAN.to_bits() {
+            return Err(Error::NonCanonical);
+        }
+        self.annotate(start, || format!("f64 {v}"));
+        visitor.visit_f64(v)
     }
 
     fn deserialize_char<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
+        self.check_peeked()?;
+        let start = self.mark()?;
         let sz = self.read_varint_usize()?;
         if sz > 4 {
             return Err(Error::BadChar);
@@ -469,6 +1571,10 @@ impl<'de, R: Read, CFG: Cfg> de::Deserializer<'de> for &mut Deserializer<'de, R,
 
         let character =
             str::from_utf8(&bytes).map_err(|_| Error::BadChar)?.chars().next().ok_or(Error::BadChar)?;
+        if CFG::canonical() && character.len_utf8() != sz {
+            return Err(Error::NonCanonical);
+        }
+        self.annotate(start, || format!("char {character:?}"));
         visitor.visit_char(character)
     }
 
@@ -476,16 +1582,35 @@ impl<'de, R: Read, CFG: Cfg> de::Deserializer<'de> for &mut Deserializer<'de, R,
     where
         V: Visitor<'de>,
     {
-        self.deserialize_string(visitor)
+        self.check_peeked()?;
+        let start = self.mark()?;
+        let sz = self.read_alloc_len(LenKind::String)?;
+        if let Some(bytes) = self.read_borrowed(sz)? {
+            let v = str::from_utf8(bytes).map_err(|_| Error::BadString)?;
+            self.annotate(start, || format!("string {v:?}"));
+            return visitor.visit_borrowed_str(v);
+        }
+
+        self.read_scratch(sz)?;
+        let v = str::from_utf8(&self.scratch).map_err(|_| Error::BadString)?;
+        if let Some(annotations) = &mut self.annotations {
+            annotations.push(Annotation { start, end: self.input.position(), desc: format!("string {v:?}") });
+        }
+        let value = visitor.visit_str(v);
+        self.release_scratch();
+        value
     }
 
     fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        let sz = self.read_varint_usize()?;
+        self.check_peeked()?;
+        let start = self.mark()?;
+        let sz = self.read_alloc_len(LenKind::String)?;
         let bytes = self.input.read(sz)?;
         let str_sl = String::from_utf8(bytes).map_err(|_| Error::BadString)?;
+        self.annotate(start, || format!("string {str_sl:?}"));
 
         visitor.visit_string(str_sl)
     }
@@ -494,15 +1619,30 @@ impl<'de, R: Read, CFG: Cfg> de::Deserializer<'de> for &mut Deserializer<'de, R,
     where
         V: Visitor<'de>,
     {
-        self.deserialize_byte_buf(visitor)
+        self.check_peeked()?;
+        let start = self.mark()?;
+        let sz = self.read_alloc_len(LenKind::Bytes)?;
+        if let Some(bytes) = self.read_borrowed(sz)? {
+            self.annotate(start, || format!("bytes len={sz}"));
+            return visitor.visit_borrowed_bytes(bytes);
+        }
+
+        self.read_scratch(sz)?;
+        self.annotate(start, || format!("bytes len={sz}"));
+        let value = visitor.visit_bytes(&self.scratch);
+        self.release_scratch();
+        value
     }
 
     fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        let sz = self.read_varint_usize()?;
+        self.check_peeked()?;
+        let start = self.mark()?;
+        let sz = self.read_alloc_len(LenKind::Bytes)?;
         let bytes = self.input.read(sz)?;
+        self.annotate(start, || format!("bytes len={sz}"));
         visitor.visit_byte_buf(bytes)
     }
 
@@ -510,9 +1650,24 @@ impl<'de, R: Read, CFG: Cfg> de::Deserializer<'de> for &mut Deserializer<'de, R,
     where
         V: Visitor<'de>,
     {
+        self.check_peeked()?;
+        let start = self.mark()?;
         match self.input.read_u8()? {
-            NONE => visitor.visit_none(),
-            SOME => visitor.visit_some(self),
+            NONE => {
+                self.annotate(start, || "option none".to_string());
+                visitor.visit_none()
+            }
+            SOME => {
+                self.annotate(start, || "option some".to_string());
+                if CFG::skippable_options() {
+                    self.input.start_skippable();
+                    let value = visitor.visit_some(&mut *self)?;
+                    self.input.end_skippable()?;
+                    Ok(value)
+                } else {
+                    visitor.visit_some(self)
+                }
+            }
             _ => Err(Error::BadOption),
         }
     }
@@ -521,6 +1676,7 @@ impl<'de, R: Read, CFG: Cfg> de::Deserializer<'de> for &mut Deserializer<'de, R,
     where
         V: Visitor<'de>,
     {
+        self.check_peeked()?;
         visitor.visit_unit()
     }
 
@@ -531,10 +1687,55 @@ impl<'de, R: Read, CFG: Cfg> de::Deserializer<'de> for &mut Deserializer<'de, R,
         self.deserialize_unit(visitor)
     }
 
-    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
+        if name == fixchar::NAME {
+            self.check_peeked()?;
+            let start = self.mark()?;
+            let c = char::from_u32(u32::from_le_bytes(self.read_fixed()?)).ok_or(Error::BadChar)?;
+            self.annotate(start, || format!("char {c:?}"));
+            return visitor.visit_char(c);
+        }
+
+        if name == char_len_string::NAME {
+            self.check_peeked()?;
+            let start = self.mark()?;
+            let s = self.read_char_len_string()?;
+            self.annotate(start, || format!("string {s:?}"));
+            return visitor.visit_string(s);
+        }
+
+        if name == raw::NAME {
+            self.check_peeked()?;
+            return visitor.visit_seq(RawAccess { deserializer: self, state: RawState::Value });
+        }
+
+        if name == le_f64_slice::NAME {
+            self.check_peeked()?;
+            return self.deserialize_f64_slice(visitor);
+        }
+
+        if name == fixint::NAME {
+            if CFG::endian() == Endian::Big {
+                self.reversed_fixint = true;
+            } else {
+                self.plain_tuple = true;
+            }
+            let res = visitor.visit_newtype_struct(&mut *self);
+            self.reversed_fixint = false;
+            self.plain_tuple = false;
+            return res;
+        }
+
+        if name == named_variant::NAME {
+            self.named_variant = true;
+            let res = visitor.visit_newtype_struct(&mut *self);
+            self.named_variant = false;
+            return res;
+        }
+
         visitor.visit_newtype_struct(self)
     }
 
@@ -542,24 +1743,18 @@ impl<'de, R: Read, CFG: Cfg> de::Deserializer<'de> for &mut Deserializer<'de, R,
     where
         V: Visitor<'de>,
     {
-        let len = match self.read_varint_usize()? {
-            SPECIAL_LEN => match self.read_varint_usize()? {
-                SPECIAL_LEN => Some(SPECIAL_LEN),
-                UNKNOWN_LEN => {
-                    self.input.start_skippable();
-                    None
-                }
-                _ => return Err(Error::BadLen),
-            },
-            len => Some(len),
-        };
+        self.check_peeked()?;
+        self.enter()?;
+        let len = self.read_len("seq")?;
 
-        let value = visitor.visit_seq(SeqAccess { deserializer: self, len })?;
+        let empty_check = EmptyCheck::new(len.known(), || self.input.position());
+        let value = visitor.visit_seq(SeqAccess { deserializer: self, len, empty_check })?;
 
-        if len.is_none() {
+        if let Len::Block = len {
             self.input.end_skippable()?;
         }
 
+        self.leave();
         Ok(value)
     }
 
@@ -567,13 +1762,75 @@ impl<'de, R: Read, CFG: Cfg> de::Deserializer<'de> for &mut Deserializer<'de, R,
     where
         V: Visitor<'de>,
     {
-        visitor.visit_seq(SeqAccess { deserializer: self, len: Some(len) })
+        self.check_peeked()?;
+        if mem::take(&mut self.reversed_fixint) {
+            let start = self.mark()?;
+            let mut bytes = self.input.read(len)?;
+            self.annotate(start, || format!("fixint {bytes:02x?}"));
+            bytes.reverse();
+            return visitor.visit_seq(SeqDeserializer::<_, Error>::new(bytes.into_iter()));
+        }
+
+        let plain = mem::take(&mut self.plain_tuple);
+        if CFG::skippable_tuples() && !plain {
+            self.enter()?;
+            let start = self.mark()?;
+            let len = self.read_varint_usize()?;
+            self.annotate(start, || format!("tuple elements={len}"));
+            self.input.start_skippable();
+            let value = visitor.visit_seq(StructSeqAccess { deserializer: &mut *self, len })?;
+            self.input.end_skippable()?;
+            self.leave();
+            return Ok(value);
+        }
+
+        self.deserialize_plain_tuple(len, visitor)
     }
 
-    fn deserialize_tuple_struct<V>(self, _name: &'static str, len: usize, visitor: V) -> Result<V::Value>
+    fn deserialize_tuple_struct<V>(self, name: &'static str, len: usize, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
+        self.check_peeked()?;
+        if let Some(bound) = bounded::expected_bound(name) {
+            self.len_bound = Some(bound);
+            let res = self.deserialize_plain_tuple(len, visitor);
+            self.len_bound = None;
+            return res;
+        }
+
+        if let Some(expected) = fixint::checked::expected_width(name) {
+            let start = self.mark()?;
+            let width = self.input.read_u8()?;
+            self.annotate(start, || format!("fixint width {width}"));
+            if usize::from(width) != expected {
+                return Err(Error::BadFixint { expected, found: width.into() });
+            }
+            return visitor.visit_seq(TagAccess { deserializer: self, tag: Some(width), done: false });
+        }
+
+        if name == padded_varint::NAME && CFG::canonical() {
+            return self.deserialize_canonical_padded(len, visitor);
+        }
+
+        if name == padded_varint::NAME
+            || name == bits::NAME
+            || name == compact_float::NAME
+            || feature_helper_tuple(name)
+        {
+            return self.deserialize_plain_tuple(len, visitor);
+        }
+
+        if let Some(expected) = versioned::expected_version(name) {
+            let start = self.mark()?;
+            let version = self.input.read_u8()?;
+            self.annotate(start, || format!("version {version}"));
+            if version != expected {
+                return Err(Error::BadVersion { expected, found: version });
+            }
+            return visitor.visit_seq(TagAccess { deserializer: self, tag: Some(version), done: false });
+        }
+
         self.deserialize_tuple(len, visitor)
     }
 
@@ -581,24 +1838,26 @@ impl<'de, R: Read, CFG: Cfg> de::Deserializer<'de> for &mut Deserializer<'de, R,
     where
         V: Visitor<'de>,
     {
-        let len = match self.read_varint_usize()? {
-            SPECIAL_LEN => match self.read_varint_usize()? {
-                SPECIAL_LEN => Some(SPECIAL_LEN),
-                UNKNOWN_LEN => {
-                    self.input.start_skippable();
-                    None
-                }
-                _ => return Err(Error::BadLen),
-            },
-            len => Some(len),
-        };
+        self.check_peeked()?;
+        self.enter()?;
+        let len = self.read_len("map")?;
+
+        // Keys of a map are distinct, thus at most one entry consumes no input
+        // and every other entry needs at least one byte.
+        if let (Some(len), Some(remaining)) = (len.known(), self.input.remaining())
+            && len as u64 > remaining.saturating_add(1)
+        {
+            return Err(Error::BadLen);
+        }
 
-        let value = visitor.visit_map(MapAccess { deserializer: self, len })?;
+        let empty_check = EmptyCheck::new(len.known(), || self.input.position());
+        let value = visitor.visit_map(MapAccess { deserializer: self, len, empty_check, last_key: None })?;
 
-        if len.is_none() {
+        if let Len::Block = len {
             self.input.end_skippable()?;
         }
 
+        self.leave();
         Ok(value)
     }
 
@@ -608,53 +1867,91 @@ impl<'de, R: Read, CFG: Cfg> de::Deserializer<'de> for &mut Deserializer<'de, R,
     where
         V: Visitor<'de>,
     {
+        self.check_peeked()?;
+        self.enter()?;
+        if Deserializer::<R, CFG, A>::postcard() {
+            let len = Len::Known(fields.len());
+            let value = visitor.visit_seq(SeqAccess { deserializer: &mut *self, len, empty_check: None })?;
+            self.leave();
+            return Ok(value);
+        }
+
+        let start = self.mark()?;
         let len = self.read_varint_usize()?;
+        self.annotate(start, || format!("struct fields={len}"));
 
-        if CFG::with_idents() {
+        let value = if CFG::with_idents() {
             if cfg!(postbag_fast_compile) {
                 // Buffered path: eagerly buffer all field data and reorder to match
                 // the expected field declaration order, then use `visit_seq`.
                 // Produces significantly less monomorphized code at the cost of
                 // buffering the entire struct payload in memory.
-                visitor.visit_seq(BufferedFieldSeqAccess::<CFG>::new(self, fields, len)?)
+                visitor.visit_seq(BufferedFieldSeqAccess::<CFG>::new(self, fields, len)?)?
             } else {
                 // Streaming path (default): read field identifiers and values
                 // directly from the wire using `visit_map` with skippable blocks.
-                visitor.visit_map(StructFieldAccess { deserializer: self, len })
+                visitor.visit_map(StructFieldAccess {
+                    deserializer: self,
+                    fields,
+                    len,
+                    last_field: None,
+                    unknown: None,
+                })?
             }
+        } else if CFG::positional_ids() {
+            visitor.visit_map(PositionalFieldAccess { deserializer: self, len, last_position: None })?
+        } else if len == 0 && CFG::omit_empty_struct_block() {
+            // A struct without fields is not enclosed in a skippable block.
+            visitor.visit_seq(StructSeqAccess { deserializer: self, len })?
         } else {
             self.input.start_skippable();
-            let value = visitor.visit_seq(StructSeqAccess { deserializer: self, len })?;
+            let mut access = StructSeqAccess { deserializer: self, len };
+            let value = visitor.visit_seq(&mut access)?;
+            if CFG::canonical() && access.len > 0 {
+                return Err(Error::NonCanonical);
+            }
             self.input.end_skippable()?;
-            Ok(value)
-        }
+            value
+        };
+
+        self.leave();
+        Ok(value)
     }
 
     fn deserialize_enum<V>(
-        self, _name: &'static str, _variants: &'static [&'static str], visitor: V,
+        self, name: &'static str, variants: &'static [&'static str], visitor: V,
     ) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_enum(self)
+        self.enter()?;
+        self.enum_variant_names =
+            if self.variant_names.is_empty() { None } else { self.variant_names.get(name).copied() };
+        self.enum_variants = variants;
+        let value = visitor.visit_enum(&mut *self)?;
+        self.leave();
+        Ok(value)
     }
 
     fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_string(self.read_identifier()?)
+        self.check_peeked()?;
+        visitor.visit_str(&self.read_identifier()?)
     }
 
     fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
+        self.check_peeked()?;
+        self.ignored = true;
         visitor.visit_unit()
     }
 }
 
-impl<'de, R: Read, CFG: Cfg> serde::de::VariantAccess<'de> for &mut Deserializer<'de, R, CFG> {
+impl<'de, R: Read, CFG: Cfg, A: BufferAlloc> serde::de::VariantAccess<'de> for &mut Deserializer<'de, R, CFG, A> {
     type Error = Error;
 
     fn unit_variant(self) -> Result<()> {
@@ -677,19 +1974,30 @@ impl<'de, R: Read, CFG: Cfg> serde::de::VariantAccess<'de> for &mut Deserializer
     }
 }
 
-impl<'de, R: Read, CFG: Cfg> serde::de::EnumAccess<'de> for &mut Deserializer<'de, R, CFG> {
+impl<'de, R: Read, CFG: Cfg, A: BufferAlloc> serde::de::EnumAccess<'de> for &mut Deserializer<'de, R, CFG, A> {
     type Error = Error;
     type Variant = Self;
 
     fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self)> {
-        let v = if CFG::with_idents() {
-            let ident = self.read_identifier()?;
-            let deserializer: StringDeserializer<Error> = ident.into_deserializer();
-            DeserializeSeed::deserialize(seed, deserializer)?
-        } else {
-            let varint = self.read_varint_u32()?;
-            let deserializer: U32Deserializer<Error> = varint.into_deserializer();
-            DeserializeSeed::deserialize(seed, deserializer)?
+        let variant_names = self.enum_variant_names.take();
+        let variants = mem::take(&mut self.enum_variants);
+        let v = match self.read_variant()? {
+            Variant::Index(index) if let Some(names) = variant_names => {
+                let name = names.get(index as usize).ok_or(Error::BadEnum)?;
+                self.unknown_variants += usize::from(!variants.contains(name));
+                let deserializer: StrDeserializer<Error> = name.into_deserializer();
+                DeserializeSeed::deserialize(seed, deserializer)?
+            }
+            Variant::Name(ident) => {
+                self.unknown_variants += usize::from(!variants.contains(&&*ident));
+                let deserializer: StrDeserializer<Error> = (*ident).into_deserializer();
+                DeserializeSeed::deserialize(seed, deserializer)?
+            }
+            Variant::Index(index) => {
+                self.unknown_variants += usize::from(index as usize >= variants.len());
+                let deserializer: U32Deserializer<Error> = index.into_deserializer();
+                DeserializeSeed::deserialize(seed, deserializer)?
+            }
         };
 
         Ok((v, self))
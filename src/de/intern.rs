@@ -0,0 +1,50 @@
+//! Identifier interning.
+
+use std::{collections::HashSet, sync::Arc};
+
+/// Interner for identifiers read during deserialization.
+///
+/// When deserializing with an interner, each distinct struct field identifier
+/// and enum variant identifier is allocated only once and shared afterwards.
+/// Reusing an interner across many values with the same identifiers thus
+/// avoids an allocation per identifier.
+///
+/// This only has an effect when identifiers are serialized, i.e. when using the
+/// [`Full`](crate::cfg::Full) configuration. In fast compile mode identifiers
+/// of nested structs are not interned.
+#[derive(Debug, Default, Clone)]
+pub struct Interner(HashSet<Arc<str>>);
+
+impl Interner {
+    /// Creates a new, empty interner.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the interned string equal to `s`, interning it if necessary.
+    pub fn intern(&mut self, s: &str) -> Arc<str> {
+        match self.0.get(s) {
+            Some(interned) => interned.clone(),
+            None => {
+                let interned: Arc<str> = s.into();
+                self.0.insert(interned.clone());
+                interned
+            }
+        }
+    }
+
+    /// Number of interned strings.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether no strings have been interned.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Removes all interned strings.
+    pub fn clear(&mut self) {
+        self.0.clear()
+    }
+}
@@ -1,9 +1,21 @@
-use deserializer::Deserializer;
-use serde::de::DeserializeOwned;
+use std::{
+    io::{Read, Seek, SeekFrom},
+    mem,
+};
 
-use crate::{cfg::Cfg, error::Result};
+use deserializer::{Deserializer, Variant};
+use intern::Interner;
+use serde::de::{Deserialize, DeserializeOwned};
 
+use crate::{
+    cfg::Cfg,
+    error::{Error, Result},
+};
+
+pub(crate) mod alloc;
+pub(crate) mod builder;
 pub(crate) mod deserializer;
+pub(crate) mod intern;
 mod skippable;
 
 /// Deserialize a value of type `T` from a [`std::io::Read`].
@@ -50,6 +62,238 @@ where
     Ok(t)
 }
 
+/// Deserialize a value of type `T` from a [`std::io::Read`], reporting the progress on failure.
+///
+/// This behaves like [`deserialize`], but on failure the error is returned together
+/// with the number of bytes that were successfully read from the reader before
+/// the error occurred. This allows locating the point of failure in truncated or
+/// corrupted data, for example to resynchronize to the next message in a log.
+///
+/// # Example
+///
+/// ```rust
+/// use postbag::{cfg::Slim, deserialize_partial, to_slim_vec};
+///
+/// let bytes = to_slim_vec(&(1u8, "hello".to_string())).unwrap();
+/// let (_err, consumed) = deserialize_partial::<Slim, _, (u8, String)>(&bytes[..4]).unwrap_err();
+/// assert_eq!(consumed, 2);
+/// ```
+pub fn deserialize_partial<CFG, R, T>(read: R) -> std::result::Result<T, (Error, u64)>
+where
+    CFG: Cfg,
+    R: std::io::Read,
+    T: DeserializeOwned,
+{
+    let mut deserializer = Deserializer::<R, CFG>::new(read);
+    match T::deserialize(&mut deserializer) {
+        Ok(t) => {
+            deserializer.finalize();
+            Ok(t)
+        }
+        Err(err) => Err((err, deserializer.position())),
+    }
+}
+
+/// Deserialize a value of type `T` from a [`std::io::Read`] using an identifier interner.
+///
+/// This behaves like [`deserialize`], but struct field identifiers and enum variant
+/// identifiers are interned using the provided [`Interner`]. Reusing the interner
+/// for many values with the same identifiers avoids allocating each identifier anew.
+///
+/// # Example
+///
+/// ```rust
+/// use serde::{Serialize, Deserialize};
+/// use postbag::{cfg::Full, deserialize_with_interner, to_full_vec, Interner};
+///
+/// #[derive(Serialize, Deserialize, Debug, PartialEq)]
+/// struct Person {
+///     name: String,
+///     age: u32,
+/// }
+///
+/// let mut interner = Interner::new();
+/// for age in 0..10 {
+///     let bytes = to_full_vec(&Person { name: "Alice".to_string(), age }).unwrap();
+///     let person: Person = deserialize_with_interner::<Full, _, _>(bytes.as_slice(), &mut interner).unwrap();
+///     assert_eq!(person.age, age);
+/// }
+/// assert_eq!(interner.len(), 2);
+/// ```
+pub fn deserialize_with_interner<CFG, R, T>(read: R, interner: &mut Interner) -> Result<T>
+where
+    CFG: Cfg,
+    R: std::io::Read,
+    T: DeserializeOwned,
+{
+    let mut deserializer = Deserializer::<R, CFG>::new(read);
+    deserializer.set_interner(mem::take(interner));
+    let res = T::deserialize(&mut deserializer);
+    *interner = deserializer.take_interner().unwrap_or_default();
+    let t = res?;
+    deserializer.finalize();
+    Ok(t)
+}
+
+/// Unknown fields of a struct as pairs of their identifier and serialized value.
+pub type UnknownFields = Vec<(String, Vec<u8>)>;
+
+/// Deserialize a struct of type `T` from a [`std::io::Read`], collecting its unknown fields.
+///
+/// This behaves like [`deserialize`], but fields of the outermost struct that are
+/// not fields of `T` are returned as pairs of their identifier and serialized value,
+/// in the order they were read, instead of being skipped. This allows passing extended
+/// data through an older reader without loss. The serialized value of a field can be
+/// deserialized separately, for example using [`from_full_slice`](crate::from_full_slice).
+///
+/// Unknown fields are only identified in configurations with identifiers,
+/// such as [`Full`](crate::cfg::Full). Unknown fields of nested structs are skipped.
+///
+/// # Example
+///
+/// ```rust
+/// use serde::{Serialize, Deserialize};
+/// use postbag::{cfg::Full, deserialize_preserving_unknown, from_full_slice, to_full_vec};
+///
+/// #[derive(Serialize)]
+/// struct ConfigV2 {
+///     name: String,
+///     retries: u32,
+/// }
+///
+/// #[derive(Deserialize, Debug, PartialEq)]
+/// struct ConfigV1 {
+///     name: String,
+/// }
+///
+/// let bytes = to_full_vec(&ConfigV2 { name: "main".to_string(), retries: 3 }).unwrap();
+/// let (config, unknown) = deserialize_preserving_unknown::<Full, _, ConfigV1>(bytes.as_slice()).unwrap();
+/// assert_eq!(config, ConfigV1 { name: "main".to_string() });
+/// assert_eq!(unknown[0].0, "retries");
+/// assert_eq!(from_full_slice::<u32>(&unknown[0].1).unwrap(), 3);
+/// ```
+pub fn deserialize_preserving_unknown<CFG, R, T>(read: R) -> Result<(T, UnknownFields)>
+where
+    CFG: Cfg,
+    R: std::io::Read,
+    T: DeserializeOwned,
+{
+    let mut deserializer = Deserializer::<R, CFG>::new(read);
+    deserializer.start_collecting_unknown_fields();
+    let t = T::deserialize(&mut deserializer)?;
+    let unknown = deserializer.take_unknown_fields();
+    deserializer.finalize();
+    Ok((t, unknown))
+}
+
+/// Deserialize a value of type `T` from several readers read one after another.
+///
+/// This behaves like [`deserialize`] on the concatenation of the readers, without
+/// copying their data into a single buffer. A value may span readers at any byte,
+/// including within an integer or a skippable block.
+///
+/// # Example
+///
+/// ```rust
+/// use postbag::{cfg::Slim, deserialize_chained, to_slim_vec};
+///
+/// let bytes = to_slim_vec(&(300u32, "hello".to_string())).unwrap();
+/// let (header, body) = bytes.split_at(1);
+///
+/// let value: (u32, String) = deserialize_chained::<Slim, _, _>([header, body]).unwrap();
+/// assert_eq!(value, (300, "hello".to_string()));
+/// ```
+pub fn deserialize_chained<CFG, I, T>(readers: I) -> Result<T>
+where
+    CFG: Cfg,
+    I: IntoIterator,
+    I::Item: Read,
+    T: DeserializeOwned,
+{
+    let mut readers = readers.into_iter();
+    deserialize::<CFG, _, T>(ChainedRead { current: readers.next(), readers })
+}
+
+/// Reader reading from a sequence of readers one after another.
+struct ChainedRead<I: Iterator> {
+    current: Option<I::Item>,
+    readers: I,
+}
+
+impl<I> Read for ChainedRead<I>
+where
+    I: Iterator,
+    I::Item: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while let Some(current) = &mut self.current {
+            let n = current.read(buf)?;
+            if n > 0 || buf.is_empty() {
+                return Ok(n);
+            }
+            self.current = self.readers.next();
+        }
+        Ok(0)
+    }
+}
+
+/// Deserialize a value of type `T` from a [`std::io::Read`], capturing the bytes read.
+///
+/// This behaves like [`deserialize`], but additionally returns all bytes read from
+/// the reader, which are exactly the serialized value. Skipped data, such as struct
+/// fields unknown to `T`, is included, thus the captured bytes can be passed on or
+/// logged unchanged without serializing the value again.
+///
+/// # Example
+///
+/// ```rust
+/// use serde::{Serialize, Deserialize};
+/// use postbag::{cfg::Full, deserialize_capturing, to_full_vec};
+///
+/// #[derive(Serialize)]
+/// struct Event {
+///     id: u32,
+///     details: String,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct EventId {
+///     id: u32,
+/// }
+///
+/// let mut bytes = to_full_vec(&Event { id: 7, details: "disk full".to_string() }).unwrap();
+/// let len = bytes.len();
+/// bytes.extend(to_full_vec(&Event { id: 8, details: String::new() }).unwrap());
+///
+/// let (event, raw) = deserialize_capturing::<Full, _, EventId>(bytes.as_slice()).unwrap();
+/// assert_eq!(event.id, 7);
+/// assert_eq!(raw, bytes[..len]);
+/// ```
+pub fn deserialize_capturing<CFG, R, T>(read: R) -> Result<(T, Vec<u8>)>
+where
+    CFG: Cfg,
+    R: Read,
+    T: DeserializeOwned,
+{
+    let mut capturing = CapturingRead { inner: read, captured: Vec::new() };
+    let value = deserialize::<CFG, _, T>(&mut capturing)?;
+    Ok((value, capturing.captured))
+}
+
+/// Reader appending all data read to a buffer.
+struct CapturingRead<R> {
+    inner: R,
+    captured: Vec<u8>,
+}
+
+impl<R: Read> Read for CapturingRead<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.captured.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+}
+
 /// Deserialize a value using the [`Full`](crate::cfg::Full) configuration.
 ///
 /// This is a convenience function equivalent to `deserialize::<Full, _, _>(reader)`.
@@ -124,7 +368,7 @@ where
 
 /// Deserialize a value from a byte slice using the [`Full`](crate::cfg::Full) configuration.
 ///
-/// This is a convenience function that calls `deserialize_full` with the provided byte slice.
+/// This is a convenience function equivalent to calling `deserialize_full` with the provided byte slice.
 /// It deserializes data that includes struct field identifiers and enum variant identifiers as strings.
 ///
 /// # Example
@@ -152,12 +396,12 @@ pub fn from_full_slice<T>(slice: &[u8]) -> Result<T>
 where
     T: DeserializeOwned,
 {
-    deserialize_full(slice)
+    from_slice::<crate::cfg::Full, T>(slice)
 }
 
 /// Deserialize a value from a byte slice using the [`Slim`](crate::cfg::Slim) configuration.
 ///
-/// This is a convenience function that calls `deserialize_slim` with the provided byte slice.
+/// This is a convenience function equivalent to calling `deserialize_slim` with the provided byte slice.
 /// It deserializes data without identifiers, using indices for enum variants.
 ///
 /// # Example
@@ -185,5 +429,232 @@ pub fn from_slim_slice<T>(slice: &[u8]) -> Result<T>
 where
     T: DeserializeOwned,
 {
-    deserialize_slim(slice)
+    from_slice::<crate::cfg::Slim, T>(slice)
+}
+
+/// Deserialize a value from the start of a byte slice using the [`Full`](crate::cfg::Full) configuration
+/// and return it together with the remainder of the slice.
+///
+/// Data following the value, such as another value or a checksum, is returned
+/// instead of being ignored.
+///
+/// # Example
+///
+/// ```rust
+/// use postbag::{to_full_vec, from_full_slice_with_tail};
+///
+/// let mut bytes = to_full_vec("first").unwrap();
+/// bytes.extend(to_full_vec("second").unwrap());
+///
+/// let (first, tail): (String, _) = from_full_slice_with_tail(&bytes).unwrap();
+/// let (second, tail): (String, _) = from_full_slice_with_tail(tail).unwrap();
+/// assert_eq!((first.as_str(), second.as_str()), ("first", "second"));
+/// assert!(tail.is_empty());
+/// ```
+pub fn from_full_slice_with_tail<T>(slice: &[u8]) -> Result<(T, &[u8])>
+where
+    T: DeserializeOwned,
+{
+    from_slice_with_tail::<crate::cfg::Full, T>(slice)
+}
+
+/// Deserialize a value from the start of a byte slice using the [`Slim`](crate::cfg::Slim) configuration
+/// and return it together with the remainder of the slice.
+///
+/// Data following the value, such as another value or a checksum, is returned
+/// instead of being ignored.
+///
+/// # Example
+///
+/// ```rust
+/// use postbag::{to_slim_vec, from_slim_slice_with_tail};
+///
+/// let mut bytes = to_slim_vec(&(1u32, 2u32)).unwrap();
+/// bytes.extend_from_slice(&[0xaa, 0xbb]);
+///
+/// let (value, tail): ((u32, u32), _) = from_slim_slice_with_tail(&bytes).unwrap();
+/// assert_eq!(value, (1, 2));
+/// assert_eq!(tail, [0xaa, 0xbb]);
+/// ```
+pub fn from_slim_slice_with_tail<T>(slice: &[u8]) -> Result<(T, &[u8])>
+where
+    T: DeserializeOwned,
+{
+    from_slice_with_tail::<crate::cfg::Slim, T>(slice)
+}
+
+/// Deserialize a value from a byte slice using the [`Canonical`](crate::cfg::Canonical) configuration.
+///
+/// The slice must contain exactly one value in canonical form, otherwise
+/// [`Error::NonCanonical`] is returned. Thus the slice is identical to the
+/// serialization of the returned value using [`to_canonical_vec`](crate::to_canonical_vec).
+///
+/// # Example
+///
+/// ```rust
+/// use std::collections::HashMap;
+/// use postbag::{to_canonical_vec, from_canonical_slice};
+///
+/// let map: HashMap<String, u32> = [("a".to_string(), 1), ("b".to_string(), 2)].into();
+///
+/// let bytes = to_canonical_vec(&map).unwrap();
+/// let deserialized: HashMap<String, u32> = from_canonical_slice(&bytes).unwrap();
+/// assert_eq!(map, deserialized);
+/// assert_eq!(to_canonical_vec(&deserialized).unwrap(), bytes);
+/// ```
+pub fn from_canonical_slice<T>(slice: &[u8]) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    from_slice::<crate::cfg::Canonical, T>(slice)
+}
+
+/// Verifies that a byte slice contains exactly one valid value of type `T`.
+///
+/// The value is deserialized and dropped. Data remaining after the value
+/// is rejected with [`Error::TrailingData`].
+///
+/// Since the serialized data is not self-describing, validation requires
+/// the type of the value and cannot be performed structurally using
+/// [`IgnoredAny`](serde::de::IgnoredAny). Thus the value is constructed
+/// during validation.
+///
+/// # Example
+///
+/// ```rust
+/// use postbag::{cfg::Slim, to_slim_vec, validate};
+///
+/// let bytes = to_slim_vec(&(1u32, "message")).unwrap();
+/// assert!(validate::<Slim, (u32, String)>(&bytes).is_ok());
+/// assert!(validate::<Slim, (u32, String)>(&bytes[..3]).is_err());
+/// ```
+pub fn validate<CFG, T>(slice: &[u8]) -> Result<()>
+where
+    CFG: Cfg,
+    T: DeserializeOwned,
+{
+    let mut deserializer = Deserializer::<_, CFG>::with_remaining(slice, slice.len() as u64);
+    T::deserialize(&mut deserializer)?;
+    if deserializer.position() != slice.len() as u64 {
+        return Err(Error::TrailingData);
+    }
+    Ok(())
+}
+
+/// Deserialize a value from a byte slice, making use of its known length.
+///
+/// In canonical mode trailing data is rejected.
+pub(crate) fn from_slice<'de, CFG, T>(slice: &'de [u8]) -> Result<T>
+where
+    CFG: Cfg,
+    T: Deserialize<'de>,
+{
+    let mut deserializer = Deserializer::<_, CFG>::from_slice(slice);
+    let t = T::deserialize(&mut deserializer)?;
+    if CFG::canonical() && deserializer.position() != slice.len() as u64 {
+        return Err(Error::NonCanonical);
+    }
+    deserializer.finalize();
+    Ok(t)
+}
+
+/// Deserialize a value from the start of a byte slice and return the remainder of the slice.
+fn from_slice_with_tail<'de, CFG, T>(slice: &'de [u8]) -> Result<(T, &'de [u8])>
+where
+    CFG: Cfg,
+    T: Deserialize<'de>,
+{
+    let mut deserializer = Deserializer::<_, CFG>::from_slice(slice);
+    let t = T::deserialize(&mut deserializer)?;
+    let pos = deserializer.position() as usize;
+    Ok((t, &slice[pos..]))
+}
+
+/// Deserialize a value of type `T` from a seekable reader.
+///
+/// This behaves like [`deserialize`], but skipped data, such as struct fields
+/// unknown to `T`, is seeked over instead of being read. This speeds up reading
+/// data containing large fields that are not deserialized.
+///
+/// Since seeking past the end of the reader is not an error, truncated data
+/// within a skipped field may go undetected.
+///
+/// # Example
+///
+/// ```rust
+/// use std::io::Cursor;
+/// use serde::{Serialize, Deserialize};
+/// use postbag::{cfg::Full, deserialize_seek, to_full_vec};
+///
+/// #[derive(Serialize)]
+/// struct Record {
+///     id: u32,
+///     blob: Vec<u8>,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct RecordId {
+///     id: u32,
+/// }
+///
+/// let bytes = to_full_vec(&Record { id: 1, blob: vec![0; 1024 * 1024] }).unwrap();
+///
+/// let record: RecordId = deserialize_seek::<Full, _, _>(Cursor::new(bytes)).unwrap();
+/// assert_eq!(record.id, 1);
+/// ```
+pub fn deserialize_seek<CFG, R, T>(read: R) -> Result<T>
+where
+    CFG: Cfg,
+    R: Read + Seek,
+    T: DeserializeOwned,
+{
+    let mut deserializer = Deserializer::<R, CFG>::with_seek(read);
+    let t = T::deserialize(&mut deserializer)?;
+    deserializer.finalize();
+    Ok(t)
+}
+
+/// Reads the variant tag of an enum from a seekable reader without consuming it.
+///
+/// After the variant tag has been read, the reader is seeked back to its
+/// original position, so that the complete enum can subsequently be deserialized
+/// into a type chosen based on the variant.
+///
+/// For non-seekable readers use [`Deserializer::peek_variant`](crate::Deserializer::peek_variant).
+///
+/// # Example
+///
+/// ```rust
+/// use std::io::Cursor;
+/// use serde::{Serialize, Deserialize};
+/// use postbag::{cfg::Full, deserialize, peek_variant, to_full_vec, Variant};
+///
+/// #[derive(Serialize, Deserialize, Debug, PartialEq)]
+/// enum Message {
+///     Ping,
+///     Data(u32),
+/// }
+///
+/// let bytes = to_full_vec(&Message::Data(1)).unwrap();
+/// let mut reader = Cursor::new(bytes);
+///
+/// let variant = peek_variant::<Full, _>(&mut reader).unwrap();
+/// assert_eq!(variant, Variant::Name("Data".into()));
+///
+/// let msg: Message = deserialize::<Full, _, _>(&mut reader).unwrap();
+/// assert_eq!(msg, Message::Data(1));
+/// ```
+pub fn peek_variant<CFG, R>(mut reader: R) -> Result<Variant>
+where
+    CFG: Cfg,
+    R: Read + Seek,
+{
+    let start = reader.stream_position()?;
+
+    let mut deserializer = Deserializer::<_, CFG>::new(&mut reader);
+    let variant = deserializer.peek_variant()?.clone();
+    drop(deserializer);
+
+    reader.seek(SeekFrom::Start(start))?;
+    Ok(variant)
 }
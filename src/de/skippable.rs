@@ -1,19 +1,60 @@
 //! Skippable blocks reader.
 
-use std::{io::Read, mem};
+use std::{
+    io::{self, Read, Seek, SeekFrom},
+    mem,
+};
 
+use super::alloc::Buffer;
 use crate::{
     Error, Result,
+    format::SKIP_CHUNK_MAX,
     varint::{max_of_last_byte, varint_max},
 };
 
+/// Maximum number of bytes allocated up front for a read of unverified length.
+const PREALLOC_MAX: usize = 64 * 1024;
+
 /// Reader that allows blocks to be (partially) skipped.
-pub struct SkipRead<R>(SkipStack<R>);
+pub struct SkipRead<R, B> {
+    stack: SkipStack<R, B>,
+    /// Whether non-canonical block encodings are rejected.
+    canonical: bool,
+}
+
+impl<R: Read, B: Buffer> SkipRead<R, B> {
+    /// Creates a new skip stack, capturing data into buffers like `buf`.
+    pub fn new(inner: R, buf: B) -> Self {
+        Self::with_base(BaseRead::new(inner, None, None, buf))
+    }
+
+    /// Creates a new skip stack over a reader with the specified number
+    /// of bytes remaining, capturing data into buffers like `buf`.
+    pub fn with_remaining(inner: R, remaining: u64, buf: B) -> Self {
+        Self::with_base(BaseRead::new(inner, Some(remaining), None, buf))
+    }
+
+    fn with_base(base: BaseRead<R, B>) -> Self {
+        Self { stack: SkipStack::Base(base), canonical: false }
+    }
+
+    /// Sets the maximum number of bytes read from the contained reader,
+    /// including skipped data.
+    ///
+    /// Reading beyond the limit fails with [`Error::ReadLimitExceeded`].
+    ///
+    /// Must be called before any block is opened.
+    pub fn set_read_limit(&mut self, limit: u64) {
+        let SkipStack::Base(base) = &mut self.stack else { panic!("a skip block is open") };
+        base.limit = Some(limit);
+    }
 
-impl<R: Read> SkipRead<R> {
-    /// Creates a new skip stack.
-    pub fn new(inner: R) -> Self {
-        SkipRead(SkipStack::Base(inner))
+    /// Sets whether non-canonical block encodings are rejected.
+    ///
+    /// If enabled, block lengths must be encoded minimally and
+    /// closing a block that has not been read completely fails.
+    pub fn set_canonical(&mut self, canonical: bool) {
+        self.canonical = canonical;
     }
 
     /// Read one byte.
@@ -23,32 +64,127 @@ impl<R: Read> SkipRead<R> {
 
     /// Read `cnt` bytes.
     pub fn read(&mut self, cnt: usize) -> Result<Vec<u8>> {
-        self.0.read(cnt)
+        self.stack.read(cnt)
+    }
+
+    /// Read `cnt` bytes and append them to `buf`.
+    pub fn read_to(&mut self, cnt: usize, buf: &mut impl Buffer) -> Result<()> {
+        self.stack.read_to(cnt, buf)
+    }
+
+    /// Skips `cnt` bytes, if they are stored contiguously in the contained reader,
+    /// and returns the position of the first of them.
+    ///
+    /// The bytes are not contiguous if they are interrupted by the chunk framing
+    /// of an open block. In this case `None` is returned and no data is consumed,
+    /// except for the framing preceding the bytes.
+    pub fn skip_contiguous(&mut self, cnt: usize) -> Result<Option<u64>> {
+        if !self.stack.contiguous(cnt)? {
+            return Ok(None);
+        }
+
+        let pos = self.position();
+        self.stack.skip(cnt)?;
+        Ok(Some(pos))
     }
 
     /// Opens a skippable block.
     ///
     /// Must be paired with a call to [`Self::end_skippable`].
     pub fn start_skippable(&mut self) {
-        let this = mem::replace(&mut self.0, SkipStack::Dummy);
-        self.0 = SkipStack::SkipBlock(SkipBlock::new(this));
+        let this = mem::replace(&mut self.stack, SkipStack::Dummy);
+        self.stack = SkipStack::SkipBlock(SkipBlock::new(this, self.canonical));
     }
 
     /// Finishes a skippable block.
     ///
     /// Remaining contents of the block are skipped if not yet read.
     pub fn end_skippable(&mut self) -> Result<()> {
-        match mem::replace(&mut self.0, SkipStack::Dummy) {
+        match mem::replace(&mut self.stack, SkipStack::Dummy) {
             SkipStack::Base(_) => panic!("no skip block is open"),
-            SkipStack::SkipBlock(sb) => self.0 = sb.finish()?,
+            SkipStack::SkipBlock(sb) => self.stack = sb.finish()?,
             SkipStack::Dummy => unreachable!(),
         }
         Ok(())
     }
 
+    /// Returns whether all contents of the innermost open block have been read.
+    ///
+    /// Returns `false` if no block is open.
+    pub fn at_block_end(&mut self) -> Result<bool> {
+        match &mut self.stack {
+            SkipStack::SkipBlock(sb) => {
+                sb.update_remaining()?;
+                Ok(sb.remaining == 0)
+            }
+            SkipStack::Base(_) => Ok(false),
+            SkipStack::Dummy => unreachable!(),
+        }
+    }
+
     /// Returns the contained reader.
     pub fn into_inner(self) -> R {
-        self.0.into_inner()
+        self.stack.into_base().inner
+    }
+
+    /// Replaces the contained reader, closing all open blocks, and returns the previous reader.
+    ///
+    /// The position is reset and the number of remaining bytes becomes unknown,
+    /// while the read limit and allocated buffers are kept.
+    pub fn reset(&mut self, inner: R) -> R {
+        let mut base = mem::replace(&mut self.stack, SkipStack::Dummy).into_base();
+        base.pos = 0;
+        base.message_start = 0;
+        base.remaining = None;
+        base.capture.buf.clear();
+        base.capture.active = 0;
+        let prev = mem::replace(&mut base.inner, inner);
+        self.stack = SkipStack::Base(base);
+        prev
+    }
+
+    /// Marks the current position as the start of a message, for detecting empty input.
+    pub fn start_message(&mut self) {
+        if let SkipStack::Base(base) = &mut self.stack {
+            base.message_start = base.pos;
+        }
+    }
+
+    /// Number of bytes read from the contained reader.
+    pub fn position(&self) -> u64 {
+        self.stack.position()
+    }
+
+    /// Number of bytes remaining in the contained reader, if known.
+    pub fn remaining(&self) -> Option<u64> {
+        self.stack.remaining()
+    }
+
+    /// Starts capturing the bytes read at the current block level,
+    /// including the framing of blocks opened subsequently.
+    ///
+    /// Returns the capture start, which must be passed to [`Self::end_capture`].
+    /// Captures can be nested but must be ended at the block level they were started.
+    /// Skipped data is read and captured.
+    pub fn start_capture(&mut self) -> usize {
+        let capture = self.stack.capture();
+        capture.active += 1;
+        capture.buf.len()
+    }
+
+    /// Ends capturing and returns the bytes read since the capture was started.
+    pub fn end_capture(&mut self, start: usize) -> B {
+        let capture = self.stack.capture();
+        capture.active -= 1;
+        if capture.active == 0 {
+            // The outermost capture starts at the beginning of the buffer.
+            let empty = capture.buf.new_like();
+            mem::replace(&mut capture.buf, empty)
+        } else {
+            let mut bytes = capture.buf.new_like();
+            bytes.extend_from_slice(&capture.buf[start..]);
+            bytes
+        }
     }
 
     /// Opens a skippable block, reads all its contents, and closes it.
@@ -56,33 +192,258 @@ impl<R: Read> SkipRead<R> {
     /// Returns the raw bytes contained within the skippable block.
     pub fn read_skippable_block(&mut self) -> Result<Vec<u8>> {
         self.start_skippable();
-        let SkipStack::SkipBlock(sb) = &mut self.0 else { unreachable!() };
+        let SkipStack::SkipBlock(sb) = &mut self.stack else { unreachable!() };
         let data = sb.read_all()?;
         self.end_skippable()?;
         Ok(data)
     }
 }
 
-enum SkipStack<R> {
-    Base(R),
-    SkipBlock(SkipBlock<R>),
+impl<R: Read + Seek, B: Buffer> SkipRead<R, B> {
+    /// Creates a new skip stack over a seekable reader, capturing data into buffers like `buf`.
+    ///
+    /// Skipped data is seeked over instead of being read.
+    pub fn with_seek(inner: R, buf: B) -> Self {
+        let seek: SeekFn<R> = |inner, ct| {
+            let ct = i64::try_from(ct).map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+            inner.seek(SeekFrom::Current(ct))?;
+            Ok(())
+        };
+        Self::with_base(BaseRead::new(inner, None, Some(seek), buf))
+    }
+}
+
+/// Function seeking forward by the specified number of bytes.
+type SeekFn<R> = fn(&mut R, u64) -> io::Result<()>;
+
+/// Bytes captured at a block level.
+struct Capture<B> {
+    buf: B,
+    /// Number of active captures.
+    active: usize,
+}
+
+impl<B> Capture<B> {
+    fn new(buf: B) -> Self {
+        Self { buf, active: 0 }
+    }
+}
+
+/// Base reader.
+struct BaseRead<R, B> {
+    inner: R,
+    /// Number of bytes read.
+    pos: u64,
+    /// Number of bytes remaining in the reader, if known.
+    remaining: Option<u64>,
+    /// Seek function, if the reader is seekable.
+    seek: Option<SeekFn<R>>,
+    /// Maximum number of bytes to read.
+    limit: Option<u64>,
+    capture: Capture<B>,
+    /// Position at which the current message starts.
+    message_start: u64,
+}
+
+impl<R: Read, B> BaseRead<R, B> {
+    fn new(inner: R, remaining: Option<u64>, seek: Option<SeekFn<R>>, buf: B) -> Self {
+        Self { inner, pos: 0, remaining, seek, limit: None, capture: Capture::new(buf), message_start: 0 }
+    }
+
+    /// Error for the end of data, distinguishing input ending before the current message.
+    fn eof(&self, obtained: bool) -> Error {
+        if !obtained && self.pos == self.message_start {
+            Error::EmptyInput
+        } else {
+            io::Error::from(io::ErrorKind::UnexpectedEof).into()
+        }
+    }
+
+    /// Reads the first byte of a message into `buf`, failing if the input has ended.
+    fn read_first(&mut self, buf: &mut [u8]) -> Result<()> {
+        loop {
+            match self.inner.read(buf) {
+                Ok(0) => return Err(Error::EmptyInput),
+                Ok(_) => return Ok(()),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    /// Verifies that reading `ct` more bytes does not exceed the read limit.
+    fn check_limit(&self, ct: usize) -> Result<()> {
+        match self.limit {
+            Some(limit) if self.pos.saturating_add(ct as u64) > limit => Err(Error::ReadLimitExceeded),
+            _ => Ok(()),
+        }
+    }
+
+    /// Reads `ct` bytes and appends them to `buf`.
+    ///
+    /// If the number of remaining bytes is known, the buffer is allocated once
+    /// or the read fails immediately. Otherwise large reads allocate incrementally,
+    /// so that a bogus length cannot force a huge allocation.
+    fn read_to(&mut self, ct: usize, buf: &mut impl Buffer) -> Result<()> {
+        self.check_limit(ct)?;
+
+        match &mut self.remaining {
+            Some(remaining) => {
+                if *remaining < ct as u64 {
+                    let obtained = *remaining > 0;
+                    return Err(self.eof(obtained));
+                }
+                *remaining -= ct as u64;
+            }
+            None if ct > PREALLOC_MAX => {
+                let n = self.read_incrementally(ct, buf)?;
+                if n != ct {
+                    let err = self.eof(n > 0);
+                    self.pos += n as u64;
+                    return Err(err);
+                }
+                self.pos += n as u64;
+                return Ok(());
+            }
+            None => (),
+        }
+
+        let mut start = buf.len();
+        if start == 0 {
+            buf.reserve_exact(ct);
+        }
+        buf.resize(start + ct);
+        if ct > 0 && self.pos == self.message_start {
+            self.read_first(&mut buf[start..start + 1])?;
+            start += 1;
+        }
+        self.inner.read_exact(&mut buf[start..])?;
+        self.pos += ct as u64;
+        Ok(())
+    }
+
+    /// Reads up to `ct` bytes, growing `buf` by at most [`PREALLOC_MAX`] bytes at a time,
+    /// and returns the number of bytes read, which is less than `ct` at the end of the reader.
+    fn read_incrementally(&mut self, ct: usize, buf: &mut impl Buffer) -> Result<usize> {
+        let start = buf.len();
+        let mut n = 0;
+        while n < ct {
+            buf.resize(start + n + (ct - n).min(PREALLOC_MAX));
+            let res = self.inner.read(&mut buf[start + n..]);
+            match res {
+                Ok(0) => break,
+                Ok(read) => n += read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+                Err(err) => {
+                    buf.truncate(start + n);
+                    return Err(err.into());
+                }
+            }
+            buf.truncate(start + n);
+        }
+        buf.truncate(start + n);
+        Ok(n)
+    }
+
+    /// Skips `ct` bytes.
+    ///
+    /// Seeks over the bytes if the reader is seekable, otherwise reads and discards them.
+    fn skip(&mut self, ct: usize) -> Result<()> {
+        self.check_limit(ct)?;
+
+        if let Some(remaining) = &mut self.remaining {
+            if *remaining < ct as u64 {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+            *remaining -= ct as u64;
+        }
+
+        match self.seek {
+            Some(seek) => seek(&mut self.inner, ct as u64)?,
+            None => {
+                let n = io::copy(&mut (&mut self.inner).take(ct as u64), &mut io::sink())?;
+                self.pos += n;
+                if n != ct as u64 {
+                    return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+                }
+                return Ok(());
+            }
+        }
+
+        self.pos += ct as u64;
+        Ok(())
+    }
+}
+
+enum SkipStack<R, B> {
+    Base(BaseRead<R, B>),
+    SkipBlock(SkipBlock<R, B>),
     Dummy,
 }
 
-impl<R: Read> SkipStack<R> {
+impl<R: Read, B: Buffer> SkipStack<R, B> {
+    /// Reads `ct` bytes into a vector of exactly this capacity,
+    /// so that converting it into a boxed slice does not reallocate.
     pub fn read(&mut self, ct: usize) -> Result<Vec<u8>> {
+        let mut buf = Vec::with_capacity(ct.min(PREALLOC_MAX));
+        self.read_to(ct, &mut buf)?;
+        if buf.capacity() != buf.len() {
+            // Only reached by large reads that were allocated incrementally.
+            buf.shrink_to_fit();
+        }
+        Ok(buf)
+    }
+
+    fn read_to(&mut self, ct: usize, buf: &mut impl Buffer) -> Result<()> {
+        let start = buf.len();
+        match self {
+            Self::Base(base) => base.read_to(ct, buf)?,
+            Self::SkipBlock(sb) => sb.read_to(ct, buf)?,
+            Self::Dummy => unreachable!(),
+        }
+
+        let capture = self.capture();
+        if capture.active > 0 {
+            capture.buf.extend_from_slice(&buf[start..]);
+        }
+
+        Ok(())
+    }
+
+    fn capture(&mut self) -> &mut Capture<B> {
+        match self {
+            Self::Base(base) => &mut base.capture,
+            Self::SkipBlock(sb) => &mut sb.capture,
+            Self::Dummy => unreachable!(),
+        }
+    }
+
+    fn skip(&mut self, ct: usize) -> Result<()> {
+        if self.capture().active > 0 {
+            let mut discarded = self.capture().buf.new_like();
+            return self.read_to(ct, &mut discarded);
+        }
+
+        match self {
+            Self::Base(base) => base.skip(ct),
+            Self::SkipBlock(sb) => sb.skip(ct),
+            Self::Dummy => unreachable!(),
+        }
+    }
+
+    /// Whether the next `ct` bytes are not interrupted by the chunk framing of a block.
+    fn contiguous(&mut self, ct: usize) -> Result<bool> {
         match self {
-            Self::Base(base) => {
-                let mut buf = vec![0; ct];
-                base.read_exact(&mut buf)?;
-                Ok(buf)
+            Self::Base(_) => Ok(true),
+            Self::SkipBlock(sb) => {
+                sb.update_remaining()?;
+                Ok(sb.remaining >= ct && sb.inner.contiguous(ct)?)
             }
-            Self::SkipBlock(sb) => sb.read(ct),
             Self::Dummy => unreachable!(),
         }
     }
 
-    fn try_take_varint_u16(&mut self) -> Result<u16> {
+    fn try_take_varint_u16(&mut self, canonical: bool) -> Result<u16> {
         let mut out = 0;
         for i in 0..varint_max::<u16>() {
             let val = self.read(1)?[0];
@@ -92,6 +453,8 @@ impl<R: Read> SkipStack<R> {
             if (val & 0x80) == 0 {
                 if i == varint_max::<u16>() - 1 && val > max_of_last_byte::<u16>() {
                     return Err(Error::BadVarint);
+                } else if canonical && i > 0 && val == 0 {
+                    return Err(Error::NonCanonical);
                 } else {
                     return Ok(out);
                 }
@@ -100,26 +463,45 @@ impl<R: Read> SkipStack<R> {
         Err(Error::BadVarint)
     }
 
-    fn into_inner(self) -> R {
+    fn position(&self) -> u64 {
+        match self {
+            SkipStack::Base(base) => base.pos,
+            SkipStack::SkipBlock(sb) => sb.inner.position(),
+            SkipStack::Dummy => unreachable!(),
+        }
+    }
+
+    fn remaining(&self) -> Option<u64> {
+        match self {
+            SkipStack::Base(base) => base.remaining,
+            SkipStack::SkipBlock(sb) => sb.inner.remaining(),
+            SkipStack::Dummy => unreachable!(),
+        }
+    }
+
+    fn into_base(self) -> BaseRead<R, B> {
         match self {
             SkipStack::Base(base) => base,
-            SkipStack::SkipBlock(sb) => sb.inner.into_inner(),
+            SkipStack::SkipBlock(sb) => sb.inner.into_base(),
             SkipStack::Dummy => unreachable!(),
         }
     }
 }
 
-struct SkipBlock<R> {
-    inner: Box<SkipStack<R>>,
+struct SkipBlock<R, B> {
+    inner: Box<SkipStack<R, B>>,
     remaining: usize,
     has_next_block: bool,
+    canonical: bool,
+    capture: Capture<B>,
 }
 
-impl<R: Read> SkipBlock<R> {
-    const MAX_LEN: usize = u16::MAX as usize;
+impl<R: Read, B: Buffer> SkipBlock<R, B> {
+    const MAX_LEN: usize = SKIP_CHUNK_MAX;
 
-    fn new(inner: SkipStack<R>) -> Self {
-        Self { inner: Box::new(inner), remaining: 0, has_next_block: true }
+    fn new(mut inner: SkipStack<R, B>, canonical: bool) -> Self {
+        let capture = Capture::new(inner.capture().buf.new_like());
+        Self { inner: Box::new(inner), remaining: 0, has_next_block: true, canonical, capture }
     }
 
     fn update_remaining(&mut self) -> Result<()> {
@@ -127,22 +509,22 @@ impl<R: Read> SkipBlock<R> {
             return Ok(());
         }
 
-        self.remaining = self.inner.try_take_varint_u16()?.into();
+        self.remaining = self.inner.try_take_varint_u16(self.canonical)?.into();
         self.has_next_block = self.remaining == Self::MAX_LEN;
 
         Ok(())
     }
 
-    fn read(&mut self, mut ct: usize) -> Result<Vec<u8>> {
+    fn read_to(&mut self, mut ct: usize, buf: &mut impl Buffer) -> Result<()> {
         self.update_remaining()?;
 
         if self.remaining >= ct {
-            let buf = self.inner.read(ct)?;
+            self.inner.read_to(ct, buf)?;
             self.remaining -= ct;
-            return Ok(buf);
+            return Ok(());
         }
 
-        let mut buf = Vec::with_capacity(ct);
+        buf.reserve_exact(ct.min(PREALLOC_MAX));
         while ct > 0 {
             self.update_remaining()?;
 
@@ -151,20 +533,40 @@ impl<R: Read> SkipBlock<R> {
             }
 
             let n = ct.min(self.remaining);
-            buf.extend(&self.inner.read(n)?);
+            self.inner.read_to(n, buf)?;
             self.remaining -= n;
             ct -= n;
         }
 
-        Ok(buf)
+        Ok(())
     }
 
-    fn finish(mut self) -> Result<SkipStack<R>> {
+    fn skip(&mut self, mut ct: usize) -> Result<()> {
+        while ct > 0 {
+            self.update_remaining()?;
+
+            if self.remaining == 0 {
+                return Err(Error::EndOfBlock);
+            }
+
+            let n = ct.min(self.remaining);
+            self.inner.skip(n)?;
+            self.remaining -= n;
+            ct -= n;
+        }
+
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<SkipStack<R, B>> {
         loop {
             self.update_remaining()?;
 
             if self.remaining > 0 {
-                self.inner.read(self.remaining)?;
+                if self.canonical {
+                    return Err(Error::NonCanonical);
+                }
+                self.inner.skip(self.remaining)?;
                 self.remaining = 0;
             } else {
                 break;
@@ -181,8 +583,7 @@ impl<R: Read> SkipBlock<R> {
             if self.remaining == 0 {
                 break;
             }
-            let chunk = self.inner.read(self.remaining)?;
-            buf.extend(chunk);
+            self.inner.read_to(self.remaining, &mut buf)?;
             self.remaining = 0;
         }
         Ok(buf)
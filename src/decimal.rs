@@ -0,0 +1,96 @@
+//! # Decimal Numbers
+//!
+//! This module, for use with `#[serde(with = "postbag::decimal")]`, serializes
+//! a [`Decimal`] losslessly as its 16-byte binary representation.
+//! It requires the `rust_decimal` feature.
+//!
+//! The value is written as 16 bytes without length prefix:
+//!
+//! | Bytes  | Content                                                 |
+//! |--------|---------------------------------------------------------|
+//! | 0..4   | flags as little-endian `u32`                            |
+//! | 4..8   | bits 0..32 of the mantissa as little-endian `u32`       |
+//! | 8..12  | bits 32..64 of the mantissa as little-endian `u32`      |
+//! | 12..16 | bits 64..96 of the mantissa as little-endian `u32`      |
+//!
+//! Bits 16..24 of the flags contain the scale, i.e. the number of decimal digits
+//! after the decimal point, which is at most 28. Bit 31 of the flags is set
+//! for negative numbers. All other bits of the flags are zero.
+//! The value of the decimal is `(-1)^sign * mantissa / 10^scale`.
+//!
+//! ```rust
+//! # use serde::{Serialize, Deserialize};
+//! use rust_decimal::Decimal;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! pub struct Payment {
+//!     #[serde(with = "postbag::decimal")]
+//!     amount: Decimal,
+//! }
+//! ```
+
+use std::fmt;
+
+use rust_decimal::Decimal;
+use serde::{
+    Deserializer, Serializer,
+    de::{self, SeqAccess, Visitor},
+    ser::SerializeTupleStruct,
+};
+
+/// Tuple struct name recognized by the serializer and deserializer.
+pub(crate) const NAME: &str = "$postbag::Decimal";
+
+/// Length of the binary representation.
+const LEN: usize = 16;
+
+/// Bits of the flags that may be set.
+const FLAGS_MASK: u32 = 0x80ff_0000;
+
+/// Serialize the decimal as its 16-byte binary representation.
+pub fn serialize<S>(val: &Decimal, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut tuple = serializer.serialize_tuple_struct(NAME, LEN)?;
+    for byte in val.serialize() {
+        tuple.serialize_field(&byte)?;
+    }
+    tuple.end()
+}
+
+/// Deserialize the decimal from its 16-byte binary representation.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Decimal, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_tuple_struct(NAME, LEN, DecimalVisitor)
+}
+
+struct DecimalVisitor;
+
+impl<'de> Visitor<'de> for DecimalVisitor {
+    type Value = Decimal;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("the 16-byte binary representation of a decimal")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Decimal, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut bytes = [0; LEN];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(i, &self))?;
+        }
+
+        let flags = u32::from_le_bytes(bytes[..4].try_into().unwrap());
+        let scale = (flags >> 16) & 0xff;
+        if flags & !FLAGS_MASK != 0 || scale > Decimal::MAX_SCALE {
+            return Err(de::Error::invalid_value(de::Unexpected::Unsigned(flags.into()), &"valid decimal flags"));
+        }
+
+        Ok(Decimal::deserialize(bytes))
+    }
+}
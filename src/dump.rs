@@ -0,0 +1,286 @@
+//! Human-readable dumps of serialized data for debugging.
+
+use std::{
+    collections::VecDeque,
+    fmt::{self, Write},
+    io::{self, Read},
+};
+
+use serde::{
+    Deserialize, Serialize, Serializer,
+    de::{self, DeserializeOwned, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    ser::SerializeStruct,
+};
+
+use crate::{cfg::Cfg, de::deserializer::Deserializer};
+
+/// Number of bytes shown per line of a hex dump.
+const LINE_LEN: usize = 16;
+
+/// Maximum number of bytes shown for an annotated value.
+const ANNOTATION_BYTES: usize = 8;
+
+/// Number of bytes captured before and after the position of a decode failure.
+const DIAGNOSTIC_WINDOW: usize = 16;
+
+/// Number of most recently read bytes kept for a decode diagnostic.
+const DIAGNOSTIC_RECENT: usize = 256;
+
+/// Description of a range of bytes read by the deserializer.
+#[derive(Debug, Clone)]
+pub(crate) struct Annotation {
+    pub start: u64,
+    pub end: u64,
+    pub desc: String,
+}
+
+/// Formats bytes as a hex dump with offsets and printable characters.
+///
+/// # Example
+///
+/// ```rust
+/// let dump = postbag::hexdump(b"postbag");
+/// assert_eq!(dump, "00000000  70 6f 73 74 62 61 67                              |postbag|\n");
+/// ```
+pub fn hexdump(slice: &[u8]) -> String {
+    let mut out = String::new();
+
+    for (line, chunk) in slice.chunks(LINE_LEN).enumerate() {
+        write!(out, "{:08x} ", line * LINE_LEN).unwrap();
+        for i in 0..LINE_LEN {
+            if i % 8 == 0 {
+                out.push(' ');
+            }
+            match chunk.get(i) {
+                Some(b) => write!(out, "{b:02x} ").unwrap(),
+                None => out.push_str("   "),
+            }
+        }
+
+        out.push_str(" |");
+        out.extend(chunk.iter().map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' }));
+        out.push_str("|\n");
+    }
+
+    out
+}
+
+/// Deserializes a value of type `T` from a slice and describes the bytes read.
+///
+/// Each line of the output contains a byte range, the bytes within it and a
+/// description of the primitive value, length prefix, identifier or enum
+/// variant read from it. Bytes not described, such as skippable block headers
+/// and skipped data, are marked as `(framing)`. If deserialization fails, the
+/// bytes read so far are described and the error is appended.
+///
+/// This is intended for debugging malformed data; the output format is not stable.
+///
+/// In fast compile mode, the fields of structs serialized with the [`Full`](crate::cfg::Full)
+/// configuration are buffered and thus not described.
+///
+/// # Example
+///
+/// ```rust
+/// use postbag::{annotate, cfg::Slim, to_slim_vec};
+///
+/// let bytes = to_slim_vec(&vec!["abc".to_string()]).unwrap();
+/// let dump = annotate::<Slim, Vec<String>>(&bytes);
+/// assert!(dump.contains(r#"string "abc""#));
+/// ```
+pub fn annotate<CFG, T>(slice: &[u8]) -> String
+where
+    CFG: Cfg,
+    T: DeserializeOwned,
+{
+    let mut deserializer = Deserializer::<_, CFG>::with_remaining(slice, slice.len() as u64);
+    deserializer.start_annotating();
+    let res = T::deserialize(&mut deserializer);
+    let mut annotations = deserializer.take_annotations();
+    annotations.sort_by_key(|a| a.start);
+
+    let mut out = String::new();
+    let mut pos = 0;
+    for Annotation { start, end, desc } in annotations {
+        if start > pos {
+            annotate_line(&mut out, slice, pos, start, "(framing)");
+        }
+        annotate_line(&mut out, slice, start, end, &desc);
+        pos = pos.max(end);
+    }
+
+    match res {
+        Ok(_) if pos < slice.len() as u64 => {
+            annotate_line(&mut out, slice, pos, slice.len() as u64, "(trailing)")
+        }
+        Ok(_) => (),
+        Err(err) => {
+            if pos < slice.len() as u64 {
+                annotate_line(&mut out, slice, pos, slice.len() as u64, "(not decoded)");
+            }
+            writeln!(out, "error: {err}").unwrap();
+        }
+    }
+
+    out
+}
+
+/// Writes a line describing the bytes from `start` to `end`.
+fn annotate_line(out: &mut String, slice: &[u8], start: u64, end: u64, desc: &str) {
+    let bytes = &slice[start as usize..end as usize];
+
+    let mut hex = String::new();
+    for b in bytes.iter().take(ANNOTATION_BYTES) {
+        write!(hex, "{b:02x} ").unwrap();
+    }
+    if bytes.len() > ANNOTATION_BYTES {
+        hex.push_str("..");
+    }
+
+    writeln!(out, "{start:>6}..{end:<6} {hex:<26} {desc}").unwrap();
+}
+
+/// Diagnostic context of a decode failure.
+///
+/// Produced by [`DeserializerBuilder::deserialize_with_diagnostic`](crate::DeserializerBuilder::deserialize_with_diagnostic).
+/// It can itself be serialized, for example to record decode failures in a structured log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeDiagnostic {
+    /// Number of bytes read when the failure was detected.
+    pub offset: u64,
+    /// Offset of the first byte of [`window`](Self::window).
+    pub window_start: u64,
+    /// Bytes around the offset of the failure, as far as available.
+    pub window: Vec<u8>,
+    /// Name of the type being deserialized.
+    pub type_name: String,
+    /// Description of the error.
+    pub message: String,
+}
+
+const DIAGNOSTIC_FIELDS: &[&str] = &["offset", "window_start", "window", "type_name", "message"];
+
+impl Serialize for DecodeDiagnostic {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("DecodeDiagnostic", DIAGNOSTIC_FIELDS.len())?;
+        s.serialize_field("offset", &self.offset)?;
+        s.serialize_field("window_start", &self.window_start)?;
+        s.serialize_field("window", &self.window)?;
+        s.serialize_field("type_name", &self.type_name)?;
+        s.serialize_field("message", &self.message)?;
+        s.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for DecodeDiagnostic {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_struct("DecodeDiagnostic", DIAGNOSTIC_FIELDS, DiagnosticVisitor)
+    }
+}
+
+struct DiagnosticVisitor;
+
+impl<'de> Visitor<'de> for DiagnosticVisitor {
+    type Value = DecodeDiagnostic;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a decode diagnostic")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<DecodeDiagnostic, A::Error> {
+        let missing = |i| de::Error::invalid_length(i, &self);
+        Ok(DecodeDiagnostic {
+            offset: seq.next_element()?.ok_or_else(|| missing(0))?,
+            window_start: seq.next_element()?.ok_or_else(|| missing(1))?,
+            window: seq.next_element()?.ok_or_else(|| missing(2))?,
+            type_name: seq.next_element()?.ok_or_else(|| missing(3))?,
+            message: seq.next_element()?.ok_or_else(|| missing(4))?,
+        })
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<DecodeDiagnostic, A::Error> {
+        let (mut offset, mut window_start, mut window, mut type_name, mut message) =
+            (None, None, None, None, None);
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "offset" => offset = Some(map.next_value()?),
+                "window_start" => window_start = Some(map.next_value()?),
+                "window" => window = Some(map.next_value()?),
+                "type_name" => type_name = Some(map.next_value()?),
+                "message" => message = Some(map.next_value()?),
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        let missing = |field| de::Error::missing_field(field);
+        Ok(DecodeDiagnostic {
+            offset: offset.ok_or_else(|| missing("offset"))?,
+            window_start: window_start.ok_or_else(|| missing("window_start"))?,
+            window: window.ok_or_else(|| missing("window"))?,
+            type_name: type_name.ok_or_else(|| missing("type_name"))?,
+            message: message.ok_or_else(|| missing("message"))?,
+        })
+    }
+}
+
+/// Reader keeping the most recently read bytes for a decode diagnostic.
+pub(crate) struct RecentRead<R> {
+    inner: R,
+    recent: VecDeque<u8>,
+    /// Number of bytes read from the inner reader.
+    total: u64,
+}
+
+impl<R: Read> RecentRead<R> {
+    pub fn new(inner: R) -> Self {
+        Self { inner, recent: VecDeque::with_capacity(DIAGNOSTIC_RECENT), total: 0 }
+    }
+
+    /// Creates the diagnostic of a failure at the specified offset.
+    ///
+    /// Reads the bytes following the offset from the inner reader, as far as available.
+    pub fn diagnostic(mut self, offset: u64, type_name: &str, message: String) -> DecodeDiagnostic {
+        let end = offset.saturating_add(DIAGNOSTIC_WINDOW as u64);
+        if self.total < end {
+            let mut buf = vec![0; (end - self.total) as usize];
+            let mut filled = 0;
+            while filled < buf.len() {
+                match self.read(&mut buf[filled..]) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => filled += n,
+                }
+            }
+        }
+
+        let recent_start = self.total - self.recent.len() as u64;
+        let window_start = offset.saturating_sub(DIAGNOSTIC_WINDOW as u64).max(recent_start);
+        let window_end = end.min(self.total).max(window_start);
+        let window = self
+            .recent
+            .range((window_start - recent_start) as usize..(window_end - recent_start) as usize)
+            .copied();
+
+        DecodeDiagnostic {
+            offset,
+            window_start,
+            window: window.collect(),
+            type_name: type_name.to_string(),
+            message,
+        }
+    }
+}
+
+impl<R: Read> Read for RecentRead<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.total += n as u64;
+        for &b in &buf[..n] {
+            if self.recent.len() == DIAGNOSTIC_RECENT {
+                self.recent.pop_front();
+            }
+            self.recent.push_back(b);
+        }
+        Ok(n)
+    }
+}
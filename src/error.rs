@@ -6,8 +6,10 @@ use std::fmt::{Display, Formatter};
 pub enum Error {
     /// [`deserialize_any`](serde::de::Deserializer::deserialize_any) is unsupported
     DeserializeAnyUnsupported,
-    /// End of block
+    /// Unexpected end of block, i.e. a value within a block is truncated
     EndOfBlock,
+    /// Input ended before the first byte of the value
+    EmptyInput,
     /// Found a varint that didn't terminate
     BadVarint,
     /// Found an invalid bool
@@ -18,20 +20,103 @@ pub enum Error {
     BadString,
     /// Found an invalid Option discriminant
     BadOption,
-    /// Found an invalid enum discriminant
+    /// Found an invalid enum discriminant, such as an index exceeding `u32::MAX`
     BadEnum,
-    /// Bad length of a sequence or map
+    /// Bad length of a sequence, map or struct
     BadLen,
     /// Bad identifier
     BadIdentifier,
     /// Overflow of target usize
     UsizeOverflow,
+    /// Sequence of unknown length is unsupported by the configuration
+    SerializeSeqLengthUnknown {
+        /// Setting of the configuration causing the rejection.
+        cause: UnknownLenCause,
+    },
+    /// Map of unknown length is unsupported by the configuration
+    SerializeMapLengthUnknown {
+        /// Setting of the configuration causing the rejection.
+        cause: UnknownLenCause,
+    },
+    /// Sequence or map of unknown length found, forbidden by the configuration
+    UnknownLengthForbidden,
+    /// Data is not in canonical form
+    NonCanonical,
+    /// Data remaining after the end of the value
+    TrailingData,
+    /// Value other than an enum deserialized after peeking the variant of an enum
+    PeekedVariant,
+    /// Maximum nesting depth exceeded
+    DepthLimitExceeded,
+    /// Maximum length of a string or byte array exceeded
+    AllocLimitExceeded,
+    /// Maximum number of bytes to read exceeded
+    ReadLimitExceeded,
+    /// Maximum size of the serialized data exceeded
+    SizeLimitExceeded,
+    /// Size of a frame body exceeds the range of its length prefix
+    FrameTooLarge,
+    /// Elements of a strided sequence differ in serialized size
+    NonUniformStride,
+    /// Struct fields not in declaration order
+    FieldOrderMismatch,
+    /// Struct field serialized after a skipped field, unsupported by the configuration
+    SkippedField,
+    /// Checksum does not match the data
+    BadChecksum,
+    /// Authentication of encrypted data failed
+    AuthFailed,
+    /// Width of a fixed-size integer does not match its type
+    BadFixint {
+        /// Width of the type in bytes.
+        expected: usize,
+        /// Width found in the data in bytes.
+        found: usize,
+    },
+    /// Version of a versioned newtype does not match
+    BadVersion {
+        /// Version of the type.
+        expected: u8,
+        /// Version found in the data.
+        found: u8,
+    },
+    /// Fingerprint of the type does not match the fingerprint found in the data
+    FingerprintMismatch {
+        /// Fingerprint of the type.
+        expected: u64,
+        /// Fingerprint found in the data.
+        found: u64,
+    },
+    /// Type cannot be traced to compute its fingerprint
+    Untraceable(String),
     /// Serde custom error
     Custom(String),
     /// I/O error.
     Io(std::io::Error),
 }
 
+/// Setting of the configuration that rejects sequences and maps of unknown length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UnknownLenCause {
+    /// [`Cfg::canonical`](crate::cfg::Cfg::canonical) is enabled
+    Canonical,
+    /// [`Cfg::unknown_len`](crate::cfg::Cfg::unknown_len) is disabled
+    UnknownLenDisabled,
+    /// [`Cfg::postcard_compat`](crate::cfg::Cfg::postcard_compat) is enabled
+    PostcardCompat,
+}
+
+impl Display for UnknownLenCause {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Canonical => write!(f, "canonical encoding"),
+            Self::UnknownLenDisabled => write!(f, "unknown_len disabled"),
+            Self::PostcardCompat => write!(f, "postcard_compat enabled"),
+        }
+    }
+}
+
 impl From<std::io::Error> for Error {
     fn from(err: std::io::Error) -> Self {
         Self::Io(err)
@@ -47,8 +132,12 @@ impl From<Error> for std::io::Error {
         }
 
         let kind = match &err {
-            Error::DeserializeAnyUnsupported => ErrorKind::Unsupported,
-            Error::EndOfBlock => ErrorKind::UnexpectedEof,
+            Error::DeserializeAnyUnsupported
+            | Error::SerializeSeqLengthUnknown { .. }
+            | Error::SerializeMapLengthUnknown { .. }
+            | Error::SkippedField
+            | Error::Untraceable(_) => ErrorKind::Unsupported,
+            Error::EndOfBlock | Error::EmptyInput => ErrorKind::UnexpectedEof,
             _ => ErrorKind::InvalidData,
         };
 
@@ -62,6 +151,7 @@ impl Display for Error {
         match self {
             DeserializeAnyUnsupported => write!(f, "deserialize_any is unsupported"),
             EndOfBlock => write!(f, "end of block"),
+            EmptyInput => write!(f, "empty input"),
             BadVarint => write!(f, "invalid integer"),
             BadBool => write!(f, "invalid bool"),
             BadChar => write!(f, "invalid char"),
@@ -71,6 +161,34 @@ impl Display for Error {
             BadEnum => write!(f, "invalid enum discriminant"),
             BadLen => write!(f, "invalid length"),
             UsizeOverflow => write!(f, "usize overflow"),
+            SerializeSeqLengthUnknown { cause } => {
+                write!(f, "sequence length unknown, unsupported by configuration ({cause})")
+            }
+            SerializeMapLengthUnknown { cause } => {
+                write!(f, "map length unknown, unsupported by configuration ({cause})")
+            }
+            UnknownLengthForbidden => write!(f, "unknown length forbidden by configuration"),
+            NonCanonical => write!(f, "data not in canonical form"),
+            TrailingData => write!(f, "trailing data after value"),
+            PeekedVariant => write!(f, "peeked enum variant not consumed by an enum"),
+            DepthLimitExceeded => write!(f, "nesting depth limit exceeded"),
+            AllocLimitExceeded => write!(f, "allocation limit exceeded"),
+            ReadLimitExceeded => write!(f, "read limit exceeded"),
+            SizeLimitExceeded => write!(f, "size limit exceeded"),
+            FrameTooLarge => write!(f, "frame too large for length prefix"),
+            NonUniformStride => write!(f, "elements differ in serialized size"),
+            FieldOrderMismatch => write!(f, "struct fields not in declaration order"),
+            SkippedField => write!(f, "struct field after skipped field, unsupported by configuration"),
+            BadChecksum => write!(f, "checksum mismatch"),
+            AuthFailed => write!(f, "authentication failed"),
+            BadFixint { expected, found } => {
+                write!(f, "fixed-size integer width mismatch: expected {expected} bytes, found {found}")
+            }
+            BadVersion { expected, found } => write!(f, "version mismatch: expected {expected}, found {found}"),
+            FingerprintMismatch { expected, found } => {
+                write!(f, "type fingerprint mismatch: expected {expected:#018x}, found {found:#018x}")
+            }
+            Untraceable(msg) => write!(f, "{msg}"),
             Custom(msg) => write!(f, "serde error: {msg}"),
             Io(err) => write!(f, "IO error: {err}"),
         }
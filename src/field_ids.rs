@@ -0,0 +1,56 @@
+//! # Field IDs
+//!
+//! In the [`Full`](crate::cfg::Full) configuration struct fields are identified by
+//! their names, thus renaming a field in the source code breaks compatibility with
+//! previously serialized data. The `field_ids` attribute macro, which requires the
+//! `derive` feature, decouples the identifier of a field from its name: a field
+//! marked `#[postbag(id = N)]` is identified by the numeric identifier `_N`, which
+//! is encoded as a single byte for `N < 60`. Such a field can be renamed freely,
+//! as long as its ID is kept.
+//!
+//! Fields without an ID keep their names as identifiers. IDs must be unique within
+//! a struct, which is checked at compile time, and cannot be combined with
+//! `#[serde(rename)]`. The attribute must be placed before `#[derive(Serialize, Deserialize)]`.
+//!
+#![cfg_attr(feature = "derive", doc = "```rust")]
+#![cfg_attr(not(feature = "derive"), doc = "```rust,ignore")]
+//! use serde::{Serialize, Deserialize};
+//! use postbag::{field_ids, from_full_slice, to_full_vec};
+//!
+//! #[field_ids]
+//! #[derive(Serialize, Deserialize)]
+//! struct UserV1 {
+//!     #[postbag(id = 0)]
+//!     name: String,
+//!     #[postbag(id = 1)]
+//!     mail: String,
+//! }
+//!
+//! #[field_ids]
+//! #[derive(Serialize, Deserialize, Debug, PartialEq)]
+//! struct UserV2 {
+//!     #[postbag(id = 1)]
+//!     email: String,
+//!     #[postbag(id = 0)]
+//!     display_name: String,
+//! }
+//!
+//! let data = to_full_vec(&UserV1 { name: "Alice".into(), mail: "alice@example.com".into() }).unwrap();
+//! let user: UserV2 = from_full_slice(&data).unwrap();
+//! assert_eq!(user, UserV2 { email: "alice@example.com".into(), display_name: "Alice".into() });
+//! ```
+//!
+//! Duplicate IDs are rejected:
+//!
+#![cfg_attr(feature = "derive", doc = "```compile_fail")]
+#![cfg_attr(not(feature = "derive"), doc = "```rust,ignore")]
+//! # use serde::Serialize;
+//! #[postbag::field_ids]
+//! #[derive(Serialize)]
+//! struct Duplicate {
+//!     #[postbag(id = 3)]
+//!     first: u32,
+//!     #[postbag(id = 3)]
+//!     second: u32,
+//! }
+//! ```
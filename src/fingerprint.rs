@@ -0,0 +1,553 @@
+//! # Schema Fingerprints
+//!
+//! [`fingerprint`] computes a stable 64-bit fingerprint of the structure of a type,
+//! covering the names and order of struct fields, the names and payloads of enum
+//! variants, the names of containers and the types of primitives.
+//! It is computed on first use by driving a recording deserializer over the type's
+//! `Deserialize` implementation, and cached afterwards.
+//!
+//! [`to_vec_fingerprinted`] prefixes the serialized value by the fingerprint of its type.
+//! [`from_slice_fingerprinted`] verifies it before deserializing, rejecting data
+//! serialized from a type of another structure with [`Error::FingerprintMismatch`].
+//! This guards against accidentally decoding data using a mismatched type.
+//! Unlike the compatibility offered by [`Full`](crate::cfg::Full), any change
+//! of the structure is rejected, including added fields.
+//!
+//! ```rust
+//! use serde::{Serialize, Deserialize};
+//! use postbag::{Error, cfg::Slim, from_slice_fingerprinted, to_vec_fingerprinted};
+//!
+//! #[derive(Serialize, Deserialize, Debug, PartialEq)]
+//! struct Point {
+//!     x: u32,
+//!     y: u32,
+//! }
+//!
+//! #[derive(Serialize, Deserialize, Debug, PartialEq)]
+//! struct Size {
+//!     width: u32,
+//!     height: u32,
+//! }
+//!
+//! let data = to_vec_fingerprinted::<Slim, _>(&Point { x: 1, y: 2 }).unwrap();
+//! assert_eq!(from_slice_fingerprinted::<Slim, Point>(&data).unwrap(), Point { x: 1, y: 2 });
+//!
+//! let res = from_slice_fingerprinted::<Slim, Size>(&data);
+//! assert!(matches!(res, Err(Error::FingerprintMismatch { .. })));
+//! ```
+//!
+//! The fingerprint is stable across program runs and compiler versions.
+//! Types that can only be deserialized from particular values, such as types
+//! validating their contents, or that require `deserialize_any` cannot be traced.
+
+use std::{
+    any::{TypeId, type_name},
+    collections::{BTreeMap, BTreeSet, HashMap},
+    io,
+    sync::{Mutex, OnceLock},
+};
+
+use serde::{
+    Serialize,
+    de::{
+        self, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+        VariantAccess, Visitor, value::U32Deserializer,
+    },
+};
+
+use crate::{
+    cfg::Cfg,
+    de::from_slice,
+    error::{Error, Result},
+    ser::serialize,
+};
+
+/// Size of the fingerprint prefix in bytes.
+const FINGERPRINT_LEN: usize = 8;
+
+/// Maximum nesting depth within a recursive type before tracing of it is considered non-terminating.
+const MINIMAL_DEPTH_MAX: usize = 256;
+
+/// Returns the fingerprint of the structure of type `T`.
+///
+/// See the [module documentation](self) for details.
+///
+/// # Panics
+///
+/// Panics if the type cannot be traced.
+pub fn fingerprint<T>() -> u64
+where
+    T: DeserializeOwned + 'static,
+{
+    try_fingerprint::<T>().unwrap_or_else(|err| panic!("{err}"))
+}
+
+/// Returns the fingerprint of the structure of type `T`, failing with
+/// [`Error::Untraceable`] if the type cannot be traced.
+fn try_fingerprint<T>() -> Result<u64>
+where
+    T: DeserializeOwned + 'static,
+{
+    static CACHE: OnceLock<Mutex<HashMap<TypeId, u64>>> = OnceLock::new();
+
+    let cache = CACHE.get_or_init(Default::default);
+    if let Some(fingerprint) = cache.lock().unwrap().get(&TypeId::of::<T>()) {
+        return Ok(*fingerprint);
+    }
+
+    let fingerprint = trace::<T>()?;
+    cache.lock().unwrap().insert(TypeId::of::<T>(), fingerprint);
+    Ok(fingerprint)
+}
+
+/// Serialize a value using the specified configuration prefixed by the fingerprint of its type
+/// as a little-endian `u64`.
+///
+/// Fails with [`Error::Untraceable`] if the type cannot be traced.
+pub fn to_vec_fingerprinted<CFG, T>(value: &T) -> Result<Vec<u8>>
+where
+    CFG: Cfg,
+    T: Serialize + DeserializeOwned + 'static,
+{
+    let mut data = try_fingerprint::<T>()?.to_le_bytes().to_vec();
+    serialize::<CFG, _, _>(&mut data, value)?;
+    Ok(data)
+}
+
+/// Deserialize a value serialized by [`to_vec_fingerprinted`] using the specified configuration,
+/// verifying the fingerprint of its type.
+///
+/// A mismatch is reported as [`Error::FingerprintMismatch`].
+/// Fails with [`Error::Untraceable`] if the type cannot be traced.
+pub fn from_slice_fingerprinted<CFG, T>(slice: &[u8]) -> Result<T>
+where
+    CFG: Cfg,
+    T: DeserializeOwned + 'static,
+{
+    let Some((found, data)) = slice.split_first_chunk::<FINGERPRINT_LEN>() else {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    };
+
+    let expected = try_fingerprint::<T>()?;
+    let found = u64::from_le_bytes(*found);
+    if found != expected {
+        return Err(Error::FingerprintMismatch { expected, found });
+    }
+
+    from_slice::<CFG, T>(data)
+}
+
+/// Traces the structure of `T` and returns its fingerprint.
+///
+/// The type is deserialized repeatedly until all variants of all reachable
+/// enums have been explored.
+fn trace<T: DeserializeOwned>() -> Result<u64> {
+    let mut tracer = Tracer::default();
+    let mut root = None;
+
+    loop {
+        tracer.start_run();
+        match tracer.record(ROOT.to_vec(), |tracer| T::deserialize(tracer).map(drop)) {
+            Ok(((), hash)) => {
+                root.get_or_insert(hash);
+            }
+            Err(_) if tracer.non_terminating => {
+                // Choose another variant for the innermost enum while producing minimal values.
+                let enum_key = tracer.last_minimal_enum.take();
+                match enum_key.and_then(|key| tracer.minimal_variants.get_mut(&key)) {
+                    Some((variant, count)) if *variant + 1 < *count => *variant += 1,
+                    _ => {
+                        return Err(Error::Untraceable(format!(
+                            "cannot fingerprint {}: recursion does not terminate",
+                            type_name::<T>()
+                        )));
+                    }
+                }
+                continue;
+            }
+            Err(err) => {
+                return Err(Error::Untraceable(format!("cannot fingerprint {}: {err}", type_name::<T>())));
+            }
+        }
+
+        // Explore the next variant of the innermost enum having unexplored variants.
+        let next = tracer.encountered.iter().rev().find_map(|key| {
+            let (variant, count) = tracer.variants.get(key).unwrap();
+            (*variant + 1 < *count).then(|| key.clone())
+        });
+        match next {
+            Some(key) => tracer.variants.get_mut(&key).unwrap().0 += 1,
+            None => break,
+        }
+    }
+
+    let mut data = root.unwrap().to_le_bytes().to_vec();
+    for record in &tracer.variant_records {
+        data.extend_from_slice(record);
+    }
+    Ok(fnv1a(&data))
+}
+
+/// 64-bit FNV-1a hash.
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3))
+}
+
+// Tokens recorded for the structure of a type.
+const ROOT: &[u8] = b"root";
+const BOOL: u8 = 1;
+const I8: u8 = 2;
+const I16: u8 = 3;
+const I32: u8 = 4;
+const I64: u8 = 5;
+const I128: u8 = 6;
+const U8: u8 = 7;
+const U16: u8 = 8;
+const U32: u8 = 9;
+const U64: u8 = 10;
+const U128: u8 = 11;
+const F32: u8 = 12;
+const F64: u8 = 13;
+const CHAR: u8 = 14;
+const STR: u8 = 15;
+const BYTES: u8 = 16;
+const OPTION: u8 = 17;
+const UNIT: u8 = 18;
+const UNIT_STRUCT: u8 = 19;
+const NEWTYPE_STRUCT: u8 = 20;
+const SEQ: u8 = 21;
+const TUPLE: u8 = 22;
+const TUPLE_STRUCT: u8 = 23;
+const MAP: u8 = 24;
+const STRUCT: u8 = 25;
+const ENUM: u8 = 26;
+const IDENTIFIER: u8 = 27;
+const IGNORED: u8 = 28;
+const UNIT_VARIANT: u8 = 29;
+const NEWTYPE_VARIANT: u8 = 30;
+const TUPLE_VARIANT: u8 = 31;
+const STRUCT_VARIANT: u8 = 32;
+/// Reference to a container by its key, used for recursion.
+const REF: u8 = 33;
+/// Hash of a traced container.
+const HASH: u8 = 34;
+
+/// Builds the key of a container from its kind, name and parts, such as field names.
+fn key(kind: u8, name: &str, parts: &[&str]) -> Vec<u8> {
+    let mut key = vec![kind];
+    for part in [name].iter().chain(parts) {
+        key.extend_from_slice(&(part.len() as u64).to_le_bytes());
+        key.extend_from_slice(part.as_bytes());
+    }
+    key
+}
+
+/// Deserializer recording the structure of the deserialized type.
+///
+/// Containers are hashed from their key and contents, and the hash is recorded
+/// within the parent. A container nested within itself is recorded by its key and
+/// deserialized from minimal values, i.e. no optional values and empty sequences.
+#[derive(Default)]
+struct Tracer {
+    /// Recorded tokens of the containers being traced.
+    bufs: Vec<Vec<u8>>,
+    /// Keys of the containers being traced.
+    stack: Vec<Vec<u8>>,
+    /// Nesting depth of containers deserialized from minimal values.
+    minimal: usize,
+    /// Set if the nesting depth of minimal values has been exceeded.
+    non_terminating: bool,
+    /// Explored variant and variant count of each enum by key.
+    variants: BTreeMap<Vec<u8>, (u32, u32)>,
+    /// Enums encountered in the current run, in order.
+    encountered: Vec<Vec<u8>>,
+    /// Variant used for minimal values and variant count of each enum by key.
+    minimal_variants: BTreeMap<Vec<u8>, (u32, u32)>,
+    /// Enum of which a variant was most recently chosen for minimal values.
+    last_minimal_enum: Option<Vec<u8>>,
+    /// Enum key, variant index and hash of the payload of each traced variant.
+    variant_records: BTreeSet<Vec<u8>>,
+}
+
+impl Tracer {
+    fn start_run(&mut self) {
+        self.bufs = vec![Vec::new()];
+        self.stack.clear();
+        self.minimal = 0;
+        self.non_terminating = false;
+        self.encountered.clear();
+    }
+
+    /// Records a token, unless producing minimal values.
+    fn token(&mut self, tag: u8, data: &[u8]) {
+        if self.minimal == 0 {
+            let buf = self.bufs.last_mut().unwrap();
+            buf.push(tag);
+            buf.extend_from_slice(&(data.len() as u64).to_le_bytes());
+            buf.extend_from_slice(data);
+        }
+    }
+
+    /// Traces a container and returns the hash of its key and contents.
+    fn record<T>(&mut self, key: Vec<u8>, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<(T, u64)> {
+        self.stack.push(key);
+        self.bufs.push(Vec::new());
+        let res = f(self);
+        let buf = self.bufs.pop().unwrap();
+        let mut key = self.stack.pop().unwrap();
+        let value = res?;
+
+        key.extend_from_slice(&buf);
+        Ok((value, fnv1a(&key)))
+    }
+
+    /// Traces a container and records its hash, or its key if nested within itself.
+    fn container<T>(&mut self, key: Vec<u8>, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        if self.minimal == 0 && !self.stack.contains(&key) {
+            let (value, hash) = self.record(key, f)?;
+            self.token(HASH, &hash.to_le_bytes());
+            return Ok(value);
+        }
+
+        self.token(REF, &key);
+        self.minimal += 1;
+        let res = if self.minimal > MINIMAL_DEPTH_MAX {
+            self.non_terminating = true;
+            Err(Error::Custom("recursion does not terminate".to_string()))
+        } else {
+            self.stack.push(key);
+            let res = f(self);
+            self.stack.pop();
+            res
+        };
+        self.minimal -= 1;
+        res
+    }
+
+    /// Chooses the variant of an enum to deserialize.
+    fn choose_variant(&mut self, key: &[u8], count: u32) -> u32 {
+        if self.minimal > 0 {
+            self.last_minimal_enum = Some(key.to_vec());
+            return self.minimal_variants.entry(key.to_vec()).or_insert((0, count)).0;
+        }
+
+        if !self.encountered.iter().any(|k| k == key) {
+            self.encountered.push(key.to_vec());
+        }
+        self.variants.entry(key.to_vec()).or_insert((0, count)).0
+    }
+
+    /// Traces the payload of an enum variant and records its hash.
+    fn variant<T>(&mut self, key: Vec<u8>, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        if self.minimal > 0 {
+            return f(self);
+        }
+
+        let (value, hash) = self.record(key.clone(), f)?;
+        let mut record = key;
+        record.extend_from_slice(&hash.to_le_bytes());
+        self.variant_records.insert(record);
+        Ok(value)
+    }
+
+    /// Number of elements of a sequence or map.
+    fn len(&self) -> usize {
+        usize::from(self.minimal == 0)
+    }
+}
+
+macro_rules! trace_primitive {
+    ($method:ident, $visit:ident, $tag:expr $(, $value:expr)?) => {
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+            self.token($tag, &[]);
+            visitor.$visit($($value)?)
+        }
+    };
+}
+
+impl<'de> de::Deserializer<'de> for &mut Tracer {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
+        Err(Error::DeserializeAnyUnsupported)
+    }
+
+    trace_primitive!(deserialize_bool, visit_bool, BOOL, false);
+    trace_primitive!(deserialize_i8, visit_i8, I8, 1);
+    trace_primitive!(deserialize_i16, visit_i16, I16, 1);
+    trace_primitive!(deserialize_i32, visit_i32, I32, 1);
+    trace_primitive!(deserialize_i64, visit_i64, I64, 1);
+    trace_primitive!(deserialize_i128, visit_i128, I128, 1);
+    trace_primitive!(deserialize_u8, visit_u8, U8, 1);
+    trace_primitive!(deserialize_u16, visit_u16, U16, 1);
+    trace_primitive!(deserialize_u32, visit_u32, U32, 1);
+    trace_primitive!(deserialize_u64, visit_u64, U64, 1);
+    trace_primitive!(deserialize_u128, visit_u128, U128, 1);
+    trace_primitive!(deserialize_f32, visit_f32, F32, 0.0);
+    trace_primitive!(deserialize_f64, visit_f64, F64, 0.0);
+    trace_primitive!(deserialize_char, visit_char, CHAR, 'a');
+    trace_primitive!(deserialize_str, visit_str, STR, "");
+    trace_primitive!(deserialize_string, visit_str, STR, "");
+    trace_primitive!(deserialize_bytes, visit_bytes, BYTES, &[]);
+    trace_primitive!(deserialize_byte_buf, visit_bytes, BYTES, &[]);
+    trace_primitive!(deserialize_unit, visit_unit, UNIT);
+    trace_primitive!(deserialize_identifier, visit_u32, IDENTIFIER, 0);
+    trace_primitive!(deserialize_ignored_any, visit_unit, IGNORED);
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.token(OPTION, &[]);
+        if self.minimal > 0 { visitor.visit_none() } else { visitor.visit_some(self) }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, name: &'static str, visitor: V) -> Result<V::Value> {
+        self.token(UNIT_STRUCT, name.as_bytes());
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, name: &'static str, visitor: V) -> Result<V::Value> {
+        self.container(key(NEWTYPE_STRUCT, name, &[]), |tracer| visitor.visit_newtype_struct(tracer))
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.token(SEQ, &[]);
+        let len = self.len();
+        visitor.visit_seq(TraceSeq { tracer: self, len })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value> {
+        self.token(TUPLE, &(len as u64).to_le_bytes());
+        visitor.visit_seq(TraceSeq { tracer: self, len })
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self, name: &'static str, len: usize, visitor: V,
+    ) -> Result<V::Value> {
+        self.container(key(TUPLE_STRUCT, name, &[&len.to_string()]), |tracer| {
+            visitor.visit_seq(TraceSeq { tracer, len })
+        })
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.token(MAP, &[]);
+        let len = self.len();
+        visitor.visit_map(TraceMap { tracer: self, len })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self, name: &'static str, fields: &'static [&'static str], visitor: V,
+    ) -> Result<V::Value> {
+        self.container(key(STRUCT, name, fields), |tracer| {
+            visitor.visit_seq(TraceSeq { tracer, len: fields.len() })
+        })
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self, name: &'static str, variants: &'static [&'static str], visitor: V,
+    ) -> Result<V::Value> {
+        let key = key(ENUM, name, variants);
+        self.container(key.clone(), |tracer| {
+            let index = tracer.choose_variant(&key, variants.len() as u32);
+            visitor.visit_enum(TraceEnum { tracer, key, index })
+        })
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+struct TraceSeq<'a> {
+    tracer: &'a mut Tracer,
+    len: usize,
+}
+
+impl<'de> SeqAccess<'de> for TraceSeq<'_> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        if self.len == 0 {
+            return Ok(None);
+        }
+        self.len -= 1;
+        seed.deserialize(&mut *self.tracer).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
+struct TraceMap<'a> {
+    tracer: &'a mut Tracer,
+    len: usize,
+}
+
+impl<'de> MapAccess<'de> for TraceMap<'_> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        if self.len == 0 {
+            return Ok(None);
+        }
+        self.len -= 1;
+        seed.deserialize(&mut *self.tracer).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        seed.deserialize(&mut *self.tracer)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
+struct TraceEnum<'a> {
+    tracer: &'a mut Tracer,
+    key: Vec<u8>,
+    index: u32,
+}
+
+impl<'de> EnumAccess<'de> for TraceEnum<'_> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(mut self, seed: V) -> Result<(V::Value, Self)> {
+        let deserializer: U32Deserializer<Error> = self.index.into_deserializer();
+        let value = seed.deserialize(deserializer)?;
+        self.key.extend_from_slice(&self.index.to_le_bytes());
+        Ok((value, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for TraceEnum<'_> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        self.tracer.variant(self.key, |tracer| {
+            tracer.token(UNIT_VARIANT, &[]);
+            Ok(())
+        })
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        self.tracer.variant(self.key, |tracer| {
+            tracer.token(NEWTYPE_VARIANT, &[]);
+            seed.deserialize(&mut *tracer)
+        })
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value> {
+        self.tracer.variant(self.key, |tracer| {
+            tracer.token(TUPLE_VARIANT, &(len as u64).to_le_bytes());
+            visitor.visit_seq(TraceSeq { tracer, len })
+        })
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value> {
+        self.tracer.variant(self.key, |tracer| {
+            tracer.token(STRUCT_VARIANT, &key(STRUCT_VARIANT, "", fields));
+            visitor.visit_seq(TraceSeq { tracer, len: fields.len() })
+        })
+    }
+}
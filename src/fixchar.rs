@@ -0,0 +1,67 @@
+//! # Fixed Size Chars
+//!
+//! By default a `char` is serialized as a UTF-8 string with a length prefix,
+//! thus occupying between two and five bytes. This module, for use with
+//! `#[serde(with = "postbag::fixchar")]`, serializes a `char` as its
+//! Unicode code point in a fixed size four byte integer instead, in the byte
+//! order given by [`Cfg::endian`](crate::cfg::Cfg::endian), little-endian by default.
+//!
+//! Deserializing an invalid Unicode scalar value, such as a surrogate,
+//! fails with [`Error::BadChar`](crate::Error::BadChar).
+//!
+//! ```rust
+//! # use serde::{Serialize, Deserialize};
+//! #[derive(Serialize, Deserialize)]
+//! pub struct Cell {
+//!     #[serde(with = "postbag::fixchar")]
+//!     c: char,
+//! }
+//! ```
+
+use std::fmt;
+
+use serde::{
+    Deserialize, Deserializer, Serializer,
+    de::{self, Unexpected, Visitor},
+};
+
+/// Newtype struct name recognized by the deserializer.
+pub(crate) const NAME: &str = "$postbag::FixChar";
+
+/// Serialize the char as a fixed size code point.
+pub fn serialize<S>(val: &char, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_newtype_struct(NAME, &u32::from(*val).to_le_bytes())
+}
+
+/// Deserialize the char from a fixed size code point.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<char, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_newtype_struct(NAME, FixCharVisitor)
+}
+
+struct FixCharVisitor;
+
+impl<'de> Visitor<'de> for FixCharVisitor {
+    type Value = char;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a Unicode code point")
+    }
+
+    fn visit_char<E>(self, v: char) -> Result<char, E> {
+        Ok(v)
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<char, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let code = u32::from_le_bytes(<[u8; 4]>::deserialize(deserializer)?);
+        char::from_u32(code).ok_or_else(|| de::Error::invalid_value(Unexpected::Unsigned(code.into()), &self))
+    }
+}
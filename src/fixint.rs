@@ -8,6 +8,10 @@
 //! field. The integer will always be serialized in the same way as a fixed
 //! size array.
 //!
+//! The byte order is determined by [`Cfg::endian`](crate::cfg::Cfg::endian).
+//!
+//! [`checked`] prefixes the integer by its width to detect width changes.
+//!
 //! Support explicitly not provided for `usize` or `isize`, as
 //! these types would not be portable between systems of different
 //! pointer widths.
@@ -21,7 +25,14 @@
 //! }
 //! ```
 
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, marker::PhantomData};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Visitor};
+
+pub mod checked;
+
+/// Newtype struct name recognized by the serializer and deserializer.
+pub(crate) const NAME: &str = "$postbag::FixInt";
 
 /// Serialize the integer value as a fixed-size array.
 pub fn serialize<S, T>(val: &T, serializer: S) -> Result<S::Ok, S::Error>
@@ -45,6 +56,8 @@ where
 #[doc(hidden)]
 pub struct LE<T>(T);
 
+struct LeVisitor<T>(PhantomData<T>);
+
 macro_rules! impl_fixint {
     ($( $int:ty ),*) => {
         $(
@@ -54,7 +67,7 @@ macro_rules! impl_fixint {
                 where
                     S: Serializer,
                 {
-                    self.0.to_le_bytes().serialize(serializer)
+                    serializer.serialize_newtype_struct(NAME, &self.0.to_le_bytes())
                 }
             }
 
@@ -63,10 +76,25 @@ macro_rules! impl_fixint {
                 fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
                 where
                     D: serde::Deserializer<'de>,
+                {
+                    deserializer.deserialize_newtype_struct(NAME, LeVisitor::<$int>(PhantomData))
+                }
+            }
+
+            impl<'de> Visitor<'de> for LeVisitor<$int> {
+                type Value = LE<$int>;
+
+                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                    formatter.write_str("a fixed-size integer")
+                }
+
+                fn visit_newtype_struct<D>(self, deserializer: D) -> Result<LE<$int>, D::Error>
+                where
+                    D: Deserializer<'de>,
                 {
                     <_ as Deserialize>::deserialize(deserializer)
                         .map(<$int>::from_le_bytes)
-                        .map(Self)
+                        .map(LE)
                 }
             }
         )*
@@ -0,0 +1,139 @@
+//! # Fixed Size Integers with Width Tag
+//!
+//! Like [`fixint`](super), but the integer is prefixed by a byte containing its width
+//! in bytes, for use with `#[serde(with = "postbag::fixint::checked")]`.
+//!
+//! When the width of a field is changed, for example from `u16` to `u32`, data serialized
+//! using the previous width is rejected with [`Error::BadFixint`](crate::Error::BadFixint)
+//! instead of being misinterpreted. This costs one byte per integer.
+//!
+//! ```rust
+//! use serde::{Serialize, Deserialize};
+//! use postbag::{Error, from_slim_slice, to_slim_vec};
+//!
+//! #[derive(Serialize)]
+//! pub struct Old {
+//!     #[serde(with = "postbag::fixint::checked")]
+//!     x: u16,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! pub struct New {
+//!     #[serde(with = "postbag::fixint::checked")]
+//!     x: u32,
+//! }
+//!
+//! let data = to_slim_vec(&Old { x: 1 }).unwrap();
+//! let res: Result<New, _> = from_slim_slice(&data);
+//! assert!(matches!(res, Err(Error::BadFixint { expected: 4, found: 2 })));
+//! ```
+
+use std::{fmt, marker::PhantomData, mem::size_of};
+
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{self, SeqAccess, Visitor},
+    ser::SerializeTupleStruct,
+};
+
+use super::LE;
+
+/// Tuple struct name recognized by the serializer.
+pub(crate) const NAME: &str = "$postbag::CheckedFixInt";
+
+/// Tuple struct name passing the expected width to the deserializer.
+///
+/// Consists of [`NAME`] followed by the expected width in bytes.
+/// The name is ignored by other formats, thus the tuple struct keeps its actual arity.
+fn expected_name(width: usize) -> &'static str {
+    match width {
+        2 => "$postbag::CheckedFixInt2",
+        4 => "$postbag::CheckedFixInt4",
+        8 => "$postbag::CheckedFixInt8",
+        16 => "$postbag::CheckedFixInt16",
+        _ => unreachable!("unsupported width {width}"),
+    }
+}
+
+/// Expected width passed by the tuple struct name, if it is the name of a checked integer.
+pub(crate) fn expected_width(name: &str) -> Option<usize> {
+    name.strip_prefix(NAME)?.parse().ok()
+}
+
+/// Serialize the integer value as its width followed by a fixed-size array.
+pub fn serialize<S, T>(val: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Copy,
+    Checked<T>: Serialize,
+{
+    Checked(*val).serialize(serializer)
+}
+
+/// Deserialize the integer value from its width followed by a fixed-size array.
+pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    Checked<T>: Deserialize<'de>,
+{
+    Checked::<T>::deserialize(deserializer).map(|x| x.0)
+}
+
+#[doc(hidden)]
+pub struct Checked<T>(T);
+
+struct CheckedVisitor<T>(PhantomData<T>);
+
+macro_rules! impl_checked {
+    ($( $int:ty ),*) => {
+        $(
+            impl Serialize for Checked<$int> {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where
+                    S: Serializer,
+                {
+                    let mut tuple = serializer.serialize_tuple_struct(NAME, 2)?;
+                    tuple.serialize_field(&(size_of::<$int>() as u8))?;
+                    tuple.serialize_field(&LE(self.0))?;
+                    tuple.end()
+                }
+            }
+
+            impl<'de> Deserialize<'de> for Checked<$int> {
+                fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                where
+                    D: Deserializer<'de>,
+                {
+                    deserializer.deserialize_tuple_struct(
+                        expected_name(size_of::<$int>()),
+                        2,
+                        CheckedVisitor::<$int>(PhantomData),
+                    )
+                }
+            }
+
+            impl<'de> Visitor<'de> for CheckedVisitor<$int> {
+                type Value = Checked<$int>;
+
+                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                    write!(formatter, "a fixed-size integer of {} bytes", size_of::<$int>())
+                }
+
+                fn visit_seq<A>(self, mut seq: A) -> Result<Checked<$int>, A::Error>
+                where
+                    A: SeqAccess<'de>,
+                {
+                    let width: u8 = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                    if usize::from(width) != size_of::<$int>() {
+                        return Err(de::Error::invalid_value(de::Unexpected::Unsigned(width.into()), &self));
+                    }
+
+                    let value: LE<$int> = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                    Ok(Checked(value.0))
+                }
+            }
+        )*
+    };
+}
+
+impl_checked![i16, i32, i64, i128, u16, u32, u64, u128];
@@ -0,0 +1,159 @@
+//! # Wire Format
+//!
+//! This module describes the serialized data format, for implementing compatible
+//! codecs in other languages, and provides the constants used by it.
+//!
+//! The format is not self-describing: the reader must know the type of each value.
+//! The configuration determines whether structs and enums are encoded using
+//! identifiers ([`Cfg::with_idents`](crate::cfg::Cfg::with_idents), e.g.
+//! [`Full`](crate::cfg::Full)) or by position (e.g. [`Slim`](crate::cfg::Slim)).
+//!
+//! ## Varints
+//!
+//! Unsigned integers wider than 8 bits, lengths and enum variant indices are encoded
+//! as varints: 7 bits per byte starting with the least significant bits, with the
+//! most significant bit of each byte set if another byte follows. A varint of a
+//! type with `N` bits takes at most `ceil(N / 7)` bytes and the unused bits of its
+//! last byte must be zero. See [`varint`](crate::varint) for an encoder.
+//! In canonical configurations a varint must not have redundant trailing zero bytes.
+//!
+//! Signed integers wider than 8 bits are zig-zag encoded before being written as
+//! varints, i.e. `n` is mapped to `2n` if non-negative and to `-2n - 1` otherwise,
+//! so that values of small magnitude yield short varints.
+//!
+//! ## Primitives
+//!
+//! | Type | Encoding |
+//! |------|----------|
+//! | `bool` | byte [`FALSE`] or [`TRUE`] |
+//! | `u8`, `i8` | single byte, `i8` in two's complement |
+//! | `u16` ..= `u128`, `usize` | varint |
+//! | `i16` ..= `i128`, `isize` | zig-zag varint |
+//! | `f32`, `f64` | IEEE 754 bits, little-endian unless [`Cfg::endian`](crate::cfg::Cfg::endian) is big |
+//! | `char` | like a string of its UTF-8 encoding |
+//! | string, bytes | varint length followed by the UTF-8 or raw bytes |
+//! | unit, unit struct | nothing |
+//! | `Option` | byte [`NONE`], or byte [`SOME`] followed by the value |
+//!
+//! With [`Cfg::skippable_options`](crate::cfg::Cfg::skippable_options) the value of
+//! `Some` is enclosed in a skippable block.
+//!
+//! ## Lengths
+//!
+//! The length of a string or byte array is a plain varint.
+//! The length of a sequence or map is a varint `n`, where the value [`SPECIAL_LEN`]
+//! is escaped and followed by a second varint:
+//!
+//! ```text
+//! length  := n                            (n != SPECIAL_LEN)
+//!          | SPECIAL_LEN SPECIAL_LEN      (literal length SPECIAL_LEN)
+//!          | SPECIAL_LEN UNKNOWN_LEN      (unknown length, elements in a skippable block)
+//!          | SPECIAL_LEN TERMINATED_LEN   (unknown length, terminated elements)
+//! ```
+//!
+//! With [`Cfg::postcard_compat`](crate::cfg::Cfg::postcard_compat) and without identifiers
+//! a length is always a plain varint.
+//!
+//! Unknown lengths are only written if supported by the configuration and are
+//! rejected in canonical configurations. An unknown length using [`UNKNOWN_LEN`]
+//! encloses all elements in a single skippable block, which ends after the last element.
+//! Using [`TERMINATED_LEN`] each element or map entry is preceded by the byte [`CONTINUE`]
+//! and the last one is followed by [`STOP`].
+//!
+//! ## Containers
+//!
+//! * A sequence is its length followed by its elements.
+//! * A map is its length followed by key and value of each entry.
+//! * Tuples, tuple structs and newtype structs are their elements without a length.
+//!   With [`Cfg::skippable_tuples`](crate::cfg::Cfg::skippable_tuples) tuples and tuple
+//!   structs are the number of elements followed by the elements enclosed in a skippable block.
+//! * A struct is the number of serialized fields followed by
+//!   * with identifiers: the identifier of each field followed by its value
+//!     enclosed in a skippable block,
+//!   * without identifiers: the field values in declaration order, all enclosed
+//!     in a single skippable block, which is omitted if no fields are serialized
+//!     and [`Cfg::omit_empty_struct_block`](crate::cfg::Cfg::omit_empty_struct_block)
+//!     is enabled,
+//!   * with [`Cfg::positional_ids`](crate::cfg::Cfg::positional_ids): the position of
+//!     each field as a varint followed by its value enclosed in a skippable block.
+//!
+//!   With [`Cfg::postcard_compat`](crate::cfg::Cfg::postcard_compat) and without identifiers
+//!   a struct is its field values in declaration order only.
+//! * An enum is its variant followed by the payload of the variant encoded like
+//!   the corresponding unit, newtype, tuple or struct. The variant is its identifier
+//!   with identifiers and the varint variant index otherwise, or a single byte with
+//!   [`Cfg::fixed_variant_index`](crate::cfg::Cfg::fixed_variant_index).
+//!
+//! Skippable blocks allow a reader to skip unknown fields and elements,
+//! enabling forward and backward compatibility.
+//!
+//! ## Identifiers
+//!
+//! An identifier is a varint `v` followed by optional data:
+//!
+//! ```text
+//! identifier := v bytes              (v < ID_LEN, identifier of v bytes)
+//!             | ID_LEN len bytes     (len >= ID_LEN in canonical form)
+//!             | v                    (ID_LEN_NAME <= v < ID_LEN_NAME + ID_COUNT)
+//! ```
+//!
+//! The last form is the numeric identifier `_N` with `N = v - ID_LEN_NAME`,
+//! as used by fields named `_0`, `_1`, ... and by [`numeric_variants`](mod@crate::numeric_variants).
+//! Such identifiers must use this form in canonical configurations.
+//!
+//! ## Skippable Blocks
+//!
+//! A skippable block is a sequence of chunks, each consisting of its length as a
+//! varint `u16` followed by its data. A chunk of length [`SKIP_CHUNK_MAX`] is followed
+//! by another chunk, while a shorter chunk, including an empty one, ends the block.
+//! Thus the data of a block may be split at arbitrary positions, not necessarily at
+//! value boundaries. Blocks nest: the chunk framing of an inner block is part of
+//! the data of the outer block.
+//!
+//! ## Helpers
+//!
+//! Helper modules for use with `#[serde(with)]`, such as [`fixint`](crate::fixint)
+//! or [`varint_slice`](crate::varint_slice), change the encoding of the values they
+//! are applied to. Their encodings are described in their documentation.
+
+/// Encoding of `false`.
+pub const FALSE: u8 = 0;
+/// Encoding of `true`.
+pub const TRUE: u8 = 1;
+
+/// Encoding of `None`.
+pub const NONE: u8 = 0;
+/// Encoding of `Some`, followed by the value.
+pub const SOME: u8 = 1;
+
+/// Length escape, followed by a second varint determining the meaning.
+pub const SPECIAL_LEN: usize = 125;
+/// Following [`SPECIAL_LEN`]: unknown length with the elements enclosed in a skippable block.
+pub const UNKNOWN_LEN: usize = 0;
+/// Following [`SPECIAL_LEN`]: unknown length with each element preceded by [`CONTINUE`]
+/// and the last one followed by [`STOP`].
+pub const TERMINATED_LEN: usize = 1;
+
+/// Flag preceding each element of a sequence or map of terminated length.
+pub const CONTINUE: u8 = 1;
+/// Flag ending a sequence or map of terminated length.
+pub const STOP: u8 = 0;
+
+/// Identifier prefix escaping identifiers of this length or more, followed by the actual length.
+pub const ID_LEN: usize = 64;
+/// Identifier prefix of the numeric identifier `_0`.
+pub const ID_LEN_NAME: usize = ID_LEN + 1;
+/// Number of numeric identifiers `_0` to `_{ID_COUNT - 1}` encoded as a single varint.
+pub const ID_COUNT: usize = 60;
+
+/// Parses an identifier of the form `_N` with `N` in canonical decimal notation.
+pub(crate) fn numeric_identifier(ident: &str) -> Option<usize> {
+    let digits = ident.strip_prefix('_')?;
+    let canonical = !digits.is_empty()
+        && digits.bytes().all(|b| b.is_ascii_digit())
+        && (digits == "0" || !digits.starts_with('0'));
+    if canonical { digits.parse().ok() } else { None }
+}
+
+/// Maximum length of a chunk of a skippable block, indicating that another chunk follows.
+pub const SKIP_CHUNK_MAX: usize = u16::MAX as usize;
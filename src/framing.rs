@@ -0,0 +1,221 @@
+//! # Length-Prefixed Frames
+//!
+//! [`FrameWriter`] writes each value as a frame consisting of the length of its
+//! serialized body followed by the body. [`FrameReader`] reads such frames from a
+//! byte stream, reading no more than the body of each frame. This allows embedding
+//! postbag data as the body codec of length-delimited protocols.
+//!
+//! The encoding of the length prefix is selected by [`PrefixFormat`]: a varint or,
+//! as mandated by many protocols, an unsigned integer of fixed width. Since the
+//! prefix precedes the body, each value is serialized twice: once to determine the
+//! length of the body and once to write it. A body exceeding the range of a fixed-width
+//! prefix fails with [`Error::FrameTooLarge`] before anything is written.
+//!
+//! ```rust
+//! use postbag::{cfg::Slim, framing::{FrameReader, FrameWriter, PrefixFormat}};
+//!
+//! let mut writer = FrameWriter::<_, Slim>::new(Vec::new(), PrefixFormat::U16Be);
+//! writer.write_frame(&(1u8, "first")).unwrap();
+//! writer.write_frame(&(2u8, "second")).unwrap();
+//! let data = writer.into_inner();
+//! assert_eq!(data[..3], [0, 7, 1]);
+//!
+//! let mut reader = FrameReader::<_, Slim>::new(data.as_slice(), PrefixFormat::U16Be);
+//! assert_eq!(reader.read_frame::<(u8, String)>().unwrap(), (1, "first".to_string()));
+//! assert_eq!(reader.read_frame::<(u8, String)>().unwrap(), (2, "second".to_string()));
+//! ```
+
+use std::{
+    io::{self, Read, Write},
+    marker::PhantomData,
+};
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{
+    cfg::Cfg,
+    de::deserialize,
+    error::{Error, Result},
+    ser::{SizeCounter, serialize},
+    varint::{varint_max, varint_u64},
+};
+
+/// Encoding of the length prefix of a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PrefixFormat {
+    /// Varint of up to 64 bits, as used by postbag for lengths.
+    #[default]
+    Varint,
+    /// Big-endian `u16`.
+    U16Be,
+    /// Big-endian `u32`.
+    U32Be,
+    /// Little-endian `u32`.
+    U32Le,
+}
+
+impl PrefixFormat {
+    /// Maximum length of a body representable by the prefix.
+    pub fn max_len(self) -> u64 {
+        match self {
+            Self::Varint => u64::MAX,
+            Self::U16Be => u16::MAX.into(),
+            Self::U32Be | Self::U32Le => u32::MAX.into(),
+        }
+    }
+
+    /// Writes the prefix for a body of length `len`.
+    fn write<W: Write>(self, mut writer: W, len: u64) -> Result<()> {
+        if len > self.max_len() {
+            return Err(Error::FrameTooLarge);
+        }
+
+        match self {
+            Self::Varint => {
+                let mut buf = [0; varint_max::<u64>()];
+                writer.write_all(varint_u64(len, &mut buf))?;
+            }
+            Self::U16Be => writer.write_all(&(len as u16).to_be_bytes())?,
+            Self::U32Be => writer.write_all(&(len as u32).to_be_bytes())?,
+            Self::U32Le => writer.write_all(&(len as u32).to_le_bytes())?,
+        }
+        Ok(())
+    }
+
+    /// Reads a prefix and returns the length of the body.
+    fn read<R: Read>(self, mut reader: R) -> Result<u64> {
+        match self {
+            Self::Varint => {
+                let mut len = 0;
+                for i in 0..varint_max::<u64>() {
+                    let mut byte = [0];
+                    reader.read_exact(&mut byte)?;
+                    len |= u64::from(byte[0] & 0x7f) << (7 * i);
+                    if byte[0] & 0x80 == 0 {
+                        if i == varint_max::<u64>() - 1 && byte[0] > 1 {
+                            return Err(Error::BadVarint);
+                        }
+                        return Ok(len);
+                    }
+                }
+                Err(Error::BadVarint)
+            }
+            Self::U16Be => {
+                let mut buf = [0; 2];
+                reader.read_exact(&mut buf)?;
+                Ok(u16::from_be_bytes(buf).into())
+            }
+            Self::U32Be => {
+                let mut buf = [0; 4];
+                reader.read_exact(&mut buf)?;
+                Ok(u32::from_be_bytes(buf).into())
+            }
+            Self::U32Le => {
+                let mut buf = [0; 4];
+                reader.read_exact(&mut buf)?;
+                Ok(u32::from_le_bytes(buf).into())
+            }
+        }
+    }
+}
+
+/// Writer of length-prefixed frames.
+///
+/// See the [module documentation](self) for details.
+#[derive(Debug)]
+pub struct FrameWriter<W, CFG> {
+    inner: W,
+    format: PrefixFormat,
+    _cfg: PhantomData<CFG>,
+}
+
+impl<W: Write, CFG: Cfg> FrameWriter<W, CFG> {
+    /// Creates a frame writer writing to `inner` using the specified prefix format.
+    pub fn new(inner: W, format: PrefixFormat) -> Self {
+        Self { inner, format, _cfg: PhantomData }
+    }
+
+    /// Serializes a value as a frame.
+    ///
+    /// Fails with [`Error::FrameTooLarge`] if the serialized value does not
+    /// fit into the prefix format. Nothing is written in this case.
+    pub fn write_frame<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        let mut sizer = SizeCounter(0);
+        serialize::<CFG, _, _>(&mut sizer, value)?;
+
+        self.format.write(&mut self.inner, sizer.0 as u64)?;
+        serialize::<CFG, _, _>(&mut self.inner, value)
+    }
+
+    /// Returns a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+/// Reader of length-prefixed frames.
+///
+/// See the [module documentation](self) for details.
+#[derive(Debug)]
+pub struct FrameReader<R, CFG> {
+    inner: R,
+    format: PrefixFormat,
+    _cfg: PhantomData<CFG>,
+}
+
+impl<R: Read, CFG: Cfg> FrameReader<R, CFG> {
+    /// Creates a frame reader reading from `inner` using the specified prefix format.
+    pub fn new(inner: R, format: PrefixFormat) -> Self {
+        Self { inner, format, _cfg: PhantomData }
+    }
+
+    /// Deserializes the value of the next frame.
+    ///
+    /// No more than the body of the frame is read, thus a body shorter than its
+    /// value fails with an I/O error of kind [`UnexpectedEof`](io::ErrorKind::UnexpectedEof).
+    /// Data remaining in the body after the value is consumed and rejected with
+    /// [`Error::TrailingData`], so that the reader stays at the start of the next frame.
+    pub fn read_frame<T>(&mut self) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        let len = self.format.read(&mut self.inner)?;
+        let mut body = (&mut self.inner).take(len);
+        let res = deserialize::<CFG, _, T>(&mut body);
+
+        let remaining = io::copy(&mut body, &mut io::sink())?;
+        let value = res?;
+        if remaining > 0 {
+            return Err(Error::TrailingData);
+        }
+        Ok(value)
+    }
+
+    /// Returns a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
@@ -0,0 +1,92 @@
+//! # Float Arrays
+//!
+//! Deserializing a `Vec<f64>` element by element reads each float separately.
+//! This module, for use with `#[serde(with = "postbag::le_f64_slice")]`, reads
+//! the floats of a sequence in bulk and converts them in a tight loop,
+//! which speeds up deserializing large numeric arrays.
+//!
+//! The serialized data is identical to a `Vec<f64>` serialized without this module,
+//! including the byte order set by [`Cfg::endian`](crate::cfg::Cfg::endian).
+//! Other serde deserializers receive a regular sequence.
+//!
+//! Supported are `Vec<f64>`, `Box<[f64]>` and `[f64; N]`, as well as `&[f64]` for serialization.
+//! An array is serialized like a `Vec<f64>`, i.e. including its length, unlike an array
+//! serialized without this module.
+//!
+//! ```rust
+//! # use serde::{Serialize, Deserialize};
+//! #[derive(Serialize, Deserialize)]
+//! pub struct Measurement {
+//!     #[serde(with = "postbag::le_f64_slice")]
+//!     samples: Vec<f64>,
+//!     #[serde(with = "postbag::le_f64_slice")]
+//!     position: [f64; 3],
+//! }
+//! ```
+
+use std::{fmt, marker::PhantomData};
+
+use serde::{
+    Deserializer, Serializer,
+    de::{self, SeqAccess, Visitor},
+};
+
+/// Newtype struct name recognized by the deserializer.
+///
+/// The deserializer provides the little-endian bytes of the floats using `visit_byte_buf`.
+pub(crate) const NAME: &str = "$postbag::LeF64Slice";
+
+/// Serialize the floats as a sequence.
+pub fn serialize<S, V>(values: &V, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    V: AsRef<[f64]> + ?Sized,
+{
+    serializer.serialize_newtype_struct(NAME, values.as_ref())
+}
+
+/// Deserialize the floats from a sequence.
+pub fn deserialize<'de, D, V>(deserializer: D) -> Result<V, D::Error>
+where
+    D: Deserializer<'de>,
+    V: TryFrom<Vec<f64>>,
+{
+    let values = deserializer.deserialize_newtype_struct(NAME, F64SliceVisitor(PhantomData))?;
+    let len = values.len();
+    V::try_from(values).map_err(|_| de::Error::invalid_length(len, &"an array of matching length"))
+}
+
+struct F64SliceVisitor<'de>(PhantomData<&'de ()>);
+
+impl<'de> Visitor<'de> for F64SliceVisitor<'de> {
+    type Value = Vec<f64>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a sequence of floats")
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Vec<f64>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Vec<f64>, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or_default().min(4096));
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(values)
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Vec<f64>, E> {
+        if !bytes.len().is_multiple_of(8) {
+            return Err(E::invalid_length(bytes.len(), &"a multiple of 8 bytes"));
+        }
+        Ok(bytes.chunks_exact(8).map(|chunk| f64::from_le_bytes(chunk.try_into().unwrap())).collect())
+    }
+}
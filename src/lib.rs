@@ -2,26 +2,107 @@
 #![warn(missing_docs)]
 #![doc = include_str!("../README.md")]
 
+pub mod bench;
+pub mod bits;
+pub mod bounded;
 pub mod cfg;
+pub mod char_len_string;
+pub mod compact_float;
+pub mod crc_prefixed;
+#[cfg(feature = "crypto")]
+pub mod crypto;
+#[cfg(feature = "time")]
+pub mod datetime;
 mod de;
+#[cfg(feature = "rust_decimal")]
+pub mod decimal;
+mod dump;
 mod error;
+pub mod field_ids;
+pub mod fingerprint;
+pub mod fixchar;
 pub mod fixint;
+pub mod format;
+pub mod framing;
+pub mod le_f64_slice;
+pub mod named_variant;
+pub mod narrow;
+pub mod numeric_variants;
+pub mod padded_varint;
+pub mod raw;
+pub mod recursive;
+pub mod ring;
+pub mod seq_iter;
 mod ser;
-mod varint;
+pub mod ser_flavors;
+pub mod serde_bytes;
+pub mod shared;
+pub mod strided;
+mod tee;
+pub mod testing;
+pub mod transcode;
+pub mod varint;
+pub mod varint_slice;
+pub mod versioned;
 
-const FALSE: u8 = 0;
-const TRUE: u8 = 1;
+use format::{
+    CONTINUE, FALSE, ID_COUNT, ID_LEN, ID_LEN_NAME, NONE, SOME, SPECIAL_LEN, STOP, TERMINATED_LEN, TRUE,
+    UNKNOWN_LEN, numeric_identifier,
+};
 
-const NONE: u8 = 0;
-const SOME: u8 = 1;
+pub use bounded::{BoundedString, BoundedVec};
+pub use crc_prefixed::to_full_vec_crc_prefixed;
+pub use de::{
+    UnknownFields,
+    alloc::{BufferAlloc, Global},
+    builder::DeserializerBuilder,
+    deserialize, deserialize_capturing, deserialize_chained, deserialize_full, deserialize_partial,
+    deserialize_preserving_unknown, deserialize_seek, deserialize_slim, deserialize_with_interner,
+    deserializer::{Deserializer, Variant},
+    from_canonical_slice, from_full_slice, from_full_slice_with_tail, from_slim_slice, from_slim_slice_with_tail,
+    intern::Interner,
+    peek_variant, validate,
+};
+pub use dump::{DecodeDiagnostic, annotate, hexdump};
+pub use error::{Error, Result, UnknownLenCause};
+pub use fingerprint::{fingerprint, from_slice_fingerprinted, to_vec_fingerprinted};
+pub use numeric_variants::NumericVariants;
+pub use padded_varint::PaddedVarint;
+pub use raw::Raw;
+pub use ring::RingWriter;
+pub use seq_iter::SeqIter;
+pub use ser::{
+    builder::SerializerBuilder,
+    serialize, serialize_counted, serialize_full, serialize_slim, serialize_to_vec,
+    serializer::{Serializer, SerializerState},
+    to_canonical_vec, to_full_vec, to_full_vec_bounded, to_full_vec_reuse, to_slim_vec,
+};
+pub use strided::{StridedSlice, to_strided_vec};
+pub use tee::Tee;
+pub use transcode::{transcode, transcode_with};
 
-const SPECIAL_LEN: usize = 125;
-const UNKNOWN_LEN: usize = 0;
+#[cfg(feature = "derive")]
+pub use postbag_derive::{Versioned, field_ids, numeric_variants};
 
-const ID_LEN: usize = 64;
-const ID_LEN_NAME: usize = ID_LEN + 1;
-const ID_COUNT: usize = 60;
+#[cfg(feature = "erased-serde")]
+pub use ser::serialize_erased;
 
-pub use de::{deserialize, deserialize_full, deserialize_slim, from_full_slice, from_slim_slice};
-pub use error::{Error, Result};
-pub use ser::{serialize, serialize_full, serialize_slim, to_full_vec, to_slim_vec};
+/// Whether a tuple struct of the given name is the encoding of a helper enabled
+/// by an optional feature and thus not enclosed in a skippable block.
+#[allow(unused_variables)]
+fn feature_helper_tuple(name: &str) -> bool {
+    #[cfg(feature = "time")]
+    if name == datetime::NAME {
+        return true;
+    }
+    #[cfg(feature = "rust_decimal")]
+    if name == decimal::NAME {
+        return true;
+    }
+    false
+}
+
+#[doc(hidden)]
+pub mod __private {
+    pub use serde;
+}
@@ -0,0 +1,72 @@
+//! # Named Enum Variants
+//!
+//! Serializes the variant of an enum using its identifier, regardless of
+//! the configuration. Use with `#[serde(with = "postbag::named_variant")]`.
+//!
+//! With the [`Slim`](crate::cfg::Slim) configuration enum variants are
+//! serialized using their index, thus variants can only be added or removed at the end.
+//! Applying this to a field of enum type encodes its variant identifier instead,
+//! so that the variants of this enum can be reordered, added or removed freely.
+//! The payload of the variant is serialized according to the configuration.
+//!
+//! Only the variant of the enum directly contained in the field is affected;
+//! nested enums are serialized according to the configuration.
+//!
+//! ```rust
+//! # use serde::{Serialize, Deserialize};
+//! #[derive(Serialize, Deserialize)]
+//! pub enum Level {
+//!     Info,
+//!     Warning,
+//!     Error,
+//! }
+//!
+//! #[derive(Serialize, Deserialize)]
+//! pub struct LogEntry {
+//!     #[serde(with = "postbag::named_variant")]
+//!     level: Level,
+//!     message: String,
+//! }
+//! ```
+
+use std::{fmt, marker::PhantomData};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Visitor};
+
+/// Newtype struct name recognized by the serializer and deserializer.
+pub(crate) const NAME: &str = "$postbag::NamedVariant";
+
+/// Serialize the enum using its variant identifier.
+pub fn serialize<S, T>(val: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Serialize + ?Sized,
+{
+    serializer.serialize_newtype_struct(NAME, val)
+}
+
+/// Deserialize the enum from its variant identifier.
+pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    deserializer.deserialize_newtype_struct(NAME, NamedVariantVisitor(PhantomData))
+}
+
+struct NamedVariantVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for NamedVariantVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an enum")
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer)
+    }
+}
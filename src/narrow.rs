@@ -0,0 +1,135 @@
+//! # Narrowing Integers
+//!
+//! Helpers for migrating an integer field to a narrower type, for example from `i64`
+//! to `i32`, while still reading data written using the wider type.
+//! Use with `#[serde(with = "postbag::narrow::saturating")]` or
+//! `#[serde(with = "postbag::narrow::checked")]`.
+//!
+//! The value is read as a varint of any width, as written for integers of 16 bits and more,
+//! and converted to the narrower type. [`saturating`] clamps values out of its range to
+//! its minimum or maximum, while [`checked`] fails deserialization for such values.
+//! The value is written as a varint of the widest integer type, which is identical to the
+//! encoding of the original wider type, thus data remains readable by the original type.
+//!
+//! Fields of the narrower type without a helper fail to deserialize values out of its range.
+//!
+//! ```rust
+//! use serde::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize)]
+//! struct Old {
+//!     offset: i64,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct New {
+//!     #[serde(with = "postbag::narrow::saturating")]
+//!     offset: i32,
+//! }
+//!
+//! let bytes = postbag::to_slim_vec(&Old { offset: 1 << 40 }).unwrap();
+//! let new: New = postbag::from_slim_slice(&bytes).unwrap();
+//! assert_eq!(new.offset, i32::MAX);
+//! ```
+
+use serde::{Deserializer, Serializer};
+
+/// Clamps values out of range of the narrower type.
+pub mod saturating {
+    use super::*;
+
+    /// Serialize the integer as a varint of the widest integer type.
+    pub fn serialize<S, T>(val: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: NarrowInt,
+    {
+        val.serialize_wide(serializer)
+    }
+
+    /// Deserialize the integer from a varint, clamping it to the range of `T`.
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: NarrowInt,
+    {
+        T::deserialize_wide(deserializer, true)
+    }
+}
+
+/// Fails deserialization for values out of range of the narrower type.
+pub mod checked {
+    use super::*;
+
+    /// Serialize the integer as a varint of the widest integer type.
+    pub fn serialize<S, T>(val: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: NarrowInt,
+    {
+        val.serialize_wide(serializer)
+    }
+
+    /// Deserialize the integer from a varint, failing if it is out of range of `T`.
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: NarrowInt,
+    {
+        T::deserialize_wide(deserializer, false)
+    }
+}
+
+/// Integer supported by the [narrowing helpers](self).
+pub trait NarrowInt: Sized + private::Sealed {
+    #[doc(hidden)]
+    fn serialize_wide<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>;
+
+    #[doc(hidden)]
+    fn deserialize_wide<'de, D: Deserializer<'de>>(deserializer: D, saturate: bool) -> Result<Self, D::Error>;
+}
+
+mod private {
+    pub trait Sealed {}
+}
+
+macro_rules! impl_narrow {
+    ($( $int:ty => $wide:ty, $ser:ident ),*) => {
+        $(
+            impl private::Sealed for $int {}
+
+            impl NarrowInt for $int {
+                fn serialize_wide<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    serializer.$ser(<$wide>::from(*self))
+                }
+
+                fn deserialize_wide<'de, D: Deserializer<'de>>(
+                    deserializer: D, saturate: bool,
+                ) -> Result<Self, D::Error> {
+                    let value = <$wide as serde::Deserialize>::deserialize(deserializer)?;
+                    match <$int>::try_from(value) {
+                        Ok(value) => Ok(value),
+                        Err(_) if saturate => {
+                            Ok(if value > <$wide>::from(<$int>::MAX) { <$int>::MAX } else { <$int>::MIN })
+                        }
+                        Err(_) => Err(serde::de::Error::custom(format_args!(
+                            "integer {value} out of range of {}",
+                            stringify!($int)
+                        ))),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_narrow![
+    u8 => u128, serialize_u128,
+    u16 => u128, serialize_u128,
+    u32 => u128, serialize_u128,
+    u64 => u128, serialize_u128,
+    i8 => i128, serialize_i128,
+    i16 => i128, serialize_i128,
+    i32 => i128, serialize_i128,
+    i64 => i128, serialize_i128
+];
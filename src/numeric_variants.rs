@@ -0,0 +1,61 @@
+//! # Numeric Variants
+//!
+//! In the [`Full`](crate::cfg::Full) configuration enum variants are identified by their
+//! names, which take one byte per character. Identifiers of the form `_N` with `N < 60`
+//! are encoded as a single byte instead. The `numeric_variants` attribute macro,
+//! which requires the `derive` feature, renames the variants of an enum to `_0`, `_1`, ...
+//! in declaration order, while keeping their human-readable names
+//!
+//! * in the source code and thus the `Debug` output,
+//! * as aliases, so that data serialized with variant names remains readable,
+//! * in the [`NumericVariants`] implementation, for translating numeric identifiers
+//!   back into names, for example for diagnostics.
+//!
+//! The readable names follow `#[serde(rename_all = "...")]` of the enum.
+//! Renaming individual variants is not supported.
+//! The attribute must be placed before `#[derive(Serialize, Deserialize)]`.
+//!
+//! Since variants are identified by their position, reordering variants is
+//! incompatible with previously serialized data, as in the [`Slim`](crate::cfg::Slim)
+//! configuration. New variants must be added at the end.
+//!
+#![cfg_attr(feature = "derive", doc = "```rust")]
+#![cfg_attr(not(feature = "derive"), doc = "```rust,ignore")]
+//! use serde::{Serialize, Deserialize};
+//! use postbag::{NumericVariants, from_full_slice, numeric_variants, to_full_vec};
+//!
+//! #[numeric_variants]
+//! #[derive(Serialize, Deserialize, Debug, PartialEq)]
+//! #[serde(rename_all = "snake_case")]
+//! enum Event {
+//!     PowerOn,
+//!     PowerOff { reason: String },
+//! }
+//!
+//! let data = to_full_vec(&Event::PowerOn).unwrap();
+//! assert_eq!(data.len(), 1);
+//! assert_eq!(from_full_slice::<Event>(&data).unwrap(), Event::PowerOn);
+//!
+//! assert_eq!(Event::variant_name("_1"), Some("power_off"));
+//! assert_eq!(Event::variant_index("power_off"), Some(1));
+//! ```
+
+use crate::numeric_identifier;
+
+/// Human-readable names of the variants of an enum encoded as numeric identifiers.
+///
+/// Implemented by the `numeric_variants` attribute macro.
+pub trait NumericVariants {
+    /// Human-readable variant names, in declaration order.
+    const VARIANT_NAMES: &'static [&'static str];
+
+    /// Returns the human-readable name of the variant with the numeric identifier `_N`.
+    fn variant_name(ident: &str) -> Option<&'static str> {
+        Self::VARIANT_NAMES.get(numeric_identifier(ident)?).copied()
+    }
+
+    /// Returns the index of the variant with the specified human-readable name.
+    fn variant_index(name: &str) -> Option<usize> {
+        Self::VARIANT_NAMES.iter().position(|n| *n == name)
+    }
+}
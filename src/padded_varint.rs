@@ -0,0 +1,179 @@
+//! # Padded Varints
+//!
+//! In some cases, such as fixed-stride records, an integer should occupy a
+//! predictable number of bytes while remaining compatible with the varint
+//! encoding. [`PaddedVarint`], for use with
+//! `#[serde(with = "postbag::PaddedVarint::<N>")]`, pads the varint of the
+//! specified integer field to at least `N` bytes using continuation bytes.
+//!
+//! Padded varints are read by the regular varint decoder, thus the padding
+//! can be added or removed without breaking compatibility. `N` must not
+//! exceed the maximum varint length of the integer type, i.e. 3 bytes for
+//! 16-bit, 5 bytes for 32-bit, 10 bytes for 64-bit and 19 bytes for 128-bit integers.
+//!
+//! Padded varints are not in canonical form. Thus the serializer refuses
+//! them and the deserializer rejects padding with
+//! [`Error::NonCanonical`](crate::Error::NonCanonical) if
+//! [`Cfg::canonical`](crate::cfg::Cfg::canonical) is enabled.
+//!
+//! Support explicitly not provided for `usize` or `isize`, as
+//! these types would not be portable between systems of different
+//! pointer widths.
+//!
+//! ```rust
+//! # use serde::{Serialize, Deserialize};
+//! #[derive(Serialize, Deserialize)]
+//! pub struct Record {
+//!     #[serde(with = "postbag::PaddedVarint::<4>")]
+//!     x: u32,
+//! }
+//! ```
+
+use std::{fmt, marker::PhantomData};
+
+use serde::{
+    Deserializer, Serializer,
+    de::{self, SeqAccess, Unexpected, Visitor},
+    ser::{self, SerializeTupleStruct},
+};
+
+use crate::varint::varint_max;
+
+/// Tuple struct name recognized by the serializer and deserializer.
+pub(crate) const NAME: &str = "$postbag::PaddedVarint";
+
+/// Pads varints to at least `N` bytes.
+///
+/// See the [module documentation](self) for details.
+pub struct PaddedVarint<const N: usize>;
+
+impl<const N: usize> PaddedVarint<N> {
+    /// Serialize the integer as a varint padded to at least `N` bytes.
+    pub fn serialize<S, T>(val: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: PaddedInt,
+    {
+        if N > T::MAX_LEN {
+            return Err(ser::Error::custom("padding exceeds maximum varint length"));
+        }
+
+        let mut value = val.to_varint();
+        let mut buf = Vec::with_capacity(T::MAX_LEN);
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 && buf.len() + 1 >= N {
+                buf.push(byte);
+                break;
+            }
+            buf.push(byte | 0x80);
+        }
+
+        let mut tuple = serializer.serialize_tuple_struct(NAME, buf.len())?;
+        for byte in &buf {
+            tuple.serialize_field(byte)?;
+        }
+        tuple.end()
+    }
+
+    /// Deserialize the integer from a varint of any length.
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: PaddedInt,
+    {
+        deserializer.deserialize_tuple_struct(NAME, T::MAX_LEN, VarintVisitor(PhantomData))
+    }
+}
+
+/// Integer supported by [`PaddedVarint`].
+pub trait PaddedInt: Sized + private::Sealed {
+    #[doc(hidden)]
+    const MAX_LEN: usize;
+
+    #[doc(hidden)]
+    fn to_varint(&self) -> u128;
+
+    #[doc(hidden)]
+    fn from_varint(v: u128) -> Option<Self>;
+}
+
+mod private {
+    pub trait Sealed {}
+}
+
+macro_rules! impl_padded_unsigned {
+    ($( $int:ty ),*) => {
+        $(
+            impl private::Sealed for $int {}
+
+            impl PaddedInt for $int {
+                const MAX_LEN: usize = varint_max::<$int>();
+
+                fn to_varint(&self) -> u128 {
+                    u128::from(*self)
+                }
+
+                fn from_varint(v: u128) -> Option<Self> {
+                    v.try_into().ok()
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_padded_signed {
+    ($( $int:ty => $uint:ty ),*) => {
+        $(
+            impl private::Sealed for $int {}
+
+            impl PaddedInt for $int {
+                const MAX_LEN: usize = varint_max::<$int>();
+
+                fn to_varint(&self) -> u128 {
+                    let zz = ((*self << 1) ^ (*self >> (<$int>::BITS - 1))) as $uint;
+                    u128::from(zz)
+                }
+
+                fn from_varint(v: u128) -> Option<Self> {
+                    let zz: $uint = v.try_into().ok()?;
+                    Some(((zz >> 1) as $int) ^ (-((zz & 0b1) as $int)))
+                }
+            }
+        )*
+    };
+}
+
+impl_padded_unsigned![u16, u32, u64, u128];
+impl_padded_signed![i16 => u16, i32 => u32, i64 => u64, i128 => u128];
+
+struct VarintVisitor<T>(PhantomData<T>);
+
+impl<'de, T: PaddedInt> Visitor<'de> for VarintVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a varint")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<T, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut value: u128 = 0;
+        for i in 0..T::MAX_LEN {
+            let byte: u8 = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(i, &self))?;
+            let bits = u128::from(byte & 0x7f);
+            if (bits << (7 * i)) >> (7 * i) != bits {
+                return Err(de::Error::invalid_value(Unexpected::Other("out of range varint"), &self));
+            }
+            value |= bits << (7 * i);
+            if byte & 0x80 == 0 {
+                return T::from_varint(value)
+                    .ok_or_else(|| de::Error::invalid_value(Unexpected::Other("out of range varint"), &self));
+            }
+        }
+        Err(de::Error::invalid_value(Unexpected::Other("unterminated varint"), &self))
+    }
+}
@@ -0,0 +1,196 @@
+//! # Pre-Serialized Values
+//!
+//! [`Raw`] holds the serialized bytes of a value, which are written verbatim
+//! into the output when serializing and captured from the input when deserializing.
+//! This allows forwarding an unchanged value or embedding a cached serialization
+//! without deserializing and serializing it again.
+//!
+//! ```rust
+//! use serde::{Serialize, Deserialize};
+//! use postbag::{Raw, cfg::Full, from_full_slice, to_full_vec};
+//!
+//! #[derive(Serialize, Deserialize, Debug, PartialEq)]
+//! struct Payload {
+//!     data: Vec<u32>,
+//! }
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Envelope {
+//!     to: String,
+//!     payload: Raw<Payload>,
+//! }
+//!
+//! let payload = Raw::new::<Full>(&Payload { data: vec![1, 2, 3] }).unwrap();
+//! let bytes = to_full_vec(&Envelope { to: "node".to_string(), payload }).unwrap();
+//!
+//! let envelope: Envelope = from_full_slice(&bytes).unwrap();
+//! assert_eq!(envelope.payload.value::<Full>().unwrap(), Payload { data: vec![1, 2, 3] });
+//! ```
+//!
+//! # Caution
+//!
+//! The bytes are written without any verification. They must be a complete serialization
+//! of a value of type `T` using the same configuration as the enclosing data.
+//! Otherwise the enclosing data becomes corrupted and cannot be deserialized.
+//!
+//! When deserializing, the value is fully deserialized to determine its extent.
+//! Serialization with other serde serializers writes the bytes as a byte array.
+
+use std::{fmt, marker::PhantomData};
+
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{self, DeserializeOwned, DeserializeSeed, SeqAccess, Visitor},
+};
+
+use crate::{cfg::Cfg, de::from_slice, error::Result, ser::serialize};
+
+/// Newtype struct name recognized by the serializer and deserializer.
+pub(crate) const NAME: &str = "$postbag::Raw";
+
+/// Serialized bytes of a value of type `T`.
+///
+/// See the [module documentation](self) for details.
+pub struct Raw<T> {
+    bytes: Vec<u8>,
+    _type: PhantomData<fn() -> T>,
+}
+
+impl<T> Raw<T> {
+    /// Wraps bytes containing a serialized value of type `T`.
+    ///
+    /// The bytes are not verified.
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self { bytes, _type: PhantomData }
+    }
+
+    /// The serialized bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the serialized bytes.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+impl<T: Serialize> Raw<T> {
+    /// Serializes a value using the specified configuration.
+    pub fn new<CFG: Cfg>(value: &T) -> Result<Self> {
+        let mut bytes = Vec::new();
+        serialize::<CFG, _, _>(&mut bytes, value)?;
+        Ok(Self::from_bytes(bytes))
+    }
+}
+
+impl<T: DeserializeOwned> Raw<T> {
+    /// Deserializes the value using the specified configuration.
+    pub fn value<CFG: Cfg>(&self) -> Result<T> {
+        from_slice::<CFG, T>(&self.bytes)
+    }
+}
+
+impl<T> Clone for Raw<T> {
+    fn clone(&self) -> Self {
+        Self::from_bytes(self.bytes.clone())
+    }
+}
+
+impl<T> fmt::Debug for Raw<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Raw").field(&self.bytes).finish()
+    }
+}
+
+impl<T> PartialEq for Raw<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.bytes == other.bytes
+    }
+}
+
+impl<T> Eq for Raw<T> {}
+
+impl<T> Serialize for Raw<T> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(NAME, &RawBytes(&self.bytes))
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Raw<T> {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(NAME, RawVisitor::<T>(PhantomData)).map(Self::from_bytes)
+    }
+}
+
+struct RawBytes<'a>(&'a [u8]);
+
+impl Serialize for RawBytes<'_> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+struct RawVisitor<T>(PhantomData<fn() -> T>);
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for RawVisitor<T> {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a serialized value")
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> std::result::Result<Vec<u8>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_byte_buf(BytesVisitor)
+    }
+
+    /// Receives the deserialized value followed by its captured bytes.
+    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<Vec<u8>, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        seq.next_element::<T>()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        seq.next_element_seed(BytesVisitor)?.ok_or_else(|| de::Error::invalid_length(1, &self))
+    }
+}
+
+struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("bytes")
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> std::result::Result<Vec<u8>, E> {
+        Ok(v.to_vec())
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> std::result::Result<Vec<u8>, E> {
+        Ok(v)
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn deserialize<D>(self, deserializer: D) -> std::result::Result<Vec<u8>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_byte_buf(self)
+    }
+}
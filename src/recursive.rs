@@ -0,0 +1,205 @@
+//! # Right-Recursive Structures
+//!
+//! Deserializing a recursive type, such as the cons-list
+//!
+//! ```rust
+//! enum List {
+//!     Cons(u32, Box<List>),
+//!     Nil,
+//! }
+//! ```
+//!
+//! using its derived [`Deserialize`](serde::Deserialize) implementation recurses once
+//! per node and thus may overflow the stack for long lists. Limiting the nesting depth
+//! using [`DeserializerBuilder::max_depth`](crate::DeserializerBuilder::max_depth) makes
+//! such input fail with [`Error::DepthLimitExceeded`](crate::Error::DepthLimitExceeded)
+//! instead, but also rejects long lists that are valid.
+//!
+//! [`deserialize_recursive`] decodes such a list iteratively instead. It repeatedly reads
+//! the variant of a node and the element it contains and finally builds the list bottom-up
+//! from the collected elements. The type must implement [`RightRecursive`], which describes
+//! the variant holding an element followed by the rest of the list, and the unit variant
+//! terminating the list. The encoding is identical to the encoding of the derived
+//! [`Serialize`](serde::Serialize) implementation.
+//! Tuple variants enclosed in a skippable block, as configured by
+//! [`Cfg::skippable_tuples`], are unsupported.
+//!
+//! Note that the derived implementations of [`Serialize`](serde::Serialize) and [`Drop`]
+//! recurse as well.
+//!
+//! ```rust
+//! use serde::Deserialize;
+//! use postbag::{cfg::Slim, recursive::{RightRecursive, deserialize_recursive}};
+//!
+//! #[derive(Deserialize, Debug, PartialEq)]
+//! enum List {
+//!     Cons(u32, Box<List>),
+//!     Nil,
+//! }
+//!
+//! impl RightRecursive for List {
+//!     type Item = u32;
+//!     const NAME: &'static str = "List";
+//!     const VARIANTS: &'static [&'static str] = &["Cons", "Nil"];
+//!     const CONS: &'static str = "Cons";
+//!     const NIL: &'static str = "Nil";
+//!
+//!     fn nil() -> Self {
+//!         List::Nil
+//!     }
+//!
+//!     fn cons(item: u32, tail: Self) -> Self {
+//!         List::Cons(item, Box::new(tail))
+//!     }
+//! }
+//!
+//! let bytes = [0, 1, 0, 2, 1];
+//! let list: List = deserialize_recursive::<Slim, _, _>(bytes.as_slice()).unwrap();
+//! assert_eq!(list, List::cons(1, List::cons(2, List::Nil)));
+//! ```
+
+use std::{fmt, io::Read, marker::PhantomData};
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed, EnumAccess, SeqAccess, VariantAccess, Visitor};
+
+use crate::{cfg::Cfg, de::deserializer::Deserializer, error::Result};
+
+/// Enum consisting of a tuple variant holding an element followed by the rest of
+/// the structure and a unit variant terminating it.
+///
+/// See the [module documentation](self) for details.
+pub trait RightRecursive: Sized {
+    /// Element held by each node.
+    type Item: DeserializeOwned;
+
+    /// Name of the enum.
+    const NAME: &'static str;
+
+    /// Names of all variants of the enum in declaration order.
+    const VARIANTS: &'static [&'static str];
+
+    /// Name of the tuple variant holding an element and the rest of the structure.
+    const CONS: &'static str;
+
+    /// Name of the unit variant terminating the structure.
+    const NIL: &'static str;
+
+    /// Creates the terminating node.
+    fn nil() -> Self;
+
+    /// Creates a node holding `item` followed by `tail`.
+    fn cons(item: Self::Item, tail: Self) -> Self;
+}
+
+/// Deserialize a right-recursive value of type `T` from a [`std::io::Read`] iteratively.
+///
+/// See the [module documentation](self) for details.
+pub fn deserialize_recursive<CFG, R, T>(read: R) -> Result<T>
+where
+    CFG: Cfg,
+    R: Read,
+    T: RightRecursive,
+{
+    let mut deserializer = Deserializer::<R, CFG>::new(read);
+    let t = deserialize_recursive_from(&mut deserializer)?;
+    deserializer.finalize();
+    Ok(t)
+}
+
+/// Deserialize a right-recursive value of type `T` from a [`Deserializer`] iteratively.
+///
+/// This allows applying the limits of a [`DeserializerBuilder`](crate::DeserializerBuilder)
+/// to the elements.
+pub fn deserialize_recursive_from<'de, R, CFG, T>(deserializer: &mut Deserializer<'de, R, CFG>) -> Result<T>
+where
+    CFG: Cfg,
+    R: Read,
+    T: RightRecursive,
+{
+    if CFG::skippable_tuples() {
+        return Err(de::Error::custom("iterative deserialization unsupported with skippable tuples"));
+    }
+
+    let mut items = Vec::new();
+    while let Some(item) = de::Deserializer::deserialize_enum(
+        &mut *deserializer,
+        T::NAME,
+        T::VARIANTS,
+        NodeVisitor::<T>(PhantomData),
+    )? {
+        items.push(item);
+    }
+
+    Ok(items.into_iter().rev().fold(T::nil(), |tail, item| T::cons(item, tail)))
+}
+
+/// Reads the variant of a node and its element, but not the rest of the structure.
+struct NodeVisitor<T>(PhantomData<T>);
+
+impl<'de, T: RightRecursive> Visitor<'de> for NodeVisitor<T> {
+    type Value = Option<T::Item>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "enum {}", T::NAME)
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> std::result::Result<Self::Value, A::Error> {
+        let (cons, variant) = data.variant_seed(VariantSeed::<T>(PhantomData))?;
+        if cons {
+            variant.tuple_variant(2, HeadVisitor::<T>(PhantomData)).map(Some)
+        } else {
+            variant.unit_variant().map(|()| None)
+        }
+    }
+}
+
+/// Identifies the variant, returning whether it is the variant holding an element.
+struct VariantSeed<T>(PhantomData<T>);
+
+impl<'de, T: RightRecursive> DeserializeSeed<'de> for VariantSeed<T> {
+    type Value = bool;
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> std::result::Result<bool, D::Error> {
+        deserializer.deserialize_identifier(self)
+    }
+}
+
+impl<'de, T: RightRecursive> Visitor<'de> for VariantSeed<T> {
+    type Value = bool;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "variant {} or {}", T::CONS, T::NIL)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> std::result::Result<bool, E> {
+        match usize::try_from(v).ok().and_then(|v| T::VARIANTS.get(v)) {
+            Some(name) => self.visit_str(name),
+            None => Err(E::invalid_value(de::Unexpected::Unsigned(v), &self)),
+        }
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> std::result::Result<bool, E> {
+        if v == T::CONS {
+            Ok(true)
+        } else if v == T::NIL {
+            Ok(false)
+        } else {
+            Err(E::unknown_variant(v, &[T::CONS, T::NIL]))
+        }
+    }
+}
+
+/// Reads the element of a node, leaving the rest of the structure unread.
+struct HeadVisitor<T>(PhantomData<T>);
+
+impl<'de, T: RightRecursive> Visitor<'de> for HeadVisitor<T> {
+    type Value = T::Item;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "tuple variant {}::{}", T::NAME, T::CONS)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<T::Item, A::Error> {
+        seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))
+    }
+}
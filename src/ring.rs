@@ -0,0 +1,167 @@
+//! # Ring Buffer
+//!
+//! [`RingWriter`] is a fixed-size circular buffer, for example for logging events
+//! on a device with bounded memory. When it is full, writing overwrites the oldest data.
+//!
+//! Messages written by [`RingWriter::write_framed`] are followed by their length,
+//! so that they can be recovered by walking backwards from the most recent message.
+//! [`RingWriter::read_framed`] returns all complete messages remaining in the buffer,
+//! skipping the oldest message if it has been partially overwritten.
+//!
+//! ```rust
+//! use postbag::{RingWriter, cfg::Slim};
+//!
+//! let mut ring = RingWriter::new(32);
+//! for i in 0..100u32 {
+//!     ring.write_framed::<Slim, _>(&(i, "event")).unwrap();
+//! }
+//!
+//! let events: Vec<(u32, String)> = ring.read_framed::<Slim, _>().unwrap();
+//! assert_eq!(events.len(), 2);
+//! assert_eq!(events[1], (99, "event".to_string()));
+//! ```
+
+use std::io::{self, Write};
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{
+    cfg::Cfg,
+    de::from_slice,
+    error::{Error, Result},
+    ser::serialize,
+};
+
+/// Size of the length following each framed message.
+const LEN_SIZE: usize = 4;
+
+/// Fixed-size circular buffer that overwrites its oldest data when full.
+///
+/// Writing through the [`Write`] implementation never fails. Use
+/// [`write_framed`](Self::write_framed) exclusively to be able to recover messages.
+///
+/// See the [module documentation](self) for details.
+#[derive(Debug, Clone)]
+pub struct RingWriter {
+    buf: Vec<u8>,
+    /// Position of the next write.
+    pos: usize,
+    /// Number of valid bytes.
+    filled: usize,
+    /// Buffer for serializing a message.
+    scratch: Vec<u8>,
+}
+
+impl RingWriter {
+    /// Creates a ring buffer of the specified capacity in bytes.
+    pub fn new(capacity: usize) -> Self {
+        Self { buf: vec![0; capacity], pos: 0, filled: 0, scratch: Vec::new() }
+    }
+
+    /// Capacity in bytes.
+    pub fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Number of valid bytes in the buffer.
+    pub fn len(&self) -> usize {
+        self.filled
+    }
+
+    /// Whether nothing has been written.
+    pub fn is_empty(&self) -> bool {
+        self.filled == 0
+    }
+
+    /// Returns the valid contents of the buffer, from the oldest to the most recent byte.
+    pub fn contents(&self) -> Vec<u8> {
+        let start = (self.pos + self.buf.len() - self.filled) % self.buf.len().max(1);
+        let mut contents = Vec::with_capacity(self.filled);
+        if start + self.filled <= self.buf.len() {
+            contents.extend_from_slice(&self.buf[start..start + self.filled]);
+        } else {
+            contents.extend_from_slice(&self.buf[start..]);
+            contents.extend_from_slice(&self.buf[..self.pos]);
+        }
+        contents
+    }
+
+    /// Removes all contents.
+    pub fn clear(&mut self) {
+        self.pos = 0;
+        self.filled = 0;
+    }
+
+    /// Serialize a value using the specified configuration and append it followed
+    /// by its length, overwriting the oldest data if necessary.
+    ///
+    /// A message larger than the capacity overwrites the whole buffer and
+    /// cannot be recovered.
+    pub fn write_framed<CFG, T>(&mut self, value: &T) -> Result<()>
+    where
+        CFG: Cfg,
+        T: Serialize + ?Sized,
+    {
+        let mut scratch = std::mem::take(&mut self.scratch);
+        scratch.clear();
+        let res = serialize::<CFG, _, _>(&mut scratch, value).and_then(|()| {
+            let len = u32::try_from(scratch.len()).map_err(|_| Error::UsizeOverflow)?;
+            self.append(&scratch);
+            self.append(&len.to_le_bytes());
+            Ok(())
+        });
+        self.scratch = scratch;
+        res
+    }
+
+    /// Deserialize all complete messages written by [`write_framed`](Self::write_framed)
+    /// using the specified configuration, from the oldest to the most recent message.
+    pub fn read_framed<CFG, T>(&self) -> Result<Vec<T>>
+    where
+        CFG: Cfg,
+        T: DeserializeOwned,
+    {
+        let contents = self.contents();
+
+        let mut frames = Vec::new();
+        let mut end = contents.len();
+        while end >= LEN_SIZE {
+            let len_start = end - LEN_SIZE;
+            let len = u32::from_le_bytes(contents[len_start..end].try_into().unwrap());
+            let Some(start) = usize::try_from(len).ok().and_then(|len| len_start.checked_sub(len)) else { break };
+            frames.push(&contents[start..len_start]);
+            end = start;
+        }
+
+        frames.into_iter().rev().map(from_slice::<CFG, T>).collect()
+    }
+
+    /// Appends data, overwriting the oldest data if necessary.
+    fn append(&mut self, mut data: &[u8]) {
+        let cap = self.buf.len();
+        if cap == 0 {
+            return;
+        }
+        if data.len() > cap {
+            data = &data[data.len() - cap..];
+        }
+
+        let first = data.len().min(cap - self.pos);
+        self.buf[self.pos..self.pos + first].copy_from_slice(&data[..first]);
+        self.buf[..data.len() - first].copy_from_slice(&data[first..]);
+
+        self.pos = (self.pos + data.len()) % cap;
+        self.filled = (self.filled + data.len()).min(cap);
+    }
+}
+
+impl Write for RingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.append(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
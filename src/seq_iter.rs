@@ -0,0 +1,146 @@
+//! # Lazy Sequences
+//!
+//! [`SeqIter`] iterates over the elements of a sequence as they are deserialized,
+//! for use within [`Visitor::visit_seq`](serde::de::Visitor::visit_seq).
+//! This allows processing a large sequence without collecting it, while the
+//! values following it are deserialized afterwards as usual.
+//!
+//! Since the serialized data is not self-describing, elements cannot be skipped
+//! without deserializing them. When a [`SeqIter`] is dropped before the end of the
+//! sequence, the remaining elements are deserialized and discarded, so that the
+//! deserializer is positioned after the sequence. Use [`SeqIter::finish`] to
+//! observe errors occurring while doing so.
+//!
+//! ```rust
+//! use std::fmt;
+//! use serde::{Serialize, Deserialize, de::{Deserializer, SeqAccess, Visitor}};
+//! use postbag::{SeqIter, from_slim_slice, to_slim_vec};
+//!
+//! /// Deserializes only the first three elements of a sequence of numbers.
+//! struct FirstThree(Vec<u32>);
+//!
+//! impl<'de> Deserialize<'de> for FirstThree {
+//!     fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+//!         deserializer.deserialize_seq(FirstThreeVisitor)
+//!     }
+//! }
+//!
+//! struct FirstThreeVisitor;
+//!
+//! impl<'de> Visitor<'de> for FirstThreeVisitor {
+//!     type Value = FirstThree;
+//!
+//!     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+//!         formatter.write_str("a sequence of numbers")
+//!     }
+//!
+//!     fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<FirstThree, A::Error> {
+//!         let mut iter = SeqIter::<_, u32>::new(&mut seq);
+//!         let first = iter.by_ref().take(3).collect::<Result<_, _>>()?;
+//!         iter.finish()?;
+//!         Ok(FirstThree(first))
+//!     }
+//! }
+//!
+//! #[derive(Serialize)]
+//! struct Data {
+//!     values: Vec<u32>,
+//!     trailer: String,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct Summary {
+//!     values: FirstThree,
+//!     trailer: String,
+//! }
+//!
+//! let bytes = to_slim_vec(&Data { values: (0..1000).collect(), trailer: "end".to_string() }).unwrap();
+//! let summary: Summary = from_slim_slice(&bytes).unwrap();
+//! assert_eq!(summary.values.0, [0, 1, 2]);
+//! assert_eq!(summary.trailer, "end");
+//! ```
+
+use std::marker::PhantomData;
+
+use serde::de::{Deserialize, SeqAccess};
+
+/// Iterator over the elements of type `T` of a sequence, borrowing its [`SeqAccess`].
+///
+/// See the [module documentation](self) for details.
+pub struct SeqIter<'a, 'de, A, T>
+where
+    A: SeqAccess<'de>,
+    T: Deserialize<'de>,
+{
+    seq: &'a mut A,
+    done: bool,
+    _marker: PhantomData<fn() -> (&'de (), T)>,
+}
+
+impl<'a, 'de, A, T> SeqIter<'a, 'de, A, T>
+where
+    A: SeqAccess<'de>,
+    T: Deserialize<'de>,
+{
+    /// Creates an iterator over the remaining elements of a sequence.
+    pub fn new(seq: &'a mut A) -> Self {
+        Self { seq, done: false, _marker: PhantomData }
+    }
+
+    /// Deserializes and discards the remaining elements of the sequence.
+    ///
+    /// This is done implicitly when the iterator is dropped, ignoring errors.
+    pub fn finish(mut self) -> Result<(), A::Error> {
+        self.drain()
+    }
+
+    fn drain(&mut self) -> Result<(), A::Error> {
+        while !self.done {
+            match self.seq.next_element::<T>() {
+                Ok(Some(_)) => (),
+                Ok(None) => self.done = true,
+                Err(err) => {
+                    self.done = true;
+                    return Err(err);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'de, A, T> Iterator for SeqIter<'_, 'de, A, T>
+where
+    A: SeqAccess<'de>,
+    T: Deserialize<'de>,
+{
+    type Item = Result<T, A::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.seq.next_element() {
+            Ok(Some(value)) => Some(Ok(value)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl<'de, A, T> Drop for SeqIter<'_, 'de, A, T>
+where
+    A: SeqAccess<'de>,
+    T: Deserialize<'de>,
+{
+    fn drop(&mut self) {
+        let _ = self.drain();
+    }
+}
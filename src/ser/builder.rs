@@ -0,0 +1,70 @@
+//! Serializer builder.
+
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::{cfg::Cfg, error::Result, ser::serializer::Serializer};
+
+/// Builder for a [`Serializer`] with limits for values derived from untrusted input.
+///
+/// By default no limits are applied.
+///
+/// # Example
+///
+/// ```rust
+/// use postbag::{cfg::Full, Error, SerializerBuilder};
+///
+/// let builder = SerializerBuilder::new().max_depth(2);
+///
+/// let mut buffer = Vec::new();
+/// builder.serialize::<Full, _, _>(&mut buffer, &vec![vec![1u8]]).unwrap();
+///
+/// let res = builder.serialize::<Full, _, _>(&mut buffer, &vec![vec![vec![1u8]]]);
+/// assert!(matches!(res, Err(Error::DepthLimitExceeded)));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SerializerBuilder {
+    max_depth: Option<usize>,
+}
+
+impl SerializerBuilder {
+    /// Creates a new builder without limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum nesting depth of sequences, tuples, maps, structs and enums.
+    ///
+    /// Exceeding it fails with [`Error::DepthLimitExceeded`](crate::Error::DepthLimitExceeded)
+    /// instead of overflowing the stack when serializing deeply nested values.
+    /// Nesting is counted like by [`DeserializerBuilder::max_depth`](crate::DeserializerBuilder::max_depth).
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Builds a serializer writing to the specified writer.
+    pub fn build<CFG, W>(&self, write: W) -> Serializer<W, CFG>
+    where
+        CFG: Cfg,
+        W: Write,
+    {
+        let mut serializer = Serializer::new(write);
+        serializer.set_max_depth(self.max_depth);
+        serializer
+    }
+
+    /// Serialize a value of type `T` to a [`std::io::Write`] applying the limits.
+    pub fn serialize<CFG, W, T>(&self, write: W, value: &T) -> Result<()>
+    where
+        CFG: Cfg,
+        W: Write,
+        T: Serialize + ?Sized,
+    {
+        let mut serializer = self.build::<CFG, W>(write);
+        value.serialize(&mut serializer)?;
+        serializer.finalize()?;
+        Ok(())
+    }
+}
@@ -1,9 +1,17 @@
+use std::io::{self, Write};
+
 use serde::Serialize;
 
-use crate::{cfg::Cfg, error::Result, ser::serializer::Serializer};
+use crate::{
+    cfg::{Cfg, Full},
+    error::{Error, Result},
+    ser::serializer::Serializer,
+};
 
+pub(crate) mod builder;
 pub(crate) mod serializer;
 pub(crate) mod skippable;
+mod varint_seq;
 
 /// Serialize a value of type `T` to a [`std::io::Write`].
 ///
@@ -11,6 +19,9 @@ pub(crate) mod skippable;
 /// - [`Full`](crate::cfg::Full): Serializes struct field identifiers and enum variant identifiers as strings
 /// - [`Slim`](crate::cfg::Slim): Serializes without identifiers, using indices for enum variants
 ///
+/// Partial writes are continued. If the writer returns `Ok(0)`, serialization fails
+/// with an I/O error of kind [`WriteZero`](std::io::ErrorKind::WriteZero).
+///
 /// # Example
 ///
 /// ```rust
@@ -40,7 +51,89 @@ where
 {
     let mut serializer = Serializer::<W, CFG>::new(writer);
     value.serialize(&mut serializer)?;
-    serializer.finalize();
+    serializer.finalize()?;
+    Ok(())
+}
+
+/// Serialize a value of type `T` to a [`std::io::Write`] and return the number of bytes written.
+///
+/// This behaves like [`serialize`], but counts the bytes accepted by the writer,
+/// for example to maintain the position within a file or to build an index.
+///
+/// # Example
+///
+/// ```rust
+/// use postbag::{serialize_counted, cfg::Full};
+///
+/// let mut buffer = Vec::new();
+/// let first = serialize_counted::<Full, _, _>(&mut buffer, "first").unwrap();
+/// let second = serialize_counted::<Full, _, _>(&mut buffer, "second").unwrap();
+/// assert_eq!(first + second, buffer.len());
+/// ```
+pub fn serialize_counted<CFG, W, T>(writer: W, value: &T) -> Result<usize>
+where
+    CFG: Cfg,
+    W: std::io::Write,
+    T: Serialize + ?Sized,
+{
+    let mut serializer = Serializer::<W, CFG>::new(writer);
+    value.serialize(&mut serializer)?;
+    let written = serializer.position();
+    serializer.finalize()?;
+    usize::try_from(written).map_err(|_| Error::UsizeOverflow)
+}
+
+/// Serialize a value of type `T` and return a `Vec<u8>`.
+///
+/// This is equivalent to [`serialize`] into a new `Vec<u8>`, but returns the vector directly.
+///
+/// # Example
+///
+/// ```rust
+/// use postbag::{serialize_to_vec, cfg::Slim};
+///
+/// let bytes = serialize_to_vec::<Slim, _>(&(1u8, "one")).unwrap();
+/// assert_eq!(bytes, [1, 3, b'o', b'n', b'e']);
+/// ```
+pub fn serialize_to_vec<CFG, T>(value: &T) -> Result<Vec<u8>>
+where
+    CFG: Cfg,
+    T: Serialize + ?Sized,
+{
+    let mut serializer = Serializer::<_, CFG>::new(Vec::new());
+    value.serialize(&mut serializer)?;
+    serializer.into_vec()
+}
+
+/// Serialize a type-erased value to a [`std::io::Write`].
+///
+/// This allows serializing trait objects of [`erased_serde::Serialize`], for example
+/// heterogeneous values stored as `Box<dyn erased_serde::Serialize>`. The encoding is
+/// identical to serializing the underlying value using [`serialize`].
+///
+/// # Example
+///
+/// ```rust
+/// use postbag::{serialize, serialize_erased, cfg::Full};
+///
+/// let value: Box<dyn erased_serde::Serialize> = Box::new((1u32, "one"));
+///
+/// let mut erased = Vec::new();
+/// serialize_erased::<Full, _>(&mut erased, &*value).unwrap();
+///
+/// let mut typed = Vec::new();
+/// serialize::<Full, _, _>(&mut typed, &(1u32, "one")).unwrap();
+/// assert_eq!(erased, typed);
+/// ```
+#[cfg(feature = "erased-serde")]
+pub fn serialize_erased<CFG, W>(writer: W, value: &dyn erased_serde::Serialize) -> Result<()>
+where
+    CFG: Cfg,
+    W: std::io::Write,
+{
+    let mut serializer = Serializer::<W, CFG>::new(writer);
+    erased_serde::serialize(value, &mut serializer)?;
+    serializer.finalize()?;
     Ok(())
 }
 
@@ -112,7 +205,7 @@ where
 
 /// Serialize a value using the [`Full`](crate::cfg::Full) configuration and return a `Vec<u8>`.
 ///
-/// This is a convenience function that creates a new `Vec<u8>` and calls `serialize_full` on it.
+/// This is a convenience function equivalent to `serialize_to_vec::<Full, _>(value)`.
 /// It serializes struct field identifiers and enum variant identifiers as strings.
 ///
 /// # Example
@@ -139,14 +232,118 @@ pub fn to_full_vec<T>(value: &T) -> Result<Vec<u8>>
 where
     T: Serialize + ?Sized,
 {
-    let mut buffer = Vec::new();
-    serialize_full(&mut buffer, value)?;
-    Ok(buffer)
+    serialize_to_vec::<Full, _>(value)
+}
+
+/// Serialize a value using the [`Full`](crate::cfg::Full) configuration into `buf`,
+/// reusing its allocation.
+///
+/// `buf` is cleared, the required capacity is determined by a dry run and reserved,
+/// and then the value is serialized into it. Returns the length of the serialized data,
+/// which equals the length of `buf` afterwards.
+///
+/// When serializing messages in a loop using the same buffer, no reallocation
+/// occurs once the buffer has grown to the size of the largest message.
+///
+/// # Example
+///
+/// ```rust
+/// use postbag::{to_full_vec, to_full_vec_reuse};
+///
+/// let mut buf = Vec::new();
+/// for msg in ["hello", "hi"] {
+///     let len = to_full_vec_reuse(&msg, &mut buf).unwrap();
+///     assert_eq!(len, msg.len() + 1);
+///     assert_eq!(buf, to_full_vec(&msg).unwrap());
+/// }
+/// ```
+pub fn to_full_vec_reuse<T>(value: &T, buf: &mut Vec<u8>) -> Result<usize>
+where
+    T: Serialize + ?Sized,
+{
+    buf.clear();
+    let mut counter = SizeCounter(0);
+    serialize_full(&mut counter, value)?;
+    buf.reserve(counter.0);
+    serialize_full(&mut *buf, value)?;
+    Ok(buf.len())
+}
+
+/// Counts written data without storing it.
+pub(crate) struct SizeCounter(pub(crate) usize);
+
+impl Write for SizeCounter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.0 += data.len();
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Serialize a value using the [`Full`](crate::cfg::Full) configuration and return a `Vec<u8>`
+/// of at most `max` bytes.
+///
+/// Serialization stops with [`Error::SizeLimitExceeded`] as soon as the serialized
+/// data exceeds `max` bytes, for example when a message must fit into a frame,
+/// without serializing the remainder of the value.
+/// Data within skippable blocks is buffered in chunks of up to 64 KiB, thus the limit
+/// may be detected up to this amount of data per nesting level after it has been exceeded.
+///
+/// # Example
+///
+/// ```rust
+/// use postbag::{Error, to_full_vec_bounded};
+///
+/// assert_eq!(to_full_vec_bounded(&vec![1u8; 10], 64).unwrap().len(), 11);
+///
+/// let res = to_full_vec_bounded(&vec![0u8; 1_000_000], 1500);
+/// assert!(matches!(res, Err(Error::SizeLimitExceeded)));
+/// ```
+pub fn to_full_vec_bounded<T>(value: &T, max: usize) -> Result<Vec<u8>>
+where
+    T: Serialize + ?Sized,
+{
+    let mut writer = BoundedWriter { buf: Vec::new(), max };
+    match serialize::<Full, _, _>(&mut writer, value) {
+        Ok(()) => Ok(writer.buf),
+        Err(Error::Io(err))
+            if err
+                .get_ref()
+                .and_then(|err| err.downcast_ref::<Error>())
+                .is_some_and(|err| matches!(err, Error::SizeLimitExceeded)) =>
+        {
+            Err(Error::SizeLimitExceeded)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Collects written data, failing if it exceeds the maximum size.
+struct BoundedWriter {
+    buf: Vec<u8>,
+    max: usize,
+}
+
+impl Write for BoundedWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if data.len() > self.max - self.buf.len() {
+            return Err(Error::SizeLimitExceeded.into());
+        }
+        self.buf.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
 }
 
 /// Serialize a value using the [`Slim`](crate::cfg::Slim) configuration and return a `Vec<u8>`.
 ///
-/// This is a convenience function that creates a new `Vec<u8>` and calls `serialize_slim` on it.
+/// This is a convenience function equivalent to `serialize_to_vec::<Slim, _>(value)`.
 /// It serializes without identifiers, using indices for enum variants.
 ///
 /// # Example
@@ -170,10 +367,34 @@ where
 /// println!("Serialized {} bytes", bytes.len());
 /// ```
 pub fn to_slim_vec<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: Serialize + ?Sized,
+{
+    serialize_to_vec::<crate::cfg::Slim, _>(value)
+}
+
+/// Serialize a value using the [`Canonical`](crate::cfg::Canonical) configuration and return a `Vec<u8>`.
+///
+/// Each value has exactly one canonical serialization, thus the returned bytes
+/// are suitable for hashing or signing. Maps are serialized sorted by key.
+/// Sequences and maps of unknown length are unsupported.
+///
+/// # Example
+///
+/// ```rust
+/// use std::collections::HashMap;
+/// use postbag::to_canonical_vec;
+///
+/// let a: HashMap<u32, u32> = (0..100).map(|i| (i, i)).collect();
+/// let b: HashMap<u32, u32> = (0..100).rev().map(|i| (i, i)).collect();
+///
+/// assert_eq!(to_canonical_vec(&a).unwrap(), to_canonical_vec(&b).unwrap());
+/// ```
+pub fn to_canonical_vec<T>(value: &T) -> Result<Vec<u8>>
 where
     T: Serialize + ?Sized,
 {
     let mut buffer = Vec::new();
-    serialize_slim(&mut buffer, value)?;
+    serialize::<crate::cfg::Canonical, _, _>(&mut buffer, value)?;
     Ok(buffer)
 }
@@ -1,30 +1,285 @@
-use std::{io::Write, marker::PhantomData};
+use std::{io::Write, marker::PhantomData, mem};
 
 use serde::{Serialize, ser};
 
 use crate::{
-    FALSE, ID_COUNT, ID_LEN, ID_LEN_NAME, NONE, SOME, SPECIAL_LEN, TRUE, UNKNOWN_LEN,
-    cfg::Cfg,
-    error::{Error, Result},
-    ser::skippable::SkipWrite,
+    CONTINUE, FALSE, ID_COUNT, ID_LEN, ID_LEN_NAME, NONE, SOME, SPECIAL_LEN, STOP, TERMINATED_LEN, TRUE,
+    UNKNOWN_LEN, bits, bounded,
+    cfg::{Cfg, Endian},
+    char_len_string, compact_float,
+    error::{Error, Result, UnknownLenCause},
+    feature_helper_tuple, fixchar, fixint, named_variant, numeric_identifier, padded_varint, raw,
+    ser::{skippable::SkipWrite, varint_seq::VarintSeqSerializer},
     varint::*,
+    varint_slice, versioned,
 };
 
-/// Serializer
+/// Serializer.
+///
+/// Use [`serialize`](crate::serialize) to serialize a single value.
+/// A serializer can be used directly to write multiple values to the same writer.
 pub struct Serializer<W, CFG> {
     output: SkipWrite<W>,
+    /// Whether the next enum variant is written using its identifier.
+    named_variant: bool,
+    /// Whether the next byte array is written verbatim.
+    raw: bool,
+    /// Whether a field of the current struct has been skipped.
+    skipped_field: bool,
+    /// Position of the next field of the current struct.
+    field_position: usize,
+    /// Whether the current struct has no fields and thus no skippable block.
+    empty_struct: bool,
+    /// Whether the next tuple is the encoding of a helper and thus not enclosed in a skippable block.
+    plain_tuple: bool,
+    /// Current nesting depth of sequences, tuples, maps, structs and enums.
+    depth: usize,
+    /// Maximum nesting depth.
+    max_depth: Option<usize>,
+    /// Number of elements written to the sequence started by [`Self::start_seq`].
+    seq_len: Option<u64>,
     _cfg: PhantomData<CFG>,
 }
 
+/// State of a [`Serializer`] between values, for resuming serialization.
+///
+/// See [`Serializer::checkpoint`] for details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SerializerState {
+    /// Number of bytes written.
+    pub position: u64,
+    /// Number of elements written, if a sequence started by
+    /// [`Serializer::start_seq`] is open.
+    pub seq_len: Option<u64>,
+}
+
 impl<W: Write, CFG: Cfg> Serializer<W, CFG> {
     /// Creates a new serializer.
     pub fn new(write: W) -> Self {
-        Self { output: SkipWrite::new(write), _cfg: PhantomData }
+        Self::restore(write, SerializerState { position: 0, seq_len: None })
+    }
+
+    /// Creates a serializer resuming serialization from a [checkpoint](Self::checkpoint).
+    ///
+    /// The writer must be positioned after the data written up to the checkpoint,
+    /// for example a file truncated to [`SerializerState::position`] bytes.
+    pub fn restore(write: W, state: SerializerState) -> Self {
+        Self {
+            output: SkipWrite::with_position(write, state.position),
+            named_variant: false,
+            raw: false,
+            skipped_field: false,
+            field_position: 0,
+            empty_struct: false,
+            plain_tuple: false,
+            depth: 0,
+            max_depth: None,
+            seq_len: state.seq_len,
+            _cfg: PhantomData,
+        }
+    }
+
+    /// Serializes a value.
+    ///
+    /// This can be called repeatedly to write multiple values as one continuous
+    /// stream before calling [`finalize`](Self::finalize). The values must be read using
+    /// [`Deserializer::deserialize_value`](crate::Deserializer::deserialize_value)
+    /// with exactly the same sequence of types and the same configuration.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use postbag::{Deserializer, Serializer, cfg::Full};
+    ///
+    /// let mut serializer = Serializer::<_, Full>::new(Vec::new());
+    /// serializer.serialize_value("header").unwrap();
+    /// for i in 0..3u32 {
+    ///     serializer.serialize_value(&i).unwrap();
+    /// }
+    /// let data = serializer.finalize().unwrap();
+    ///
+    /// let mut deserializer = Deserializer::<_, Full>::new(data.as_slice());
+    /// assert_eq!(deserializer.deserialize_value::<String>().unwrap(), "header");
+    /// for i in 0..3u32 {
+    ///     assert_eq!(deserializer.deserialize_value::<u32>().unwrap(), i);
+    /// }
+    /// ```
+    pub fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    /// Flushes and returns the writer.
+    pub fn finalize(self) -> Result<W> {
+        Ok(self.output.into_inner()?)
+    }
+
+    /// Number of bytes written between values.
+    pub(crate) fn position(&self) -> u64 {
+        self.output.position()
+    }
+
+    /// Flushes the writer and returns the state of the serializer.
+    ///
+    /// Between values no data is buffered by the serializer, thus all data written so far
+    /// has been passed to the writer. After a crash serialization can be resumed from the
+    /// returned state using [`restore`](Self::restore), given the data written up to this
+    /// point has been persisted.
+    ///
+    /// To write a large value incrementally with a checkpoint after each element, serialize
+    /// it as a sequence using [`start_seq`](Self::start_seq),
+    /// [`serialize_element`](Self::serialize_element) and [`end_seq`](Self::end_seq).
+    ///
+    /// Panics if the serializer is not between values, which is only possible
+    /// after serialization of a value failed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use postbag::{Serializer, cfg::Slim};
+    ///
+    /// let mut serializer = Serializer::<_, Slim>::new(Vec::new());
+    /// serializer.start_seq().unwrap();
+    /// serializer.serialize_element("first").unwrap();
+    /// let state = serializer.checkpoint().unwrap();
+    /// serializer.serialize_element("lost").unwrap();
+    ///
+    /// // Resume after a crash, discarding data written after the checkpoint.
+    /// let mut data = serializer.into_vec().unwrap();
+    /// data.truncate(state.position as usize);
+    /// let mut serializer = Serializer::<_, Slim>::restore(data, state);
+    /// serializer.serialize_element("second").unwrap();
+    /// serializer.end_seq().unwrap();
+    ///
+    /// let data = serializer.into_vec().unwrap();
+    /// assert_eq!(postbag::from_slim_slice::<Vec<String>>(&data).unwrap(), ["first", "second"]);
+    /// ```
+    pub fn checkpoint(&mut self) -> Result<SerializerState> {
+        self.output.flush()?;
+        Ok(SerializerState { position: self.output.position(), seq_len: self.seq_len })
+    }
+
+    /// Starts a sequence of unknown length whose elements are serialized individually
+    /// using [`serialize_element`](Self::serialize_element).
+    ///
+    /// The sequence is written in terminated mode, regardless of
+    /// [`Cfg::terminated_unknown_len`], so that no data is buffered between elements.
+    /// It must be finished using [`end_seq`](Self::end_seq).
+    ///
+    /// Fails with [`Error::SerializeSeqLengthUnknown`] if sequences of unknown length
+    /// are unsupported by the configuration. Panics if a sequence is already open.
+    pub fn start_seq(&mut self) -> Result<()> {
+        assert!(self.seq_len.is_none(), "sequence is already open");
+        if let Some(cause) = Self::unknown_len_rejected() {
+            return Err(Error::SerializeSeqLengthUnknown { cause });
+        }
+
+        self.write_usize(SPECIAL_LEN)?;
+        self.write_usize(TERMINATED_LEN)?;
+        self.seq_len = Some(0);
+        Ok(())
     }
 
-    /// Get the writer.
-    pub fn finalize(self) -> W {
-        self.output.into_inner()
+    /// Serializes an element of the sequence started by [`start_seq`](Self::start_seq).
+    ///
+    /// Panics if no sequence is open.
+    pub fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let len = self.seq_len.expect("no sequence is open");
+        self.enter()?;
+        self.output.write(&[CONTINUE])?;
+        value.serialize(&mut *self)?;
+        self.leave();
+        self.seq_len = Some(len + 1);
+        Ok(())
+    }
+
+    /// Finishes the sequence started by [`start_seq`](Self::start_seq).
+    ///
+    /// Panics if no sequence is open.
+    pub fn end_seq(&mut self) -> Result<()> {
+        assert!(self.seq_len.is_some(), "no sequence is open");
+        self.output.write(&[STOP])?;
+        self.seq_len = None;
+        Ok(())
+    }
+
+    /// Sets the maximum nesting depth of sequences, tuples, maps, structs and enums.
+    pub(crate) fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.max_depth = max_depth;
+    }
+
+    /// Enters a nested value, verifying the depth limit.
+    fn enter(&mut self) -> Result<()> {
+        self.depth += 1;
+        if self.max_depth.is_some_and(|max| self.depth > max) {
+            return Err(Error::DepthLimitExceeded);
+        }
+        Ok(())
+    }
+
+    /// Leaves a nested value.
+    fn leave(&mut self) {
+        self.depth -= 1;
+    }
+
+    /// Serializes a value into a separate buffer using the same configuration and nesting depth.
+    fn serialize_to_buf<T>(&self, value: &T, plain_tuple: bool) -> Result<Vec<u8>>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut serializer = Serializer::<_, CFG>::new(Vec::new());
+        serializer.plain_tuple = plain_tuple;
+        serializer.depth = self.depth;
+        serializer.max_depth = self.max_depth;
+        value.serialize(&mut serializer)?;
+        serializer.finalize()
+    }
+
+    /// Writes a fixed-width value given by its bytes in little-endian order.
+    fn write_fixed<const N: usize>(&mut self, mut bytes: [u8; N]) -> Result<()> {
+        if CFG::endian() == Endian::Big {
+            bytes.reverse();
+        }
+        Ok(self.output.write(&bytes)?)
+    }
+
+    /// Starts a tuple, enclosing it in a skippable block if configured.
+    fn start_tuple(&mut self, len: usize) -> Result<TupleSerializer<'_, W, CFG>> {
+        self.enter()?;
+        let plain = mem::take(&mut self.plain_tuple);
+        let block = CFG::skippable_tuples() && !plain;
+        if block {
+            self.write_usize(len)?;
+            self.output.start_skippable();
+        }
+        Ok(TupleSerializer { serializer: self, block })
+    }
+
+    /// Writes the header of a sequence or map of unknown length.
+    fn write_unknown_len(&mut self) -> Result<()> {
+        self.write_usize(SPECIAL_LEN)?;
+        if CFG::terminated_unknown_len() {
+            self.write_usize(TERMINATED_LEN)
+        } else {
+            self.write_usize(UNKNOWN_LEN)?;
+            self.output.start_skippable();
+            Ok(())
+        }
+    }
+
+    /// Ends a sequence or map of unknown length.
+    fn end_unknown_len(&mut self) -> Result<()> {
+        if CFG::terminated_unknown_len() {
+            self.output.write(&[STOP])?;
+        } else {
+            self.output.end_skippable()?;
+        }
+        Ok(())
     }
 
     fn write_usize(&mut self, data: usize) -> Result<()> {
@@ -60,8 +315,26 @@ impl<W: Write, CFG: Cfg> Serializer<W, CFG> {
         Ok(())
     }
 
+    fn write_variant(&mut self, variant_index: u32, variant: &'static str) -> Result<()> {
+        let named = mem::take(&mut self.named_variant);
+        if CFG::with_idents() || named {
+            self.write_identifier(variant)
+        } else if CFG::fixed_variant_index() {
+            let index = u8::try_from(variant_index).map_err(|_| Error::BadEnum)?;
+            Ok(self.output.write(&[index])?)
+        } else {
+            self.write_u32(variant_index)
+        }
+    }
+
+    /// Writes an identifier.
+    ///
+    /// Identifiers of the form `_N` with `N < ID_COUNT` in canonical decimal notation
+    /// are written as the single value `ID_LEN_NAME + N`. Other identifiers are
+    /// written as their length followed by their bytes, where lengths of `ID_LEN` or
+    /// more are escaped by writing `ID_LEN` before the actual length.
     fn write_identifier(&mut self, ident: &str) -> Result<()> {
-        match ident.strip_prefix("_").and_then(|s| s.parse::<usize>().ok()) {
+        match numeric_identifier(ident) {
             Some(id) if id < ID_COUNT => {
                 self.write_usize(ID_LEN_NAME + id)?;
             }
@@ -80,6 +353,128 @@ impl<W: Write, CFG: Cfg> Serializer<W, CFG> {
 
         Ok(())
     }
+
+    /// Writes a field of a struct or struct variant.
+    ///
+    /// Without identifiers fields are identified by their position, thus
+    /// a field following a skipped field cannot be represented.
+    fn write_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let position = self.field_position;
+        if self.empty_struct {
+            return Err(Error::BadLen);
+        }
+
+        if CFG::with_idents() {
+            self.write_identifier(key)?;
+            self.output.start_skippable();
+        } else if CFG::positional_ids() && !Self::postcard() {
+            self.write_usize(position)?;
+            self.output.start_skippable();
+        } else if self.skipped_field {
+            return Err(Error::SkippedField);
+        }
+
+        value.serialize(&mut *self)?;
+        self.skipped_field = false;
+        self.field_position = position + 1;
+
+        if CFG::with_idents() || (CFG::positional_ids() && !Self::postcard()) {
+            self.output.end_skippable()?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether the encoding is compatible with postcard.
+    fn postcard() -> bool {
+        CFG::postcard_compat() && !CFG::with_idents()
+    }
+
+    /// Setting of the configuration rejecting sequences and maps of unknown length, if any.
+    fn unknown_len_rejected() -> Option<UnknownLenCause> {
+        if Self::postcard() {
+            Some(UnknownLenCause::PostcardCompat)
+        } else if CFG::unknown_len() {
+            None
+        } else if CFG::canonical() {
+            Some(UnknownLenCause::Canonical)
+        } else {
+            Some(UnknownLenCause::UnknownLenDisabled)
+        }
+    }
+
+    /// Starts a struct or struct variant.
+    fn start_struct(&mut self, len: usize) -> Result<()> {
+        self.enter()?;
+        self.field_position = 0;
+        if Self::postcard() {
+            return Ok(());
+        }
+
+        self.write_usize(len)?;
+        if !CFG::with_idents() && !CFG::positional_ids() {
+            // Without fields there is nothing to skip, thus no block is needed.
+            if len == 0 && CFG::omit_empty_struct_block() {
+                self.empty_struct = true;
+            } else {
+                self.output.start_skippable();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Skips a field of a struct or struct variant.
+    ///
+    /// Postcard reads all fields of a struct, thus no field may be skipped.
+    fn skip_struct_field(&mut self) -> Result<()> {
+        if Self::postcard() {
+            return Err(Error::SkippedField);
+        }
+
+        self.skipped_field = true;
+        self.field_position += 1;
+        Ok(())
+    }
+
+    /// Finishes a struct or struct variant.
+    fn end_struct(&mut self) -> Result<()> {
+        self.skipped_field = false;
+        self.leave();
+
+        if !CFG::with_idents()
+            && !CFG::positional_ids()
+            && !Self::postcard()
+            && !mem::take(&mut self.empty_struct)
+        {
+            self.output.end_skippable()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<CFG: Cfg> Serializer<Vec<u8>, CFG> {
+    /// Returns the serialized data.
+    ///
+    /// This is equivalent to [`finalize`](Self::finalize), but avoids flushing
+    /// the in-memory buffer.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use postbag::{Serializer, cfg::Slim};
+    ///
+    /// let mut serializer = Serializer::<_, Slim>::new(Vec::new());
+    /// serializer.serialize_value(&(1u8, "one")).unwrap();
+    /// assert_eq!(serializer.into_vec().unwrap(), postbag::to_slim_vec(&(1u8, "one")).unwrap());
+    /// ```
+    pub fn into_vec(self) -> Result<Vec<u8>> {
+        Ok(self.output.into_inner_unflushed())
+    }
 }
 
 impl<'a, W, CFG> ser::Serializer for &'a mut Serializer<W, CFG>
@@ -91,9 +486,9 @@ where
     type Error = Error;
 
     type SerializeSeq = SeqSerializer<'a, W, CFG>;
-    type SerializeTuple = Self;
-    type SerializeTupleStruct = Self;
-    type SerializeTupleVariant = Self;
+    type SerializeTuple = TupleSerializer<'a, W, CFG>;
+    type SerializeTupleStruct = TupleSerializer<'a, W, CFG>;
+    type SerializeTupleVariant = TupleSerializer<'a, W, CFG>;
     type SerializeMap = MapSerializer<'a, W, CFG>;
     type SerializeStruct = Self;
     type SerializeStructVariant = Self;
@@ -106,6 +501,7 @@ where
         self.serialize_u8(if v { TRUE } else { FALSE })
     }
 
+    // 8-bit integers are written as a raw byte in two's complement, without zigzag encoding.
     fn serialize_i8(self, v: i8) -> Result<()> {
         self.serialize_u8(v.to_le_bytes()[0])
     }
@@ -151,13 +547,13 @@ where
     }
 
     fn serialize_f32(self, v: f32) -> Result<()> {
-        let buf = v.to_bits().to_le_bytes();
-        Ok(self.output.write(&buf)?)
+        let v = if CFG::canonical() && v.is_nan() { f32::NAN } else { v };
+        self.write_fixed(v.to_bits().to_le_bytes())
     }
 
     fn serialize_f64(self, v: f64) -> Result<()> {
-        let buf = v.to_bits().to_le_bytes();
-        Ok(self.output.write(&buf)?)
+        let v = if CFG::canonical() && v.is_nan() { f64::NAN } else { v };
+        self.write_fixed(v.to_bits().to_le_bytes())
     }
 
     fn serialize_char(self, v: char) -> Result<()> {
@@ -173,6 +569,10 @@ where
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        if mem::take(&mut self.raw) {
+            return Ok(self.output.write(v)?);
+        }
+
         self.write_usize(v.len())?;
         Ok(self.output.write(v)?)
     }
@@ -186,7 +586,13 @@ where
         T: ?Sized + Serialize,
     {
         self.serialize_u8(SOME)?;
-        value.serialize(self)
+        if CFG::skippable_options() {
+            self.output.start_skippable();
+            value.serialize(&mut *self)?;
+            Ok(self.output.end_skippable()?)
+        } else {
+            value.serialize(self)
+        }
     }
 
     fn serialize_unit(self) -> Result<()> {
@@ -200,18 +606,49 @@ where
     fn serialize_unit_variant(
         self, _name: &'static str, variant_index: u32, variant: &'static str,
     ) -> Result<()> {
-        if CFG::with_idents() {
-            self.write_identifier(variant)?;
-        } else {
-            self.write_u32(variant_index)?;
-        }
+        self.enter()?;
+        self.write_variant(variant_index, variant)?;
+        self.leave();
         Ok(())
     }
 
-    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<()>
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
+        if name == named_variant::NAME {
+            self.named_variant = true;
+            let res = value.serialize(&mut *self);
+            self.named_variant = false;
+            return res;
+        }
+
+        if name == raw::NAME {
+            self.raw = true;
+            let res = value.serialize(&mut *self);
+            self.raw = false;
+            return res;
+        }
+
+        if name == varint_slice::NAME {
+            return value
+                .serialize(&mut VarintSeqSerializer::new(&mut self.output, !Serializer::<W, CFG>::postcard()));
+        }
+
+        let helper = name == fixint::NAME || name == fixchar::NAME;
+        if helper && CFG::endian() == Endian::Big {
+            let mut buf = self.serialize_to_buf(value, true)?;
+            buf.reverse();
+            return Ok(self.output.write(&buf)?);
+        }
+
+        if helper || name == char_len_string::NAME {
+            self.plain_tuple = true;
+            let res = value.serialize(&mut *self);
+            self.plain_tuple = false;
+            return res;
+        }
+
         value.serialize(self)
     }
 
@@ -221,95 +658,91 @@ where
     where
         T: ?Sized + Serialize,
     {
-        if CFG::with_idents() {
-            self.write_identifier(variant)?;
-        } else {
-            self.write_u32(variant_index)?;
-        }
-        value.serialize(self)?;
+        self.enter()?;
+        self.write_variant(variant_index, variant)?;
+        value.serialize(&mut *self)?;
+        self.leave();
 
         Ok(())
     }
 
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        self.enter()?;
         match len {
-            Some(SPECIAL_LEN) => {
+            Some(SPECIAL_LEN) if !Serializer::<W, CFG>::postcard() => {
                 self.write_usize(SPECIAL_LEN)?;
                 self.write_usize(SPECIAL_LEN)?;
             }
             Some(len) => self.write_usize(len)?,
-            None => {
-                self.write_usize(SPECIAL_LEN)?;
-                self.write_usize(UNKNOWN_LEN)?;
-                self.output.start_skippable();
-            }
+            None => match Serializer::<W, CFG>::unknown_len_rejected() {
+                Some(cause) => return Err(Error::SerializeSeqLengthUnknown { cause }),
+                None => self.write_unknown_len()?,
+            },
         }
 
         Ok(SeqSerializer { serializer: self, len })
     }
 
-    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
-        Ok(self)
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.start_tuple(len)
     }
 
-    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct> {
-        Ok(self)
+    fn serialize_tuple_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeTupleStruct> {
+        if name == padded_varint::NAME && CFG::canonical() {
+            return Err(Error::NonCanonical);
+        }
+
+        if name == bounded::NAME
+            || name == fixint::checked::NAME
+            || name == versioned::NAME
+            || name == padded_varint::NAME
+            || name == bits::NAME
+            || name == compact_float::NAME
+            || feature_helper_tuple(name)
+        {
+            self.plain_tuple = true;
+        }
+        self.start_tuple(len)
     }
 
     fn serialize_tuple_variant(
-        self, _name: &'static str, variant_index: u32, variant: &'static str, _len: usize,
+        self, _name: &'static str, variant_index: u32, variant: &'static str, len: usize,
     ) -> Result<Self::SerializeTupleVariant> {
-        if CFG::with_idents() {
-            self.write_identifier(variant)?;
-        } else {
-            self.write_u32(variant_index)?;
-        }
+        self.enter()?;
+        self.write_variant(variant_index, variant)?;
 
-        Ok(self)
+        self.start_tuple(len)
     }
 
     fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
+        self.enter()?;
         match len {
-            Some(SPECIAL_LEN) => {
+            Some(SPECIAL_LEN) if !Serializer::<W, CFG>::postcard() => {
                 self.write_usize(SPECIAL_LEN)?;
                 self.write_usize(SPECIAL_LEN)?;
             }
             Some(len) => self.write_usize(len)?,
-            None => {
-                self.write_usize(SPECIAL_LEN)?;
-                self.write_usize(UNKNOWN_LEN)?;
-                self.output.start_skippable();
-            }
+            None => match Serializer::<W, CFG>::unknown_len_rejected() {
+                Some(cause) => return Err(Error::SerializeMapLengthUnknown { cause }),
+                None => self.write_unknown_len()?,
+            },
         }
 
-        Ok(MapSerializer { serializer: self, len })
+        let sorted = if CFG::sort_map_keys() { Some(Vec::new()) } else { None };
+        Ok(MapSerializer { serializer: self, len, sorted })
     }
 
     fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
-        self.write_usize(len)?;
-
-        if !CFG::with_idents() {
-            self.output.start_skippable();
-        }
-
+        self.start_struct(len)?;
         Ok(self)
     }
 
     fn serialize_struct_variant(
         self, _name: &'static str, variant_index: u32, variant: &'static str, len: usize,
     ) -> Result<Self::SerializeStructVariant> {
-        if CFG::with_idents() {
-            self.write_identifier(variant)?;
-        } else {
-            self.write_u32(variant_index)?;
-        }
-
-        self.write_usize(len)?;
-
-        if !CFG::with_idents() {
-            self.output.start_skippable();
-        }
-
+        self.enter()?;
+        self.write_variant(variant_index, variant)?;
+        self.start_struct(len)?;
         Ok(self)
     }
 }
@@ -332,19 +765,39 @@ where
     where
         T: ?Sized + Serialize,
     {
+        if self.len.is_none() && CFG::terminated_unknown_len() {
+            self.serializer.output.write(&[CONTINUE])?;
+        }
         value.serialize(&mut *self.serializer)
     }
 
     fn end(self) -> Result<()> {
         if self.len.is_none() {
-            self.serializer.output.end_skippable()?;
+            self.serializer.end_unknown_len()?;
         }
 
+        self.serializer.leave();
+        Ok(())
+    }
+}
+
+pub struct TupleSerializer<'a, W, CFG> {
+    serializer: &'a mut Serializer<W, CFG>,
+    /// Whether the tuple is enclosed in a skippable block.
+    block: bool,
+}
+
+impl<W: Write, CFG: Cfg> TupleSerializer<'_, W, CFG> {
+    fn end_tuple(self) -> Result<()> {
+        if self.block {
+            self.serializer.output.end_skippable()?;
+        }
+        self.serializer.leave();
         Ok(())
     }
 }
 
-impl<W, CFG> ser::SerializeTuple for &mut Serializer<W, CFG>
+impl<'a, W, CFG> ser::SerializeTuple for TupleSerializer<'a, W, CFG>
 where
     W: Write,
     CFG: Cfg,
@@ -357,15 +810,15 @@ where
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        value.serialize(&mut *self.serializer)
     }
 
     fn end(self) -> Result<()> {
-        Ok(())
+        self.end_tuple()
     }
 }
 
-impl<W, CFG> ser::SerializeTupleStruct for &mut Serializer<W, CFG>
+impl<'a, W, CFG> ser::SerializeTupleStruct for TupleSerializer<'a, W, CFG>
 where
     W: Write,
     CFG: Cfg,
@@ -378,15 +831,15 @@ where
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        value.serialize(&mut *self.serializer)
     }
 
     fn end(self) -> Result<()> {
-        Ok(())
+        self.end_tuple()
     }
 }
 
-impl<W, CFG> ser::SerializeTupleVariant for &mut Serializer<W, CFG>
+impl<'a, W, CFG> ser::SerializeTupleVariant for TupleSerializer<'a, W, CFG>
 where
     W: Write,
     CFG: Cfg,
@@ -399,17 +852,20 @@ where
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        value.serialize(&mut *self.serializer)
     }
 
     fn end(self) -> Result<()> {
-        Ok(())
+        self.serializer.leave();
+        self.end_tuple()
     }
 }
 
 pub struct MapSerializer<'a, W, CFG> {
     serializer: &'a mut Serializer<W, CFG>,
     len: Option<usize>,
+    /// Serialized key/value pairs, when map keys are sorted.
+    sorted: Option<Vec<(Vec<u8>, Vec<u8>)>>,
 }
 
 impl<'a, W, CFG> ser::SerializeMap for MapSerializer<'a, W, CFG>
@@ -425,7 +881,18 @@ where
     where
         T: ?Sized + Serialize,
     {
-        key.serialize(&mut *self.serializer)
+        match &mut self.sorted {
+            Some(entries) => {
+                entries.push((self.serializer.serialize_to_buf(key, false)?, Vec::new()));
+                Ok(())
+            }
+            None => {
+                if self.len.is_none() && CFG::terminated_unknown_len() {
+                    self.serializer.output.write(&[CONTINUE])?;
+                }
+                key.serialize(&mut *self.serializer)
+            }
+        }
     }
 
     #[inline(never)]
@@ -433,14 +900,35 @@ where
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut *self.serializer)
+        match &mut self.sorted {
+            Some(entries) => {
+                let Some((_, buf)) = entries.last_mut() else {
+                    return Err(ser::Error::custom("serialize_value called before serialize_key"));
+                };
+                *buf = self.serializer.serialize_to_buf(value, false)?;
+                Ok(())
+            }
+            None => value.serialize(&mut *self.serializer),
+        }
     }
 
     fn end(self) -> Result<()> {
+        if let Some(mut entries) = self.sorted {
+            entries.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+            for (key, value) in entries {
+                if self.len.is_none() && CFG::terminated_unknown_len() {
+                    self.serializer.output.write(&[CONTINUE])?;
+                }
+                self.serializer.output.write(&key)?;
+                self.serializer.output.write(&value)?;
+            }
+        }
+
         if self.len.is_none() {
-            self.serializer.output.end_skippable()?;
+            self.serializer.end_unknown_len()?;
         }
 
+        self.serializer.leave();
         Ok(())
     }
 }
@@ -458,26 +946,15 @@ where
     where
         T: ?Sized + Serialize,
     {
-        if CFG::with_idents() {
-            self.write_identifier(key)?;
-            self.output.start_skippable();
-        }
-
-        value.serialize(&mut **self)?;
-
-        if CFG::with_idents() {
-            self.output.end_skippable()?;
-        }
+        self.write_field(key, value)
+    }
 
-        Ok(())
+    fn skip_field(&mut self, _key: &'static str) -> Result<()> {
+        self.skip_struct_field()
     }
 
     fn end(self) -> Result<()> {
-        if !CFG::with_idents() {
-            self.output.end_skippable()?;
-        }
-
-        Ok(())
+        self.end_struct()
     }
 }
 
@@ -494,41 +971,31 @@ where
     where
         T: ?Sized + Serialize,
     {
-        if CFG::with_idents() {
-            self.write_identifier(key)?;
-            self.output.start_skippable();
-        }
-
-        value.serialize(&mut **self)?;
-
-        if CFG::with_idents() {
-            self.output.end_skippable()?;
-        }
+        self.write_field(key, value)
+    }
 
-        Ok(())
+    fn skip_field(&mut self, _key: &'static str) -> Result<()> {
+        self.skip_struct_field()
     }
 
     fn end(self) -> Result<()> {
-        if !CFG::with_idents() {
-            self.output.end_skippable()?;
-        }
-
-        Ok(())
+        self.leave();
+        self.end_struct()
     }
 }
 
-fn zig_zag_i16(n: i16) -> u16 {
+pub(super) fn zig_zag_i16(n: i16) -> u16 {
     ((n << 1) ^ (n >> 15)) as u16
 }
 
-fn zig_zag_i32(n: i32) -> u32 {
+pub(super) fn zig_zag_i32(n: i32) -> u32 {
     ((n << 1) ^ (n >> 31)) as u32
 }
 
-fn zig_zag_i64(n: i64) -> u64 {
+pub(super) fn zig_zag_i64(n: i64) -> u64 {
     ((n << 1) ^ (n >> 63)) as u64
 }
 
-fn zig_zag_i128(n: i128) -> u128 {
+pub(super) fn zig_zag_i128(n: i128) -> u128 {
     ((n << 1) ^ (n >> 127)) as u128
 }
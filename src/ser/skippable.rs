@@ -5,15 +5,38 @@ use std::{
     mem,
 };
 
-use crate::varint::{varint_max, varint_u16};
+use crate::{
+    format::SKIP_CHUNK_MAX,
+    varint::{varint_max, varint_u16},
+};
 
 /// Writer that allows block to be (partially) skipped during reading.
-pub struct SkipWrite<W>(SkipStack<W>);
+pub struct SkipWrite<W>(SkipStack<Counting<W>>);
 
 impl<W: Write> SkipWrite<W> {
-    /// Creates a new skip writer.
-    pub fn new(inner: W) -> Self {
-        Self(SkipStack::Base(inner))
+    /// Creates a new skip writer, whose inner writer is positioned after `position` bytes.
+    pub fn with_position(inner: W, position: u64) -> Self {
+        Self(SkipStack::Base(Counting { inner, written: position }))
+    }
+
+    /// Number of bytes written to the inner writer.
+    ///
+    /// Panics if a skip block is open.
+    pub fn position(&self) -> u64 {
+        match &self.0 {
+            SkipStack::Base(base) => base.written,
+            _ => panic!("skip block is open"),
+        }
+    }
+
+    /// Flushes the inner writer.
+    ///
+    /// Panics if a skip block is open.
+    pub fn flush(&mut self) -> Result<()> {
+        match &mut self.0 {
+            SkipStack::Base(base) => base.inner.flush(),
+            _ => panic!("skip block is open"),
+        }
     }
 
     /// Write bytes.
@@ -39,9 +62,40 @@ impl<W: Write> SkipWrite<W> {
         Ok(())
     }
 
-    /// Returns the contained writer.
-    pub fn into_inner(self) -> W {
-        self.0.into_inner()
+    /// Flushes and returns the contained writer.
+    pub fn into_inner(self) -> Result<W> {
+        let mut inner = self.into_inner_unflushed();
+        inner.flush()?;
+        Ok(inner)
+    }
+
+    /// Returns the contained writer without flushing it.
+    pub fn into_inner_unflushed(self) -> W {
+        self.0.into_inner().inner
+    }
+}
+
+/// Writer counting the bytes written to the inner writer.
+struct Counting<W> {
+    inner: W,
+    written: u64,
+}
+
+impl<W: Write> Write for Counting<W> {
+    fn write(&mut self, data: &[u8]) -> Result<usize> {
+        let n = self.inner.write(data)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn write_all(&mut self, data: &[u8]) -> Result<()> {
+        self.inner.write_all(data)?;
+        self.written += data.len() as u64;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
     }
 }
 
@@ -75,35 +129,46 @@ struct SkipBlock<W> {
 }
 
 impl<W: Write> SkipBlock<W> {
-    const MAX_LEN: usize = u16::MAX as usize;
+    const MAX_LEN: usize = SKIP_CHUNK_MAX;
 
     fn new(inner: SkipStack<W>) -> Self {
         Self { inner: Box::new(inner), buf: Vec::new() }
     }
 
-    fn write(&mut self, data: &[u8]) -> Result<()> {
-        self.buf.extend_from_slice(data);
-        self.flush_buf_if_required()?;
-
-        Ok(())
-    }
-
-    fn flush_buf_if_required(&mut self) -> Result<()> {
-        while self.buf.len() >= Self::MAX_LEN {
-            let rem = self.buf.split_off(Self::MAX_LEN);
-            self.flush_buf()?;
-            self.buf = rem;
+    fn write(&mut self, mut data: &[u8]) -> Result<()> {
+        while !data.is_empty() {
+            if self.buf.is_empty() && data.len() >= Self::MAX_LEN {
+                // Pass full chunks through to the inner writer without buffering.
+                let (chunk, rem) = data.split_at(Self::MAX_LEN);
+                Self::write_chunk(&mut self.inner, chunk)?;
+                data = rem;
+                continue;
+            }
+
+            let n = data.len().min(Self::MAX_LEN - self.buf.len());
+            let (part, rem) = data.split_at(n);
+            self.buf.extend_from_slice(part);
+            data = rem;
+
+            if self.buf.len() == Self::MAX_LEN {
+                self.flush_buf()?;
+                self.buf.clear();
+            }
         }
 
         Ok(())
     }
 
     fn flush_buf(&mut self) -> Result<()> {
+        Self::write_chunk(&mut self.inner, &self.buf)
+    }
+
+    fn write_chunk(inner: &mut SkipStack<W>, chunk: &[u8]) -> Result<()> {
         let mut len_buf = [0; varint_max::<u16>()];
-        let len_buf = varint_u16(self.buf.len().try_into().unwrap(), &mut len_buf);
-        self.inner.write(len_buf)?;
+        let len_buf = varint_u16(chunk.len().try_into().unwrap(), &mut len_buf);
+        inner.write(len_buf)?;
 
-        self.inner.write(&self.buf)
+        inner.write(chunk)
     }
 
     fn finish(mut self) -> Result<SkipStack<W>> {
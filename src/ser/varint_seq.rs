@@ -0,0 +1,247 @@
+//! Fast path for serializing sequences of integers.
+
+use std::io::Write;
+
+use serde::{Serialize, ser};
+
+use crate::{
+    SPECIAL_LEN,
+    error::{Error, Result},
+    ser::{
+        serializer::{zig_zag_i16, zig_zag_i32, zig_zag_i64, zig_zag_i128},
+        skippable::SkipWrite,
+    },
+    varint::*,
+};
+
+/// Size of the buffer collecting encoded integers before they are written.
+const BUF_LEN: usize = 1024;
+
+/// Serializes a sequence of integers into a stack buffer, which is written
+/// to the output when full.
+///
+/// The output is identical to the serialization of the sequence by
+/// [`Serializer`](super::serializer::Serializer).
+pub(crate) struct VarintSeqSerializer<'a, W> {
+    output: &'a mut SkipWrite<W>,
+    buf: [u8; BUF_LEN],
+    pos: usize,
+    /// Whether the length `SPECIAL_LEN` is escaped, which is not the case in postcard format.
+    escape_special_len: bool,
+}
+
+impl<'a, W: Write> VarintSeqSerializer<'a, W> {
+    pub(crate) fn new(output: &'a mut SkipWrite<W>, escape_special_len: bool) -> Self {
+        Self { output, buf: [0; BUF_LEN], pos: 0, escape_special_len }
+    }
+
+    #[inline]
+    fn push(&mut self, data: &[u8]) -> Result<()> {
+        if self.pos + data.len() > BUF_LEN {
+            self.flush()?;
+        }
+        self.buf[self.pos..self.pos + data.len()].copy_from_slice(data);
+        self.pos += data.len();
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.output.write(&self.buf[..self.pos])?;
+        self.pos = 0;
+        Ok(())
+    }
+
+    #[inline]
+    fn push_u64(&mut self, v: u64) -> Result<()> {
+        let mut buf = [0u8; varint_max::<u64>()];
+        let used = varint_u64(v, &mut buf);
+        self.push(used)
+    }
+}
+
+fn unsupported() -> Error {
+    Error::Custom("only sequences of integers are supported".to_string())
+}
+
+impl<'a, W: Write> ser::Serializer for &mut VarintSeqSerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Self;
+    type SerializeTuple = ser::Impossible<(), Error>;
+    type SerializeTupleStruct = ser::Impossible<(), Error>;
+    type SerializeTupleVariant = ser::Impossible<(), Error>;
+    type SerializeMap = ser::Impossible<(), Error>;
+    type SerializeStruct = ser::Impossible<(), Error>;
+    type SerializeStructVariant = ser::Impossible<(), Error>;
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self> {
+        let len = u64::try_from(len.ok_or_else(unsupported)?).map_err(|_| Error::UsizeOverflow)?;
+        if self.escape_special_len && len == SPECIAL_LEN as u64 {
+            self.push_u64(len)?;
+        }
+        self.push_u64(len)?;
+        Ok(self)
+    }
+
+    // 8-bit integers are written as a raw byte in two's complement, without zigzag encoding.
+    fn serialize_i8(self, v: i8) -> Result<()> {
+        self.push(&v.to_le_bytes())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<()> {
+        self.serialize_u16(zig_zag_i16(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<()> {
+        self.serialize_u32(zig_zag_i32(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<()> {
+        self.push_u64(zig_zag_i64(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<()> {
+        self.serialize_u128(zig_zag_i128(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<()> {
+        self.push(&[v])
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<()> {
+        let mut buf = [0u8; varint_max::<u16>()];
+        let used = varint_u16(v, &mut buf);
+        self.push(used)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<()> {
+        let mut buf = [0u8; varint_max::<u32>()];
+        let used = varint_u32(v, &mut buf);
+        self.push(used)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<()> {
+        self.push_u64(v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<()> {
+        let mut buf = [0u8; varint_max::<u128>()];
+        let used = varint_u128(v, &mut buf);
+        self.push(used)
+    }
+
+    fn serialize_bool(self, _v: bool) -> Result<()> {
+        Err(unsupported())
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<()> {
+        Err(unsupported())
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<()> {
+        Err(unsupported())
+    }
+
+    fn serialize_char(self, _v: char) -> Result<()> {
+        Err(unsupported())
+    }
+
+    fn serialize_str(self, _v: &str) -> Result<()> {
+        Err(unsupported())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<()> {
+        Err(unsupported())
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        Err(unsupported())
+    }
+
+    fn serialize_some<T>(self, _value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(unsupported())
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        Err(unsupported())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        Err(unsupported())
+    }
+
+    fn serialize_unit_variant(
+        self, _name: &'static str, _variant_index: u32, _variant: &'static str,
+    ) -> Result<()> {
+        Err(unsupported())
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, _value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(unsupported())
+    }
+
+    fn serialize_newtype_variant<T>(
+        self, _name: &'static str, _variant_index: u32, _variant: &'static str, _value: &T,
+    ) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(unsupported())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(unsupported())
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct> {
+        Err(unsupported())
+    }
+
+    fn serialize_tuple_variant(
+        self, _name: &'static str, _variant_index: u32, _variant: &'static str, _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(unsupported())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(unsupported())
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(unsupported())
+    }
+
+    fn serialize_struct_variant(
+        self, _name: &'static str, _variant_index: u32, _variant: &'static str, _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(unsupported())
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+impl<'a, W: Write> ser::SerializeSeq for &mut VarintSeqSerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    #[inline]
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<()> {
+        self.flush()
+    }
+}
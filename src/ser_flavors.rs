@@ -0,0 +1,254 @@
+//! # Serialization Flavors
+//!
+//! Compatibility with the flavors of [postcard](https://docs.rs/postcard), to ease
+//! migrating code that frames its serialized data using a stack of flavors.
+//!
+//! A [`Flavor`] receives the serialized bytes and produces an output, such as a
+//! vector or a slice. Modifier flavors, such as [`Cobs`] and [`Crc32`], transform
+//! the bytes and pass them on to the flavor they wrap. [`serialize_with_flavor`]
+//! feeds the output of the postbag serializer through a flavor.
+//!
+//! ```rust
+//! use postbag::{
+//!     cfg::Slim,
+//!     ser_flavors::{Cobs, Crc32, StdVec, serialize_with_flavor},
+//! };
+//!
+//! let framed = serialize_with_flavor::<Slim, _, _>(&(1u8, "data"), Crc32::new(Cobs::new(StdVec::new()))).unwrap();
+//! assert_eq!(framed.last(), Some(&0));
+//! assert!(!framed[..framed.len() - 1].contains(&0));
+//! ```
+
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+use crate::{
+    cfg::Cfg,
+    error::{Error, Result},
+    ser::serialize,
+};
+
+/// Storage or modifier of serialized data.
+///
+/// This mirrors the `Flavor` trait of postcard.
+pub trait Flavor {
+    /// Output of the flavor when finalized.
+    type Output;
+
+    /// Appends bytes.
+    fn try_extend(&mut self, data: &[u8]) -> Result<()> {
+        data.iter().try_for_each(|byte| self.try_push(*byte))
+    }
+
+    /// Appends a byte.
+    fn try_push(&mut self, data: u8) -> Result<()>;
+
+    /// Completes the data and returns the output.
+    fn finalize(self) -> Result<Self::Output>;
+}
+
+/// Serialize a value using the specified configuration through a flavor and return its output.
+pub fn serialize_with_flavor<CFG, T, F>(value: &T, flavor: F) -> Result<F::Output>
+where
+    CFG: Cfg,
+    T: Serialize + ?Sized,
+    F: Flavor,
+{
+    let mut writer = FlavorWriter(flavor);
+    serialize::<CFG, _, _>(&mut writer, value)?;
+    writer.0.finalize()
+}
+
+/// Passes written data to a flavor.
+struct FlavorWriter<F>(F);
+
+impl<F: Flavor> Write for FlavorWriter<F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.try_extend(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Flavor storing data in a vector.
+#[derive(Debug, Clone, Default)]
+pub struct StdVec(Vec<u8>);
+
+impl StdVec {
+    /// Creates an empty vector flavor.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Flavor for StdVec {
+    type Output = Vec<u8>;
+
+    fn try_extend(&mut self, data: &[u8]) -> Result<()> {
+        self.0.extend_from_slice(data);
+        Ok(())
+    }
+
+    fn try_push(&mut self, data: u8) -> Result<()> {
+        self.0.push(data);
+        Ok(())
+    }
+
+    fn finalize(self) -> Result<Vec<u8>> {
+        Ok(self.0)
+    }
+}
+
+/// Flavor storing data in a slice.
+///
+/// The output is the used part of the slice.
+/// Exceeding the slice fails with an I/O error of kind [`WriteZero`](io::ErrorKind::WriteZero).
+#[derive(Debug)]
+pub struct Slice<'a> {
+    buf: &'a mut [u8],
+    pos: usize,
+}
+
+impl<'a> Slice<'a> {
+    /// Creates a flavor storing data in the specified slice.
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+}
+
+impl<'a> Flavor for Slice<'a> {
+    type Output = &'a mut [u8];
+
+    fn try_extend(&mut self, data: &[u8]) -> Result<()> {
+        let end = self.pos + data.len();
+        if end > self.buf.len() {
+            return Err(Error::Io(io::ErrorKind::WriteZero.into()));
+        }
+        self.buf[self.pos..end].copy_from_slice(data);
+        self.pos = end;
+        Ok(())
+    }
+
+    fn try_push(&mut self, data: u8) -> Result<()> {
+        self.try_extend(&[data])
+    }
+
+    fn finalize(self) -> Result<&'a mut [u8]> {
+        Ok(&mut self.buf[..self.pos])
+    }
+}
+
+/// Maximum number of data bytes in a COBS block.
+const COBS_BLOCK_MAX: usize = 254;
+
+/// Modifier flavor encoding data using consistent overhead byte stuffing (COBS).
+///
+/// The encoded data contains no zero bytes and is terminated by a zero byte,
+/// allowing it to be used as a frame in a byte stream.
+#[derive(Debug)]
+pub struct Cobs<B> {
+    inner: B,
+    /// Data of the current block, excluding its code byte.
+    block: Vec<u8>,
+}
+
+impl<B: Flavor> Cobs<B> {
+    /// Creates a COBS encoder passing the encoded data to `inner`.
+    pub fn new(inner: B) -> Self {
+        Self { inner, block: Vec::with_capacity(COBS_BLOCK_MAX) }
+    }
+
+    /// Passes the current block prefixed by its code byte to the inner flavor.
+    fn emit_block(&mut self) -> Result<()> {
+        self.inner.try_push(self.block.len() as u8 + 1)?;
+        self.inner.try_extend(&self.block)?;
+        self.block.clear();
+        Ok(())
+    }
+}
+
+impl<B: Flavor> Flavor for Cobs<B> {
+    type Output = B::Output;
+
+    fn try_push(&mut self, data: u8) -> Result<()> {
+        // A full block is emitted only once more data follows, since
+        // a full block at the end needs no empty block after it.
+        if self.block.len() == COBS_BLOCK_MAX {
+            self.emit_block()?;
+        }
+
+        match data {
+            0 => self.emit_block(),
+            _ => {
+                self.block.push(data);
+                Ok(())
+            }
+        }
+    }
+
+    fn finalize(mut self) -> Result<B::Output> {
+        self.emit_block()?;
+        self.inner.try_push(0)?;
+        self.inner.finalize()
+    }
+}
+
+/// Modifier flavor appending the CRC-32 checksum of the data in little-endian byte order.
+///
+/// The checksum uses the CRC-32/ISO-HDLC parameters, as used by Ethernet and zlib.
+#[derive(Debug)]
+pub struct Crc32<B> {
+    inner: B,
+    crc: u32,
+}
+
+impl<B: Flavor> Crc32<B> {
+    /// Creates a CRC-32 modifier passing the data and checksum to `inner`.
+    pub fn new(inner: B) -> Self {
+        Self { inner, crc: !0 }
+    }
+}
+
+impl<B: Flavor> Flavor for Crc32<B> {
+    type Output = B::Output;
+
+    fn try_extend(&mut self, data: &[u8]) -> Result<()> {
+        self.crc = data.iter().fold(self.crc, |crc, byte| crc32_update(crc, *byte));
+        self.inner.try_extend(data)
+    }
+
+    fn try_push(&mut self, data: u8) -> Result<()> {
+        self.crc = crc32_update(self.crc, data);
+        self.inner.try_push(data)
+    }
+
+    fn finalize(mut self) -> Result<B::Output> {
+        self.inner.try_extend(&(!self.crc).to_le_bytes())?;
+        self.inner.finalize()
+    }
+}
+
+/// Lookup table of the reflected CRC-32 polynomial.
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+pub(crate) fn crc32_update(crc: u32, byte: u8) -> u32 {
+    CRC32_TABLE[((crc ^ u32::from(byte)) & 0xff) as usize] ^ (crc >> 8)
+}
@@ -0,0 +1,182 @@
+//! # Byte Arrays
+//!
+//! Serde serializes `Vec<u8>` and `[u8; N]` as sequences of `u8` elements, which are
+//! processed element by element. This module, for use with `#[serde(with = "postbag::serde_bytes")]`,
+//! serializes them as byte arrays instead, which are written and read in one piece.
+//! It is a replacement for the `serde_bytes` crate supporting `Vec<u8>`, `Box<[u8]>`,
+//! `Cow<[u8]>`, `[u8; N]` and options of these.
+//! A `Cow<[u8]>` is always deserialized as [`Cow::Owned`], since the data is read from
+//! a reader and cannot be borrowed.
+//!
+//! Since postbag encodes a `u8` as a single byte, a sequence of `u8` and a byte array
+//! of the same length are encoded identically. Thus this module can be applied to
+//! or removed from a `Vec<u8>` or `Box<[u8]>` field without breaking compatibility
+//! and without changing the serialized size; it only reduces the processing cost.
+//! This does not hold for `[u8; N]`, which serde serializes as a tuple without length,
+//! while a byte array is prefixed by its length.
+//!
+//! ```rust
+//! # use serde::{Serialize, Deserialize};
+//! #[derive(Serialize, Deserialize)]
+//! pub struct Blob(#[serde(with = "postbag::serde_bytes")] Vec<u8>);
+//! ```
+
+use std::{borrow::Cow, fmt, marker::PhantomData};
+
+use serde::{
+    Deserializer, Serialize, Serializer,
+    de::{self, Visitor},
+};
+
+/// Serialize the value as a byte array.
+pub fn serialize<S, T>(val: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Bytes,
+{
+    val.serialize(serializer)
+}
+
+/// Deserialize the value from a byte array.
+pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Bytes,
+{
+    T::deserialize(deserializer)
+}
+
+/// Byte array supported by this module.
+pub trait Bytes: Sized + private::Sealed {
+    #[doc(hidden)]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>;
+
+    #[doc(hidden)]
+    fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>;
+}
+
+mod private {
+    pub trait Sealed {}
+}
+
+impl private::Sealed for Vec<u8> {}
+
+impl Bytes for Vec<u8> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self)
+    }
+
+    fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_byte_buf(ByteBufVisitor)
+    }
+}
+
+impl private::Sealed for Box<[u8]> {}
+
+impl Bytes for Box<[u8]> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self)
+    }
+
+    fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_byte_buf(ByteBufVisitor).map(Vec::into_boxed_slice)
+    }
+}
+
+impl private::Sealed for Cow<'_, [u8]> {}
+
+impl Bytes for Cow<'_, [u8]> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self)
+    }
+
+    fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_byte_buf(ByteBufVisitor).map(Cow::Owned)
+    }
+}
+
+impl<const N: usize> private::Sealed for [u8; N] {}
+
+impl<const N: usize> Bytes for [u8; N] {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self)
+    }
+
+    fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_bytes(ArrayVisitor::<N>)
+    }
+}
+
+impl<T: Bytes> private::Sealed for Option<T> {}
+
+impl<T: Bytes> Bytes for Option<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Some(value) => serializer.serialize_some(&AsBytes(value)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_option(OptionVisitor(PhantomData))
+    }
+}
+
+/// Serializes the wrapped value as a byte array.
+struct AsBytes<'a, T>(&'a T);
+
+impl<T: Bytes> Serialize for AsBytes<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+struct ByteBufVisitor;
+
+impl<'de> Visitor<'de> for ByteBufVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a byte array")
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Vec<u8>, E> {
+        Ok(v.to_vec())
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Vec<u8>, E> {
+        Ok(v)
+    }
+}
+
+struct ArrayVisitor<const N: usize>;
+
+impl<'de, const N: usize> Visitor<'de> for ArrayVisitor<N> {
+    type Value = [u8; N];
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a byte array of length {N}")
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<[u8; N], E> {
+        v.try_into().map_err(|_| de::Error::invalid_length(v.len(), &self))
+    }
+}
+
+struct OptionVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Bytes> Visitor<'de> for OptionVisitor<T> {
+    type Value = Option<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an optional byte array")
+    }
+
+    fn visit_none<E>(self) -> Result<Option<T>, E> {
+        Ok(None)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Option<T>, D::Error> {
+        T::deserialize(deserializer).map(Some)
+    }
+}
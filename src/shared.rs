@@ -0,0 +1,123 @@
+//! # Shared Strings and Byte Arrays
+//!
+//! Serializes `Arc<str>`, `Rc<str>`, `Arc<[u8]>` and `Rc<[u8]>` like `&str`
+//! and `&[u8]` respectively, without requiring the `rc` feature of serde.
+//! Use with `#[serde(with = "postbag::shared")]`.
+//!
+//! During deserialization the data is read into a reusable buffer and
+//! copied into the shared allocation, avoiding an intermediate `String`
+//! or `Vec<u8>`.
+//!
+//! ```rust
+//! # use std::sync::Arc;
+//! # use serde::{Serialize, Deserialize};
+//! #[derive(Serialize, Deserialize)]
+//! pub struct CacheEntry {
+//!     #[serde(with = "postbag::shared")]
+//!     key: Arc<str>,
+//!     #[serde(with = "postbag::shared")]
+//!     data: Arc<[u8]>,
+//! }
+//! ```
+
+use std::{fmt, marker::PhantomData, rc::Rc, sync::Arc};
+
+use serde::{Deserializer, Serializer, de::Visitor};
+
+/// Serialize the shared string or byte array.
+pub fn serialize<S, T>(val: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Shared,
+{
+    val.serialize(serializer)
+}
+
+/// Deserialize the shared string or byte array.
+pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Shared,
+{
+    T::deserialize(deserializer)
+}
+
+/// Shared string or byte array supported by this module.
+pub trait Shared: Sized + private::Sealed {
+    #[doc(hidden)]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>;
+
+    #[doc(hidden)]
+    fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>;
+}
+
+mod private {
+    pub trait Sealed {}
+}
+
+macro_rules! impl_shared_str {
+    ($( $ty:ty ),*) => {
+        $(
+            impl private::Sealed for $ty {}
+
+            impl Shared for $ty {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    serializer.serialize_str(self)
+                }
+
+                fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    deserializer.deserialize_str(StrVisitor(PhantomData))
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_shared_bytes {
+    ($( $ty:ty ),*) => {
+        $(
+            impl private::Sealed for $ty {}
+
+            impl Shared for $ty {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    serializer.serialize_bytes(self)
+                }
+
+                fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    deserializer.deserialize_bytes(BytesVisitor(PhantomData))
+                }
+            }
+        )*
+    };
+}
+
+impl_shared_str![Arc<str>, Rc<str>];
+impl_shared_bytes![Arc<[u8]>, Rc<[u8]>];
+
+struct StrVisitor<T>(PhantomData<T>);
+
+impl<'de, T: for<'a> From<&'a str>> Visitor<'de> for StrVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<T, E> {
+        Ok(T::from(v))
+    }
+}
+
+struct BytesVisitor<T>(PhantomData<T>);
+
+impl<'de, T: for<'a> From<&'a [u8]>> Visitor<'de> for BytesVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a byte array")
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<T, E> {
+        Ok(T::from(v))
+    }
+}
@@ -0,0 +1,133 @@
+//! # Strided Sequences
+//!
+//! A sequence serialized by [`to_strided_vec`] allows accessing its elements by index
+//! without deserializing the preceding elements, for example to binary search it.
+//!
+//! The number of elements is written as a little-endian `u64`, followed by the
+//! serialized elements. All elements must serialize to the same number of bytes,
+//! called the stride, otherwise serialization fails with [`Error::NonUniformStride`].
+//! The element at index `i` is thus located at offset `8 + i * stride`, where the
+//! stride is the number of bytes following the element count divided by the element count.
+//!
+//! This format is not compatible with the serialization of a sequence using [`serialize`].
+//!
+//! ```rust
+//! use postbag::{StridedSlice, cfg::Slim, to_strided_vec};
+//!
+//! let records: Vec<(u32, u32)> = (0..100).map(|i| (i, 100 - i)).collect();
+//! let data = to_strided_vec::<Slim, _>(&records).unwrap();
+//!
+//! let strided = StridedSlice::<Slim, (u32, u32)>::new(&data).unwrap();
+//! assert_eq!(strided.len(), 100);
+//! assert_eq!(strided.get(42).unwrap().unwrap(), (42, 58));
+//! ```
+
+use std::{fmt, io, marker::PhantomData};
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{
+    cfg::Cfg,
+    de::from_slice,
+    error::{Error, Result},
+    ser::serialize,
+};
+
+/// Size of the element count.
+const COUNT_LEN: usize = 8;
+
+/// Serialize a sequence whose elements all have the same serialized size and return a `Vec<u8>`.
+///
+/// See the [module documentation](self) for the format.
+pub fn to_strided_vec<CFG, T>(values: &[T]) -> Result<Vec<u8>>
+where
+    CFG: Cfg,
+    T: Serialize,
+{
+    let mut buffer = (values.len() as u64).to_le_bytes().to_vec();
+
+    let mut stride = None;
+    for value in values {
+        let start = buffer.len();
+        serialize::<CFG, _, _>(&mut buffer, value)?;
+        let len = buffer.len() - start;
+
+        if *stride.get_or_insert(len) != len {
+            return Err(Error::NonUniformStride);
+        }
+    }
+
+    Ok(buffer)
+}
+
+/// Elements of a sequence serialized by [`to_strided_vec`], deserialized on access.
+///
+/// See the [module documentation](self) for the format.
+pub struct StridedSlice<'a, CFG, T> {
+    data: &'a [u8],
+    len: usize,
+    stride: usize,
+    _phantom: PhantomData<(CFG, fn() -> T)>,
+}
+
+impl<'a, CFG: Cfg, T: DeserializeOwned> StridedSlice<'a, CFG, T> {
+    /// Reads the element count and determines the stride.
+    ///
+    /// Fails with [`Error::NonUniformStride`] if the data following the element count
+    /// cannot be split evenly into the elements.
+    pub fn new(slice: &'a [u8]) -> Result<Self> {
+        let Some((count, data)) = slice.split_first_chunk::<COUNT_LEN>() else {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        };
+        let len = usize::try_from(u64::from_le_bytes(*count)).map_err(|_| Error::UsizeOverflow)?;
+
+        let stride = match len {
+            0 if !data.is_empty() => return Err(Error::TrailingData),
+            0 => 0,
+            len if data.len() % len != 0 => return Err(Error::NonUniformStride),
+            len => data.len() / len,
+        };
+
+        Ok(Self { data, len, stride, _phantom: PhantomData })
+    }
+
+    /// Number of elements.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the sequence is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Serialized size of each element.
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// Serialized data of the element at `index`, or `None` if out of bounds.
+    pub fn get_bytes(&self, index: usize) -> Option<&'a [u8]> {
+        if index >= self.len {
+            return None;
+        }
+        let start = index * self.stride;
+        Some(&self.data[start..start + self.stride])
+    }
+
+    /// Deserializes the element at `index`, or returns `None` if out of bounds.
+    pub fn get(&self, index: usize) -> Option<Result<T>> {
+        self.get_bytes(index).map(from_slice::<CFG, T>)
+    }
+
+    /// Deserializes all elements in order.
+    pub fn iter(&self) -> impl Iterator<Item = Result<T>> + '_ {
+        (0..self.len).map(|index| self.get(index).unwrap())
+    }
+}
+
+impl<CFG, T> fmt::Debug for StridedSlice<'_, CFG, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StridedSlice").field("len", &self.len).field("stride", &self.stride).finish()
+    }
+}
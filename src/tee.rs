@@ -0,0 +1,68 @@
+//! Writer duplicating data to two writers.
+
+use std::io::{Result, Write};
+
+/// Writer that writes all data to two writers.
+///
+/// This allows serializing a value once while sending the serialized data
+/// to two destinations, for example a network connection and a log file,
+/// without buffering it.
+///
+/// Each write is first performed on the first writer. The number of bytes
+/// it accepted is then written completely to the second writer, so that both
+/// writers always receive the same data. An error of either writer is returned.
+/// Flushing flushes both writers, thus [`serialize`](crate::serialize) flushes
+/// both writers when done.
+///
+/// # Example
+///
+/// ```rust
+/// use postbag::{Tee, cfg::Full, serialize};
+///
+/// let mut tee = Tee::new(Vec::new(), Vec::new());
+/// serialize::<Full, _, _>(&mut tee, &(1u8, "message")).unwrap();
+///
+/// let (network, log) = tee.into_inner();
+/// assert_eq!(network, log);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Tee<W1, W2> {
+    first: W1,
+    second: W2,
+}
+
+impl<W1, W2> Tee<W1, W2> {
+    /// Creates a new writer that writes to `first` and `second`.
+    pub fn new(first: W1, second: W2) -> Self {
+        Self { first, second }
+    }
+
+    /// Returns references to the writers.
+    pub fn get_ref(&self) -> (&W1, &W2) {
+        (&self.first, &self.second)
+    }
+
+    /// Returns mutable references to the writers.
+    pub fn get_mut(&mut self) -> (&mut W1, &mut W2) {
+        (&mut self.first, &mut self.second)
+    }
+
+    /// Returns the writers.
+    pub fn into_inner(self) -> (W1, W2) {
+        (self.first, self.second)
+    }
+}
+
+impl<W1: Write, W2: Write> Write for Tee<W1, W2> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let n = self.first.write(buf)?;
+        self.second.write_all(&buf[..n])?;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        let first = self.first.flush();
+        let second = self.second.flush();
+        first.and(second)
+    }
+}
@@ -0,0 +1,75 @@
+//! # Testing Schema Compatibility
+//!
+//! Helpers for test suites verifying that a changed type can read data serialized
+//! using a previous version of the type, or vice versa.
+//!
+//! ```rust
+//! use serde::{Serialize, Deserialize};
+//! use postbag::{cfg::Full, testing::assert_compatible};
+//!
+//! #[derive(Serialize, Debug)]
+//! struct PersonV1 {
+//!     name: String,
+//! }
+//!
+//! #[derive(Deserialize, Debug)]
+//! struct PersonV2 {
+//!     name: String,
+//!     #[serde(default)]
+//!     age: Option<u32>,
+//! }
+//!
+//! let person: PersonV2 = assert_compatible::<Full, _, _>(&PersonV1 { name: "Alice".to_string() });
+//! assert_eq!(person.age, None);
+//! ```
+
+use std::{any::type_name, fmt::Debug};
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{annotate, cfg::Cfg, de::deserializer::Deserializer, hexdump, ser::serialize};
+
+/// Asserts that `sample` of type `A` serialized using the configuration `CFG`
+/// can be deserialized as type `B` and returns the deserialized value.
+///
+/// The value of type `B` must consume all serialized data, so that the data
+/// remains readable when embedded in a larger value.
+///
+/// # Panics
+///
+/// Panics with a description of the sample, the serialized data and the
+/// error if serialization or deserialization fails.
+#[track_caller]
+pub fn assert_compatible<CFG, A, B>(sample: &A) -> B
+where
+    CFG: Cfg,
+    A: Serialize + Debug + ?Sized,
+    B: DeserializeOwned,
+{
+    let (a, b) = (type_name::<A>(), type_name::<B>());
+
+    let mut data = Vec::new();
+    if let Err(err) = serialize::<CFG, _, _>(&mut data, sample) {
+        panic!("serializing {a} failed: {err}\nsample: {sample:#?}");
+    }
+
+    let mut deserializer = Deserializer::<_, CFG>::with_remaining(data.as_slice(), data.len() as u64);
+    let value = match B::deserialize(&mut deserializer) {
+        Ok(value) => value,
+        Err(err) => panic!(
+            "{b} cannot deserialize data serialized from {a}: {err}\nsample: {sample:#?}\ndata read as {b}:\n{}",
+            annotate::<CFG, B>(&data)
+        ),
+    };
+
+    let consumed = deserializer.position();
+    if consumed != data.len() as u64 {
+        panic!(
+            "{b} consumed only {consumed} of {} bytes serialized from {a}\nsample: {sample:#?}\ndata:\n{}",
+            data.len(),
+            hexdump(&data)
+        );
+    }
+
+    value
+}
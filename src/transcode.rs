@@ -0,0 +1,64 @@
+//! # Transcoding
+//!
+//! [`transcode`] re-encodes data serialized using the [`Full`] configuration
+//! using the [`Slim`] configuration, for example to reduce the size of stored
+//! records. [`transcode_with`] converts between arbitrary configurations.
+//!
+//! Since the format is not self-describing, the data is decoded into a value
+//! of the Rust type it was serialized from, which is then serialized again.
+//! Thus fields and variants unknown to this type are not preserved and the
+//! result is the encoding of the value as the type understands it.
+//!
+//! ```rust
+//! use serde::{Serialize, Deserialize};
+//! use postbag::{from_slim_slice, to_full_vec, transcode};
+//!
+//! #[derive(Serialize, Deserialize, Debug, PartialEq)]
+//! struct Record {
+//!     name: String,
+//!     size: u64,
+//! }
+//!
+//! let record = Record { name: "archive".to_string(), size: 1024 };
+//! let full = to_full_vec(&record).unwrap();
+//!
+//! let slim = transcode::<Record>(&full).unwrap();
+//! assert!(slim.len() < full.len());
+//! assert_eq!(from_slim_slice::<Record>(&slim).unwrap(), record);
+//! ```
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{
+    cfg::{Cfg, Full, Slim},
+    de::from_slice,
+    error::Result,
+    ser::serialize,
+};
+
+/// Re-encodes data serialized using the [`Full`] configuration using the [`Slim`] configuration.
+///
+/// The data is decoded as a value of type `T`.
+/// See the [module documentation](self) for details.
+pub fn transcode<T>(full: &[u8]) -> Result<Vec<u8>>
+where
+    T: Serialize + DeserializeOwned,
+{
+    transcode_with::<Full, Slim, T>(full)
+}
+
+/// Re-encodes data serialized using the configuration `FROM` using the configuration `TO`.
+///
+/// The data is decoded as a value of type `T`.
+/// See the [module documentation](self) for details.
+pub fn transcode_with<FROM, TO, T>(data: &[u8]) -> Result<Vec<u8>>
+where
+    FROM: Cfg,
+    TO: Cfg,
+    T: Serialize + DeserializeOwned,
+{
+    let value: T = from_slice::<FROM, T>(data)?;
+    let mut out = Vec::with_capacity(data.len());
+    serialize::<TO, _, _>(&mut out, &value)?;
+    Ok(out)
+}
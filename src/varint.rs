@@ -1,7 +1,83 @@
+//! # Varints
+//!
+//! Allocation-free encoding of unsigned integers as varints, as used by postbag,
+//! for example for building custom framing around serialized data.
+//!
+//! A varint stores 7 bits of the value per byte, least significant group first.
+//! The most significant bit of each byte is set if another byte follows.
+//! The encoding is canonical, i.e. it uses the minimal number of bytes.
+//!
+//! ```rust
+//! use postbag::varint::{encode, encoded_len};
+//!
+//! let mut buf = [0; 16];
+//! assert_eq!(encode(300u32, &mut buf), [0xac, 0x02]);
+//! assert_eq!(encoded_len(300u32), 2);
+//! ```
+
 use std::mem::size_of;
 
+/// Encodes `value` as a varint into the start of `out` and returns the encoded bytes.
+///
+/// # Panics
+///
+/// Panics if `out` is shorter than [`encoded_len`] of the value.
+pub fn encode<T: Unsigned>(value: T, out: &mut [u8]) -> &[u8] {
+    let out = &mut out[..value.encoded_len()];
+    value.encode(out);
+    out
+}
+
+/// Returns the number of bytes of the varint encoding of `value`.
+///
+/// This is between 1 and the maximum for the type, for example 10 for `u64`.
+pub fn encoded_len<T: Unsigned>(value: T) -> usize {
+    value.encoded_len()
+}
+
+/// Unsigned integer type that can be encoded as a varint.
+///
+/// `u8` is not included, since postbag writes it as a raw byte.
+pub trait Unsigned: Copy + private::Sealed {}
+
+mod private {
+    pub trait Sealed {
+        fn encoded_len(self) -> usize;
+
+        /// Encodes into `out`, which has exactly the encoded length.
+        fn encode(self, out: &mut [u8]);
+    }
+}
+
+macro_rules! impl_unsigned {
+    ($( $int:ty ),*) => {
+        $(
+            impl private::Sealed for $int {
+                fn encoded_len(self) -> usize {
+                    let bits = (<$int>::BITS - self.leading_zeros()) as usize;
+                    bits.div_ceil(7).max(1)
+                }
+
+                fn encode(self, out: &mut [u8]) {
+                    let mut value = self;
+                    let (last, head) = out.split_last_mut().unwrap();
+                    for byte in head {
+                        *byte = value.to_le_bytes()[0] | 0x80;
+                        value >>= 7;
+                    }
+                    *last = value.to_le_bytes()[0];
+                }
+            }
+
+            impl Unsigned for $int {}
+        )*
+    };
+}
+
+impl_unsigned!(u16, u32, u64, u128, usize);
+
 /// Returns the maximum number of bytes required to encode T.
-pub const fn varint_max<T: Sized>() -> usize {
+pub(crate) const fn varint_max<T: Sized>() -> usize {
     const BITS_PER_BYTE: usize = 8;
     const BITS_PER_VARINT_BYTE: usize = 7;
 
@@ -18,13 +94,13 @@ pub const fn varint_max<T: Sized>() -> usize {
 }
 
 /// Returns the maximum value stored in the last encoded byte.
-pub const fn max_of_last_byte<T: Sized>() -> u8 {
+pub(crate) const fn max_of_last_byte<T: Sized>() -> u8 {
     let max_bits = size_of::<T>() * 8;
     let extra_bits = max_bits % 7;
     (1 << extra_bits) - 1
 }
 
-pub fn varint_u16(n: u16, out: &mut [u8; varint_max::<u16>()]) -> &mut [u8] {
+pub(crate) fn varint_u16(n: u16, out: &mut [u8; varint_max::<u16>()]) -> &mut [u8] {
     let mut value = n;
     for i in 0..varint_max::<u16>() {
         out[i] = value.to_le_bytes()[0];
@@ -39,7 +115,7 @@ pub fn varint_u16(n: u16, out: &mut [u8; varint_max::<u16>()]) -> &mut [u8] {
     &mut out[..]
 }
 
-pub fn varint_u32(n: u32, out: &mut [u8; varint_max::<u32>()]) -> &mut [u8] {
+pub(crate) fn varint_u32(n: u32, out: &mut [u8; varint_max::<u32>()]) -> &mut [u8] {
     let mut value = n;
     for i in 0..varint_max::<u32>() {
         out[i] = value.to_le_bytes()[0];
@@ -54,7 +130,7 @@ pub fn varint_u32(n: u32, out: &mut [u8; varint_max::<u32>()]) -> &mut [u8] {
     &mut out[..]
 }
 
-pub fn varint_u64(n: u64, out: &mut [u8; varint_max::<u64>()]) -> &mut [u8] {
+pub(crate) fn varint_u64(n: u64, out: &mut [u8; varint_max::<u64>()]) -> &mut [u8] {
     let mut value = n;
     for i in 0..varint_max::<u64>() {
         out[i] = value.to_le_bytes()[0];
@@ -69,7 +145,7 @@ pub fn varint_u64(n: u64, out: &mut [u8; varint_max::<u64>()]) -> &mut [u8] {
     &mut out[..]
 }
 
-pub fn varint_u128(n: u128, out: &mut [u8; varint_max::<u128>()]) -> &mut [u8] {
+pub(crate) fn varint_u128(n: u128, out: &mut [u8; varint_max::<u128>()]) -> &mut [u8] {
     let mut value = n;
     for i in 0..varint_max::<u128>() {
         out[i] = value.to_le_bytes()[0];
@@ -99,4 +175,47 @@ mod test {
 
         assert_eq!(&[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01], res);
     }
+
+    #[test]
+    fn last_byte_bounds() {
+        // 128 = 18 * 7 + 2, thus the 19th byte holds the top 2 bits.
+        assert_eq!(varint_max::<u128>(), 19);
+        assert_eq!(max_of_last_byte::<u128>(), 0b11);
+        assert_eq!(varint_max::<u64>(), 10);
+        assert_eq!(max_of_last_byte::<u64>(), 0b1);
+        assert_eq!(varint_max::<u32>(), 5);
+        assert_eq!(max_of_last_byte::<u32>(), 0b1111);
+        assert_eq!(varint_max::<u16>(), 3);
+        assert_eq!(max_of_last_byte::<u16>(), 0b11);
+
+        let mut buf = [0; varint_max::<u128>()];
+        let res = varint_u128(u128::MAX, &mut buf);
+        assert_eq!(res.len(), 19);
+        assert!(res[..18].iter().all(|&b| b == 0xFF));
+        assert_eq!(res[18], max_of_last_byte::<u128>());
+    }
+
+    #[test]
+    fn encode_boundaries() {
+        let mut buf = [0; varint_max::<u128>()];
+        for (value, len) in [(0u64, 1), (127, 1), (128, 2), (16383, 2), (16384, 3), (u64::MAX, 10)] {
+            assert_eq!(encoded_len(value), len, "{value}");
+            let mut expected = [0; varint_max::<u64>()];
+            assert_eq!(encode(value, &mut buf), varint_u64(value, &mut expected), "{value}");
+        }
+
+        assert_eq!(encoded_len(u16::MAX), 3);
+        assert_eq!(encode(u16::MAX, &mut buf), [0xFF, 0xFF, 0x03]);
+        assert_eq!(encoded_len(u32::MAX), 5);
+        assert_eq!(encode(u32::MAX, &mut buf), [0xFF, 0xFF, 0xFF, 0xFF, 0x0F]);
+        assert_eq!(encoded_len(u128::MAX), 19);
+        assert_eq!(encode(u128::MAX, &mut buf).last(), Some(&max_of_last_byte::<u128>()));
+        assert_eq!(encode(0usize, &mut buf), [0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn encode_short_buffer() {
+        encode(128u32, &mut [0; 1]);
+    }
 }
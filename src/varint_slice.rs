@@ -0,0 +1,86 @@
+//! # Integer Sequences
+//!
+//! Serde serializes a `Vec<u64>` element by element, which is correct but adds per-element
+//! overhead for large numeric arrays. This module, for use with
+//! `#[serde(with = "postbag::varint_slice")]`, serializes sequences of integers using
+//! a fast path that encodes the varints in a tight loop into a stack buffer,
+//! which is written to the output in large chunks.
+//!
+//! The serialized data is identical to a sequence serialized without this module,
+//! thus it can be applied to or removed from a field without breaking compatibility.
+//! Deserialization reads the length followed by the varints, as for any sequence.
+//! Other serde serializers receive a regular sequence.
+//!
+//! Supported are `Vec<T>` and `Box<[T]>`, as well as `&[T]` for serialization,
+//! where `T` is an integer type.
+//!
+//! ```rust
+//! # use serde::{Serialize, Deserialize};
+//! #[derive(Serialize, Deserialize)]
+//! pub struct Samples {
+//!     #[serde(with = "postbag::varint_slice")]
+//!     values: Vec<u64>,
+//! }
+//! ```
+
+use std::{fmt, marker::PhantomData};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Visitor};
+
+/// Newtype struct name recognized by the serializer.
+pub(crate) const NAME: &str = "$postbag::VarintSlice";
+
+/// Serialize the integers as a sequence.
+pub fn serialize<S, V, T>(values: &V, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    V: AsRef<[T]> + ?Sized,
+    T: Integer,
+{
+    serializer.serialize_newtype_struct(NAME, values.as_ref())
+}
+
+/// Deserialize the integers from a sequence.
+pub fn deserialize<'de, D, V, T>(deserializer: D) -> Result<V, D::Error>
+where
+    D: Deserializer<'de>,
+    V: From<Vec<T>>,
+    T: Integer + Deserialize<'de>,
+{
+    deserializer.deserialize_newtype_struct(NAME, SliceVisitor(PhantomData)).map(V::from)
+}
+
+/// Integer type supported by this module.
+pub trait Integer: Serialize + Copy + private::Sealed {}
+
+mod private {
+    pub trait Sealed {}
+}
+
+macro_rules! impl_integer {
+    ($( $int:ty ),*) => {
+        $(
+            impl private::Sealed for $int {}
+            impl Integer for $int {}
+        )*
+    };
+}
+
+impl_integer!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+struct SliceVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for SliceVisitor<T> {
+    type Value = Vec<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a sequence of integers")
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Vec<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Vec::deserialize(deserializer)
+    }
+}
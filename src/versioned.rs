@@ -0,0 +1,124 @@
+//! # Versioned Newtypes
+//!
+//! A versioned newtype struct is serialized as a version byte followed by its inner value.
+//! When deserializing, the version byte is verified and data of another version is
+//! rejected with [`Error::BadVersion`](crate::Error::BadVersion). This allows changing
+//! the encoding of the wrapped type, while older readers reject data of the new encoding
+//! explicitly instead of misinterpreting it.
+//!
+//! The version is usually specified using the `Versioned` derive macro,
+//! which requires the `derive` feature and implements `Serialize` and `Deserialize`
+//! using [`serialize`] and [`deserialize`].
+//!
+#![cfg_attr(feature = "derive", doc = "```rust")]
+#![cfg_attr(not(feature = "derive"), doc = "```rust,ignore")]
+//! use serde::{Serialize, Deserialize};
+//! use postbag::{Error, Versioned, from_slim_slice, to_slim_vec};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Point { x: u32, y: u32 }
+//!
+//! #[derive(Versioned)]
+//! #[postbag(version = 1)]
+//! struct PointV1(Point);
+//!
+//! #[derive(Versioned)]
+//! #[postbag(version = 2)]
+//! struct PointV2(Point);
+//!
+//! let data = to_slim_vec(&PointV2(Point { x: 1, y: 2 })).unwrap();
+//! assert_eq!(data[0], 2);
+//!
+//! let res: Result<PointV1, _> = from_slim_slice(&data);
+//! assert!(matches!(res, Err(Error::BadVersion { expected: 1, found: 2 })));
+//! ```
+
+use std::{fmt, marker::PhantomData};
+
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{self, SeqAccess, Visitor},
+    ser::SerializeTupleStruct,
+};
+
+/// Tuple struct name recognized by the serializer.
+pub(crate) const NAME: &str = "$postbag::Versioned";
+
+/// Length of the tuple struct names recognized by the deserializer.
+const EXPECTED_NAME_LEN: usize = NAME.len() + 3;
+
+/// Tuple struct names recognized by the deserializer, indexed by the expected version.
+///
+/// Each consists of [`NAME`] followed by the expected version as three decimal digits.
+/// The name is ignored by other formats, thus the tuple struct keeps its actual arity.
+static EXPECTED_NAMES: [[u8; EXPECTED_NAME_LEN]; 256] = expected_names();
+
+const fn expected_names() -> [[u8; EXPECTED_NAME_LEN]; 256] {
+    let mut names = [[0; EXPECTED_NAME_LEN]; 256];
+    let mut version = 0;
+    while version < 256 {
+        let mut i = 0;
+        while i < NAME.len() {
+            names[version][i] = NAME.as_bytes()[i];
+            i += 1;
+        }
+        names[version][i] = b'0' + (version / 100) as u8;
+        names[version][i + 1] = b'0' + (version / 10 % 10) as u8;
+        names[version][i + 2] = b'0' + (version % 10) as u8;
+        version += 1;
+    }
+    names
+}
+
+/// Tuple struct name passing the expected version to the deserializer.
+fn expected_name(version: u8) -> &'static str {
+    std::str::from_utf8(&EXPECTED_NAMES[usize::from(version)]).expect("name is ASCII")
+}
+
+/// Expected version passed by the tuple struct name, if it is the name of a versioned newtype.
+pub(crate) fn expected_version(name: &str) -> Option<u8> {
+    name.strip_prefix(NAME)?.parse().ok()
+}
+
+/// Serialize the value prefixed by the version.
+pub fn serialize<S, T>(version: u8, value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Serialize + ?Sized,
+{
+    let mut tuple = serializer.serialize_tuple_struct(NAME, 2)?;
+    tuple.serialize_field(&version)?;
+    tuple.serialize_field(value)?;
+    tuple.end()
+}
+
+/// Deserialize a value prefixed by the version, failing if the version differs.
+pub fn deserialize<'de, D, T>(version: u8, deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    deserializer.deserialize_tuple_struct(expected_name(version), 2, VersionedVisitor(version, PhantomData))
+}
+
+struct VersionedVisitor<T>(u8, PhantomData<T>);
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for VersionedVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a value of version {}", self.0)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<T, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let version: u8 = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        if version != self.0 {
+            return Err(de::Error::invalid_value(de::Unexpected::Unsigned(version.into()), &self));
+        }
+
+        seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))
+    }
+}
@@ -0,0 +1,358 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    borrow::Cow,
+    cell::Cell,
+};
+
+use postbag::{
+    Deserializer,
+    cfg::{Cfg, Full, Slim},
+    serialize,
+};
+
+/// Allocator counting the allocations of the current thread.
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|n| n.set(n.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|n| n.set(n.get() + 1));
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+/// Returns the number of allocations performed by `f`.
+fn allocations<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let start = ALLOCATIONS.with(Cell::get);
+    let value = f();
+    (value, ALLOCATIONS.with(Cell::get) - start)
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Message {
+    id: u32,
+    topic: String,
+    payload: Vec<u8>,
+}
+
+fn messages<CFG: Cfg>() -> Vec<Vec<u8>> {
+    (0..50u32)
+        .map(|id| {
+            let message =
+                Message { id, topic: format!("topic number {}", id % 7), payload: vec![id as u8; id as usize] };
+            let mut data = Vec::new();
+            serialize::<CFG, _, _>(&mut data, &message).unwrap();
+            data
+        })
+        .collect()
+}
+
+fn check<CFG: Cfg>() {
+    let messages = messages::<CFG>();
+
+    // Correctness across several messages, including after a partially read message.
+    let mut deserializer = Deserializer::<_, CFG>::new(messages[0].as_slice());
+    for (id, data) in messages.iter().enumerate() {
+        let prev = deserializer.reset(data.as_slice());
+        assert!(id == 0 || prev.is_empty(), "message {id} not fully read");
+
+        let message: Message = deserializer.deserialize_value().unwrap();
+        assert_eq!(message.id, id as u32);
+        assert_eq!(message.topic, format!("topic number {}", id % 7));
+        assert_eq!(message.payload, vec![id as u8; id]);
+        assert_eq!(deserializer.position(), data.len() as u64);
+    }
+
+    deserializer.reset(&messages[10][..5]);
+    assert!(deserializer.deserialize_value::<Message>().is_err());
+    deserializer.reset(messages[10].as_slice());
+    assert_eq!(deserializer.deserialize_value::<Message>().unwrap().id, 10);
+
+    // Resetting does not allocate.
+    let ((), n) = allocations(|| {
+        for data in &messages {
+            deserializer.reset(data.as_slice());
+        }
+    });
+    assert_eq!(n, 0, "reset allocated");
+
+    // A reused deserializer allocates less than a new one per message.
+    let (_, reused) = allocations(|| {
+        for data in &messages {
+            deserializer.reset(data.as_slice());
+            deserializer.deserialize_value::<Message>().unwrap();
+        }
+    });
+    let (_, fresh) = allocations(|| {
+        for data in &messages {
+            Deserializer::<_, CFG>::new(data.as_slice()).deserialize_value::<Message>().unwrap();
+        }
+    });
+    assert!(reused < fresh, "reused deserializer allocated {reused} times, new deserializers {fresh} times");
+}
+
+#[test]
+fn reset_full() {
+    check::<Full>();
+}
+
+#[test]
+fn reset_slim() {
+    check::<Slim>();
+}
+
+#[test]
+fn cow_serialize_without_allocation() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Blob<'a>(#[serde(with = "postbag::serde_bytes")] Cow<'a, [u8]>);
+
+    let bytes: Vec<u8> = (0..=255).collect();
+    let text = "text ".repeat(100);
+    let values = (
+        Cow::<str>::Borrowed(&text),
+        Cow::<str>::Owned(text.clone()),
+        Blob(Cow::Borrowed(&bytes)),
+        Blob(Cow::Owned(bytes.clone())),
+    );
+
+    let mut data = Vec::with_capacity(4096);
+    let ((), n) = allocations(|| serialize::<Full, _, _>(&mut data, &values).unwrap());
+    assert_eq!(n, 0, "serializing cows allocated");
+
+    // Strings and byte arrays of both variants are encoded identically.
+    let expected = postbag::to_full_vec(&(&text, &text, Bytes(&bytes), Bytes(&bytes))).unwrap();
+    assert_eq!(data, expected);
+
+    let (a, b, c, d): (Cow<str>, Cow<str>, Blob, Blob) = postbag::from_full_slice(&data).unwrap();
+    assert_eq!((a, b), (values.0, values.1));
+    assert_eq!((c, d), (values.2, values.3));
+
+    /// Byte array serialized from a borrowed slice.
+    struct Bytes<'a>(&'a [u8]);
+
+    impl Serialize for Bytes<'_> {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_bytes(self.0)
+        }
+    }
+}
+
+#[test]
+fn to_full_vec_reuse() {
+    let messages: Vec<_> = (0..50u32)
+        .map(|id| (id, format!("topic number {}", id % 7), vec![id as u8; (id as usize * 37) % 300]))
+        .collect();
+    let largest = messages.iter().max_by_key(|msg| msg.2.len()).unwrap();
+
+    let mut buf = Vec::new();
+    postbag::to_full_vec_reuse(largest, &mut buf).unwrap();
+    let capacity = buf.capacity();
+
+    for msg in &messages {
+        let (len, n) = allocations(|| postbag::to_full_vec_reuse(msg, &mut buf).unwrap());
+        assert_eq!(n, 0, "serializing into reused buffer allocated");
+        assert_eq!(buf.capacity(), capacity);
+        assert_eq!(len, buf.len());
+        assert_eq!(buf, postbag::to_full_vec(msg).unwrap());
+    }
+
+    // Grows for larger messages.
+    let large = vec![1u8; 2 * capacity];
+    let len = postbag::to_full_vec_reuse(&large, &mut buf).unwrap();
+    assert_eq!(buf, postbag::to_full_vec(&large).unwrap());
+    assert_eq!(len, buf.len());
+}
+
+/// Length and capacity of a deserialized string or byte buffer.
+#[derive(Debug, PartialEq)]
+struct Capacity {
+    len: usize,
+    capacity: usize,
+}
+
+impl<'de> Deserialize<'de> for Capacity {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl serde::de::Visitor<'_> for Visitor {
+            type Value = Capacity;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("byte buffer")
+            }
+
+            fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Capacity, E> {
+                Ok(Capacity { len: v.len(), capacity: v.capacity() })
+            }
+
+            fn visit_string<E>(self, v: String) -> Result<Capacity, E> {
+                Ok(Capacity { len: v.len(), capacity: v.capacity() })
+            }
+
+            fn visit_str<E: serde::de::Error>(self, _v: &str) -> Result<Capacity, E> {
+                Err(E::custom("borrowed string"))
+            }
+        }
+
+        deserializer.deserialize_byte_buf(Visitor)
+    }
+}
+
+#[test]
+fn exact_capacity() {
+    #[derive(Serialize)]
+    struct Bytes(#[serde(with = "postbag::serde_bytes")] Vec<u8>);
+
+    #[derive(Deserialize, Debug)]
+    struct Field {
+        data: Capacity,
+    }
+
+    #[derive(Serialize)]
+    struct BytesField {
+        data: Bytes,
+    }
+
+    for len in [0, 1, 5, 1000, 65_535, 65_536, 200_000] {
+        let bytes = postbag::to_slim_vec(&Bytes(vec![7; len])).unwrap();
+        let string = postbag::to_slim_vec(&"s".repeat(len)).unwrap();
+        let field = postbag::to_full_vec(&BytesField { data: Bytes(vec![7; len]) }).unwrap();
+        let expected = Capacity { len, capacity: len };
+
+        // Input of known length.
+        assert_eq!(postbag::from_slim_slice::<Capacity>(&bytes).unwrap(), expected);
+        assert_eq!(postbag::from_slim_slice::<Capacity>(&string).unwrap(), expected);
+        assert_eq!(postbag::from_full_slice::<Field>(&field).unwrap().data, expected);
+
+        // Input of unknown length.
+        assert_eq!(postbag::deserialize_slim::<_, Capacity>(bytes.as_slice()).unwrap(), expected);
+        assert_eq!(postbag::deserialize_full::<_, Field>(field.as_slice()).unwrap().data, expected);
+    }
+
+    // Boxed values are converted without reallocation.
+    let data = postbag::to_slim_vec(&"x".repeat(100)).unwrap();
+    let (boxed, n) = allocations(|| postbag::from_slim_slice::<Box<str>>(&data).unwrap());
+    assert_eq!(&*boxed, "x".repeat(100));
+    let (_, string) = allocations(|| postbag::from_slim_slice::<String>(&data).unwrap());
+    assert_eq!(n, string, "boxing reallocated");
+}
+
+#[test]
+fn replace_scratch() {
+    /// Checksum of a byte array provided by reference.
+    #[derive(Debug, PartialEq)]
+    struct Sum(u64);
+
+    impl<'de> Deserialize<'de> for Sum {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct Visitor;
+
+            impl serde::de::Visitor<'_> for Visitor {
+                type Value = Sum;
+
+                fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    f.write_str("bytes")
+                }
+
+                fn visit_bytes<E>(self, v: &[u8]) -> Result<Sum, E> {
+                    Ok(Sum(v.iter().map(|&b| u64::from(b)).sum()))
+                }
+            }
+
+            deserializer.deserialize_bytes(Visitor)
+        }
+    }
+
+    #[derive(Serialize)]
+    struct Bytes(#[serde(with = "postbag::serde_bytes")] Vec<u8>);
+
+    let data = postbag::to_slim_vec(&Bytes(vec![2; 1000])).unwrap();
+
+    let (_, fresh) = allocations(|| {
+        let mut deserializer = Deserializer::<_, Slim>::new(data.as_slice());
+        assert_eq!(deserializer.deserialize_value::<Sum>().unwrap(), Sum(2000));
+    });
+
+    // A supplied buffer is used instead of allocating one and can be recovered.
+    let pool = vec![1; 4096];
+    let ptr = pool.as_ptr();
+    let (pool, supplied) = allocations(|| {
+        let mut deserializer = Deserializer::<_, Slim>::new(data.as_slice());
+        assert!(deserializer.replace_scratch(pool).is_empty());
+        assert_eq!(deserializer.deserialize_value::<Sum>().unwrap(), Sum(2000));
+        deserializer.replace_scratch(Vec::new())
+    });
+    assert!(supplied < fresh, "supplied buffer allocated {supplied} times, new buffer {fresh} times");
+    assert_eq!(pool.as_ptr(), ptr);
+    assert!(pool.is_empty());
+
+    // Large buffers are freed.
+    let data = postbag::to_slim_vec(&Bytes(vec![1; 100_000])).unwrap();
+    let mut deserializer = Deserializer::<_, Slim>::new(data.as_slice());
+    deserializer.replace_scratch(pool);
+    assert_eq!(deserializer.deserialize_value::<Sum>().unwrap(), Sum(100_000));
+    assert_eq!(deserializer.replace_scratch(Vec::new()).capacity(), 0);
+}
+
+#[cfg(feature = "allocator-api2")]
+#[test]
+fn buffers_in_allocator() {
+    use allocator_api2::alloc::{AllocError, Allocator};
+    use postbag::cfg::Canonical;
+    use std::{collections::BTreeMap, ptr::NonNull};
+
+    /// Allocator counting its allocations, bypassing the global allocator.
+    #[derive(Default)]
+    struct Arena(Cell<usize>);
+
+    unsafe impl Allocator for Arena {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            self.0.set(self.0.get() + 1);
+            System.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            unsafe { System.deallocate(ptr, layout) }
+        }
+    }
+
+    // Identifiers are read into the scratch buffer.
+    let data = &messages::<Full>()[20];
+    let (expected, global) = allocations(|| {
+        let mut deserializer = Deserializer::<_, Full>::new(data.as_slice());
+        deserializer.deserialize_value::<Message>().unwrap()
+    });
+
+    let arena = Arena::default();
+    let (message, in_arena) = allocations(|| {
+        let mut deserializer = Deserializer::<_, Full, _>::new_in(data.as_slice(), &arena);
+        deserializer.deserialize_value::<Message>().unwrap()
+    });
+    assert_eq!(message, expected);
+    assert!(arena.0.get() > 0);
+    assert!(in_arena < global, "{in_arena} global allocations with arena, {global} without");
+
+    // Map keys are captured for verifying their order.
+    let map: BTreeMap<u32, u32> = (0..100).map(|i| (i, i * 2)).collect();
+    let data = postbag::to_canonical_vec(&map).unwrap();
+    let arena = Arena::default();
+    let mut deserializer = Deserializer::<_, Canonical, _>::from_slice_in(&data, &arena);
+    assert_eq!(deserializer.deserialize_value::<BTreeMap<u32, u32>>().unwrap(), map);
+    assert!(arena.0.get() >= 100, "{} allocations", arena.0.get());
+}
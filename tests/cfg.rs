@@ -0,0 +1,832 @@
+use serde::{
+    Deserialize, Serialize, Serializer,
+    de::DeserializeOwned,
+    ser::{SerializeMap, SerializeSeq},
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Debug,
+};
+
+use postbag::{
+    Error, UnknownLenCause,
+    cfg::{BigEndianFull, BigEndianSlim, Canonical, Cfg, Full, Slim},
+    deserialize, from_canonical_slice, serialize, to_canonical_vec,
+};
+
+/// Full configuration with sorted map keys.
+struct SortedFull;
+
+impl Cfg for SortedFull {
+    fn with_idents() -> bool {
+        true
+    }
+
+    fn sort_map_keys() -> bool {
+        true
+    }
+}
+
+/// Slim configuration with sorted map keys.
+struct SortedSlim;
+
+impl Cfg for SortedSlim {
+    fn with_idents() -> bool {
+        false
+    }
+
+    fn sort_map_keys() -> bool {
+        true
+    }
+}
+
+#[track_caller]
+fn to_vec<CFG: Cfg, T: Serialize>(value: &T) -> Vec<u8> {
+    let mut serialized = Vec::new();
+    serialize::<CFG, _, _>(&mut serialized, value).expect("serialization failed");
+    serialized
+}
+
+#[track_caller]
+fn loopback<CFG: Cfg, T: Serialize + DeserializeOwned + Debug + PartialEq>(value: &T) -> Vec<u8> {
+    let serialized = to_vec::<CFG, _>(value);
+    let deserialized: T = deserialize::<CFG, _, _>(serialized.as_slice()).expect("deserialization failed");
+    assert_eq!(*value, deserialized, "deserialized value does not match original value");
+    serialized
+}
+
+#[test]
+fn sorted_map_keys_deterministic() {
+    let entries: Vec<(String, u32)> = (0..100).map(|i| (format!("key{i}"), i)).collect();
+
+    // Each map has its own random hasher state and thus its own iteration order.
+    let a: HashMap<String, u32> = entries.iter().cloned().collect();
+    let b: HashMap<String, u32> = entries.iter().rev().cloned().collect();
+
+    assert_eq!(loopback::<SortedFull, _>(&a), loopback::<SortedFull, _>(&b));
+    assert_eq!(loopback::<SortedSlim, _>(&a), loopback::<SortedSlim, _>(&b));
+}
+
+#[test]
+fn sorted_map_keys_nested() {
+    let mut a: HashMap<u32, HashMap<String, Vec<u8>>> = HashMap::new();
+    for i in 0..20 {
+        let inner = (0..i).map(|j| (j.to_string(), vec![j as u8; j as usize])).collect();
+        a.insert(i, inner);
+    }
+
+    // Rebuild both levels in reverse order, so that every map has its own hasher state.
+    let b: HashMap<u32, HashMap<String, Vec<u8>>> = (0..20)
+        .rev()
+        .map(|i| {
+            let inner = (0..i).rev().map(|j| (j.to_string(), vec![j as u8; j as usize])).collect();
+            (i, inner)
+        })
+        .collect();
+    assert_eq!(a, b);
+
+    assert_eq!(loopback::<SortedFull, _>(&a), loopback::<SortedFull, _>(&b));
+    assert_eq!(loopback::<SortedSlim, _>(&a), loopback::<SortedSlim, _>(&b));
+}
+
+/// Configuration that does not support sequences and maps of unknown length.
+struct KnownLen;
+
+impl Cfg for KnownLen {
+    fn with_idents() -> bool {
+        true
+    }
+
+    fn unknown_len() -> bool {
+        false
+    }
+}
+
+/// Serializes as a sequence of unknown length.
+struct UnknownLenSeq(Vec<u32>);
+
+impl Serialize for UnknownLenSeq {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(None)?;
+        for item in &self.0 {
+            seq.serialize_element(item)?;
+        }
+        seq.end()
+    }
+}
+
+/// Serializes as a map of unknown length.
+struct UnknownLenMap(Vec<(String, u32)>);
+
+impl Serialize for UnknownLenMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        for (k, v) in &self.0 {
+            map.serialize_entry(k, v)?;
+        }
+        map.end()
+    }
+}
+
+#[test]
+fn unknown_len_supported() {
+    let seq = UnknownLenSeq(vec![1, 2, 3]);
+    let map = UnknownLenMap(vec![("a".to_string(), 1), ("b".to_string(), 2)]);
+
+    let expected_map: HashMap<String, u32> = map.0.iter().cloned().collect();
+
+    let v: Vec<u32> = deserialize::<Full, _, _>(to_vec::<Full, _>(&seq).as_slice()).unwrap();
+    assert_eq!(v, seq.0);
+    let v: Vec<u32> = deserialize::<Slim, _, _>(to_vec::<Slim, _>(&seq).as_slice()).unwrap();
+    assert_eq!(v, seq.0);
+    let m: HashMap<String, u32> = deserialize::<Full, _, _>(to_vec::<Full, _>(&map).as_slice()).unwrap();
+    assert_eq!(m, expected_map);
+    let m: HashMap<String, u32> = deserialize::<Slim, _, _>(to_vec::<Slim, _>(&map).as_slice()).unwrap();
+    assert_eq!(m, expected_map);
+}
+
+#[test]
+fn unknown_len_truncated_element() {
+    let data = to_vec::<Slim, _>(&UnknownLenSeq(vec![1, 2, 300]));
+    assert_eq!(data, [125, 0, 4, 1, 2, 0xac, 0x02]);
+
+    // Element truncated by the end of the block is an error, not the end of the sequence.
+    let res = deserialize::<Slim, _, Vec<u32>>([125, 0, 3, 1, 2, 0xac].as_slice());
+    assert!(matches!(res, Err(Error::EndOfBlock)), "{res:?}");
+
+    let data = to_vec::<Slim, _>(&UnknownLenMap(vec![("a".to_string(), 1), ("b".to_string(), 300)]));
+    assert_eq!(data, [125, 0, 7, 1, b'a', 1, 1, b'b', 0xac, 0x02]);
+    let res = deserialize::<Slim, _, HashMap<String, u32>>([125, 0, 6, 1, b'a', 1, 1, b'b', 0xac].as_slice());
+    assert!(matches!(res, Err(Error::EndOfBlock)), "{res:?}");
+    let res = deserialize::<Slim, _, HashMap<String, u32>>([125, 0, 4, 1, b'a', 1, 1].as_slice());
+    assert!(matches!(res, Err(Error::EndOfBlock)), "{res:?}");
+
+    // Empty sequence ends cleanly.
+    let v: Vec<u32> = deserialize::<Slim, _, _>(to_vec::<Slim, _>(&UnknownLenSeq(vec![])).as_slice()).unwrap();
+    assert!(v.is_empty());
+}
+
+#[test]
+fn unknown_len_unsupported() {
+    let mut buf = Vec::new();
+    let res = serialize::<KnownLen, _, _>(&mut buf, &UnknownLenSeq(vec![1, 2, 3]));
+    assert!(
+        matches!(res, Err(Error::SerializeSeqLengthUnknown { cause: UnknownLenCause::UnknownLenDisabled })),
+        "{res:?}"
+    );
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "sequence length unknown, unsupported by configuration (unknown_len disabled)"
+    );
+
+    let mut buf = Vec::new();
+    let res = serialize::<KnownLen, _, _>(&mut buf, &UnknownLenMap(vec![("a".to_string(), 1)]));
+    assert!(
+        matches!(res, Err(Error::SerializeMapLengthUnknown { cause: UnknownLenCause::UnknownLenDisabled })),
+        "{res:?}"
+    );
+
+    // Known lengths are unaffected.
+    loopback::<KnownLen, _>(&vec![1u32, 2, 3]);
+}
+
+/// Slim configuration serializing sequences and maps of unknown length in terminated mode.
+struct TerminatedSlim;
+
+impl Cfg for TerminatedSlim {
+    fn with_idents() -> bool {
+        false
+    }
+
+    fn terminated_unknown_len() -> bool {
+        true
+    }
+}
+
+/// Full configuration with sorted map keys in terminated mode.
+struct TerminatedSortedFull;
+
+impl Cfg for TerminatedSortedFull {
+    fn with_idents() -> bool {
+        true
+    }
+
+    fn sort_map_keys() -> bool {
+        true
+    }
+
+    fn terminated_unknown_len() -> bool {
+        true
+    }
+}
+
+#[test]
+fn unknown_len_terminated() {
+    let seq = UnknownLenSeq(vec![1, 2, 300]);
+    let data = to_vec::<TerminatedSlim, _>(&seq);
+    assert_eq!(data, [125, 1, 1, 1, 1, 2, 1, 0xac, 0x02, 0]);
+    let v: Vec<u32> = deserialize::<TerminatedSlim, _, _>(data.as_slice()).unwrap();
+    assert_eq!(v, seq.0);
+
+    // Both modes are deserialized by any configuration.
+    let v: Vec<u32> = deserialize::<Slim, _, _>(data.as_slice()).unwrap();
+    assert_eq!(v, seq.0);
+    let v: Vec<u32> = deserialize::<TerminatedSlim, _, _>(to_vec::<Slim, _>(&seq).as_slice()).unwrap();
+    assert_eq!(v, seq.0);
+
+    let map = UnknownLenMap(vec![("b".to_string(), 300), ("a".to_string(), 1)]);
+    let data = to_vec::<TerminatedSlim, _>(&map);
+    assert_eq!(data, [125, 1, 1, 1, b'b', 0xac, 0x02, 1, 1, b'a', 1, 0]);
+    let m: HashMap<String, u32> = deserialize::<TerminatedSlim, _, _>(data.as_slice()).unwrap();
+    assert_eq!(m, map.0.iter().cloned().collect());
+
+    let data = to_vec::<TerminatedSortedFull, _>(&map);
+    assert_eq!(data, [125, 1, 1, 1, b'a', 1, 1, 1, b'b', 0xac, 0x02, 0]);
+    let m: HashMap<String, u32> = deserialize::<Full, _, _>(data.as_slice()).unwrap();
+    assert_eq!(m, map.0.iter().cloned().collect());
+
+    // Empty sequence.
+    assert_eq!(to_vec::<TerminatedSlim, _>(&UnknownLenSeq(vec![])), [125, 1, 0]);
+    let v: Vec<u32> = deserialize::<TerminatedSlim, _, _>([125, 1, 0].as_slice()).unwrap();
+    assert!(v.is_empty());
+
+    // Nested within a struct of unknown length sequences.
+    #[derive(Serialize)]
+    struct Outer {
+        a: UnknownLenSeq,
+        b: UnknownLenSeq,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct OuterDe {
+        a: Vec<u32>,
+        b: Vec<u32>,
+    }
+
+    let outer = Outer { a: UnknownLenSeq(vec![1]), b: UnknownLenSeq(vec![2, 3]) };
+    let de: OuterDe =
+        deserialize::<TerminatedSortedFull, _, _>(to_vec::<TerminatedSortedFull, _>(&outer).as_slice()).unwrap();
+    assert_eq!(de, OuterDe { a: vec![1], b: vec![2, 3] });
+
+    // Invalid flag and missing terminator.
+    let res = deserialize::<Slim, _, Vec<u32>>([125, 1, 1, 1, 2].as_slice());
+    assert!(matches!(res, Err(Error::BadLen)), "{res:?}");
+    let res = deserialize::<Slim, _, Vec<u32>>([125, 1, 1, 1].as_slice());
+    assert!(matches!(res, Err(Error::Io(_))), "{res:?}");
+
+    // Rejected by the canonical encoding.
+    assert_non_canonical::<Vec<u32>>(&[125, 1, 0]);
+}
+
+/// Slim configuration rejecting sequences and maps of unknown length when deserializing.
+struct ForbidUnknownLenSlim;
+
+impl Cfg for ForbidUnknownLenSlim {
+    fn with_idents() -> bool {
+        false
+    }
+
+    fn forbid_unknown_len() -> bool {
+        true
+    }
+}
+
+#[test]
+fn forbid_unknown_len() {
+    let seq = UnknownLenSeq(vec![1, 2, 3]);
+    let map = UnknownLenMap(vec![("a".to_string(), 1)]);
+
+    for data in [to_vec::<Slim, _>(&seq), to_vec::<TerminatedSlim, _>(&seq)] {
+        let v: Vec<u32> = deserialize::<Slim, _, _>(data.as_slice()).unwrap();
+        assert_eq!(v, seq.0);
+        let res = deserialize::<ForbidUnknownLenSlim, _, Vec<u32>>(data.as_slice());
+        assert!(matches!(res, Err(Error::UnknownLengthForbidden)), "{res:?}");
+    }
+
+    for data in [to_vec::<Slim, _>(&map), to_vec::<TerminatedSlim, _>(&map)] {
+        let m: HashMap<String, u32> = deserialize::<Slim, _, _>(data.as_slice()).unwrap();
+        assert_eq!(m, map.0.iter().cloned().collect());
+        let res = deserialize::<ForbidUnknownLenSlim, _, HashMap<String, u32>>(data.as_slice());
+        assert!(matches!(res, Err(Error::UnknownLengthForbidden)), "{res:?}");
+    }
+
+    // Known lengths, including the escaped length `SPECIAL_LEN`, are unaffected.
+    loopback::<ForbidUnknownLenSlim, _>(&vec![1u32, 2, 3]);
+    loopback::<ForbidUnknownLenSlim, _>(&vec![0u8; postbag::format::SPECIAL_LEN]);
+    loopback::<ForbidUnknownLenSlim, _>(&BTreeMap::from([(1u8, "a".to_string())]));
+}
+
+/// Canonical configuration with identifiers.
+struct CanonicalFull;
+
+impl Cfg for CanonicalFull {
+    fn with_idents() -> bool {
+        true
+    }
+
+    fn canonical() -> bool {
+        true
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+struct Key {
+    id: u32,
+    name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+enum Shape {
+    Point,
+    Circle(f64),
+    Rect { w: f32, h: f32 },
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+struct Document {
+    #[serde(rename = "_1")]
+    title: String,
+    initial: char,
+    tags: HashMap<String, Vec<u8>>,
+    keys: BTreeMap<Key, Option<i64>>,
+    shapes: Vec<Shape>,
+    nested: HashMap<u16, HashMap<String, bool>>,
+}
+
+fn document(rev: bool) -> Document {
+    let mut tags: Vec<_> = (0..50).map(|i| (format!("tag{i}"), vec![i as u8; i])).collect();
+    let mut nested: Vec<_> =
+        (0..20).map(|i| (i * 1000, (0..i).map(|j| (j.to_string(), j % 2 == 0)).collect())).collect();
+    if rev {
+        tags.reverse();
+        nested.reverse();
+    }
+
+    Document {
+        title: "title".to_string(),
+        initial: 'ß',
+        tags: tags.into_iter().collect(),
+        keys: (0..30)
+            .map(|i| (Key { id: 300 - i * 10, name: "k".repeat(i as usize) }, Some(-(i as i64))))
+            .collect(),
+        shapes: vec![Shape::Point, Shape::Circle(1.5), Shape::Rect { w: 2.0, h: -0.0 }],
+        nested: nested.into_iter().collect(),
+    }
+}
+
+#[track_caller]
+fn canonical_loopback<CFG: Cfg, T: Serialize + DeserializeOwned + Debug + PartialEq>(value: &T) -> Vec<u8> {
+    let serialized = loopback::<CFG, _>(value);
+    let deserialized: T = deserialize::<CFG, _, _>(serialized.as_slice()).unwrap();
+    assert_eq!(to_vec::<CFG, _>(&deserialized), serialized, "serialization not stable");
+    serialized
+}
+
+#[test]
+fn canonical_stable() {
+    let a = canonical_loopback::<Canonical, _>(&document(false));
+    let b = canonical_loopback::<Canonical, _>(&document(true));
+    assert_eq!(a, b);
+    assert_eq!(to_canonical_vec(&document(false)).unwrap(), a);
+    assert_eq!(from_canonical_slice::<Document>(&a).unwrap(), document(false));
+
+    let a = canonical_loopback::<CanonicalFull, _>(&document(false));
+    let b = canonical_loopback::<CanonicalFull, _>(&document(true));
+    assert_eq!(a, b);
+
+    // NaN is serialized with a single bit pattern.
+    let nan = f64::from_bits(f64::NAN.to_bits() | 1);
+    assert_eq!(to_canonical_vec(&nan).unwrap(), to_canonical_vec(&f64::NAN).unwrap());
+    assert!(from_canonical_slice::<f64>(&to_canonical_vec(&nan).unwrap()).unwrap().is_nan());
+    let nan = -f32::NAN;
+    assert_eq!(to_canonical_vec(&nan).unwrap(), to_canonical_vec(&f32::NAN).unwrap());
+
+    // Unknown lengths are unsupported.
+    let res = to_canonical_vec(&UnknownLenSeq(vec![1]));
+    assert!(
+        matches!(res, Err(Error::SerializeSeqLengthUnknown { cause: UnknownLenCause::Canonical })),
+        "{res:?}"
+    );
+    let res = to_canonical_vec(&UnknownLenMap(vec![("a".to_string(), 1)]));
+    assert!(
+        matches!(res, Err(Error::SerializeMapLengthUnknown { cause: UnknownLenCause::Canonical })),
+        "{res:?}"
+    );
+}
+
+#[track_caller]
+fn assert_non_canonical<T: DeserializeOwned + Debug>(data: &[u8]) {
+    let res = from_canonical_slice::<T>(data);
+    assert!(matches!(res, Err(Error::NonCanonical)), "{res:?}");
+}
+
+#[test]
+fn canonical_rejects_non_canonical() {
+    // Varint with redundant byte.
+    assert_eq!(from_canonical_slice::<u32>(&[0x81, 0x01]).unwrap(), 129);
+    assert_non_canonical::<u32>(&[0x81, 0x00]);
+    assert_non_canonical::<i64>(&[0x80, 0x80, 0x00]);
+
+    // NaN with non-canonical bit pattern.
+    assert_non_canonical::<f32>(&to_vec::<Slim, _>(&f32::from_bits(f32::NAN.to_bits() | 1)));
+    assert_non_canonical::<f64>(&to_vec::<Slim, _>(&-f64::NAN));
+
+    // Char with trailing bytes.
+    assert_non_canonical::<char>(&[2, b'a', b'b']);
+
+    // Trailing data.
+    assert_non_canonical::<u8>(&[1, 2]);
+
+    // Map keys not in ascending order or duplicated.
+    assert_eq!(from_canonical_slice::<BTreeMap<u8, u8>>(&[2, 1, 0, 2, 0]).unwrap().len(), 2);
+    assert_non_canonical::<BTreeMap<u8, u8>>(&[2, 2, 0, 1, 0]);
+    assert_non_canonical::<BTreeMap<u8, u8>>(&[2, 1, 0, 1, 0]);
+
+    // Sequence of unknown length.
+    assert_non_canonical::<Vec<u32>>(&to_vec::<Slim, _>(&UnknownLenSeq(vec![1, 2])));
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct One {
+        a: u8,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Two {
+        a: u8,
+        #[serde(default)]
+        b: u8,
+    }
+
+    // Block length with redundant byte.
+    assert_eq!(from_canonical_slice::<One>(&[1, 1, 5]).unwrap(), One { a: 5 });
+    assert_non_canonical::<One>(&[1, 0x81, 0x00, 5]);
+
+    // Unknown or missing struct fields.
+    assert_non_canonical::<One>(&to_vec::<Canonical, _>(&Two { a: 1, b: 2 }));
+    assert_non_canonical::<Two>(&to_vec::<Canonical, _>(&One { a: 1 }));
+}
+
+#[test]
+fn canonical_full_rejects_non_canonical() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Ab {
+        a: u8,
+        b: u8,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Ba {
+        b: u8,
+        a: u8,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Numbered {
+        #[serde(rename = "_5")]
+        a: u8,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Abc {
+        a: u8,
+        b: u8,
+        c: u8,
+    }
+
+    #[track_caller]
+    fn assert_non_canonical_full<T: DeserializeOwned + Debug>(data: &[u8]) {
+        let res = deserialize::<CanonicalFull, _, T>(data);
+        assert!(matches!(res, Err(Error::NonCanonical)), "{res:?}");
+    }
+
+    let data = to_vec::<CanonicalFull, _>(&Ab { a: 1, b: 2 });
+    assert_eq!(deserialize::<CanonicalFull, _, Ab>(data.as_slice()).unwrap(), Ab { a: 1, b: 2 });
+    assert_eq!(deserialize::<Full, _, Ba>(data.as_slice()).unwrap(), Ba { b: 2, a: 1 });
+
+    // Fields not in declaration order.
+    assert_non_canonical_full::<Ab>(&to_vec::<CanonicalFull, _>(&Ba { b: 2, a: 1 }));
+
+    // Unknown fields.
+    assert_non_canonical_full::<Ab>(&to_vec::<CanonicalFull, _>(&Abc { a: 1, b: 2, c: 3 }));
+
+    // Numeric identifier encoded as string.
+    let data = to_vec::<CanonicalFull, _>(&Numbered { a: 1 });
+    assert_eq!(data, [1, 65 + 5, 1, 1]);
+    assert_eq!(deserialize::<Full, _, Numbered>([1, 2, b'_', b'5', 1, 1].as_slice()).unwrap(), Numbered { a: 1 });
+    assert_non_canonical_full::<Numbered>(&[1, 2, b'_', b'5', 1, 1]);
+
+    // Short identifier with escaped length.
+    assert_eq!(deserialize::<Full, _, One>([1, 64, 1, b'a', 1, 1].as_slice()).unwrap(), One { a: 1 });
+    assert_non_canonical_full::<One>(&[1, 64, 1, b'a', 1, 1]);
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct One {
+        a: u8,
+    }
+}
+
+/// Full configuration requiring struct fields in declaration order.
+struct StrictFull;
+
+impl Cfg for StrictFull {
+    fn with_idents() -> bool {
+        true
+    }
+
+    fn strict_field_order() -> bool {
+        true
+    }
+}
+
+#[test]
+fn strict_field_order() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Abc {
+        a: u8,
+        b: u8,
+        #[serde(default)]
+        c: u8,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Ab {
+        a: u8,
+        b: u8,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Cba {
+        c: u8,
+        b: u8,
+        a: u8,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Abcd {
+        a: u8,
+        b: u8,
+        c: u8,
+        d: u8,
+    }
+
+    #[track_caller]
+    fn assert_mismatch<T: DeserializeOwned + Debug>(data: &[u8]) {
+        let res = deserialize::<StrictFull, _, T>(data);
+        assert!(matches!(res, Err(Error::FieldOrderMismatch)), "{res:?}");
+    }
+
+    let abc = Abc { a: 1, b: 2, c: 3 };
+    let data = loopback::<StrictFull, _>(&abc);
+    assert_eq!(data, to_vec::<Full, _>(&abc));
+
+    // Missing fields are permitted.
+    let data = to_vec::<StrictFull, _>(&Ab { a: 1, b: 2 });
+    assert_eq!(deserialize::<StrictFull, _, Abc>(data.as_slice()).unwrap(), Abc { a: 1, b: 2, c: 0 });
+
+    // Reordered fields.
+    let data = to_vec::<Full, _>(&Cba { c: 3, b: 2, a: 1 });
+    assert_eq!(deserialize::<Full, _, Abc>(data.as_slice()).unwrap(), abc);
+    assert_mismatch::<Abc>(&data);
+
+    // Unknown fields.
+    let data = to_vec::<Full, _>(&Abcd { a: 1, b: 2, c: 3, d: 4 });
+    assert_eq!(deserialize::<Full, _, Abc>(data.as_slice()).unwrap(), abc);
+    assert_mismatch::<Abc>(&data);
+}
+
+#[test]
+fn big_endian() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Fixed {
+        #[serde(with = "postbag::fixint")]
+        int: u32,
+        #[serde(with = "postbag::fixchar")]
+        c: char,
+        f: f32,
+        d: f64,
+        var: u32,
+    }
+
+    let value = Fixed { int: 0x1234_5678, c: '🦀', f: 1.5, d: -0.25, var: 300 };
+
+    // Field count and length of the struct, followed by the fields.
+    let data = loopback::<BigEndianSlim, _>(&value);
+    let mut expected = vec![5, 22];
+    expected.extend(0x1234_5678u32.to_be_bytes());
+    expected.extend(u32::from('🦀').to_be_bytes());
+    expected.extend(1.5f32.to_be_bytes());
+    expected.extend((-0.25f64).to_be_bytes());
+    // Varints have no byte order.
+    expected.extend([0xac, 0x02]);
+    assert_eq!(data, expected);
+
+    let data = loopback::<BigEndianFull, _>(&value);
+    assert_ne!(data, to_vec::<Full, _>(&value));
+    assert_eq!(data.len(), to_vec::<Full, _>(&value).len());
+}
+
+/// Full configuration requiring plausible identifiers.
+struct StrictIdentsFull;
+
+impl Cfg for StrictIdentsFull {
+    fn with_idents() -> bool {
+        true
+    }
+
+    fn strict_identifiers() -> bool {
+        true
+    }
+}
+
+#[test]
+fn strict_identifiers() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Plain {
+        name: u8,
+        _1: u8,
+        unicode_ä: u8,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Empty {
+        #[serde(rename = "")]
+        a: u8,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Control {
+        #[serde(rename = "a\u{1b}[31m")]
+        a: u8,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Spaced {
+        #[serde(rename = "two words")]
+        A,
+    }
+
+    #[track_caller]
+    fn assert_bad<T: Serialize + DeserializeOwned + Debug + PartialEq>(value: &T) {
+        let data = loopback::<Full, _>(value);
+        let res = deserialize::<StrictIdentsFull, _, T>(data.as_slice());
+        assert!(matches!(res, Err(Error::BadIdentifier)), "{res:?}");
+    }
+
+    loopback::<StrictIdentsFull, _>(&Plain { name: 1, _1: 2, unicode_ä: 3 });
+    assert_bad(&Empty { a: 1 });
+    assert_bad(&Control { a: 1 });
+    assert_bad(&Spaced::A);
+}
+
+#[test]
+fn format_constants() {
+    use postbag::format::*;
+
+    #[derive(Serialize)]
+    struct Fields {
+        _3: bool,
+        a: Option<u8>,
+    }
+
+    let seq = postbag::to_slim_vec(&vec![0u8; SPECIAL_LEN]).unwrap();
+    assert_eq!(seq[..2], [SPECIAL_LEN as u8, SPECIAL_LEN as u8]);
+    assert_eq!(seq.len(), 2 + SPECIAL_LEN);
+
+    let string = postbag::to_slim_vec(&"x".repeat(SPECIAL_LEN)).unwrap();
+    assert_eq!(string[0], SPECIAL_LEN as u8);
+    assert_eq!(string.len(), 1 + SPECIAL_LEN);
+
+    let unknown = postbag::to_slim_vec(&UnknownLenSeq(vec![7, 8])).unwrap();
+    assert_eq!(unknown, [SPECIAL_LEN as u8, UNKNOWN_LEN as u8, 2, 7, 8]);
+
+    let terminated = to_vec::<TerminatedSlim, _>(&UnknownLenSeq(vec![7, 8]));
+    assert_eq!(terminated, [SPECIAL_LEN as u8, TERMINATED_LEN as u8, CONTINUE, 7, CONTINUE, 8, STOP]);
+
+    let fields = postbag::to_full_vec(&Fields { _3: true, a: Some(5) }).unwrap();
+    assert_eq!(fields, [2, (ID_LEN_NAME + 3) as u8, 1, TRUE, 1, b'a', 2, SOME, 5]);
+    let fields = postbag::to_slim_vec(&Fields { _3: false, a: None }).unwrap();
+    assert_eq!(fields, [2, 2, FALSE, NONE]);
+
+    let long = postbag::to_full_vec(&"y".repeat(ID_LEN)).unwrap();
+    assert_eq!(long[0], ID_LEN as u8);
+
+    // A struct filling a chunk of its skippable block exactly is followed by an empty chunk.
+    #[derive(Serialize)]
+    struct Blob {
+        data: Vec<u8>,
+    }
+
+    let block = postbag::to_slim_vec(&Blob { data: vec![0; SKIP_CHUNK_MAX - 3] }).unwrap();
+    assert_eq!(block.len(), 1 + 3 + SKIP_CHUNK_MAX + 1);
+    assert_eq!(block[1..4], [0xff, 0xff, 0x03]);
+    assert_eq!(block[block.len() - 1], 0);
+    assert_eq!(ID_COUNT, 60);
+}
+
+struct LenientSlim;
+
+impl Cfg for LenientSlim {
+    fn with_idents() -> bool {
+        false
+    }
+
+    fn lenient_bool() -> bool {
+        true
+    }
+}
+
+#[test]
+fn lenient_bool() {
+    let lenient = |data: &[u8]| deserialize::<LenientSlim, _, bool>(data);
+    assert!(!lenient(&[0]).unwrap());
+    assert!(lenient(&[1]).unwrap());
+    assert!(lenient(&[2]).unwrap());
+    assert!(lenient(&[0xff]).unwrap());
+
+    // Strict by default.
+    assert!(matches!(postbag::from_slim_slice::<bool>(&[2]), Err(Error::BadBool)));
+    assert!(matches!(postbag::from_full_slice::<bool>(&[0xff]), Err(Error::BadBool)));
+    assert!(matches!(from_canonical_slice::<bool>(&[2]), Err(Error::BadBool)));
+
+    // Serialization is unaffected.
+    assert_eq!(to_vec::<LenientSlim, _>(&true), [1]);
+}
+
+struct FixedVariantSlim;
+
+impl Cfg for FixedVariantSlim {
+    fn with_idents() -> bool {
+        false
+    }
+
+    fn fixed_variant_index() -> bool {
+        true
+    }
+}
+
+/// Unit variant of an enum with `N` variants, given by its index.
+#[derive(Debug, PartialEq)]
+struct Indexed<const N: usize>(u32);
+
+impl<const N: usize> Indexed<N> {
+    const VARIANTS: &[&str] = &["V"; N];
+}
+
+impl<const N: usize> Serialize for Indexed<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_unit_variant("Indexed", self.0, "V")
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for Indexed<N> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor<const N: usize>;
+
+        impl<'de, const N: usize> serde::de::Visitor<'de> for Visitor<N> {
+            type Value = Indexed<N>;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "enum of {N} variants")
+            }
+
+            fn visit_enum<A: serde::de::EnumAccess<'de>>(self, data: A) -> Result<Indexed<N>, A::Error> {
+                use serde::de::VariantAccess;
+                let (index, variant) = data.variant::<u32>()?;
+                variant.unit_variant()?;
+                Ok(Indexed(index))
+            }
+        }
+
+        deserializer.deserialize_enum("Indexed", Self::VARIANTS, Visitor::<N>)
+    }
+}
+
+#[test]
+fn fixed_variant_index() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Small {
+        A,
+        B(u16),
+        C { x: u8 },
+    }
+
+    // Enum of 200 variants.
+    for index in [0, 127, 128, 199] {
+        let data = loopback::<FixedVariantSlim, _>(&Indexed::<200>(index));
+        assert_eq!(data, [index as u8]);
+    }
+    assert_eq!(postbag::to_slim_vec(&Indexed::<200>(128)).unwrap(), [0x80, 0x01]);
+
+    assert_eq!(loopback::<FixedVariantSlim, _>(&Small::B(300)), [1, 0xac, 0x02]);
+    assert_eq!(loopback::<FixedVariantSlim, _>(&Small::C { x: 1 }), [2, 1, 1, 1]);
+
+    // Enum of more than 256 variants.
+    loopback::<FixedVariantSlim, _>(&Indexed::<300>(255));
+    let res = serialize::<FixedVariantSlim, _, _>(Vec::new(), &Indexed::<300>(256));
+    assert!(matches!(res, Err(Error::BadEnum)), "{res:?}");
+
+    // Identifiers are unaffected.
+    #[derive(Serialize)]
+    struct Named(#[serde(with = "postbag::named_variant")] Small);
+    assert_eq!(to_vec::<FixedVariantSlim, _>(&Named(Small::A)), [1, b'A']);
+}
@@ -91,6 +91,64 @@ fn added_struct_fields() {
     assert_eq!(b.f4, f4_default());
 }
 
+#[test]
+fn added_two_struct_fields_slim() {
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    struct Inner {
+        v: u32,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    struct A {
+        f1: u32,
+        inner: Inner,
+        blob: Vec<u8>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct B {
+        f1: u32,
+        inner: Inner,
+        blob: Vec<u8>,
+        #[serde(default = "f4_default")]
+        f4: u32,
+        #[serde(default)]
+        f5: Option<Inner>,
+    }
+
+    const fn f4_default() -> u32 {
+        4
+    }
+
+    // The last field of the old struct ends with a nested block or spans
+    // multiple chunks of the struct's block, and the struct is followed by
+    // further data that must be read from the correct position.
+    for blob in [vec![], vec![7; 10], vec![7; 100_000]] {
+        let a = (vec![A { f1: 1, inner: Inner { v: 2 }, blob: blob.clone() }; 2], 99u32);
+
+        let (bs, x): (Vec<B>, u32) = transform::<_, _, Slim>(&a);
+        assert_eq!(x, 99);
+        assert_eq!(bs.len(), 2);
+        for b in bs {
+            assert_eq!(b, B { f1: 1, inner: Inner { v: 2 }, blob: blob.clone(), f4: f4_default(), f5: None });
+        }
+    }
+
+    // Missing fields without a default are still an error.
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct C {
+        f1: u32,
+        inner: Inner,
+        blob: Vec<u8>,
+        f4: u32,
+    }
+
+    let data = postbag::to_slim_vec(&A { f1: 1, inner: Inner { v: 2 }, blob: vec![] }).unwrap();
+    let res = postbag::from_slim_slice::<C>(&data);
+    assert!(res.is_err(), "{res:?}");
+}
+
 #[test]
 fn changed_struct_variant_fields() {
     #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
@@ -720,3 +778,849 @@ fn removed_struct_variant_field_from_middle() {
     assert_eq!(f1, 1);
     assert_eq!(f3, 3);
 }
+
+#[test]
+fn reordered_named_variants_slim_encoding() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    enum Inner {
+        I1,
+        I2,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    enum KindA {
+        K1,
+        K2(u32),
+        K3 { inner: Inner },
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct A {
+        #[serde(with = "postbag::named_variant")]
+        kind: KindA,
+        other: Inner,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    enum KindB {
+        K0,
+        K3 { inner: Inner },
+        K2(u32),
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct B {
+        #[serde(with = "postbag::named_variant")]
+        kind: KindB,
+        other: Inner,
+    }
+
+    let a = A { kind: KindA::K2(5), other: Inner::I2 };
+    let b: B = transform::<_, _, Slim>(&a);
+    assert_eq!(b, B { kind: KindB::K2(5), other: Inner::I2 });
+
+    let a = A { kind: KindA::K3 { inner: Inner::I2 }, other: Inner::I1 };
+    let b: B = transform::<_, _, Slim>(&a);
+    assert_eq!(b, B { kind: KindB::K3 { inner: Inner::I2 }, other: Inner::I1 });
+
+    let b: B = transform::<_, _, Full>(&a);
+    assert_eq!(b, B { kind: KindB::K3 { inner: Inner::I2 }, other: Inner::I1 });
+
+    // Only the variant of the field is named; nested enums use indices.
+    let bytes = postbag::to_slim_vec(&a).unwrap();
+    assert_eq!(bytes, [2, 7, 2, b'K', b'3', 1, 1, 1, 0]);
+}
+
+#[test]
+fn removed_large_struct_field_seek() {
+    use std::io::{Cursor, Read, Seek, SeekFrom};
+
+    /// Reader counting the number of bytes read.
+    struct CountingReader {
+        inner: Cursor<Vec<u8>>,
+        read: usize,
+    }
+
+    impl Read for CountingReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.inner.read(buf)?;
+            self.read += n;
+            Ok(n)
+        }
+    }
+
+    impl Seek for CountingReader {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Record {
+        id: u32,
+        blob: Vec<u8>,
+        name: String,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct RecordName {
+        id: u32,
+        name: String,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Outer<T> {
+        record: T,
+        trailer: u8,
+    }
+
+    let value =
+        Outer { record: Record { id: 1, blob: vec![0xaa; 1_000_000], name: "name".to_string() }, trailer: 2 };
+    let expected = Outer { record: RecordName { id: 1, name: "name".to_string() }, trailer: 2 };
+
+    let mut serialized = Vec::new();
+    serialize::<Full, _, _>(&mut serialized, &value).unwrap();
+    let len = serialized.len() as u64;
+
+    let mut reader = CountingReader { inner: Cursor::new(serialized.clone()), read: 0 };
+    let deserialized: Outer<RecordName> = postbag::deserialize_seek::<Full, _, _>(&mut reader).unwrap();
+    assert_eq!(deserialized, expected);
+    assert_eq!(reader.inner.position(), len);
+    if !cfg!(postbag_fast_compile) {
+        assert!(reader.read < 1000, "read {} bytes", reader.read);
+    }
+
+    // Non-seekable readers skip by reading.
+    let deserialized: Outer<RecordName> = deserialize::<Full, _, _>(serialized.as_slice()).unwrap();
+    assert_eq!(deserialized, expected);
+
+    // Truncated data is detected when it is read.
+    let res =
+        postbag::deserialize_seek::<Full, _, Outer<RecordName>>(Cursor::new(&serialized[..len as usize - 1]));
+    assert!(res.is_err());
+}
+
+#[test]
+fn reordered_variants_slim_encoding_with_name_table() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    #[serde(rename = "Kind")]
+    enum KindA {
+        K1,
+        K2(u32),
+        K3 { inner: Inner },
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    enum Inner {
+        I1,
+        I2,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct A {
+        kinds: Vec<KindA>,
+        other: Inner,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    #[serde(rename = "Kind")]
+    enum KindB {
+        K0,
+        K3 { inner: Inner },
+        K2(u32),
+        K1,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct B {
+        kinds: Vec<KindB>,
+        other: Inner,
+    }
+
+    let a = A { kinds: vec![KindA::K1, KindA::K2(5), KindA::K3 { inner: Inner::I2 }], other: Inner::I2 };
+    let data = postbag::to_slim_vec(&a).unwrap();
+
+    // Variant names of `KindA` in index order; other enums still use indices.
+    let mut deserializer = postbag::Deserializer::<_, Slim>::new(data.as_slice());
+    deserializer.set_variant_names("Kind", &["K1", "K2", "K3"]);
+    let b: B = deserializer.deserialize_value().unwrap();
+    assert_eq!(b, B { kinds: vec![KindB::K1, KindB::K2(5), KindB::K3 { inner: Inner::I2 }], other: Inner::I2 });
+
+    // Without the table, indices are resolved against the new order.
+    let res = postbag::from_slim_slice::<B>(&data);
+    assert!(res.is_err(), "{res:?}");
+
+    // Index beyond the table.
+    let mut deserializer = postbag::Deserializer::<_, Slim>::new(data.as_slice());
+    deserializer.set_variant_names("Kind", &["K1", "K2"]);
+    let res = deserializer.deserialize_value::<B>();
+    assert!(matches!(res, Err(postbag::Error::BadEnum)), "{res:?}");
+}
+
+#[test]
+fn unknown_variants_counted() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    enum New {
+        A,
+        B(u32),
+        C { x: u8 },
+        D,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    enum Old {
+        A,
+        B(u32),
+        #[serde(other)]
+        Unknown,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Msg<T> {
+        first: T,
+        rest: Vec<T>,
+    }
+
+    fn check<CFG: Cfg>(value: &Msg<New>, expected: Msg<Old>, unknown: usize) {
+        let mut data = Vec::new();
+        serialize::<CFG, _, _>(&mut data, value).unwrap();
+
+        let mut deserializer = postbag::Deserializer::<_, CFG>::new(data.as_slice());
+        assert!(!deserializer.saw_unknown_variant());
+        let old: Msg<Old> = deserializer.deserialize_value().unwrap();
+        assert_eq!(old, expected);
+        assert_eq!(deserializer.unknown_variants(), unknown);
+        assert_eq!(deserializer.saw_unknown_variant(), unknown > 0);
+    }
+
+    let known = Msg { first: New::A, rest: vec![New::B(1), New::A] };
+    check::<Full>(&known, Msg { first: Old::A, rest: vec![Old::B(1), Old::A] }, 0);
+    check::<Slim>(&known, Msg { first: Old::A, rest: vec![Old::B(1), Old::A] }, 0);
+
+    let value = Msg { first: New::C { x: 1 }, rest: vec![New::B(2), New::D] };
+    check::<Full>(&value, Msg { first: Old::Unknown, rest: vec![Old::B(2), Old::Unknown] }, 2);
+
+    // The slim encoding cannot skip the payload of an unknown variant.
+    let value = Msg { first: New::D, rest: vec![New::B(2), New::D] };
+    check::<Slim>(&value, Msg { first: Old::Unknown, rest: vec![Old::B(2), Old::Unknown] }, 2);
+
+    // Variant names resolved through a name table.
+    let data = postbag::to_slim_vec(&Msg { first: New::D, rest: vec![] }).unwrap();
+    let mut deserializer = postbag::Deserializer::<_, Slim>::new(data.as_slice());
+    deserializer.set_variant_names("Old", &["A", "B", "C", "D"]);
+    let old: Msg<Old> = deserializer.deserialize_value().unwrap();
+    assert_eq!(old, Msg { first: Old::Unknown, rest: vec![] });
+    assert_eq!(deserializer.unknown_variants(), 1);
+}
+
+#[derive(Serialize, Debug)]
+struct RecordV1 {
+    id: u32,
+    name: String,
+}
+
+#[test]
+fn assert_compatible_added_field() {
+    use postbag::testing::assert_compatible;
+
+    #[derive(Deserialize, Debug, PartialEq, Eq)]
+    struct RecordV2 {
+        id: u32,
+        #[serde(default)]
+        tags: Vec<String>,
+    }
+
+    let v1 = RecordV1 { id: 7, name: "seven".to_string() };
+    assert_eq!(assert_compatible::<Full, _, RecordV2>(&v1), RecordV2 { id: 7, tags: vec![] });
+}
+
+#[test]
+#[should_panic(expected = "cannot deserialize data serialized from")]
+fn assert_compatible_missing_field() {
+    use postbag::testing::assert_compatible;
+
+    #[derive(Deserialize, Debug)]
+    struct Renamed {
+        #[allow(dead_code)]
+        key: u32,
+    }
+
+    assert_compatible::<Full, _, Renamed>(&RecordV1 { id: 7, name: "seven".to_string() });
+}
+
+#[test]
+#[should_panic(expected = "consumed only 1 of 2 bytes")]
+fn assert_compatible_trailing_data() {
+    use postbag::testing::assert_compatible;
+
+    assert_compatible::<Slim, _, u32>(&(1u32, 2u32));
+}
+
+/// Full configuration enclosing the value of each `Some` in a skippable block.
+struct SkippableOptionsFull;
+
+impl Cfg for SkippableOptionsFull {
+    fn with_idents() -> bool {
+        true
+    }
+
+    fn skippable_options() -> bool {
+        true
+    }
+}
+
+#[test]
+fn evolved_top_level_option() {
+    use postbag::testing::assert_compatible;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct PointV1(u32, u32);
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct PointV2(u32, u32, String);
+
+    let v2 = Some(PointV2(1, 2, "label".to_string()));
+    let v1: Option<PointV1> = transform::<_, _, SkippableOptionsFull>(&v2);
+    assert_eq!(v1, Some(PointV1(1, 2)));
+
+    let none: Option<PointV1> = transform::<Option<PointV2>, _, SkippableOptionsFull>(&None);
+    assert_eq!(none, None);
+
+    // Within a tuple, the value following the option remains readable.
+    let v1: (Option<PointV1>, u32) = assert_compatible::<SkippableOptionsFull, _, _>(&(&v2, 99u32));
+    assert_eq!(v1, (Some(PointV1(1, 2)), 99));
+
+    // Without the skippable block the added element is left unread.
+    let data = postbag::to_full_vec(&v2).unwrap();
+    let (v1, tail) = postbag::from_full_slice_with_tail::<Option<PointV1>>(&data).unwrap();
+    assert_eq!(v1, Some(PointV1(1, 2)));
+    assert!(!tail.is_empty());
+}
+
+#[test]
+fn skip_serializing_if() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Middle {
+        first: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        middle: Option<u32>,
+        last: u32,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Trailing {
+        first: u32,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        last: Option<u32>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    enum Wrapper {
+        Variant {
+            #[serde(default, skip_serializing_if = "Option::is_none")]
+            first: Option<u32>,
+            second: u32,
+        },
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Outer {
+        inner: Trailing,
+        after: u32,
+    }
+
+    fn roundtrip<CFG: Cfg, T: Serialize + DeserializeOwned + Debug + Eq>(value: &T) {
+        let mut data = Vec::new();
+        serialize::<CFG, _, _>(&mut data, value).unwrap();
+        assert_eq!(deserialize::<CFG, _, T>(data.as_slice()).unwrap(), *value);
+    }
+
+    fn skipped<CFG: Cfg, T: Serialize>(value: &T) {
+        let mut data = Vec::new();
+        let res = serialize::<CFG, _, _>(&mut data, value);
+        assert!(matches!(res, Err(postbag::Error::SkippedField)), "{res:?}");
+    }
+
+    let present = Middle { first: 1, middle: Some(2), last: 3 };
+    let absent = Middle { first: 1, middle: None, last: 3 };
+    let trailing = Trailing { first: 1, tags: vec![], last: None };
+    let hole = Trailing { first: 1, tags: vec![], last: Some(3) };
+    let variant = Wrapper::Variant { first: None, second: 2 };
+
+    // Full identifies fields by name, thus any field may be skipped.
+    roundtrip::<Full, _>(&present);
+    roundtrip::<Full, _>(&trailing);
+    if !cfg!(postbag_fast_compile) {
+        // fast_compile does not support missing fields in the middle.
+        roundtrip::<Full, _>(&absent);
+        roundtrip::<Full, _>(&hole);
+        roundtrip::<Full, _>(&variant);
+    }
+
+    // Slim identifies fields by position, thus only trailing fields may be skipped.
+    roundtrip::<Slim, _>(&present);
+    roundtrip::<Slim, _>(&trailing);
+    skipped::<Slim, _>(&absent);
+    skipped::<Slim, _>(&hole);
+    skipped::<Slim, _>(&variant);
+
+    // Skipping trailing fields of a nested struct does not affect the outer struct.
+    roundtrip::<Slim, _>(&Outer { inner: trailing, after: 2 });
+}
+
+#[test]
+fn preserving_unknown() {
+    use postbag::deserialize_preserving_unknown;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct InnerV2 {
+        a: u32,
+        b: u32,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct InnerV1 {
+        a: u32,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct ConfigV2 {
+        extra: Vec<String>,
+        name: String,
+        inner: InnerV2,
+        limit: Option<u64>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct ConfigV1 {
+        name: String,
+        inner: InnerV1,
+    }
+
+    let v2 = ConfigV2 {
+        extra: vec!["x".to_string(), "y".to_string()],
+        name: "main".to_string(),
+        inner: InnerV2 { a: 1, b: 2 },
+        limit: Some(1000),
+    };
+    let v1 = ConfigV1 { name: "main".to_string(), inner: InnerV1 { a: 1 } };
+
+    let data = postbag::to_full_vec(&v2).unwrap();
+    let (value, unknown) = deserialize_preserving_unknown::<Full, _, ConfigV1>(data.as_slice()).unwrap();
+    assert_eq!(value, v1);
+
+    // Only unknown fields of the outermost struct are collected, in order.
+    let idents: Vec<_> = unknown.iter().map(|(ident, _)| ident.as_str()).collect();
+    assert_eq!(idents, ["extra", "limit"]);
+    assert_eq!(postbag::from_full_slice::<Vec<String>>(&unknown[0].1).unwrap(), v2.extra);
+    assert_eq!(postbag::from_full_slice::<Option<u64>>(&unknown[1].1).unwrap(), v2.limit);
+
+    // Known data yields no unknown fields.
+    let data = postbag::to_full_vec(&v1).unwrap();
+    let (value, unknown) = deserialize_preserving_unknown::<Full, _, ConfigV1>(data.as_slice()).unwrap();
+    assert_eq!(value, v1);
+    assert!(unknown.is_empty());
+
+    // Fields are identified by position without identifiers.
+    let data = postbag::to_slim_vec(&v1).unwrap();
+    let (value, unknown) = deserialize_preserving_unknown::<Slim, _, ConfigV1>(data.as_slice()).unwrap();
+    assert_eq!(value, v1);
+    assert!(unknown.is_empty());
+}
+
+struct SkippableTuplesSlim;
+
+impl Cfg for SkippableTuplesSlim {
+    fn with_idents() -> bool {
+        false
+    }
+
+    fn skippable_tuples() -> bool {
+        true
+    }
+}
+
+#[test]
+fn evolved_tuple_arity() {
+    use postbag::{BoundedVec, testing::assert_compatible};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Segment {
+        range: (u32, u32),
+        label: String,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct SegmentV2 {
+        range: (u32, u32, u32),
+        label: String,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Color(u8, u8, u8);
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct ColorV2(u8, u8, u8, #[serde(default)] Option<u8>);
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    enum Shape {
+        Line(u32, u32),
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    enum ShapeV2 {
+        Line(u32, u32, u32),
+    }
+
+    // Extra elements are skipped.
+    let v1: Segment = transform::<_, _, SkippableTuplesSlim>(&SegmentV2 { range: (1, 2, 3), label: "a".into() });
+    assert_eq!(v1, Segment { range: (1, 2), label: "a".into() });
+    let v1: (Shape, u32) = transform::<_, _, SkippableTuplesSlim>(&(ShapeV2::Line(1, 2, 3), 4u32));
+    assert_eq!(v1, (Shape::Line(1, 2), 4));
+    let v1: ([u16; 2], u8) = transform::<_, _, SkippableTuplesSlim>(&([1u16, 2, 3], 4u8));
+    assert_eq!(v1, ([1, 2], 4));
+
+    // Missing elements are defaulted where possible.
+    let v2: ColorV2 = assert_compatible::<SkippableTuplesSlim, _, _>(&Color(1, 2, 3));
+    assert_eq!(v2, ColorV2(1, 2, 3, None));
+    let v1: Color = assert_compatible::<SkippableTuplesSlim, _, _>(&ColorV2(1, 2, 3, Some(4)));
+    assert_eq!(v1, Color(1, 2, 3));
+    let data = postbag::to_slim_vec(&Segment { range: (1, 2), label: "a".into() }).unwrap();
+    assert!(postbag::from_slim_slice::<SegmentV2>(&data).is_err());
+
+    // Without the skippable block the stream desynchronizes.
+    let data = postbag::to_slim_vec(&SegmentV2 { range: (1, 2, 3), label: "a".into() }).unwrap();
+    assert!(!matches!(postbag::from_slim_slice::<Segment>(&data), Ok(v) if v.label == "a"));
+
+    // Helpers keep their encodings.
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Helpers {
+        #[serde(with = "postbag::fixint")]
+        fixed: u32,
+        #[serde(with = "postbag::fixint::checked")]
+        checked: i16,
+        #[serde(with = "postbag::fixchar")]
+        c: char,
+        #[serde(with = "postbag::char_len_string")]
+        text: String,
+        bounded: BoundedVec<(u8, u8), 4>,
+    }
+
+    let helpers = Helpers {
+        fixed: 0x1234_5678,
+        checked: -2,
+        c: '🦀',
+        text: "hé".into(),
+        bounded: vec![(1, 2), (3, 4)].into(),
+    };
+    let v: Helpers = transform::<_, _, SkippableTuplesSlim>(&helpers);
+    assert_eq!(v, helpers);
+    let mut data = Vec::new();
+    serialize::<SkippableTuplesSlim, _, _>(&mut data, &helpers).unwrap();
+    assert_eq!(data[2..6], 0x1234_5678u32.to_le_bytes());
+}
+
+#[test]
+fn helper_tuples_not_skippable() {
+    use postbag::PaddedVarint;
+
+    /// Serializes a value using [`SkippableTuplesSlim`], checking that the encoding is unchanged.
+    fn to_vec<T: Serialize>(value: &T) -> Vec<u8> {
+        let mut data = Vec::new();
+        serialize::<SkippableTuplesSlim, _, _>(&mut data, value).unwrap();
+        assert_eq!(data, postbag::to_slim_vec(value).unwrap());
+        data
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Padded(#[serde(with = "PaddedVarint::<4>")] u32);
+
+    let data = to_vec(&Padded(5));
+    assert_eq!(data, [133, 128, 128, 0]);
+    assert_eq!(deserialize::<SkippableTuplesSlim, _, Padded>(data.as_slice()).unwrap(), Padded(5));
+    assert_eq!(deserialize::<SkippableTuplesSlim, _, u32>(data.as_slice()).unwrap(), 5);
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Bits(#[serde(with = "postbag::bits")] Vec<bool>);
+
+    let bits = Bits(vec![true, false, true]);
+    let data = to_vec(&bits);
+    assert_eq!(data, [3, 0b101]);
+    assert_eq!(deserialize::<SkippableTuplesSlim, _, Bits>(data.as_slice()).unwrap(), bits);
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Float(#[serde(with = "postbag::compact_float")] f64);
+
+    let data = to_vec(&Float(3.0));
+    assert_eq!(data, [postbag::compact_float::INTEGER, 6]);
+    assert_eq!(deserialize::<SkippableTuplesSlim, _, Float>(data.as_slice()).unwrap(), Float(3.0));
+    let data = to_vec(&Float(0.5));
+    assert_eq!(data[0], postbag::compact_float::RAW);
+    assert_eq!(deserialize::<SkippableTuplesSlim, _, Float>(data.as_slice()).unwrap(), Float(0.5));
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn datetime_not_skippable() {
+    use time::OffsetDateTime;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Trade(#[serde(with = "postbag::datetime")] OffsetDateTime);
+
+    let mut data = Vec::new();
+    serialize::<SkippableTuplesSlim, _, _>(&mut data, &Trade(OffsetDateTime::UNIX_EPOCH)).unwrap();
+    assert_eq!(data, [0, 0]);
+    assert_eq!(data, postbag::to_slim_vec(&Trade(OffsetDateTime::UNIX_EPOCH)).unwrap());
+    let trade: Trade = deserialize::<SkippableTuplesSlim, _, _>(data.as_slice()).unwrap();
+    assert_eq!(trade, Trade(OffsetDateTime::UNIX_EPOCH));
+}
+
+#[cfg(feature = "rust_decimal")]
+#[test]
+fn decimal_not_skippable() {
+    use rust_decimal::Decimal;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Amount(#[serde(with = "postbag::decimal")] Decimal);
+
+    let amount = Amount(Decimal::new(-300, 1));
+    let mut data = Vec::new();
+    serialize::<SkippableTuplesSlim, _, _>(&mut data, &amount).unwrap();
+    assert_eq!(data, [0, 0, 1, 0x80, 0x2c, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(data, postbag::to_slim_vec(&amount).unwrap());
+    assert_eq!(deserialize::<SkippableTuplesSlim, _, Amount>(data.as_slice()).unwrap(), amount);
+}
+
+#[test]
+fn transcode() {
+    use postbag::{Error, cfg::Canonical, transcode, transcode_with};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    enum Kind {
+        File,
+        Directory { entries: u32 },
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct RecordV1 {
+        name: String,
+        kind: Kind,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct RecordV2 {
+        name: String,
+        owner: String,
+        kind: Kind,
+    }
+
+    let record = RecordV1 { name: "docs".into(), kind: Kind::Directory { entries: 3 } };
+    let full = postbag::to_full_vec(&record).unwrap();
+    let slim = transcode::<RecordV1>(&full).unwrap();
+    assert_eq!(slim, postbag::to_slim_vec(&record).unwrap());
+    assert_eq!(postbag::from_slim_slice::<RecordV1>(&slim).unwrap(), record);
+
+    // Fields unknown to the type are dropped.
+    let v2 = RecordV2 { name: "docs".into(), owner: "root".into(), kind: Kind::File };
+    let full = postbag::to_full_vec(&v2).unwrap();
+    let slim = transcode::<RecordV1>(&full).unwrap();
+    assert_eq!(
+        postbag::from_slim_slice::<RecordV1>(&slim).unwrap(),
+        RecordV1 { name: "docs".into(), kind: Kind::File }
+    );
+
+    let canonical = transcode_with::<Slim, Canonical, RecordV1>(&slim).unwrap();
+    assert_eq!(canonical, slim);
+
+    let res = transcode::<RecordV1>(&full[..full.len() - 1]);
+    assert!(matches!(res, Err(Error::Io(_))), "{res:?}");
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn field_ids() {
+    use postbag::field_ids;
+
+    #[field_ids]
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct SensorV1 {
+        #[postbag(id = 0)]
+        temp: i16,
+        #[postbag(id = 1)]
+        hum: u8,
+        location: String,
+    }
+
+    #[field_ids]
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct SensorV2 {
+        location: String,
+        #[postbag(id = 1)]
+        humidity: u8,
+        #[postbag(id = 0)]
+        temperature: i16,
+        #[postbag(id = 59)]
+        #[serde(default)]
+        pressure: Option<u32>,
+    }
+
+    let v1 = SensorV1 { temp: -5, hum: 40, location: "roof".into() };
+    let v2: SensorV2 = transform::<_, _, Full>(&v1);
+    assert_eq!(v2, SensorV2 { location: "roof".into(), humidity: 40, temperature: -5, pressure: None });
+
+    let v2 = SensorV2 { pressure: Some(1013), ..v2 };
+    let v1: SensorV1 = transform::<_, _, Full>(&v2);
+    assert_eq!(v1, SensorV1 { temp: -5, hum: 40, location: "roof".into() });
+
+    // Fields with an ID are identified by a single byte.
+    #[derive(Serialize)]
+    struct Named {
+        temperature: i16,
+    }
+
+    #[field_ids]
+    #[derive(Serialize)]
+    struct Numeric {
+        #[postbag(id = 0)]
+        temperature: i16,
+    }
+
+    let named = postbag::to_full_vec(&Named { temperature: 1 }).unwrap();
+    let numeric = postbag::to_full_vec(&Numeric { temperature: 1 }).unwrap();
+    assert_eq!(numeric.len() + "temperature".len(), named.len());
+}
+
+#[test]
+fn positional_ids() {
+    use postbag::{Error, cfg::PositionalSlim};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+    struct AccountV1 {
+        id: u32,
+        #[serde(default)]
+        nickname: String,
+        balance: i64,
+    }
+
+    /// Middle field removed, keeping its position by a placeholder.
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct AccountV2 {
+        id: u32,
+        #[serde(default, skip_serializing_if = "removed")]
+        _nickname: (),
+        balance: i64,
+        #[serde(default)]
+        currency: Option<String>,
+    }
+
+    fn removed<T>(_: &T) -> bool {
+        true
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Optional {
+        first: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        middle: Option<u32>,
+        last: Vec<AccountV1>,
+    }
+
+    let v1 = AccountV1 { id: 7, nickname: "bob".into(), balance: -100 };
+    let v2: AccountV2 = transform::<_, _, PositionalSlim>(&v1);
+    assert_eq!(v2, AccountV2 { id: 7, _nickname: (), balance: -100, currency: None });
+
+    let v2 = AccountV2 { currency: Some("EUR".into()), ..v2 };
+    let v1: AccountV1 = transform::<_, _, PositionalSlim>(&v2);
+    assert_eq!(v1, AccountV1 { id: 7, nickname: String::new(), balance: -100 });
+
+    // Skipped fields in the middle are supported.
+    let nested = vec![AccountV1 { id: 1, nickname: "a".into(), balance: 2 }];
+    for middle in [None, Some(5)] {
+        let value = Optional { first: 1, middle, last: nested.clone() };
+        let back: Optional = transform::<_, _, PositionalSlim>(&value);
+        assert_eq!(back, value);
+    }
+
+    // Without positions the skipped field cannot be serialized.
+    let mut data = Vec::new();
+    let res = serialize::<Slim, _, _>(&mut data, &Optional { first: 1, middle: None, last: nested });
+    assert!(matches!(res, Err(Error::SkippedField)), "{res:?}");
+}
+
+#[test]
+fn positional_ids_aliased_field() {
+    use postbag::{Error, cfg::PositionalSlim};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Aliased {
+        a: u8,
+        #[serde(alias = "bb")]
+        b: u8,
+        c: u8,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Extended {
+        a: u8,
+        b: u8,
+        c: u8,
+        d: u8,
+    }
+
+    // Positions refer to actual fields, not to the aliases listed by serde.
+    let value = Aliased { a: 1, b: 2, c: 3 };
+    let mut data = Vec::new();
+    serialize::<PositionalSlim, _, _>(&mut data, &value).unwrap();
+    assert_eq!(data, [3, 0, 1, 1, 1, 1, 2, 2, 1, 3]);
+    assert_eq!(transform::<_, Aliased, PositionalSlim>(&value), value);
+
+    let aliased: Aliased = transform::<_, _, PositionalSlim>(&Extended { a: 1, b: 2, c: 3, d: 4 });
+    assert_eq!(aliased, value);
+
+    /// Canonical configuration with positional field identifiers.
+    struct CanonicalPositional;
+
+    impl Cfg for CanonicalPositional {
+        fn with_idents() -> bool {
+            false
+        }
+
+        fn canonical() -> bool {
+            true
+        }
+
+        fn positional_ids() -> bool {
+            true
+        }
+    }
+
+    let mut data = Vec::new();
+    serialize::<CanonicalPositional, _, _>(&mut data, &value).unwrap();
+    assert_eq!(deserialize::<CanonicalPositional, _, Aliased>(data.as_slice()).unwrap(), value);
+
+    // A field of unknown position is not canonical.
+    let mut data = Vec::new();
+    serialize::<CanonicalPositional, _, _>(&mut data, &Extended { a: 1, b: 2, c: 3, d: 4 }).unwrap();
+    let res = deserialize::<CanonicalPositional, _, Aliased>(data.as_slice());
+    assert!(matches!(res, Err(Error::NonCanonical)), "{res:?}");
+}
+
+#[test]
+fn empty_struct_slim_encoding() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Empty {}
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Outer {
+        a: Empty,
+        b: u8,
+    }
+
+    // Encoding produced by earlier versions, where an empty struct is enclosed in an empty block.
+    let data = [2, 3, 0, 0, 7];
+    let value = Outer { a: Empty {}, b: 7 };
+    assert_eq!(postbag::from_slim_slice::<Outer>(&data).unwrap(), value);
+    assert_eq!(postbag::to_slim_vec(&value).unwrap(), data);
+}
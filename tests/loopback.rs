@@ -26,8 +26,27 @@ where
     dbg!(serialized.len());
 
     let deserialized: T = deserialize::<CFG, _, _>(serialized.as_slice()).expect("deserialization failed");
-
     assert_eq!(*value, deserialized, "deserialized value does not match original value");
+
+    let deserialized: T =
+        deserialize::<CFG, _, _>(OneByteReader(&serialized)).expect("deserialization byte by byte failed");
+    assert_eq!(*value, deserialized, "value deserialized byte by byte does not match original value");
+}
+
+/// Reader returning at most one byte per read call.
+pub struct OneByteReader<'a>(pub &'a [u8]);
+
+impl std::io::Read for OneByteReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match (self.0.split_first(), buf.first_mut()) {
+            (Some((byte, rest)), Some(target)) => {
+                *target = *byte;
+                self.0 = rest;
+                Ok(1)
+            }
+            _ => Ok(0),
+        }
+    }
 }
 
 /// Performs serialization followed by deserialization and checks that the
@@ -64,6 +83,33 @@ fn primitives_signed_integers() {
     loopback(-19490127978232325886905073712831_i128); // large negative i128
 }
 
+#[test]
+fn primitives_8bit_integers() {
+    // 8-bit integers are encoded as a single raw byte, without varint or zigzag encoding.
+    fn check<CFG: Cfg>() {
+        for v in u8::MIN..=u8::MAX {
+            let mut data = Vec::new();
+            serialize::<CFG, _, _>(&mut data, &v).unwrap();
+            assert_eq!(data, [v]);
+            assert_eq!(deserialize::<CFG, _, u8>(data.as_slice()).unwrap(), v);
+        }
+
+        for v in i8::MIN..=i8::MAX {
+            let mut data = Vec::new();
+            serialize::<CFG, _, _>(&mut data, &v).unwrap();
+            assert_eq!(data, v.to_le_bytes());
+            assert_eq!(deserialize::<CFG, _, i8>(data.as_slice()).unwrap(), v);
+        }
+    }
+
+    check::<Full>();
+    check::<Slim>();
+
+    let mut data = Vec::new();
+    serialize::<Slim, _, _>(&mut data, &(i8::MIN, -1i8, 0i8, i8::MAX)).unwrap();
+    assert_eq!(data, [0x80, 0xff, 0x00, 0x7f]);
+}
+
 #[test]
 fn primitives_booleans() {
     loopback(false);
@@ -181,6 +227,53 @@ fn enums_with_data() {
     loopback(DataEnum::Sho(0x6969, 0x07));
 }
 
+#[test]
+fn enums_peek_variant() {
+    use postbag::Variant;
+
+    fn peek<CFG: Cfg>(value: &DataEnum, expected: Variant) {
+        let mut serialized = Vec::new();
+        serialize::<CFG, _, _>(&mut serialized, value).unwrap();
+        serialized.extend_from_slice(&[0xAB]);
+
+        let mut deser = postbag::Deserializer::<_, CFG>::new(serialized.as_slice());
+        assert_eq!(*deser.peek_variant().unwrap(), expected);
+        assert_eq!(*deser.peek_variant().unwrap(), expected);
+
+        let deserialized = DataEnum::deserialize(&mut deser).unwrap();
+        assert_eq!(*value, deserialized);
+        assert_eq!(deser.finalize(), &[0xAB]);
+
+        let mut cursor = std::io::Cursor::new(&serialized);
+        assert_eq!(postbag::peek_variant::<CFG, _>(&mut cursor).unwrap(), expected);
+        assert_eq!(cursor.position(), 0);
+    }
+
+    let value = DataEnum::Chi { a: 0x0F, b: 0xC7C7C7C7 };
+    peek::<Full>(&value, Variant::Name("Chi".into()));
+    peek::<Slim>(&value, Variant::Index(4));
+
+    // A peeked variant must be consumed by an enum.
+    let data = postbag::to_slim_vec(&(DataEnum::Bap(5), 7u32)).unwrap();
+    let mut deser = postbag::Deserializer::<_, Slim>::new(data.as_slice());
+    assert_eq!(*deser.peek_variant().unwrap(), Variant::Index(2));
+    let res = deser.deserialize_value::<(u32, u32)>();
+    assert!(matches!(res, Err(Error::PeekedVariant)), "{res:?}");
+
+    // A variant written with its identifier is peeked as by a normal read.
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Named(#[serde(with = "postbag::named_variant")] DataEnum);
+
+    for (value, name) in [(DataEnum::Sho(1, 2), "Sho"), (DataEnum::Kim(EnumStruct { eight: 1, sixt: 2 }), "Kim")]
+    {
+        let data = postbag::to_full_vec(&Named(value)).unwrap();
+        let mut deser = postbag::Deserializer::<_, Full>::new(data.as_slice());
+        assert_eq!(*deser.peek_variant().unwrap(), Variant::Name(name.into()));
+        let peeked = deser.deserialize_value::<Named>().unwrap();
+        assert_eq!(peeked, postbag::from_full_slice::<Named>(&data).unwrap());
+    }
+}
+
 // =============================================================================
 // Nested Structure Tests
 // =============================================================================
@@ -223,6 +316,111 @@ fn nested_structs() {
     loopback(deeply_nested);
 }
 
+/// Slim configuration omitting the skippable block of structs without fields.
+struct EmptyStructSlim;
+
+impl Cfg for EmptyStructSlim {
+    fn with_idents() -> bool {
+        false
+    }
+
+    fn omit_empty_struct_block() -> bool {
+        true
+    }
+}
+
+/// Serializes a value using [`EmptyStructSlim`].
+fn to_empty_struct_vec<T: Serialize>(value: &T) -> Result<Vec<u8>, Error> {
+    let mut data = Vec::new();
+    serialize::<EmptyStructSlim, _, _>(&mut data, value)?;
+    Ok(data)
+}
+
+#[test]
+fn nested_empty_structs() {
+    #[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+    struct Empty {}
+
+    #[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+    enum Kind {
+        Empty {},
+        Value { value: u8 },
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+    struct Node {
+        empty: Empty,
+        kind: Kind,
+        children: Vec<Node>,
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        label: Option<String>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+    struct Skipped {
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        a: Option<u8>,
+    }
+
+    // By default a struct without fields is enclosed in an empty skippable block.
+    assert_eq!(postbag::to_slim_vec(&Empty {}).unwrap(), [0, 0]);
+    assert_eq!(postbag::to_slim_vec(&(Empty {}, 5u8)).unwrap(), [0, 0, 5]);
+
+    // The block can be omitted.
+    assert_eq!(to_empty_struct_vec(&Empty {}).unwrap(), [0]);
+    assert_eq!(to_empty_struct_vec(&Kind::Empty {}).unwrap(), [0, 0]);
+    assert_eq!(to_empty_struct_vec(&Skipped { a: None }).unwrap(), [0]);
+    assert_eq!(to_empty_struct_vec(&(Empty {}, 5u8)).unwrap(), [0, 5]);
+
+    let node = Node {
+        empty: Empty {},
+        kind: Kind::Empty {},
+        label: None,
+        children: vec![
+            Node {
+                empty: Empty {},
+                kind: Kind::Value { value: 1 },
+                label: Some("a".to_string()),
+                children: vec![],
+            },
+            Node {
+                empty: Empty {},
+                kind: Kind::Empty {},
+                label: None,
+                children: vec![Node { empty: Empty {}, kind: Kind::Empty {}, label: None, children: vec![] }],
+            },
+        ],
+    };
+    loopback_with_cfg::<_, EmptyStructSlim>(&Empty {});
+    loopback_with_cfg::<_, EmptyStructSlim>(&Skipped { a: None });
+    loopback_with_cfg::<_, EmptyStructSlim>(&Skipped { a: Some(1) });
+    loopback_with_cfg::<_, EmptyStructSlim>(&vec![Empty {}, Empty {}]);
+    loopback_with_cfg::<_, EmptyStructSlim>(&node);
+    loopback(node);
+
+    // Fields added to and removed from an empty struct.
+    let data = to_empty_struct_vec(&Skipped { a: Some(1) }).unwrap();
+    deserialize::<EmptyStructSlim, _, Empty>(data.as_slice()).unwrap();
+    let data = to_empty_struct_vec(&(Empty {}, 5u8)).unwrap();
+    let value: (Skipped, u8) = deserialize::<EmptyStructSlim, _, _>(data.as_slice()).unwrap();
+    assert_eq!(value, (Skipped { a: None }, 5));
+
+    // Fields cannot be written to a struct declared without fields.
+    struct Bad;
+
+    impl Serialize for Bad {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            use serde::ser::SerializeStruct;
+            let mut s = serializer.serialize_struct("Bad", 0)?;
+            s.serialize_field("a", &1u8)?;
+            s.end()
+        }
+    }
+
+    let res = to_empty_struct_vec(&Bad);
+    assert!(matches!(res, Err(Error::BadLen)), "{res:?}");
+}
+
 #[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
 enum InnerEnum {
     Alpha(u8),
@@ -408,6 +606,100 @@ fn id_enum_fields() {
     loopback(IdEnum::MyLongVariant5 { long_field_name_a: 4, long_field_name_b: 4 });
 }
 
+#[test]
+fn long_and_numeric_identifiers() {
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+    struct Idents {
+        #[serde(rename = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa")]
+        len63: u8,
+        #[serde(rename = "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb")]
+        len64: u8,
+        #[serde(rename = "ccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccc")]
+        len65: u8,
+        #[serde(
+            rename = "dddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddd"
+        )]
+        len200: u8,
+        #[serde(rename = "_59")]
+        id59: u8,
+        #[serde(rename = "_60")]
+        id60: u8,
+        #[serde(rename = "_007")]
+        leading_zeros: u8,
+        #[serde(rename = "_+8")]
+        plus: u8,
+        #[serde(rename = "_")]
+        underscore: u8,
+        #[serde(rename = "")]
+        empty: u8,
+    }
+
+    loopback(Idents {
+        len63: 1,
+        len64: 2,
+        len65: 3,
+        len200: 4,
+        id59: 5,
+        id60: 6,
+        leading_zeros: 7,
+        plus: 8,
+        underscore: 9,
+        empty: 10,
+    });
+
+    // Encoding of a single identifier is its length, escaped from 64 bytes on.
+    #[derive(Serialize)]
+    enum Variants {
+        #[serde(rename = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa")]
+        Len63,
+        #[serde(rename = "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb")]
+        Len64,
+        #[serde(
+            rename = "dddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddd"
+        )]
+        Len200,
+        #[serde(rename = "_59")]
+        Id59,
+        #[serde(rename = "_007")]
+        LeadingZeros,
+    }
+
+    assert_eq!(postbag::to_full_vec(&Variants::Len63).unwrap()[..1], [63]);
+    assert_eq!(postbag::to_full_vec(&Variants::Len64).unwrap()[..2], [64, 64]);
+    assert_eq!(postbag::to_full_vec(&Variants::Len200).unwrap()[..3], [64, 0xC8, 0x01]);
+    assert_eq!(postbag::to_full_vec(&Variants::Id59).unwrap(), [65 + 59]);
+    assert_eq!(postbag::to_full_vec(&Variants::LeadingZeros).unwrap(), [4, b'_', b'0', b'0', b'7']);
+}
+
+#[test]
+fn interned_identifiers() {
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+    struct Item {
+        name: String,
+        #[serde(rename = "_7")]
+        count: u32,
+        kind: DataEnum,
+    }
+
+    let items: Vec<Item> = (0..50)
+        .map(|i| Item { name: format!("item{i}"), count: i, kind: DataEnum::Chi { a: i as u8, b: i } })
+        .collect();
+    let serialized = postbag::to_full_vec(&items).unwrap();
+
+    let mut interner = postbag::Interner::new();
+    for _ in 0..2 {
+        let deserialized: Vec<Item> =
+            postbag::deserialize_with_interner::<Full, _, _>(serialized.as_slice(), &mut interner).unwrap();
+        assert_eq!(deserialized, items);
+    }
+
+    // name, _7, kind, Chi, a, b
+    // Fast compile mode deserializes buffered fields without the interner.
+    if !cfg!(postbag_fast_compile) {
+        assert_eq!(interner.len(), 6);
+    }
+}
+
 // =============================================================================
 // Collection Tests
 // =============================================================================
@@ -467,6 +759,67 @@ fn collections_maps() {
     loopback(input);
 }
 
+#[test]
+fn collections_maps_in_structs() {
+    #[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+    struct WithMaps {
+        strings: std::collections::HashMap<String, u32>,
+        numbers: BTreeMap<u32, String>,
+    }
+
+    // Keys resembling numerical identifiers must be treated as plain strings.
+    let long_key = "k".repeat(100);
+    let strings = [("_3".to_string(), 3), ("_60".to_string(), 60), ("".to_string(), 0), (long_key, 100)]
+        .into_iter()
+        .collect();
+    let numbers = [(1, "_1".to_string()), (2, "two".to_string())].into_iter().collect();
+    loopback(WithMaps { strings, numbers });
+
+    let mut single = std::collections::HashMap::new();
+    single.insert("_3".to_string(), 3u32);
+    let serialized = postbag::to_full_vec(&WithMaps { strings: single, numbers: BTreeMap::new() }).unwrap();
+    assert!(serialized.windows(3).any(|w| w == [2, b'_', b'3']), "{serialized:02x?}");
+}
+
+#[test]
+fn flattened_structs() {
+    #[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+    struct Inner {
+        b: u32,
+        c: String,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+    struct Outer {
+        a: u8,
+        #[serde(flatten)]
+        inner: Inner,
+    }
+
+    /// Map with the same entries as `Outer`.
+    struct OuterMap;
+
+    impl Serialize for OuterMap {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            use serde::ser::SerializeMap;
+            let mut map = serializer.serialize_map(None)?;
+            map.serialize_entry("a", &1u8)?;
+            map.serialize_entry("b", &2u32)?;
+            map.serialize_entry("c", "three")?;
+            map.end()
+        }
+    }
+
+    // Flattened struct is serialized as a map of unknown length with inner and outer keys.
+    let value = Outer { a: 1, inner: Inner { b: 2, c: "three".to_string() } };
+    assert_eq!(postbag::to_full_vec(&value).unwrap(), postbag::to_full_vec(&OuterMap).unwrap());
+    assert_eq!(postbag::to_slim_vec(&value).unwrap(), postbag::to_slim_vec(&OuterMap).unwrap());
+
+    // Deserialization of flattened structs requires a self-describing format.
+    let serialized = postbag::to_full_vec(&value).unwrap();
+    assert!(matches!(postbag::from_full_slice::<Outer>(&serialized), Err(Error::DeserializeAnyUnsupported)));
+}
+
 #[test]
 fn collections_maps_special() {
     for len in [0, 124, 125, 126] {
@@ -478,6 +831,52 @@ fn collections_maps_special() {
     }
 }
 
+#[test]
+fn collections_maps_wide_keys() {
+    let unsigned: BTreeMap<u128, u64> =
+        [0, 1, 127, 128, u64::MAX as u128, u64::MAX as u128 + 1, u128::MAX - 1, u128::MAX]
+            .into_iter()
+            .zip(0..)
+            .collect();
+    loopback(unsigned.clone());
+
+    let signed: BTreeMap<i128, i128> =
+        [i128::MIN, i128::MIN + 1, i64::MIN as i128 - 1, -1, 0, 1, i128::MAX - 1, i128::MAX]
+            .into_iter()
+            .map(|key| (key, key.wrapping_neg()))
+            .collect();
+    loopback(signed.clone());
+
+    // Keys of maximum width are followed by the value.
+    let max = BTreeMap::from([(u128::MAX, 5u64)]);
+    let data = postbag::to_slim_vec(&max).unwrap();
+    assert_eq!(data.len(), 1 + 19 + 1);
+    assert_eq!(data.last(), Some(&5));
+
+    let data = postbag::to_canonical_vec(&signed).unwrap();
+    assert_eq!(postbag::from_canonical_slice::<BTreeMap<i128, i128>>(&data).unwrap(), signed);
+    let data = postbag::to_canonical_vec(&unsigned).unwrap();
+    assert_eq!(postbag::from_canonical_slice::<BTreeMap<u128, u64>>(&data).unwrap(), unsigned);
+}
+
+#[test]
+fn collections_large_fields() {
+    #[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+    struct Large {
+        before: u8,
+        data: String,
+        nested: Vec<InnerStruct>,
+        after: u8,
+    }
+
+    const MAX_LEN: usize = u16::MAX as usize;
+    for len in [MAX_LEN - 1, MAX_LEN, MAX_LEN + 1, 3 * MAX_LEN, 3 * MAX_LEN + 7, 1 << 20] {
+        let data = "x".repeat(len);
+        let nested = vec![InnerStruct { id: 1, name: data.clone() }];
+        loopback(Large { before: 1, data, nested, after: 2 });
+    }
+}
+
 #[test]
 fn collections_cstring() {
     // CString (uses serialize_bytes/deserialize_byte_buf)
@@ -840,68 +1239,3001 @@ fn error_handling_vec_bounds() {
 }
 
 #[test]
-fn varint_boundary_tests() {
-    loopback(u32::MAX);
+fn error_handling_bogus_length() {
+    // Length of 2^48 bytes followed by a short payload.
+    let data = [0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x01, 1, 2, 3];
 
-    let deser = deserialize::<Slim, _, u32>([0xFF, 0xFF, 0xFF, 0xFF, 0x1F].as_slice());
-    assert!(matches!(deser, Err(Error::BadVarint)));
+    // Length is checked against remaining slice before allocating.
+    assert!(matches!(
+        postbag::from_slim_slice::<Vec<u8>>(&data),
+        Err(Error::Io(io)) if io.kind() == ErrorKind::UnexpectedEof
+    ));
+    assert!(matches!(
+        postbag::from_slim_slice::<String>(&data),
+        Err(Error::Io(io)) if io.kind() == ErrorKind::UnexpectedEof
+    ));
+
+    // Reader of unknown length allocates incrementally.
+    assert!(matches!(
+        deserialize::<Slim, _, Vec<u8>>(data.as_slice()),
+        Err(Error::Io(io)) if io.kind() == ErrorKind::UnexpectedEof
+    ));
+
+    // Honest large lengths still work through both paths.
+    let large = "a".repeat(1 << 20);
+    let serialized = postbag::to_slim_vec(&large).unwrap();
+    assert_eq!(postbag::from_slim_slice::<String>(&serialized).unwrap(), large);
+    assert_eq!(deserialize::<Slim, _, String>(serialized.as_slice()).unwrap(), large);
 }
 
-// =============================================================================
-// Fixed int encoding
-// =============================================================================
+#[test]
+fn error_handling_zst_length() {
+    // Length of u64::MAX for elements consuming no input.
+    let data = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01];
+    let res = postbag::from_slim_slice::<Vec<ZSTStruct>>(&data);
+    assert!(matches!(res, Err(Error::BadLen)), "{res:?}");
+    let res = deserialize::<Full, _, Vec<()>>(data.as_slice());
+    assert!(matches!(res, Err(Error::BadLen)), "{res:?}");
+    let res = postbag::from_slim_slice::<BTreeMap<(), ()>>(&data);
+    assert!(matches!(res, Err(Error::BadLen)), "{res:?}");
+
+    // Large sequences of elements consuming input are unaffected.
+    let large = vec![Some(()); (1 << 20) + 1];
+    let serialized = postbag::to_slim_vec(&large).unwrap();
+    assert_eq!(postbag::from_slim_slice::<Vec<Option<()>>>(&serialized).unwrap(), large);
+
+    // Sequences of zero-sized elements up to the limit are accepted.
+    let zsts = vec![(); 1 << 20];
+    let serialized = postbag::to_slim_vec(&zsts).unwrap();
+    assert_eq!(postbag::from_slim_slice::<Vec<()>>(&serialized).unwrap(), zsts);
+}
 
 #[test]
-fn fixed_int() {
-    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
-    pub struct DefinitelyLE {
-        #[serde(with = "postbag::fixint")]
-        x: u16,
+fn error_handling_map_length() {
+    use std::collections::HashMap;
+
+    // Length of 2^28 entries followed by a short body.
+    let data = [0x80, 0x80, 0x80, 0x80, 0x01, 1, 0, 2, 0];
+    let res = postbag::from_slim_slice::<HashMap<u64, String>>(&data);
+    assert!(matches!(res, Err(Error::BadLen)), "{res:?}");
+
+    // Small lengths are checked as well.
+    let data = [0xe8, 0x07, 1, 0, 2, 0];
+    let res = postbag::from_slim_slice::<HashMap<u64, String>>(&data);
+    assert!(matches!(res, Err(Error::BadLen)), "{res:?}");
+
+    // A single entry may consume no input.
+    let unit: BTreeMap<(), ()> = BTreeMap::from([((), ())]);
+    assert_eq!(
+        postbag::from_slim_slice::<BTreeMap<(), ()>>(&postbag::to_slim_vec(&unit).unwrap()).unwrap(),
+        unit
+    );
+
+    // The size hint is bounded for readers of unknown length.
+    struct SizeHint(Option<usize>);
+
+    impl<'de> Deserialize<'de> for SizeHint {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct SizeHintVisitor;
+
+            impl<'de> serde::de::Visitor<'de> for SizeHintVisitor {
+                type Value = SizeHint;
+
+                fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    formatter.write_str("a map")
+                }
+
+                fn visit_map<A: serde::de::MapAccess<'de>>(self, map: A) -> Result<SizeHint, A::Error> {
+                    Ok(SizeHint(map.size_hint()))
+                }
+            }
+
+            deserializer.deserialize_map(SizeHintVisitor)
+        }
     }
 
-    loopback(DefinitelyLE { x: 0xABCD });
+    let hint = deserialize::<Slim, _, SizeHint>(data.as_slice()).unwrap().0.unwrap();
+    assert!(hint <= 4096, "{hint}");
+    let hint = postbag::from_slim_slice::<SizeHint>(&[3, 1, 2, 3, 4, 5, 6]).unwrap().0;
+    assert_eq!(hint, Some(3));
+
+    // Honest large maps are accepted.
+    let large: HashMap<u32, ()> = (0..(1 << 20) + 1).map(|i| (i, ())).collect();
+    let serialized = postbag::to_slim_vec(&large).unwrap();
+    assert_eq!(postbag::from_slim_slice::<HashMap<u32, ()>>(&serialized).unwrap(), large);
 }
 
-// =============================================================================
-// Serde alias tests
-// =============================================================================
+#[test]
+fn error_handling_partial() {
+    let value = OuterStruct { inner: InnerStruct { id: 7, name: "hello".to_string() }, metadata: vec![1, 2, 3] };
+
+    let full = postbag::to_full_vec(&value).unwrap();
+    let (err, consumed) =
+        postbag::deserialize_partial::<Full, _, OuterStruct>(&full[..full.len() - 2]).unwrap_err();
+    assert!(matches!(err, Error::Io(io) if io.kind() == ErrorKind::UnexpectedEof));
+    assert!(consumed > 0 && consumed <= full.len() as u64 - 2, "{consumed}");
+
+    // Slim: outer len, block len, inner len, block len, id, name len, name
+    let slim = postbag::to_slim_vec(&value).unwrap();
+    let (err, consumed) = postbag::deserialize_partial::<Slim, _, OuterStruct>(&slim[..8]).unwrap_err();
+    assert!(matches!(err, Error::Io(io) if io.kind() == ErrorKind::UnexpectedEof));
+    assert_eq!(consumed, 6);
+
+    let (err, consumed) = postbag::deserialize_partial::<Slim, _, (u8, bool)>([1, 2].as_slice()).unwrap_err();
+    assert!(matches!(err, Error::BadBool));
+    assert_eq!(consumed, 2);
+
+    let value: OuterStruct = postbag::deserialize_partial::<Slim, _, _>(slim.as_slice()).unwrap();
+    assert_eq!(value.inner.name, "hello");
+}
 
-/// Exposure.
-#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
-pub struct Exposure {
-    /// Gain level.
-    pub gain: u8,
-    /// Exposure time in microseconds.
-    #[serde(alias = "time_100usec")]
-    pub time_usec: u32,
+#[test]
+fn error_handling_empty_input() {
+    let res = postbag::from_full_slice::<OuterStruct>(&[]);
+    assert!(matches!(res, Err(Error::EmptyInput)), "{res:?}");
+    let res = deserialize::<Slim, _, f64>([].as_slice());
+    assert!(matches!(res, Err(Error::EmptyInput)), "{res:?}");
+    assert_eq!(std::io::Error::from(Error::EmptyInput).kind(), ErrorKind::UnexpectedEof);
+
+    // Input ending within the value.
+    let res = postbag::from_slim_slice::<f64>(&[1, 2]);
+    assert!(matches!(&res, Err(Error::Io(err)) if err.kind() == ErrorKind::UnexpectedEof), "{res:?}");
+    let res = deserialize::<Slim, _, f64>([1, 2].as_slice());
+    assert!(matches!(&res, Err(Error::Io(err)) if err.kind() == ErrorKind::UnexpectedEof), "{res:?}");
+    let res = deserialize::<Slim, _, (u8, u32)>([1].as_slice());
+    assert!(matches!(&res, Err(Error::Io(err)) if err.kind() == ErrorKind::UnexpectedEof), "{res:?}");
+
+    // End of a stream of values.
+    let mut data = Vec::new();
+    let mut serializer = postbag::Serializer::<_, Full>::new(&mut data);
+    serializer.serialize_value(&1u32).unwrap();
+    serializer.serialize_value("two").unwrap();
+    serializer.finalize().unwrap();
+    let mut deserializer = postbag::Deserializer::<_, Full>::new(data.as_slice());
+    assert_eq!(deserializer.deserialize_value::<u32>().unwrap(), 1);
+    assert_eq!(deserializer.deserialize_value::<String>().unwrap(), "two");
+    let res = deserializer.deserialize_value::<u32>();
+    assert!(matches!(res, Err(Error::EmptyInput)), "{res:?}");
 }
 
 #[test]
-fn serde_alias_loopback() {
-    loopback(Exposure { gain: 42, time_usec: 100_000 });
-    loopback(Exposure { gain: 0, time_usec: 0 });
-    loopback(Exposure { gain: 255, time_usec: u32::MAX });
+fn deserializer_position() {
+    let value = OuterStruct { inner: InnerStruct { id: 7, name: "hello".to_string() }, metadata: vec![1, 2, 3] };
+
+    let mut serialized = postbag::to_full_vec(&value).unwrap();
+    let first_len = serialized.len() as u64;
+    serialized.extend(postbag::to_full_vec(&42u32).unwrap());
+
+    let mut deser = postbag::Deserializer::<_, Full>::new(serialized.as_slice());
+    assert_eq!(deser.position(), 0);
+    assert_eq!(OuterStruct::deserialize(&mut deser).unwrap(), value);
+    assert_eq!(deser.position(), first_len);
+    assert_eq!(u32::deserialize(&mut deser).unwrap(), 42);
+    assert_eq!(deser.position(), serialized.len() as u64);
 }
 
 #[test]
-fn serde_alias_compat() {
-    // Old struct uses the original field name that is now an alias.
-    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
-    struct ExposureOld {
-        gain: u8,
-        time_100usec: u32,
+fn multiple_values() {
+    use postbag::{Deserializer, Serializer};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Header {
+        version: u16,
+        count: u32,
     }
 
-    let old = ExposureOld { gain: 42, time_100usec: 100_000 };
+    fn check<CFG: Cfg>() {
+        let bodies: Vec<Result<String, u8>> = vec![Ok("a".to_string()), Err(2), Ok("c".to_string())];
 
-    // Serialize with old field name, deserialize with new struct that has alias.
-    // This only works in Full mode (field names are serialized).
-    let mut serialized = Vec::new();
-    serialize::<Full, _, _>(&mut serialized, &old).expect("serialization failed");
-    println!("old serialized: {serialized:02x?}");
+        let mut serializer = Serializer::<_, CFG>::new(Vec::new());
+        serializer.serialize_value(&Header { version: 1, count: bodies.len() as u32 }).unwrap();
+        for body in &bodies {
+            serializer.serialize_value(body).unwrap();
+        }
+        let data = serializer.finalize().unwrap();
 
-    let new: Exposure =
-        deserialize::<Full, _, _>(serialized.as_slice()).expect("deserialization with alias failed");
-    assert_eq!(new.gain, old.gain);
-    assert_eq!(new.time_usec, old.time_100usec);
+        let mut deserializer = Deserializer::<_, CFG>::new(data.as_slice());
+        let header: Header = deserializer.deserialize_value().unwrap();
+        assert_eq!(header, Header { version: 1, count: 3 });
+        for body in &bodies {
+            assert_eq!(&deserializer.deserialize_value::<Result<String, u8>>().unwrap(), body);
+        }
+        assert_eq!(deserializer.position(), data.len() as u64);
+    }
+
+    check::<Full>();
+    check::<Slim>();
+}
+
+#[test]
+fn error_into_io_error() {
+    let io = std::io::Error::new(ErrorKind::BrokenPipe, "pipe closed");
+    let err: std::io::Error = Error::Io(io).into();
+    assert_eq!(err.kind(), ErrorKind::BrokenPipe);
+    assert_eq!(err.to_string(), "pipe closed");
+
+    let err: std::io::Error = Error::BadBool.into();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert_eq!(err.to_string(), Error::BadBool.to_string());
+
+    let err: std::io::Error = deserialize::<Slim, _, bool>([2].as_slice()).unwrap_err().into();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert!(matches!(err.into_inner().unwrap().downcast::<Error>().as_deref(), Ok(Error::BadBool)));
+}
+
+#[test]
+fn error_handling_flush() {
+    /// Writer that fails on flush.
+    struct FailingFlush(Vec<u8>);
+
+    impl std::io::Write for FailingFlush {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Err(std::io::Error::new(ErrorKind::BrokenPipe, "flush failed"))
+        }
+    }
+
+    let res = postbag::serialize_full(FailingFlush(Vec::new()), &TupleStruct((1, 2)));
+    assert!(matches!(&res, Err(Error::Io(err)) if err.kind() == ErrorKind::BrokenPipe), "{res:?}");
+
+    let res = postbag::serialize_slim(FailingFlush(Vec::new()), &TupleStruct((1, 2)));
+    assert!(matches!(&res, Err(Error::Io(err)) if err.kind() == ErrorKind::BrokenPipe), "{res:?}");
+}
+
+#[test]
+fn error_handling_write_zero() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Message {
+        id: u32,
+        payload: Vec<u8>,
+    }
+
+    /// Writer that accepts at most `max` bytes per call and returns `Ok(0)` on the call with index `zero_at`.
+    struct ChokingWriter {
+        data: Vec<u8>,
+        max: usize,
+        calls: usize,
+        zero_at: Option<usize>,
+    }
+
+    impl std::io::Write for ChokingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let call = self.calls;
+            self.calls += 1;
+            if self.zero_at == Some(call) {
+                return Ok(0);
+            }
+            let n = buf.len().min(self.max);
+            self.data.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let value = Message { id: 7, payload: vec![1; 200_000] };
+
+    // Partial writes are continued.
+    let mut writer = ChokingWriter { data: Vec::new(), max: 1000, calls: 0, zero_at: None };
+    postbag::serialize_full(&mut writer, &value).unwrap();
+    assert_eq!(postbag::from_full_slice::<Message>(&writer.data).unwrap(), value);
+
+    // A writer making no progress fails serialization instead of being retried forever.
+    for zero_at in [0, 1, 5, 100] {
+        for max in [1, 1000, usize::MAX] {
+            let mut writer = ChokingWriter { data: Vec::new(), max, calls: 0, zero_at: Some(zero_at) };
+            let res = postbag::serialize_full(&mut writer, &value);
+            if writer.calls > zero_at {
+                assert!(matches!(&res, Err(Error::Io(err)) if err.kind() == ErrorKind::WriteZero), "{res:?}");
+                assert_eq!(writer.calls, zero_at + 1);
+            } else {
+                res.unwrap();
+            }
+
+            let mut writer = ChokingWriter { data: Vec::new(), max, calls: 0, zero_at: Some(zero_at) };
+            let res = postbag::serialize_slim(&mut writer, &value);
+            if writer.calls > zero_at {
+                assert!(matches!(&res, Err(Error::Io(err)) if err.kind() == ErrorKind::WriteZero), "{res:?}");
+                assert_eq!(writer.calls, zero_at + 1);
+            } else {
+                res.unwrap();
+            }
+        }
+    }
+}
+
+#[test]
+fn tee_writer() {
+    use postbag::Tee;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Message {
+        id: u32,
+        payload: Vec<u8>,
+        inner: Option<Box<Message>>,
+    }
+
+    /// Writer that accepts at most `max` bytes per call, counts flushes and fails after `fail_after` bytes.
+    #[derive(Default)]
+    struct Sink {
+        data: Vec<u8>,
+        max: usize,
+        flushes: usize,
+        fail_after: Option<usize>,
+    }
+
+    impl std::io::Write for Sink {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.fail_after.is_some_and(|n| self.data.len() >= n) {
+                return Err(std::io::Error::new(ErrorKind::BrokenPipe, "sink closed"));
+            }
+            let n = buf.len().min(self.max);
+            self.data.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.flushes += 1;
+            Ok(())
+        }
+    }
+
+    let value = Message {
+        id: 1,
+        payload: vec![1; 100_000],
+        inner: Some(Box::new(Message { id: 2, payload: vec![2; 10], inner: None })),
+    };
+    let expected = postbag::to_full_vec(&value).unwrap();
+
+    // Both writers receive the same data exactly once, even with partial writes.
+    for (max1, max2) in [(usize::MAX, usize::MAX), (7, usize::MAX), (usize::MAX, 7), (1000, 3)] {
+        let mut tee =
+            Tee::new(Sink { max: max1, ..Default::default() }, Sink { max: max2, ..Default::default() });
+        postbag::serialize_full(&mut tee, &value).unwrap();
+        let (first, second) = tee.into_inner();
+        assert_eq!(first.data, expected);
+        assert_eq!(second.data, expected);
+        assert_eq!(first.flushes, 1);
+        assert_eq!(second.flushes, 1);
+    }
+
+    // Errors of either writer are returned.
+    let failing = || Sink { max: usize::MAX, fail_after: Some(1000), ..Default::default() };
+    let res = postbag::serialize_full(Tee::new(failing(), Vec::new()), &value);
+    assert!(matches!(&res, Err(Error::Io(err)) if err.kind() == ErrorKind::BrokenPipe), "{res:?}");
+    let res = postbag::serialize_full(Tee::new(Vec::new(), failing()), &value);
+    assert!(matches!(&res, Err(Error::Io(err)) if err.kind() == ErrorKind::BrokenPipe), "{res:?}");
+}
+
+#[test]
+fn error_handling_validate() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Message {
+        id: u32,
+        payload: Vec<u8>,
+        kind: Option<String>,
+    }
+
+    let msg = Message { id: 7, payload: vec![1, 2, 3], kind: Some("kind".to_string()) };
+    let full = postbag::to_full_vec(&msg).unwrap();
+    let slim = postbag::to_slim_vec(&msg).unwrap();
+
+    postbag::validate::<Full, Message>(&full).unwrap();
+    postbag::validate::<Slim, Message>(&slim).unwrap();
+
+    for len in 0..full.len() {
+        assert!(postbag::validate::<Full, Message>(&full[..len]).is_err(), "truncated to {len}");
+    }
+    for len in 0..slim.len() {
+        assert!(postbag::validate::<Slim, Message>(&slim[..len]).is_err(), "truncated to {len}");
+    }
+
+    let mut trailing = slim.clone();
+    trailing.push(0);
+    assert!(matches!(postbag::validate::<Slim, Message>(&trailing), Err(Error::TrailingData)));
+
+    assert!(matches!(postbag::validate::<Slim, bool>(&[2]), Err(Error::BadBool)));
+}
+
+#[test]
+fn error_handling_builder_limits() {
+    use postbag::DeserializerBuilder;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Big {
+        id: u32,
+        blob: Vec<u8>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Small {
+        id: u32,
+    }
+
+    // Nesting depth.
+    let nested = vec![vec![vec![1u8]]];
+    let data = postbag::to_full_vec(&nested).unwrap();
+    let builder = DeserializerBuilder::new().max_depth(3);
+    assert_eq!(builder.deserialize::<Full, _, Vec<Vec<Vec<u8>>>>(data.as_slice()).unwrap(), nested);
+    let builder = DeserializerBuilder::new().max_depth(2);
+    let res = builder.deserialize::<Full, _, Vec<Vec<Vec<u8>>>>(data.as_slice());
+    assert!(matches!(res, Err(Error::DepthLimitExceeded)), "{res:?}");
+
+    let value = Some(Big { id: 1, blob: vec![1] });
+    let data = postbag::to_slim_vec(&value).unwrap();
+    let builder = DeserializerBuilder::new().max_depth(1);
+    let res = builder.deserialize::<Slim, _, Option<Big>>(data.as_slice());
+    assert!(matches!(res, Err(Error::DepthLimitExceeded)), "{res:?}");
+
+    // String and byte array length.
+    let data = postbag::to_full_vec(&"a".repeat(1024)).unwrap();
+    let builder = DeserializerBuilder::new().max_alloc(1024);
+    assert_eq!(builder.deserialize::<Full, _, String>(data.as_slice()).unwrap().len(), 1024);
+    let builder = DeserializerBuilder::new().max_alloc(1023);
+    let res = builder.deserialize::<Full, _, String>(data.as_slice());
+    assert!(matches!(res, Err(Error::AllocLimitExceeded)), "{res:?}");
+
+    // Separate limits for strings and byte arrays.
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct File {
+        name: String,
+        #[serde(with = "postbag::serde_bytes")]
+        content: Vec<u8>,
+    }
+
+    let builder = DeserializerBuilder::new().max_string(16).max_bytes(1 << 20);
+    for (name, content, ok) in [
+        ("a".repeat(16), vec![0; 1 << 20], true),
+        ("a".repeat(17), vec![0; 16], false),
+        ("a".repeat(16), vec![0; (1 << 20) + 1], false),
+    ] {
+        let file = File { name, content };
+        let data = postbag::to_full_vec(&file).unwrap();
+        let res = builder.deserialize::<Full, _, File>(data.as_slice());
+        if ok {
+            assert_eq!(res.unwrap(), file);
+        } else {
+            assert!(matches!(res, Err(Error::AllocLimitExceeded)), "{res:?}");
+        }
+    }
+    let data = postbag::to_full_vec(&BTreeMap::from([("k".repeat(17), 1u8)])).unwrap();
+    let res = builder.deserialize::<Full, _, BTreeMap<String, u8>>(data.as_slice());
+    assert!(matches!(res, Err(Error::AllocLimitExceeded)), "{res:?}");
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Chars(#[serde(with = "postbag::char_len_string")] String);
+
+    let data = postbag::to_full_vec(&Chars("ä".repeat(8))).unwrap();
+    assert_eq!(builder.deserialize::<Full, _, Chars>(data.as_slice()).unwrap(), Chars("ä".repeat(8)));
+    let data = postbag::to_full_vec(&Chars("ä".repeat(9))).unwrap();
+    let res = builder.deserialize::<Full, _, Chars>(data.as_slice());
+    assert!(matches!(res, Err(Error::AllocLimitExceeded)), "{res:?}");
+
+    // Total bytes read, including skipped data.
+    let data = postbag::to_full_vec(&Big { id: 1, blob: vec![0; 1000] }).unwrap();
+    let builder = DeserializerBuilder::new().max_total(data.len() as u64);
+    assert_eq!(builder.deserialize::<Full, _, Small>(data.as_slice()).unwrap(), Small { id: 1 });
+    let builder = DeserializerBuilder::new().max_total(data.len() as u64 - 1);
+    let res = builder.deserialize::<Full, _, Small>(data.as_slice());
+    assert!(matches!(res, Err(Error::ReadLimitExceeded)), "{res:?}");
+
+    // Varints with redundant bytes.
+    let builder = DeserializerBuilder::new();
+    assert_eq!(builder.deserialize::<Slim, _, u32>([0x81, 0x00].as_slice()).unwrap(), 1);
+    let builder = DeserializerBuilder::new().canonical_varints(true);
+    let res = builder.deserialize::<Slim, _, u32>([0x81, 0x00].as_slice());
+    assert!(matches!(res, Err(Error::NonCanonical)), "{res:?}");
+
+    // Building a deserializer.
+    let mut deserializer = DeserializerBuilder::new().max_depth(4).build::<Slim, _>([1, 1, 7].as_slice());
+    assert_eq!(Small::deserialize(&mut deserializer).unwrap(), Small { id: 7 });
+}
+
+#[test]
+fn identifier_hook() {
+    use postbag::DeserializerBuilder;
+    use std::{
+        collections::BTreeSet,
+        sync::{Arc, Mutex},
+    };
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Kind {
+        Plain,
+        Tagged { tag: String },
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Current {
+        id: u32,
+        #[serde(rename = "_4")]
+        kind: Kind,
+        unused: Vec<u8>,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Reader {
+        #[serde(rename = "_4")]
+        kind: Kind,
+        id: u32,
+    }
+
+    // Nested identifiers are read after the enclosing struct in fast compile mode.
+    let seen = Arc::new(Mutex::new(BTreeSet::new()));
+    let hook = seen.clone();
+    let builder = DeserializerBuilder::new().on_identifier(move |ident| {
+        hook.lock().unwrap().insert(ident.to_string());
+    });
+
+    // Known and unknown identifiers, including numeric identifiers and variants.
+    let value = Current { id: 1, kind: Kind::Tagged { tag: "t".to_string() }, unused: vec![1, 2] };
+    let data = postbag::to_full_vec(&value).unwrap();
+    assert_eq!(builder.deserialize::<Full, _, Current>(data.as_slice()).unwrap(), value);
+    assert_eq!(*seen.lock().unwrap(), ["id", "_4", "Tagged", "tag", "unused"].map(String::from).into());
+
+    seen.lock().unwrap().clear();
+    let reader = builder.deserialize::<Full, _, Reader>(data.as_slice()).unwrap();
+    assert_eq!(reader, Reader { kind: Kind::Tagged { tag: "t".to_string() }, id: 1 });
+    assert_eq!(*seen.lock().unwrap(), ["id", "_4", "Tagged", "tag", "unused"].map(String::from).into());
+
+    // No identifiers without them.
+    seen.lock().unwrap().clear();
+    let data = postbag::to_slim_vec(&value).unwrap();
+    assert_eq!(builder.deserialize::<Slim, _, Current>(data.as_slice()).unwrap(), value);
+    assert!(seen.lock().unwrap().is_empty());
+}
+
+#[test]
+fn serializer_limits() {
+    use postbag::{DeserializerBuilder, SerializerBuilder};
+    use std::collections::BTreeMap;
+
+    /// Recursive value, for example built from untrusted input.
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Tree {
+        Leaf(u32),
+        Node(Vec<Tree>),
+        Map { children: BTreeMap<u8, Tree> },
+    }
+
+    fn deep(depth: usize) -> Tree {
+        (0..depth).fold(Tree::Leaf(1), |tree, _| Tree::Node(vec![tree]))
+    }
+
+    // Depth is counted like during deserialization.
+    let values = [
+        Tree::Leaf(7),
+        deep(5),
+        Tree::Map { children: [(1, deep(2)), (2, Tree::Leaf(3))].into() },
+        Tree::Node(vec![Tree::Map { children: [(0, deep(1))].into() }]),
+    ];
+    for value in &values {
+        let data = postbag::to_full_vec(value).unwrap();
+        let depth = (1..)
+            .find(|&max| {
+                DeserializerBuilder::new().max_depth(max).deserialize::<Full, _, Tree>(data.as_slice()).is_ok()
+            })
+            .unwrap();
+
+        let mut buf = Vec::new();
+        SerializerBuilder::new().max_depth(depth).serialize::<Full, _, _>(&mut buf, value).unwrap();
+        assert_eq!(buf, data);
+        let res = SerializerBuilder::new().max_depth(depth - 1).serialize::<Full, _, _>(Vec::new(), value);
+        assert!(matches!(res, Err(Error::DepthLimitExceeded)), "{res:?}");
+    }
+
+    // Deeply nested values fail instead of overflowing the stack.
+    let value = deep(10_000);
+    let builder = SerializerBuilder::new().max_depth(64);
+    let res = builder.serialize::<Slim, _, _>(Vec::new(), &value);
+    assert!(matches!(res, Err(Error::DepthLimitExceeded)), "{res:?}");
+
+    // Sorted map keys and values are serialized at their nesting depth.
+    #[derive(Serialize)]
+    struct Sorted(BTreeMap<u8, Vec<Vec<u8>>>);
+
+    let value = Sorted([(1, vec![vec![1]])].into());
+    let builder = SerializerBuilder::new().max_depth(2);
+    let res = builder.serialize::<postbag::cfg::Canonical, _, _>(Vec::new(), &value);
+    assert!(matches!(res, Err(Error::DepthLimitExceeded)), "{res:?}");
+    SerializerBuilder::new().max_depth(3).serialize::<postbag::cfg::Canonical, _, _>(Vec::new(), &value).unwrap();
+
+    // Building a serializer.
+    let mut serializer = SerializerBuilder::new().max_depth(1).build::<Slim, _>(Vec::new());
+    serializer.serialize_value(&[1u8, 2]).unwrap();
+    assert!(serializer.serialize_value(&[[1u8]]).is_err());
+}
+
+#[test]
+fn annotated_dump() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Kind {
+        A,
+        B,
+        C(i32),
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Record {
+        name: String,
+        tag: Option<u8>,
+        kinds: Vec<Kind>,
+    }
+
+    let value = Record { name: "abc".to_string(), tag: None, kinds: vec![Kind::B, Kind::C(-2)] };
+
+    let data = postbag::to_slim_vec(&value).unwrap();
+    print!("{}", postbag::hexdump(&data));
+    let dump = postbag::annotate::<Slim, Record>(&data);
+    print!("{dump}");
+    assert_eq!(
+        dump.lines().collect::<Vec<_>>(),
+        [
+            "     0..1      03                         struct fields=3",
+            "     1..2      09                         (framing)",
+            "     2..6      03 61 62 63                string \"abc\"",
+            "     6..7      00                         option none",
+            "     7..8      02                         seq len=2",
+            "     8..9      01                         variant 1",
+            "     9..10     02                         variant 2",
+            "    10..11     03                         i32 -2",
+        ]
+    );
+
+    let data = postbag::to_full_vec(&value).unwrap();
+    let dump = postbag::annotate::<Full, Record>(&data);
+    print!("{dump}");
+    assert!(dump.contains(r#"identifier "kinds""#));
+
+    // Errors are reported after the bytes read.
+    let dump = postbag::annotate::<Slim, Record>(&data[..5]);
+    print!("{dump}");
+    assert!(dump.ends_with("error: unexpected end of file\n"), "{dump}");
+
+    assert_eq!(
+        postbag::hexdump(&[0; 17]),
+        "\
+00000000  00 00 00 00 00 00 00 00  00 00 00 00 00 00 00 00  |................|
+00000010  00                                                |.|
+"
+    );
+}
+
+#[test]
+fn borrowed_map_keys() {
+    use std::ops::Range;
+
+    let map: BTreeMap<&str, u32> = [("alpha", 1), ("beta", 2)].into();
+    let within = |data: &[u8], key: &str| data.as_ptr_range().contains(&key.as_ptr());
+
+    let data = postbag::to_slim_vec(&map).unwrap();
+    let borrowed: BTreeMap<&str, u32> =
+        postbag::Deserializer::<_, Slim>::from_slice(&data).deserialize_value().unwrap();
+    assert_eq!(borrowed, map);
+    assert!(borrowed.keys().all(|key| within(&data, key)));
+
+    let data = postbag::to_full_vec(&map).unwrap();
+    let borrowed: BTreeMap<&str, u32> =
+        postbag::Deserializer::<_, Full>::from_slice(&data).deserialize_value().unwrap();
+    assert_eq!(borrowed, map);
+    assert!(borrowed.keys().all(|key| within(&data, key)));
+
+    // Data read through a reader cannot be borrowed.
+    let res = postbag::Deserializer::<_, Slim>::new(data.as_slice()).deserialize_value::<BTreeMap<&str, u32>>();
+    assert!(matches!(&res, Err(Error::Custom(msg)) if msg.contains("borrowed string")), "{res:?}");
+
+    // A string interrupted by the chunk framing of a skippable block is copied.
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Named {
+        name: String,
+        range: Range<u32>,
+    }
+    let named = Named { name: "x".repeat(100_000), range: 1..2 };
+    let data = postbag::to_slim_vec(&named).unwrap();
+    assert_eq!(postbag::from_slim_slice::<Named>(&data).unwrap(), named);
+}
+
+#[test]
+fn decode_diagnostic() {
+    use postbag::{DecodeDiagnostic, DeserializerBuilder};
+
+    let mut data = postbag::to_slim_vec(&(vec![7u8; 40], true, vec![9u8; 40])).unwrap();
+    data[41] = 2;
+
+    let builder = DeserializerBuilder::new();
+    let (err, diagnostic) =
+        builder.deserialize_with_diagnostic::<Slim, _, (Vec<u8>, bool, Vec<u8>)>(data.as_slice()).unwrap_err();
+    assert!(matches!(err, Error::BadBool));
+    assert_eq!(diagnostic.offset, 42);
+    assert_eq!(diagnostic.window_start, 26);
+    assert_eq!(diagnostic.window, data[26..58]);
+    assert_eq!(diagnostic.message, "invalid bool");
+    assert!(diagnostic.type_name.contains("bool"), "{}", diagnostic.type_name);
+
+    // Failure near the start and end of the data.
+    let (_, diagnostic) =
+        builder.deserialize_with_diagnostic::<Slim, _, String>([3, b'a'].as_slice()).unwrap_err();
+    assert_eq!((diagnostic.offset, diagnostic.window_start), (1, 0));
+    assert_eq!(diagnostic.window, [3, b'a']);
+
+    // Diagnostics are serializable.
+    loopback(diagnostic.clone());
+    let serialized = postbag::to_full_vec(&diagnostic).unwrap();
+    assert_eq!(postbag::from_full_slice::<DecodeDiagnostic>(&serialized).unwrap(), diagnostic);
+
+    let value = builder.deserialize_with_diagnostic::<Slim, _, String>([1, b'a'].as_slice()).unwrap();
+    assert_eq!(value, "a");
+}
+
+#[test]
+fn varint_boundary_tests() {
+    loopback(u32::MAX);
+
+    let deser = deserialize::<Slim, _, u32>([0xFF, 0xFF, 0xFF, 0xFF, 0x1F].as_slice());
+    assert!(matches!(deser, Err(Error::BadVarint)));
+}
+
+#[test]
+fn enum_index_overflow() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    pub enum E {
+        A,
+        B(u8),
+    }
+
+    // Largest valid index, unknown to the enum.
+    let res = deserialize::<Slim, _, E>([0xFF, 0xFF, 0xFF, 0xFF, 0x0F].as_slice());
+    assert!(!matches!(res, Err(Error::BadEnum) | Err(Error::BadVarint)), "{res:?}");
+
+    // 5-byte varint exceeding u32::MAX.
+    let res = deserialize::<Slim, _, E>([0x80, 0x80, 0x80, 0x80, 0x10].as_slice());
+    assert!(matches!(res, Err(Error::BadEnum)), "{res:?}");
+    let res = deserialize::<Slim, _, E>([0xFF, 0xFF, 0xFF, 0xFF, 0x1F, 0x00].as_slice());
+    assert!(matches!(res, Err(Error::BadEnum)), "{res:?}");
+}
+
+#[test]
+fn varint_boundary_tests_128() {
+    loopback(u128::MAX);
+    loopback(u128::MAX - 1);
+    loopback(i128::MAX);
+    loopback(i128::MIN);
+    loopback(i128::MIN + 1);
+    loopback(-1i128);
+
+    // u128::MAX: 18 bytes with 7 bits each, followed by the top 2 bits.
+    let mut max = vec![0xFF; 18];
+    max.push(0x03);
+    assert_eq!(postbag::to_slim_vec(&u128::MAX).unwrap(), max);
+    assert_eq!(deserialize::<Slim, _, u128>(max.as_slice()).unwrap(), u128::MAX);
+    // Zig-zag encoding maps i128::MIN to u128::MAX.
+    assert_eq!(deserialize::<Slim, _, i128>(max.as_slice()).unwrap(), i128::MIN);
+
+    // u128::MAX + 1 sets a bit beyond 128 bits in the last byte.
+    let mut overflow = vec![0xFF; 18];
+    overflow.push(0x04);
+    assert!(matches!(deserialize::<Slim, _, u128>(overflow.as_slice()), Err(Error::BadVarint)));
+    assert!(matches!(deserialize::<Slim, _, i128>(overflow.as_slice()), Err(Error::BadVarint)));
+
+    // Over-long encoding with continuation bit set in the 19th byte.
+    let mut overlong = vec![0x80; 19];
+    overlong.push(0x00);
+    assert!(matches!(deserialize::<Slim, _, u128>(overlong.as_slice()), Err(Error::BadVarint)));
+    assert!(matches!(deserialize::<Slim, _, i128>(overlong.as_slice()), Err(Error::BadVarint)));
+}
+
+// =============================================================================
+// Fixed int encoding
+// =============================================================================
+
+#[test]
+fn fixed_int() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    pub struct DefinitelyLE {
+        #[serde(with = "postbag::fixint")]
+        x: u16,
+    }
+
+    loopback(DefinitelyLE { x: 0xABCD });
+}
+
+#[test]
+fn fixed_int_checked() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    pub struct Narrow {
+        #[serde(with = "postbag::fixint::checked")]
+        x: u16,
+        #[serde(with = "postbag::fixint::checked")]
+        y: i64,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    pub struct Wide {
+        #[serde(with = "postbag::fixint::checked")]
+        x: u32,
+        #[serde(with = "postbag::fixint::checked")]
+        y: i64,
+    }
+
+    loopback(Narrow { x: 0xABCD, y: -2 });
+    loopback(Wide { x: 0x1234_5678, y: i64::MIN });
+
+    let data = postbag::to_slim_vec(&Narrow { x: 0xABCD, y: -2 }).unwrap();
+    assert_eq!(data[2..5], [2, 0xCD, 0xAB]);
+    assert_eq!(data[5..], [[8].as_slice(), &(-2i64).to_le_bytes()].concat());
+
+    // Width changed.
+    let res = postbag::from_slim_slice::<Wide>(&data);
+    assert!(matches!(res, Err(Error::BadFixint { expected: 4, found: 2 })), "{res:?}");
+    let data = postbag::to_full_vec(&Wide { x: 1, y: 2 }).unwrap();
+    let res = postbag::from_full_slice::<Narrow>(&data);
+    assert!(matches!(res, Err(Error::BadFixint { expected: 2, found: 4 })), "{res:?}");
+}
+
+/// Records the length passed to `deserialize_tuple_struct` and fails.
+struct TupleStructLen(Option<usize>);
+
+impl<'de> Deserializer<'de> for &mut TupleStructLen {
+    type Error = serde::de::value::Error;
+
+    fn deserialize_any<V: serde::de::Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(serde::de::Error::custom("unsupported"))
+    }
+
+    fn deserialize_tuple_struct<V: serde::de::Visitor<'de>>(
+        self, _name: &'static str, len: usize, _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.0 = Some(len);
+        Err(serde::de::Error::custom("recorded"))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option
+        unit unit_struct newtype_struct seq tuple map struct enum identifier ignored_any
+    }
+}
+
+#[test]
+fn helper_tuple_struct_len() {
+    use postbag::{BoundedString, BoundedVec};
+
+    /// Length passed to `deserialize_tuple_struct` by `deserialize`.
+    fn len<T: Debug>(
+        deserialize: impl FnOnce(&mut TupleStructLen) -> Result<T, serde::de::value::Error>,
+    ) -> Option<usize> {
+        let mut deserializer = TupleStructLen(None);
+        deserialize(&mut deserializer).unwrap_err();
+        deserializer.0
+    }
+
+    // The element count of the serialized tuple struct, independent of width and bound.
+    assert_eq!(len(|d| postbag::fixint::checked::deserialize::<_, u64>(d)), Some(2));
+    assert_eq!(len(|d| BoundedVec::<u8, 100>::deserialize(d)), Some(1));
+    assert_eq!(len(|d| BoundedString::<{ usize::MAX }>::deserialize(d)), Some(1));
+
+    let data = postbag::to_slim_vec("abc").unwrap();
+    let res = postbag::from_slim_slice::<BoundedString<{ usize::MAX }>>(&data);
+    assert_eq!(res.unwrap().into_inner(), "abc");
+    let res = postbag::from_slim_slice::<BoundedString<2>>(&data);
+    assert!(matches!(res, Err(Error::BadLen)), "{res:?}");
+}
+
+#[test]
+fn compact_float() {
+    #[derive(Serialize, Deserialize, Debug)]
+    struct Compact(#[serde(with = "postbag::compact_float")] f64);
+
+    let integer_max = 2f64.powi(53);
+    for (value, integer) in [
+        (0.0, true),
+        (1.0, true),
+        (-3.0, true),
+        (1e15, true),
+        (integer_max, true),
+        (-integer_max, true),
+        (0.5, false),
+        (std::f64::consts::PI, false),
+        (-0.0, false),
+        (integer_max * 2.0, false),
+        (f64::INFINITY, false),
+        (f64::NAN, false),
+    ] {
+        for data in
+            [postbag::to_full_vec(&Compact(value)).unwrap(), postbag::to_slim_vec(&Compact(value)).unwrap()]
+        {
+            let plain = postbag::to_slim_vec(&value).unwrap();
+            assert_eq!(data[0] == postbag::compact_float::INTEGER, integer, "{value}");
+            assert!(data.len() <= plain.len() + 1, "{value}");
+            if integer && value.abs() < 1e6 {
+                assert!(data.len() < plain.len(), "{value}");
+            }
+        }
+
+        let back =
+            postbag::from_full_slice::<Compact>(&postbag::to_full_vec(&Compact(value)).unwrap()).unwrap().0;
+        assert_eq!(back.to_bits(), value.to_bits(), "{value}");
+        let back =
+            postbag::from_slim_slice::<Compact>(&postbag::to_slim_vec(&Compact(value)).unwrap()).unwrap().0;
+        assert_eq!(back.to_bits(), value.to_bits(), "{value}");
+    }
+    assert_eq!(postbag::to_slim_vec(&Compact(-3.0)).unwrap(), [0, 5]);
+
+    // Integers beyond the exactly representable range and unknown tags are rejected.
+    let data = postbag::to_slim_vec(&(0u8, 1i64 << 54)).unwrap();
+    assert!(postbag::from_slim_slice::<Compact>(&data).is_err());
+    assert!(postbag::from_slim_slice::<Compact>(&[2, 0]).is_err());
+}
+
+#[test]
+fn fixed_char() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    pub struct DefinitelyFixed {
+        #[serde(with = "postbag::fixchar")]
+        c: char,
+    }
+
+    for c in ['\0', 'a', 'ß', '€', '🦀', char::MAX] {
+        loopback(DefinitelyFixed { c });
+        assert_eq!(postbag::to_slim_vec(&DefinitelyFixed { c }).unwrap()[2..], u32::from(c).to_le_bytes());
+    }
+
+    // Surrogates are not valid chars.
+    let res = postbag::from_slim_slice::<DefinitelyFixed>(&[1, 4, 0x00, 0xd8, 0x00, 0x00]);
+    assert!(matches!(res, Err(Error::BadChar)), "{res:?}");
+    let res = postbag::from_slim_slice::<DefinitelyFixed>(&[1, 4, 0x00, 0x00, 0x11, 0x00]);
+    assert!(matches!(res, Err(Error::BadChar)), "{res:?}");
+}
+
+#[test]
+fn char_len_string() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Label {
+        #[serde(with = "postbag::char_len_string")]
+        text: String,
+        id: u8,
+    }
+
+    for text in ["", "abc", "héllo wörld", "🦀 crab", &"ü".repeat(1000)] {
+        loopback(Label { text: text.to_string(), id: 7 });
+    }
+
+    // Char count followed by UTF-8 bytes.
+    let serialized = postbag::to_slim_vec(&Label { text: "hé🦀".to_string(), id: 1 }).unwrap();
+    assert_eq!(serialized, [2, 9, 3, b'h', 0xc3, 0xa9, 0xf0, 0x9f, 0xa6, 0x80, 1]);
+
+    // Invalid start byte, invalid continuation byte and overlong encoding.
+    for bytes in [&[1, 0x80][..], &[1, 0xc3, 0x41], &[1, 0xe0, 0x80, 0x80]] {
+        let mut data = vec![2, bytes.len() as u8 + 1];
+        data.extend_from_slice(bytes);
+        data.push(1);
+        let res = postbag::from_slim_slice::<Label>(&data);
+        assert!(matches!(res, Err(Error::BadString)), "{res:?}");
+    }
+
+    // Truncated char.
+    let res = postbag::from_slim_slice::<Label>(&[2, 2, 1, 0xc3]);
+    assert!(matches!(res, Err(Error::EndOfBlock)), "{res:?}");
+}
+
+#[test]
+fn shared_strings() {
+    use std::{rc::Rc, sync::Arc};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    pub struct Shared {
+        #[serde(with = "postbag::shared")]
+        a: Arc<str>,
+        #[serde(with = "postbag::shared")]
+        b: Rc<str>,
+        #[serde(with = "postbag::shared")]
+        c: Arc<[u8]>,
+        #[serde(with = "postbag::shared")]
+        d: Rc<[u8]>,
+    }
+
+    #[derive(Serialize)]
+    pub struct Plain<'a> {
+        a: &'a str,
+        b: String,
+        #[serde(serialize_with = "serialize_bytes")]
+        c: &'a [u8],
+        #[serde(serialize_with = "serialize_bytes")]
+        d: &'a [u8],
+    }
+
+    fn serialize_bytes<S: Serializer>(v: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(v)
+    }
+
+    let long = "x".repeat(100_000);
+    for s in ["", "key", long.as_str()] {
+        let value = Shared { a: s.into(), b: s.into(), c: s.as_bytes().into(), d: s.as_bytes().into() };
+        loopback(value);
+
+        let shared = Shared { a: s.into(), b: s.into(), c: s.as_bytes().into(), d: s.as_bytes().into() };
+        let plain = Plain { a: s, b: s.to_string(), c: s.as_bytes(), d: s.as_bytes() };
+        assert_eq!(postbag::to_full_vec(&shared).unwrap(), postbag::to_full_vec(&plain).unwrap());
+        assert_eq!(postbag::to_slim_vec(&shared).unwrap(), postbag::to_slim_vec(&plain).unwrap());
+
+        // With the serde `rc` feature shared strings serialize like `&str`.
+        let arc: Arc<str> = s.into();
+        let rc: Rc<str> = s.into();
+        assert_eq!(postbag::to_slim_vec(&arc).unwrap(), postbag::to_slim_vec(s).unwrap());
+        assert_eq!(postbag::to_slim_vec(&rc).unwrap(), postbag::to_slim_vec(s).unwrap());
+    }
+
+    let res = postbag::from_slim_slice::<Shared>(&[4, 3, 1, 0xff, 0, 0, 0]);
+    assert!(matches!(res, Err(Error::BadString)), "{res:?}");
+}
+
+#[test]
+fn padded_varint() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    pub struct Padded {
+        #[serde(with = "postbag::PaddedVarint::<4>")]
+        a: u32,
+        #[serde(with = "postbag::PaddedVarint::<3>")]
+        b: i16,
+        #[serde(with = "postbag::PaddedVarint::<10>")]
+        c: i64,
+        #[serde(with = "postbag::PaddedVarint::<1>")]
+        d: u128,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    pub struct Unpadded {
+        a: u32,
+        b: i16,
+        c: i64,
+        d: u128,
+    }
+
+    for (a, b, c, d) in
+        [(0, 0, 0, 0), (1, -1, 1, 1), (200_000, i16::MIN, i64::MIN, u128::MAX), (u32::MAX, i16::MAX, i64::MAX, 7)]
+    {
+        let padded = Padded { a, b, c, d };
+        loopback(padded);
+
+        let padded = postbag::to_slim_vec(&Padded { a, b, c, d }).unwrap();
+        let unpadded = postbag::to_slim_vec(&Unpadded { a, b, c, d }).unwrap();
+        assert!(padded.len() >= 20, "{padded:x?}");
+        assert_eq!(postbag::from_slim_slice::<Unpadded>(&padded).unwrap(), Unpadded { a, b, c, d });
+        assert_eq!(postbag::from_slim_slice::<Padded>(&unpadded).unwrap(), Padded { a, b, c, d });
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    pub struct PaddedU32(#[serde(with = "postbag::PaddedVarint::<4>")] u32);
+
+    assert_eq!(postbag::to_slim_vec(&PaddedU32(1)).unwrap(), [0x81, 0x80, 0x80, 0x00]);
+    assert_eq!(postbag::to_slim_vec(&PaddedU32(300)).unwrap(), [0xac, 0x82, 0x80, 0x00]);
+    assert_eq!(postbag::to_slim_vec(&PaddedU32(u32::MAX)).unwrap(), [0xff, 0xff, 0xff, 0xff, 0x0f]);
+
+    // Out of range values are rejected.
+    assert!(postbag::from_slim_slice::<PaddedU32>(&[0xff, 0xff, 0xff, 0xff, 0x1f]).is_err());
+    assert!(postbag::from_slim_slice::<PaddedU32>(&[0xff, 0xff, 0xff, 0xff, 0xff]).is_err());
+
+    // Padding is not canonical.
+    let res = postbag::from_canonical_slice::<u32>(&[0x81, 0x80, 0x80, 0x00]);
+    assert!(matches!(res, Err(Error::NonCanonical)), "{res:?}");
+    let res = postbag::from_canonical_slice::<PaddedU32>(&[0x81, 0x80, 0x80, 0x00]);
+    assert!(matches!(res, Err(Error::NonCanonical)), "{res:?}");
+    assert_eq!(postbag::from_canonical_slice::<PaddedU32>(&[0x81, 0x01]).unwrap(), PaddedU32(129));
+    let res = postbag::to_canonical_vec(&PaddedU32(1));
+    assert!(matches!(res, Err(Error::NonCanonical)), "{res:?}");
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    pub struct Record {
+        #[serde(with = "postbag::PaddedVarint::<4>")]
+        x: u32,
+    }
+    let res = postbag::to_canonical_vec(&Record { x: 1 });
+    assert!(matches!(res, Err(Error::NonCanonical)), "{res:?}");
+    let res = postbag::from_canonical_slice::<Record>(&[1, 4, 0x81, 0x80, 0x80, 0x00]);
+    assert!(matches!(res, Err(Error::NonCanonical)), "{res:?}");
+    assert_eq!(postbag::from_canonical_slice::<Record>(&[1, 1, 0x01]).unwrap(), Record { x: 1 });
+
+    // Padding must not exceed the maximum varint length.
+    #[derive(Serialize, Debug)]
+    pub struct Excessive(#[serde(with = "postbag::PaddedVarint::<6>")] u32);
+    assert!(postbag::to_slim_vec(&Excessive(1)).is_err());
+}
+
+// =============================================================================
+// Bit-packed boolean sequences
+// =============================================================================
+
+#[test]
+fn bit_packed_bools() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    pub struct Bitmap {
+        #[serde(with = "postbag::bits")]
+        bits: Vec<bool>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    pub struct Flags(#[serde(with = "postbag::bits")] [bool; 9]);
+
+    for len in [0usize, 1, 7, 8, 9, 100] {
+        let bits: Vec<bool> = (0..len).map(|i| i % 3 == 0).collect();
+        loopback(Bitmap { bits: bits.clone() });
+
+        let serialized = postbag::to_slim_vec(&Bitmap { bits }).unwrap();
+        assert_eq!(serialized.len(), 2 + 1 + len.div_ceil(8), "length {len}");
+    }
+
+    loopback(Flags([true, false, false, true, true, false, true, false, true]));
+
+    // Length, followed by packed bits with trailing bits zeroed.
+    let serialized =
+        postbag::to_slim_vec(&Flags([true, false, false, true, true, false, true, false, true])).unwrap();
+    assert_eq!(serialized, [9, 0b0101_1001, 0b0000_0001]);
+
+    // Trailing bits are ignored when reading.
+    let bitmap: Bitmap = postbag::from_slim_slice(&[1, 2, 3, 0xff]).unwrap();
+    assert_eq!(bitmap.bits, [true, true, true]);
+
+    // Length must match for arrays.
+    let res = postbag::from_slim_slice::<Flags>(&[8, 0xff]);
+    assert!(res.is_err(), "{res:?}");
+}
+
+#[test]
+fn strided_sequence() {
+    use postbag::{PaddedVarint, StridedSlice, to_strided_vec};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+    struct Record {
+        #[serde(with = "postbag::fixint")]
+        key: u32,
+        #[serde(with = "PaddedVarint::<3>")]
+        value: u64,
+        flag: bool,
+    }
+
+    let records: Vec<Record> =
+        (0..1000).map(|i| Record { key: i * 3, value: u64::from(i) * 1000, flag: i % 2 == 0 }).collect();
+
+    for data in [to_strided_vec::<Slim, _>(&records).unwrap(), to_strided_vec::<Full, _>(&records).unwrap()] {
+        assert_eq!(data[..8], 1000u64.to_le_bytes());
+    }
+
+    let data = to_strided_vec::<Slim, _>(&records).unwrap();
+    let strided = StridedSlice::<Slim, Record>::new(&data).unwrap();
+    assert_eq!(strided.len(), 1000);
+    assert_eq!(strided.stride(), (data.len() - 8) / 1000);
+    assert_eq!(strided.get(500).unwrap().unwrap(), records[500]);
+    assert!(strided.get(1000).is_none());
+    assert_eq!(strided.iter().collect::<Result<Vec<_>, _>>().unwrap(), records);
+
+    // Binary search by key.
+    let mut lo = 0;
+    let mut hi = strided.len();
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        if strided.get(mid).unwrap().unwrap().key < 2001 { lo = mid + 1 } else { hi = mid }
+    }
+    assert_eq!(strided.get(lo).unwrap().unwrap().key, 2001);
+
+    // Empty sequence.
+    let data = to_strided_vec::<Slim, Record>(&[]).unwrap();
+    assert_eq!(data, [0; 8]);
+    let strided = StridedSlice::<Slim, Record>::new(&data).unwrap();
+    assert!(strided.is_empty());
+
+    // Elements of different sizes.
+    let res = to_strided_vec::<Slim, _>(&[1u32, 1000]);
+    assert!(matches!(res, Err(Error::NonUniformStride)), "{res:?}");
+    let res = to_strided_vec::<Slim, _>(&["a", "b", "cd"]);
+    assert!(matches!(res, Err(Error::NonUniformStride)), "{res:?}");
+
+    // Data not divisible into elements.
+    let mut data = to_strided_vec::<Slim, _>(&[1u8, 2, 3]).unwrap();
+    data.push(4);
+    let res = StridedSlice::<Slim, u8>::new(&data);
+    assert!(matches!(res, Err(Error::NonUniformStride)), "{res:?}");
+    let res = StridedSlice::<Slim, u8>::new(&data[..5]);
+    assert!(matches!(res, Err(Error::Io(_))), "{res:?}");
+}
+
+#[test]
+fn raw_value() {
+    use postbag::{Raw, from_full_slice, to_full_vec};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+    struct Payload {
+        id: u32,
+        data: Vec<String>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Envelope {
+        to: String,
+        payload: Raw<Payload>,
+        seq: u64,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Plain {
+        to: String,
+        payload: Payload,
+        seq: u64,
+    }
+
+    fn check<CFG: Cfg>() {
+        let payload = Payload { id: 7, data: vec!["a".to_string(), "bc".to_string()] };
+        let plain = Plain { to: "node".to_string(), payload: payload.clone(), seq: 1000 };
+        let envelope =
+            Envelope { to: "node".to_string(), payload: Raw::new::<CFG>(&payload).unwrap(), seq: 1000 };
+
+        // Raw bytes are spliced into the output as if the value was serialized directly.
+        let mut plain_data = Vec::new();
+        serialize::<CFG, _, _>(&mut plain_data, &plain).unwrap();
+        let mut envelope_data = Vec::new();
+        serialize::<CFG, _, _>(&mut envelope_data, &envelope).unwrap();
+        assert_eq!(plain_data, envelope_data);
+
+        // The bytes of the value are captured during deserialization.
+        let deserialized: Envelope = deserialize::<CFG, _, _>(envelope_data.as_slice()).unwrap();
+        assert_eq!(deserialized, envelope);
+        assert_eq!(deserialized.payload.value::<CFG>().unwrap(), payload);
+
+        loopback_with_cfg::<_, CFG>(&envelope);
+        loopback_with_cfg::<_, CFG>(&vec![envelope.payload.clone(), envelope.payload]);
+    }
+
+    check::<Full>();
+    check::<Slim>();
+
+    // Unknown fields skipped within the raw value are captured.
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct PayloadV2 {
+        id: u32,
+        extra: String,
+        data: Vec<String>,
+    }
+
+    let v2 = PayloadV2 { id: 1, extra: "new".to_string(), data: vec!["x".to_string()] };
+    let data = to_full_vec(&(v2, 5u8)).unwrap();
+    let (raw, tail): (Raw<Payload>, u8) = from_full_slice(&data).unwrap();
+    assert_eq!(tail, 5);
+    assert_eq!(raw.as_bytes(), &data[..data.len() - 1]);
+    assert_eq!(raw.value::<Full>().unwrap(), Payload { id: 1, data: vec!["x".to_string()] });
+
+    // Invalid value.
+    let res: Result<(Raw<Payload>, u8), _> = from_full_slice(&[1, 2, 3]);
+    assert!(res.is_err());
+}
+
+#[test]
+fn bounded_collections() {
+    use postbag::{BoundedString, BoundedVec, from_full_slice, from_slim_slice, to_full_vec, to_slim_vec};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Bounded {
+        name: BoundedString<8>,
+        values: BoundedVec<u32, 4>,
+        nested: BoundedVec<BoundedString<2>, 2>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Unbounded {
+        name: String,
+        values: Vec<u32>,
+        nested: Vec<String>,
+    }
+
+    let bounded = Bounded {
+        name: "12345678".into(),
+        values: vec![1, 2, 3, 4].into(),
+        nested: vec!["ab".into(), "c".into()].into(),
+    };
+    loopback_with_cfg::<_, Full>(&bounded);
+    loopback_with_cfg::<_, Slim>(&bounded);
+
+    // Encoded like the unbounded types.
+    let unbounded =
+        Unbounded { name: "12345678".into(), values: vec![1, 2, 3, 4], nested: vec!["ab".into(), "c".into()] };
+    assert_eq!(to_full_vec(&bounded).unwrap(), to_full_vec(&unbounded).unwrap());
+    assert_eq!(to_slim_vec(&bounded).unwrap(), to_slim_vec(&unbounded).unwrap());
+
+    // Lengths exceeding the bound are rejected.
+    for unbounded in [
+        Unbounded { name: "123456789".into(), values: vec![], nested: vec![] },
+        Unbounded { name: String::new(), values: vec![1, 2, 3, 4, 5], nested: vec![] },
+        Unbounded { name: String::new(), values: vec![], nested: vec!["a".into(), "b".into(), "c".into()] },
+        Unbounded { name: String::new(), values: vec![], nested: vec!["abc".into()] },
+    ] {
+        let res: Result<Bounded, _> = from_full_slice(&to_full_vec(&unbounded).unwrap());
+        assert!(matches!(res, Err(Error::BadLen)), "{res:?}");
+        let res: Result<Bounded, _> = from_slim_slice(&to_slim_vec(&unbounded).unwrap());
+        assert!(matches!(res, Err(Error::BadLen)), "{res:?}");
+    }
+
+    // Bogus length is rejected before reading.
+    let res: Result<BoundedVec<u8, 16>, _> = deserialize::<Slim, _, _>(&[0xff, 0xff, 0xff, 0xff, 0x0f][..]);
+    assert!(matches!(res, Err(Error::BadLen)), "{res:?}");
+    let res: Result<BoundedString<16>, _> = deserialize::<Slim, _, _>(&[17][..]);
+    assert!(matches!(res, Err(Error::BadLen)), "{res:?}");
+
+    // Values exceeding the bound are not serialized.
+    let res = to_slim_vec(&BoundedVec::<u8, 2>(vec![1, 2, 3]));
+    assert!(matches!(res, Err(Error::Custom(_))), "{res:?}");
+    let res = to_slim_vec(&BoundedString::<2>("abc".into()));
+    assert!(matches!(res, Err(Error::Custom(_))), "{res:?}");
+}
+
+#[test]
+fn serialization_flavors() {
+    use postbag::{
+        ser_flavors::{Cobs, Crc32, Flavor, Slice, StdVec, serialize_with_flavor},
+        to_full_vec, to_slim_vec,
+    };
+
+    let value = (1u8, "data".to_string(), vec![0u32, 300]);
+
+    let data = serialize_with_flavor::<Full, _, _>(&value, StdVec::new()).unwrap();
+    assert_eq!(data, to_full_vec(&value).unwrap());
+
+    let mut buf = [0xaa; 64];
+    let data = serialize_with_flavor::<Slim, _, _>(&value, Slice::new(&mut buf)).unwrap();
+    assert_eq!(data, to_slim_vec(&value).unwrap());
+
+    let mut buf = [0; 4];
+    let res = serialize_with_flavor::<Slim, _, _>(&value, Slice::new(&mut buf));
+    assert!(matches!(&res, Err(Error::Io(err)) if err.kind() == ErrorKind::WriteZero), "{res:?}");
+
+    // COBS encoding.
+    let cobs = |data: &[u8]| {
+        let mut flavor = Cobs::new(StdVec::new());
+        flavor.try_extend(data).unwrap();
+        flavor.finalize().unwrap()
+    };
+    assert_eq!(cobs(&[]), [0x01, 0x00]);
+    assert_eq!(cobs(&[0x00]), [0x01, 0x01, 0x00]);
+    assert_eq!(cobs(&[0x00, 0x00]), [0x01, 0x01, 0x01, 0x00]);
+    assert_eq!(cobs(&[0x11, 0x22, 0x00, 0x33]), [0x03, 0x11, 0x22, 0x02, 0x33, 0x00]);
+    let block: Vec<u8> = (1..=254).collect();
+    assert_eq!(cobs(&block), [&[0xff], &block[..], &[0x00]].concat());
+    let data = [&block[..], &[0x00]].concat();
+    assert_eq!(cobs(&data), [&[0xff], &block[..], &[0x01, 0x01, 0x00]].concat());
+    let data = [&block[..], &[0xff]].concat();
+    assert_eq!(cobs(&data), [&[0xff], &block[..], &[0x02, 0xff, 0x00]].concat());
+
+    // CRC-32 check value.
+    let mut flavor = Crc32::new(StdVec::new());
+    flavor.try_extend(b"12345").unwrap();
+    flavor.try_push(b'6').unwrap();
+    flavor.try_extend(b"789").unwrap();
+    assert_eq!(flavor.finalize().unwrap(), [&b"123456789"[..], &0xcbf4_3926u32.to_le_bytes()].concat());
+
+    // Stacked flavors.
+    let data = serialize_with_flavor::<Slim, _, _>(&value, Crc32::new(Cobs::new(StdVec::new()))).unwrap();
+    assert_eq!(data.iter().filter(|b| **b == 0).count(), 1);
+    assert_eq!(data.last(), Some(&0));
+}
+
+#[test]
+fn serde_bytes() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Seq(Vec<u8>);
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Blob(#[serde(with = "postbag::serde_bytes")] Vec<u8>);
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Blobs {
+        #[serde(with = "postbag::serde_bytes")]
+        boxed: Box<[u8]>,
+        #[serde(with = "postbag::serde_bytes")]
+        array: [u8; 4],
+        #[serde(with = "postbag::serde_bytes")]
+        some: Option<Vec<u8>>,
+        #[serde(with = "postbag::serde_bytes")]
+        none: Option<Vec<u8>>,
+        #[serde(with = "postbag::serde_bytes")]
+        cow: std::borrow::Cow<'static, [u8]>,
+    }
+
+    let data: Vec<u8> = (0..=255).cycle().take(1000).collect();
+    loopback(Blob(data.clone()));
+    loopback(Blobs {
+        boxed: data.clone().into_boxed_slice(),
+        array: [0, 1, 0xfe, 0xff],
+        some: Some(data.clone()),
+        none: None,
+        cow: data.clone().into(),
+    });
+
+    // Sequences of u8 and byte arrays are encoded identically.
+    for (seq, blob) in [
+        (to_vec::<Full>(&Seq(data.clone())), to_vec::<Full>(&Blob(data.clone()))),
+        (to_vec::<Slim>(&Seq(data.clone())), to_vec::<Slim>(&Blob(data.clone()))),
+    ] {
+        assert_eq!(seq.len(), blob.len());
+        assert_eq!(seq, blob);
+    }
+    assert_eq!(deserialize::<Slim, _, Blob>(to_vec::<Slim>(&Seq(data.clone())).as_slice()).unwrap().0, data);
+    assert_eq!(deserialize::<Slim, _, Seq>(to_vec::<Slim>(&Blob(data.clone())).as_slice()).unwrap().0, data);
+
+    // Array of wrong length.
+    let res = deserialize::<Slim, _, Blobs>(to_vec::<Slim>(&(vec![1u8], [0u8; 3])).as_slice());
+    assert!(matches!(res, Err(Error::Custom(_))), "{res:?}");
+
+    fn to_vec<CFG: Cfg>(value: &impl Serialize) -> Vec<u8> {
+        let mut data = Vec::new();
+        serialize::<CFG, _, _>(&mut data, value).unwrap();
+        data
+    }
+}
+
+#[test]
+fn one_byte_reader_multi_chunk_block() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Large {
+        a: String,
+        b: Vec<u8>,
+        c: u64,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Known {
+        c: u64,
+    }
+
+    // Fields spanning multiple chunks of their skippable blocks.
+    let large = Large { a: "x".repeat(200_000), b: (0..=255).cycle().take(150_000).collect(), c: u64::MAX };
+    loopback_with_cfg::<_, Full>(&large);
+    loopback_with_cfg::<_, Slim>(&large);
+
+    // Unknown fields spanning multiple chunks are skipped.
+    let mut data = Vec::new();
+    serialize::<Full, _, _>(&mut data, &large).unwrap();
+    let known: Known = deserialize::<Full, _, _>(OneByteReader(&data)).unwrap();
+    assert_eq!(known, Known { c: u64::MAX });
+
+    // Truncated data.
+    let res: Result<Large, _> = deserialize::<Full, _, _>(OneByteReader(&data[..data.len() - 1]));
+    assert!(matches!(&res, Err(Error::Io(err)) if err.kind() == ErrorKind::UnexpectedEof), "{res:?}");
+}
+
+#[cfg(feature = "rust_decimal")]
+#[test]
+fn decimal() {
+    use rust_decimal::Decimal;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Payment {
+        #[serde(with = "postbag::decimal")]
+        amount: Decimal,
+    }
+
+    for amount in [
+        Decimal::ZERO,
+        Decimal::new(12345, 2),
+        Decimal::new(-12345, 2),
+        Decimal::new(-1, 28),
+        Decimal::MAX,
+        Decimal::MIN,
+        Decimal::from_parts(0, 0, 0, true, 5),
+    ] {
+        loopback(Payment { amount });
+
+        // Scale and sign are preserved exactly.
+        let data = postbag::to_slim_vec(&Payment { amount }).unwrap();
+        let payment: Payment = postbag::from_slim_slice(&data).unwrap();
+        assert_eq!(payment.amount.serialize(), amount.serialize());
+    }
+
+    // Flags, followed by the mantissa.
+    let data = postbag::to_slim_vec(&Payment { amount: Decimal::new(-300, 1) }).unwrap();
+    assert_eq!(data, [1, 16, 0, 0, 1, 0x80, 0x2c, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+    // Invalid scale.
+    let mut data = data;
+    data[4] = 29;
+    let res = postbag::from_slim_slice::<Payment>(&data);
+    assert!(res.is_err(), "{res:?}");
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn datetime() {
+    use time::{OffsetDateTime, UtcOffset};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Trade {
+        #[serde(with = "postbag::datetime")]
+        executed: OffsetDateTime,
+    }
+
+    let offset = UtcOffset::from_hms(-5, -30, 0).unwrap();
+    for (nanos, offset) in [
+        (0, UtcOffset::UTC),
+        (1_700_000_000_123_456_789, UtcOffset::UTC),
+        (1_700_000_000_123_456_789, offset),
+        (-1, offset),
+        (-1_000_000_000_000_000_001, UtcOffset::from_hms(14, 0, 0).unwrap()),
+    ] {
+        let executed = OffsetDateTime::from_unix_timestamp_nanos(nanos).unwrap().to_offset(offset);
+        loopback(Trade { executed });
+
+        // Offset is preserved.
+        let data = postbag::to_slim_vec(&Trade { executed }).unwrap();
+        let trade: Trade = postbag::from_slim_slice(&data).unwrap();
+        assert_eq!(trade.executed.offset(), offset);
+        assert_eq!(trade.executed.unix_timestamp_nanos(), nanos);
+    }
+
+    // Nanoseconds and offset in seconds as zigzag varints.
+    let executed = OffsetDateTime::from_unix_timestamp_nanos(-1).unwrap().to_offset(offset);
+    let data = postbag::to_slim_vec(&Trade { executed }).unwrap();
+    assert_eq!(data, [1, 4, 1, 0xaf, 0xb5, 0x02]);
+
+    // Offset out of range.
+    let res = postbag::from_slim_slice::<Trade>(&[1, 4, 1, 0xc0, 0x9a, 0x0c]);
+    assert!(res.is_err(), "{res:?}");
+}
+
+// =============================================================================
+// Serde alias tests
+// =============================================================================
+
+/// Exposure.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+pub struct Exposure {
+    /// Gain level.
+    pub gain: u8,
+    /// Exposure time in microseconds.
+    #[serde(alias = "time_100usec")]
+    pub time_usec: u32,
+}
+
+#[test]
+fn serde_alias_loopback() {
+    loopback(Exposure { gain: 42, time_usec: 100_000 });
+    loopback(Exposure { gain: 0, time_usec: 0 });
+    loopback(Exposure { gain: 255, time_usec: u32::MAX });
+}
+
+#[test]
+fn serde_alias_compat() {
+    // Old struct uses the original field name that is now an alias.
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct ExposureOld {
+        gain: u8,
+        time_100usec: u32,
+    }
+
+    let old = ExposureOld { gain: 42, time_100usec: 100_000 };
+
+    // Serialize with old field name, deserialize with new struct that has alias.
+    // This only works in Full mode (field names are serialized).
+    let mut serialized = Vec::new();
+    serialize::<Full, _, _>(&mut serialized, &old).expect("serialization failed");
+    println!("old serialized: {serialized:02x?}");
+
+    let new: Exposure =
+        deserialize::<Full, _, _>(serialized.as_slice()).expect("deserialization with alias failed");
+    assert_eq!(new.gain, old.gain);
+    assert_eq!(new.time_usec, old.time_100usec);
+}
+
+#[test]
+fn crc_prefixed() {
+    use postbag::{
+        crc_prefixed::{CHUNK_LEN, CrcReader, deserialize_crc_prefixed},
+        ser_flavors::{Crc32, StdVec, serialize_with_flavor},
+        to_full_vec, to_full_vec_crc_prefixed,
+    };
+    use std::io::Read;
+
+    let value: Vec<String> = (0..1000).map(|i| format!("element {i}")).collect();
+    let body = to_full_vec(&value).unwrap();
+    let data = to_full_vec_crc_prefixed(&value).unwrap();
+
+    // Length followed by chunks, each preceded by a checksum.
+    let chunks = body.len().div_ceil(CHUNK_LEN);
+    assert!(chunks > 2);
+    assert_eq!(data.len(), 8 + 4 * chunks + body.len());
+    assert_eq!(data[..8], (body.len() as u64).to_le_bytes());
+    assert_eq!(data[12..12 + CHUNK_LEN], body[..CHUNK_LEN]);
+    assert_eq!(data[data.len() - body.len() % CHUNK_LEN..], body[(chunks - 1) * CHUNK_LEN..]);
+
+    // The last checksum is the checksum of the Crc32 flavor.
+    let crc = serialize_with_flavor::<Full, _, _>(&value, Crc32::new(StdVec::new())).unwrap();
+    let last = data.len() - body.len() % CHUNK_LEN - 4;
+    assert_eq!(data[last..last + 4], crc[crc.len() - 4..]);
+
+    assert_eq!(deserialize_crc_prefixed::<Full, _, Vec<String>>(data.as_slice()).unwrap(), value);
+    assert_eq!(deserialize_crc_prefixed::<Full, _, Vec<String>>(OneByteReader(&data)).unwrap(), value);
+    assert_eq!(
+        deserialize_crc_prefixed::<Full, _, ()>(to_full_vec_crc_prefixed(&()).unwrap().as_slice()).unwrap(),
+        ()
+    );
+
+    // Corrupted string content in the last chunk.
+    let mut corrupted = data.clone();
+    let pos = data.len() - 2;
+    corrupted[pos] ^= 0x01;
+    let res = deserialize_crc_prefixed::<Full, _, Vec<String>>(corrupted.as_slice());
+    assert!(matches!(res, Err(Error::BadChecksum)), "{res:?}");
+
+    // Corruption in the first chunk fails before the remainder of the body is read.
+    let mut corrupted = data.clone();
+    corrupted[12] = 0xff;
+    let mut input = corrupted.as_slice();
+    let res = deserialize_crc_prefixed::<Full, _, Vec<String>>(&mut input);
+    assert!(matches!(res, Err(Error::BadChecksum)), "{res:?}");
+    assert_eq!(input.len(), data.len() - 12 - CHUNK_LEN);
+
+    // No data of a corrupted chunk is returned.
+    let mut reader = CrcReader::new(OneByteReader(&corrupted)).unwrap();
+    assert_eq!(reader.remaining(), body.len() as u64);
+    let err = reader.read(&mut [0; 16]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+    // Chunks are verified as they are read.
+    let mut corrupted = data.clone();
+    corrupted[12 + 4 + CHUNK_LEN + 4] ^= 0x01;
+    let mut reader = CrcReader::new(corrupted.as_slice()).unwrap();
+    let mut buf = vec![0; CHUNK_LEN];
+    reader.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, body[..CHUNK_LEN]);
+    assert_eq!(reader.remaining(), (body.len() - CHUNK_LEN) as u64);
+    let err = reader.read(&mut [0; 16]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+    // Truncated body.
+    let res = deserialize_crc_prefixed::<Full, _, Vec<String>>(&data[..data.len() - 1]);
+    assert!(matches!(&res, Err(Error::Io(err)) if err.kind() == ErrorKind::UnexpectedEof), "{res:?}");
+    let res = deserialize_crc_prefixed::<Full, _, Vec<String>>(&data[..4]);
+    assert!(matches!(&res, Err(Error::Io(err)) if err.kind() == ErrorKind::UnexpectedEof), "{res:?}");
+}
+
+#[test]
+fn varint_slice() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Fast {
+        #[serde(with = "postbag::varint_slice")]
+        unsigned: Vec<u64>,
+        #[serde(with = "postbag::varint_slice")]
+        signed: Box<[i32]>,
+        #[serde(with = "postbag::varint_slice")]
+        bytes: Vec<u8>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Generic {
+        unsigned: Vec<u64>,
+        signed: Box<[i32]>,
+        bytes: Vec<u8>,
+    }
+
+    fn check<CFG: Cfg>(len: usize) {
+        let unsigned: Vec<u64> =
+            (0..len as u64).map(|i| i.wrapping_mul(0x9e37_79b9_7f4a_7c15) >> (i % 64)).collect();
+        let signed: Box<[i32]> = (0..len as i32).map(|i| if i % 2 == 0 { -i } else { i << 16 }).collect();
+        let bytes: Vec<u8> = (0..len).map(|i| i as u8).collect();
+
+        let fast = Fast { unsigned: unsigned.clone(), signed: signed.clone(), bytes: bytes.clone() };
+        loopback_with_cfg::<_, CFG>(&fast);
+
+        // Identical to the serialization of a sequence.
+        let mut fast_data = Vec::new();
+        serialize::<CFG, _, _>(&mut fast_data, &fast).unwrap();
+        let mut generic_data = Vec::new();
+        serialize::<CFG, _, _>(&mut generic_data, &Generic { unsigned, signed, bytes }).unwrap();
+        assert_eq!(fast_data, generic_data, "length {len}");
+    }
+
+    // Including the length with special encoding and lengths exceeding the buffer.
+    for len in [0, 1, 124, 125, 126, 1000, 100_000] {
+        check::<Full>(len);
+        check::<Slim>(len);
+    }
+}
+
+#[test]
+fn slice_with_tail() {
+    use postbag::{from_full_slice_with_tail, from_slim_slice_with_tail, to_full_vec, to_slim_vec};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Message {
+        id: u32,
+        text: String,
+    }
+
+    let first = Message { id: 1, text: "first".to_string() };
+    let second = Message { id: 2, text: "second".to_string() };
+
+    let mut data = to_full_vec(&first).unwrap();
+    data.extend(to_full_vec(&second).unwrap());
+    let (value, tail) = from_full_slice_with_tail::<Message>(&data).unwrap();
+    assert_eq!(value, first);
+    let (value, tail) = from_full_slice_with_tail::<Message>(tail).unwrap();
+    assert_eq!(value, second);
+    assert!(tail.is_empty());
+
+    let mut data = to_slim_vec(&first).unwrap();
+    data.extend_from_slice(&0xcbf4_3926u32.to_le_bytes());
+    let (value, tail) = from_slim_slice_with_tail::<Message>(&data).unwrap();
+    assert_eq!(value, first);
+    assert_eq!(tail, 0xcbf4_3926u32.to_le_bytes());
+
+    // Truncated value.
+    let res = from_slim_slice_with_tail::<Message>(&data[..3]);
+    assert!(res.is_err(), "{res:?}");
+}
+
+#[cfg(feature = "crypto")]
+#[test]
+fn sealed() {
+    use postbag::crypto::{Aead, AeadError, from_sealed_slice, from_sealed_vec, to_sealed_vec};
+
+    /// Keystream cipher with a checksum as tag, for testing only.
+    struct TestCipher(u8);
+
+    impl TestCipher {
+        fn tag(&self, nonce: &[u8], data: &[u8]) -> [u8; 2] {
+            let sum =
+                nonce.iter().chain(data).fold(u16::from(self.0), |acc, b| acc.rotate_left(3) ^ u16::from(*b));
+            sum.to_le_bytes()
+        }
+
+        fn apply(&self, nonce: &[u8], data: &mut [u8]) {
+            for (i, b) in data.iter_mut().enumerate() {
+                *b ^= self.0.wrapping_add(nonce[i % nonce.len()]).wrapping_add(i as u8);
+            }
+        }
+    }
+
+    impl Aead for TestCipher {
+        fn seal_in_place(&self, nonce: &[u8], buffer: &mut Vec<u8>) -> Result<(), AeadError> {
+            let tag = self.tag(nonce, buffer);
+            self.apply(nonce, buffer);
+            buffer.extend(tag);
+            Ok(())
+        }
+
+        fn open_in_place(&self, nonce: &[u8], buffer: &mut Vec<u8>) -> Result<(), AeadError> {
+            let len = buffer.len().checked_sub(2).ok_or(AeadError)?;
+            let tag = buffer.split_off(len);
+            self.apply(nonce, buffer);
+            if self.tag(nonce, buffer) != *tag {
+                return Err(AeadError);
+            }
+            Ok(())
+        }
+    }
+
+    let value = (42u32, "secret".to_string(), vec![1u8, 2, 3]);
+    let cipher = TestCipher(0x5a);
+
+    let sealed = to_sealed_vec::<Full, _, _>(&value, &cipher, b"nonce").unwrap();
+    assert_eq!(sealed.len(), postbag::to_full_vec(&value).unwrap().len() + 2);
+    assert!(!sealed.windows(6).any(|w| w == b"secret"));
+
+    let opened: (u32, String, Vec<u8>) = from_sealed_slice::<Full, _, _>(&sealed, &cipher, b"nonce").unwrap();
+    assert_eq!(opened, value);
+    let opened: (u32, String, Vec<u8>) =
+        from_sealed_vec::<Full, _, _>(sealed.clone(), &cipher, b"nonce").unwrap();
+    assert_eq!(opened, value);
+
+    // Wrong nonce, wrong key and tampered data.
+    let res = from_sealed_slice::<Full, (u32, String, Vec<u8>), _>(&sealed, &cipher, b"other");
+    assert!(matches!(res, Err(Error::AuthFailed)), "{res:?}");
+    let res = from_sealed_slice::<Full, (u32, String, Vec<u8>), _>(&sealed, &TestCipher(0x5b), b"nonce");
+    assert!(matches!(res, Err(Error::AuthFailed)), "{res:?}");
+    let mut tampered = sealed.clone();
+    tampered[3] ^= 0x01;
+    let res = from_sealed_slice::<Full, (u32, String, Vec<u8>), _>(&tampered, &cipher, b"nonce");
+    assert!(matches!(res, Err(Error::AuthFailed)), "{res:?}");
+}
+
+#[test]
+fn seq_iter() {
+    use postbag::SeqIter;
+    use serde::de::{SeqAccess, Visitor};
+    use std::fmt;
+
+    /// Sums the first `N` elements of a sequence of strings, dropping the iterator early.
+    #[derive(Debug, PartialEq, Eq)]
+    struct PartialSum<const N: usize>(usize);
+
+    impl<'de, const N: usize> Deserialize<'de> for PartialSum<N> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_seq(PartialSumVisitor::<N>)
+        }
+    }
+
+    struct PartialSumVisitor<const N: usize>;
+
+    impl<'de, const N: usize> Visitor<'de> for PartialSumVisitor<N> {
+        type Value = PartialSum<N>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a sequence of strings")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<PartialSum<N>, A::Error> {
+            let mut sum = 0;
+            for value in SeqIter::<_, String>::new(&mut seq).take(N) {
+                sum += value?.len();
+            }
+            Ok(PartialSum(sum))
+        }
+    }
+
+    #[derive(Serialize)]
+    struct Data {
+        head: u32,
+        values: Vec<String>,
+        tail: String,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq, Eq)]
+    struct Partial<const N: usize> {
+        head: u32,
+        values: PartialSum<N>,
+        tail: String,
+    }
+
+    fn check<CFG: Cfg>(data: &Data) {
+        let mut bytes = Vec::new();
+        serialize::<CFG, _, _>(&mut bytes, data).unwrap();
+
+        let partial: Partial<2> = deserialize::<CFG, _, _>(bytes.as_slice()).unwrap();
+        assert_eq!(partial, Partial { head: 1, values: PartialSum(1), tail: "tail".to_string() });
+        let partial: Partial<100> = deserialize::<CFG, _, _>(OneByteReader(&bytes)).unwrap();
+        assert_eq!(partial, Partial { head: 1, values: PartialSum(45), tail: "tail".to_string() });
+    }
+
+    let data = Data { head: 1, values: (0..10).map(|i| "x".repeat(i)).collect(), tail: "tail".to_string() };
+    check::<Full>(&data);
+    check::<Slim>(&data);
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn versioned() {
+    use postbag::Versioned;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    pub struct Point {
+        x: u32,
+        y: u32,
+    }
+
+    #[derive(Versioned, Debug, PartialEq, Eq)]
+    #[postbag(version = 1)]
+    pub struct PointV1(Point);
+
+    #[derive(Versioned, Debug, PartialEq, Eq)]
+    #[postbag(version = 2)]
+    pub struct PointV2<T>(T);
+
+    loopback(PointV1(Point { x: 1, y: 2 }));
+    loopback(PointV2(Point { x: 3, y: 4 }));
+    loopback(vec![PointV2("a".to_string()), PointV2("b".to_string())]);
+
+    let data = postbag::to_slim_vec(&PointV2(Point { x: 3, y: 4 })).unwrap();
+    assert_eq!(data[0], 2);
+
+    let res = postbag::from_slim_slice::<PointV1>(&data);
+    assert!(matches!(res, Err(Error::BadVersion { expected: 1, found: 2 })), "{res:?}");
+    let data = postbag::to_full_vec(&PointV1(Point { x: 1, y: 2 })).unwrap();
+    let res = postbag::from_full_slice::<PointV2<Point>>(&data);
+    assert!(matches!(res, Err(Error::BadVersion { expected: 2, found: 1 })), "{res:?}");
+}
+
+#[test]
+fn seq_size_hint() {
+    use serde::de::{SeqAccess, Visitor};
+    use std::fmt;
+
+    /// Records the size hint of a sequence without reading its elements.
+    #[derive(Debug)]
+    struct SizeHint(Option<usize>);
+
+    impl<'de> Deserialize<'de> for SizeHint {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_seq(SizeHintVisitor)
+        }
+    }
+
+    struct SizeHintVisitor;
+
+    impl<'de> Visitor<'de> for SizeHintVisitor {
+        type Value = SizeHint;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a sequence")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<SizeHint, A::Error> {
+            Ok(SizeHint(seq.size_hint()))
+        }
+    }
+
+    // Honest length.
+    let data = postbag::to_slim_vec(&vec![1000u32; 10]).unwrap();
+    let hint: SizeHint = postbag::from_slim_slice(&data).unwrap();
+    assert_eq!(hint.0, Some(10));
+
+    // Declared length exceeding the remaining input.
+    let data = [0x80, 0x80, 0x80, 0x80, 0x0f, 1, 2, 3];
+    let hint: SizeHint = postbag::from_slim_slice(&data).unwrap();
+    assert_eq!(hint.0, Some(3));
+
+    // Remaining input unknown.
+    let hint: SizeHint = postbag::deserialize_slim(data.as_slice()).unwrap();
+    assert_eq!(hint.0, Some(0xf000_0000));
+
+    // A lying length fails at the end of the input.
+    let res = postbag::from_slim_slice::<Vec<u8>>(&data);
+    assert!(res.is_err(), "{res:?}");
+    loopback(vec![7u8; 1000]);
+}
+
+#[test]
+fn capturing() {
+    use postbag::{
+        cfg::{Full, Slim},
+        deserialize_capturing,
+    };
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    pub struct Record {
+        id: u32,
+        blob: Vec<u8>,
+        tags: Vec<String>,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq, Eq)]
+    pub struct RecordId {
+        id: u32,
+    }
+
+    let record = Record { id: 5, blob: vec![9; 100_000], tags: vec!["a".to_string(), "b".to_string()] };
+    let full = postbag::to_full_vec(&record).unwrap();
+    let slim = postbag::to_slim_vec(&record).unwrap();
+
+    // Captured bytes re-serialize identically.
+    let (value, raw) = deserialize_capturing::<Full, _, Record>(full.as_slice()).unwrap();
+    assert_eq!(value, record);
+    assert_eq!(raw, full);
+    assert_eq!(postbag::to_full_vec(&value).unwrap(), raw);
+
+    // Skipped fields are captured.
+    let (value, raw) = deserialize_capturing::<Full, _, RecordId>(full.as_slice()).unwrap();
+    assert_eq!(value, RecordId { id: 5 });
+    assert_eq!(raw, full);
+    let (value, raw) = deserialize_capturing::<Slim, _, RecordId>(slim.as_slice()).unwrap();
+    assert_eq!(value, RecordId { id: 5 });
+    assert_eq!(raw, slim);
+
+    // Only the bytes of the value are captured.
+    let mut two = slim.clone();
+    two.extend_from_slice(&slim);
+    let mut reader = two.as_slice();
+    let (_, raw) = deserialize_capturing::<Slim, _, Record>(&mut reader).unwrap();
+    assert_eq!(raw, slim);
+    assert_eq!(reader, slim);
+
+    let res = deserialize_capturing::<Slim, _, Record>(&slim[..slim.len() - 1]);
+    assert!(res.is_err(), "{res:?}");
+}
+
+#[test]
+fn chained() {
+    use postbag::{
+        cfg::{Full, Slim},
+        deserialize_chained,
+    };
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+    pub struct Inner {
+        a: u64,
+        b: String,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+    pub struct Outer {
+        x: u32,
+        inner: Inner,
+        extra: Vec<i64>,
+        y: i128,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq, Eq)]
+    pub struct Reduced {
+        x: u32,
+        y: i128,
+    }
+
+    let value = Outer {
+        x: 300,
+        inner: Inner { a: u64::MAX, b: "hello".to_string() },
+        extra: vec![-1, 1 << 40, i64::MIN],
+        y: -(1 << 100),
+    };
+
+    // Split at every position, including within varints and skippable blocks.
+    let full = postbag::to_full_vec(&value).unwrap();
+    let slim = postbag::to_slim_vec(&value).unwrap();
+    for at in 0..=full.len() {
+        let (head, body) = full.split_at(at);
+        assert_eq!(deserialize_chained::<Full, _, Outer>([head, body]).unwrap(), value);
+        let reduced: Reduced = deserialize_chained::<Full, _, _>([head, body]).unwrap();
+        assert_eq!(reduced, Reduced { x: value.x, y: value.y });
+    }
+    for at in 0..=slim.len() {
+        let (head, body) = slim.split_at(at);
+        assert_eq!(deserialize_chained::<Slim, _, Outer>([head, body]).unwrap(), value);
+    }
+
+    // One reader per byte.
+    let value2: Outer = deserialize_chained::<Full, _, _>(full.chunks(1)).unwrap();
+    assert_eq!(value2, value);
+
+    // Truncated input.
+    let res = deserialize_chained::<Full, _, Outer>([&full[..3], &full[3..full.len() - 1]]);
+    assert!(matches!(res, Err(Error::Io(_))), "{res:?}");
+}
+
+#[test]
+fn ring_writer() {
+    use postbag::{RingWriter, cfg::Full};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    pub struct Event {
+        #[serde(with = "postbag::fixint")]
+        seq: u32,
+        level: u8,
+    }
+
+    const N: u32 = 50;
+    const M: usize = 7;
+
+    // Fixed-size messages followed by their 4 byte length.
+    let frame = postbag::to_full_vec(&Event { seq: 0, level: 1 }).unwrap().len() + 4;
+    for capacity in [M * frame, M * frame + 1, (M + 1) * frame - 1] {
+        let mut ring = RingWriter::new(capacity);
+        for seq in 0..N {
+            ring.write_framed::<Full, _>(&Event { seq, level: 1 }).unwrap();
+        }
+        assert_eq!(ring.len(), capacity);
+
+        let events: Vec<Event> = ring.read_framed::<Full, _>().unwrap();
+        let seqs: Vec<u32> = events.iter().map(|event| event.seq).collect();
+        assert_eq!(seqs, (N - M as u32..N).collect::<Vec<_>>(), "capacity {capacity}");
+    }
+
+    // Not wrapped yet.
+    let mut ring = RingWriter::new(1024);
+    assert!(ring.read_framed::<Full, String>().unwrap().is_empty());
+    ring.write_framed::<Full, _>("a").unwrap();
+    ring.write_framed::<Full, _>("bc").unwrap();
+    assert_eq!(ring.read_framed::<Full, String>().unwrap(), ["a", "bc"]);
+
+    // Messages of varying size.
+    let mut ring = RingWriter::new(64);
+    for i in 0..30 {
+        ring.write_framed::<Full, _>(&"x".repeat(i % 7)).unwrap();
+    }
+    let messages: Vec<String> = ring.read_framed::<Full, _>().unwrap();
+    let expected: Vec<String> = (0..30).map(|i| "x".repeat(i % 7)).collect();
+    assert!(expected.ends_with(&messages), "{messages:?}");
+    let complete = messages.iter().map(|m| m.len() + 1 + 4).sum::<usize>();
+    let previous = expected[expected.len() - messages.len() - 1].len() + 1 + 4;
+    assert!(complete <= 64 && complete + previous > 64);
+
+    // Oversized message.
+    let mut ring = RingWriter::new(8);
+    ring.write_framed::<Full, _>(&"too long to fit").unwrap();
+    assert!(ring.read_framed::<Full, String>().unwrap().is_empty());
+}
+
+/// Types of a crate not implementing `Serialize` and `Deserialize`.
+mod foreign {
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Point {
+        pub x: i32,
+        pub y: i32,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum Shape {
+        Empty,
+        Dot(Point),
+        Line(Point, Point),
+        Rect { corner: Point, width: u32, height: u32 },
+    }
+}
+
+#[test]
+fn remote_derive() {
+    use foreign::{Point, Shape};
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(remote = "Point")]
+    struct PointDef {
+        x: i32,
+        y: i32,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(remote = "Shape")]
+    enum ShapeDef {
+        Empty,
+        Dot(#[serde(with = "PointDef")] Point),
+        Line(#[serde(with = "PointDef")] Point, #[serde(with = "PointDef")] Point),
+        Rect {
+            #[serde(with = "PointDef")]
+            corner: Point,
+            width: u32,
+            height: u32,
+        },
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    pub struct Drawing {
+        #[serde(with = "PointDef")]
+        origin: Point,
+        #[serde(with = "ShapeDef")]
+        shape: Shape,
+        name: String,
+    }
+
+    /// Remote type at the top level.
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    pub struct TopLevel(#[serde(with = "ShapeDef")] Shape);
+
+    let shapes = [
+        Shape::Empty,
+        Shape::Dot(Point { x: -1, y: 1 }),
+        Shape::Line(Point { x: 0, y: 0 }, Point { x: 3, y: -4 }),
+        Shape::Rect { corner: Point { x: 5, y: 6 }, width: 7, height: 8 },
+    ];
+    for shape in shapes {
+        loopback(TopLevel(shape.clone()));
+        loopback(Drawing { origin: Point { x: 10, y: -20 }, shape, name: "sketch".to_string() });
+    }
+
+    // The remote definition serializes like a local type of the same shape.
+    #[derive(Serialize)]
+    pub struct LocalPoint {
+        x: i32,
+        y: i32,
+    }
+
+    #[derive(Serialize)]
+    pub enum LocalShape {
+        #[allow(dead_code)]
+        Empty,
+        Dot(LocalPoint),
+    }
+
+    let remote = TopLevel(Shape::Dot(Point { x: 1, y: 2 }));
+    let local = LocalShape::Dot(LocalPoint { x: 1, y: 2 });
+    assert_eq!(postbag::to_full_vec(&remote).unwrap(), postbag::to_full_vec(&local).unwrap());
+}
+
+#[test]
+fn transparent() {
+    use std::{collections::BTreeMap, marker::PhantomData};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+    pub struct Inner {
+        a: u32,
+        b: String,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, PartialOrd, Ord)]
+    pub enum Kind {
+        A,
+        B(u16),
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, PartialOrd, Ord)]
+    #[serde(transparent)]
+    pub struct Wrap<T>(T);
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+    #[serde(transparent)]
+    pub struct Named<T> {
+        value: T,
+        #[serde(skip)]
+        _marker: PhantomData<u8>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    pub struct Outer<I, K: Ord> {
+        inner: I,
+        kind: K,
+        map: BTreeMap<K, I>,
+    }
+
+    #[track_caller]
+    fn assert_same<T, W>(value: T, wrapped: W)
+    where
+        T: Serialize + DeserializeOwned + Debug + Eq,
+        W: Serialize + DeserializeOwned + Debug + Eq,
+    {
+        assert_eq!(postbag::to_full_vec(&wrapped).unwrap(), postbag::to_full_vec(&value).unwrap());
+        assert_eq!(postbag::to_slim_vec(&wrapped).unwrap(), postbag::to_slim_vec(&value).unwrap());
+        loopback(wrapped);
+    }
+
+    let inner = Inner { a: 1, b: "b".to_string() };
+    assert_same(7u32, Wrap(7u32));
+    assert_same(inner.clone(), Wrap(inner.clone()));
+    assert_same(inner.clone(), Named { value: inner.clone(), _marker: PhantomData });
+    assert_same(Kind::B(3), Wrap(Kind::B(3)));
+    assert_same(Some(Kind::A), Some(Wrap(Kind::A)));
+    assert_same(inner.clone(), Wrap(Wrap(inner.clone())));
+
+    // As struct fields and map keys.
+    let map = BTreeMap::from([(Kind::A, inner.clone()), (Kind::B(9), inner.clone())]);
+    let wrapped_map = map.iter().map(|(k, v)| (Wrap(k.clone()), Wrap(v.clone()))).collect();
+    assert_same(
+        Outer { inner: inner.clone(), kind: Kind::B(1), map },
+        Outer { inner: Wrap(inner), kind: Wrap(Kind::B(1)), map: wrapped_map },
+    );
+}
+
+#[test]
+fn le_f64_slice() {
+    use postbag::cfg::{BigEndianSlim, Canonical};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Fast {
+        #[serde(with = "postbag::le_f64_slice")]
+        values: Vec<f64>,
+        #[serde(with = "postbag::le_f64_slice")]
+        boxed: Box<[f64]>,
+        #[serde(with = "postbag::le_f64_slice")]
+        array: [f64; 3],
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Generic {
+        values: Vec<f64>,
+        boxed: Box<[f64]>,
+        array: Vec<f64>,
+    }
+
+    fn check<CFG: Cfg>(len: usize) {
+        let values: Vec<f64> = (0..len).map(|i| i as f64 * -1.25 + 0.5).collect();
+        let boxed: Box<[f64]> = values.iter().map(|v| v * 3.0).collect();
+        let array = [f64::MIN, f64::INFINITY, -0.0];
+        let fast = Fast { values: values.clone(), boxed: boxed.clone(), array };
+        let generic = Generic { values, boxed, array: array.to_vec() };
+
+        // Identical to the serialization of a sequence.
+        let mut data = Vec::new();
+        serialize::<CFG, _, _>(&mut data, &fast).unwrap();
+        let mut generic_data = Vec::new();
+        serialize::<CFG, _, _>(&mut generic_data, &generic).unwrap();
+        assert_eq!(data, generic_data, "length {len}");
+
+        assert_eq!(deserialize::<CFG, _, Fast>(data.as_slice()).unwrap(), fast);
+        assert_eq!(deserialize::<CFG, _, Fast>(OneByteReader(&data)).unwrap(), fast);
+        assert_eq!(deserialize::<CFG, _, Generic>(data.as_slice()).unwrap(), generic);
+    }
+
+    for len in [0, 1, 127, 128, 10_000] {
+        check::<Full>(len);
+        check::<Slim>(len);
+        check::<BigEndianSlim>(len);
+        check::<Canonical>(len);
+    }
+
+    // Wrong array length.
+    let data =
+        postbag::to_slim_vec(&Generic { values: vec![], boxed: Box::new([]), array: vec![1.0; 4] }).unwrap();
+    assert!(postbag::from_slim_slice::<Fast>(&data).is_err());
+
+    // Sequence of unknown length.
+    struct Unsized(Vec<f64>);
+
+    impl Serialize for Unsized {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(self.0.iter().filter(|_| true))
+        }
+    }
+
+    let data = postbag::to_full_vec(&(Unsized(vec![1.0, 2.0]), 7u8)).unwrap();
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Wrapped(#[serde(with = "postbag::le_f64_slice")] Vec<f64>, u8);
+    assert_eq!(postbag::from_full_slice::<Wrapped>(&data).unwrap(), Wrapped(vec![1.0, 2.0], 7));
+
+    // Non-canonical NaN.
+    let nan = f64::from_bits(0x7ff8_0000_0000_0001);
+    let data =
+        postbag::to_slim_vec(&Generic { values: vec![nan], boxed: Box::new([]), array: vec![0.0; 3] }).unwrap();
+    let res = postbag::from_canonical_slice::<Fast>(&data);
+    assert!(matches!(res, Err(Error::NonCanonical)), "{res:?}");
+}
+
+#[test]
+fn fingerprint() {
+    use postbag::{fingerprint, from_slice_fingerprinted, to_vec_fingerprinted};
+
+    mod v1 {
+        use super::*;
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        pub struct Point {
+            pub x: u32,
+            pub y: u32,
+        }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        pub enum Shape {
+            Empty,
+            Circle { center: Point, radius: f64 },
+        }
+    }
+
+    #[allow(dead_code)]
+    mod renamed {
+        use super::*;
+
+        #[derive(Deserialize)]
+        pub struct Point {
+            pub x: u32,
+            pub z: u32,
+        }
+    }
+
+    #[allow(dead_code)]
+    mod reordered {
+        use super::*;
+
+        #[derive(Deserialize)]
+        pub struct Point {
+            pub y: u32,
+            pub x: u32,
+        }
+    }
+
+    #[allow(dead_code)]
+    mod retyped {
+        use super::*;
+
+        #[derive(Deserialize)]
+        pub struct Point {
+            pub x: u32,
+            pub y: u64,
+        }
+
+        #[derive(Deserialize)]
+        pub enum Shape {
+            Empty,
+            Circle { center: Point, radius: f64 },
+        }
+    }
+
+    #[allow(dead_code)]
+    mod variant {
+        use super::*;
+
+        #[derive(Deserialize)]
+        pub struct Point {
+            pub x: u32,
+            pub y: u32,
+        }
+
+        #[derive(Deserialize)]
+        pub enum Shape {
+            Empty,
+            Circle { center: Point, radius: f32 },
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct List {
+        value: u8,
+        next: Option<Box<List>>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Tree {
+        Node(Box<Tree>, Box<Tree>),
+        Leaf(Vec<Tree>),
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Expr {
+        Neg(Box<Expr>),
+        Add(Box<Expr>, Box<Expr>),
+        Lit(i64),
+    }
+
+    // Stable within a program and for identical types.
+    assert_eq!(fingerprint::<v1::Point>(), fingerprint::<v1::Point>());
+    assert_eq!(fingerprint::<v1::Point>(), fingerprint::<variant::Point>());
+
+    let points = [
+        fingerprint::<v1::Point>(),
+        fingerprint::<renamed::Point>(),
+        fingerprint::<reordered::Point>(),
+        fingerprint::<retyped::Point>(),
+    ];
+    for (i, a) in points.iter().enumerate() {
+        for b in &points[i + 1..] {
+            assert_ne!(a, b);
+        }
+    }
+
+    // Changes nested within enum variants.
+    let shapes = [fingerprint::<v1::Shape>(), fingerprint::<retyped::Shape>(), fingerprint::<variant::Shape>()];
+    assert_ne!(shapes[0], shapes[1]);
+    assert_ne!(shapes[0], shapes[2]);
+    assert_ne!(shapes[1], shapes[2]);
+
+    // Primitives and standard containers.
+    assert_ne!(fingerprint::<u32>(), fingerprint::<i32>());
+    assert_ne!(fingerprint::<Vec<u8>>(), fingerprint::<Option<u8>>());
+    assert_ne!(fingerprint::<BTreeMap<String, u8>>(), fingerprint::<Vec<(String, u8)>>());
+
+    // Recursive types.
+    assert_ne!(fingerprint::<List>(), fingerprint::<Tree>());
+    assert_ne!(fingerprint::<Tree>(), fingerprint::<Expr>());
+    assert_ne!(fingerprint::<Option<List>>(), fingerprint::<List>());
+
+    fn check<CFG: Cfg>() {
+        let list = List { value: 1, next: Some(Box::new(List { value: 2, next: None })) };
+        let data = to_vec_fingerprinted::<CFG, _>(&list).unwrap();
+        assert_eq!(data[..8], fingerprint::<List>().to_le_bytes());
+        assert_eq!(from_slice_fingerprinted::<CFG, List>(&data).unwrap(), list);
+
+        let expr = Expr::Add(Box::new(Expr::Neg(Box::new(Expr::Lit(3)))), Box::new(Expr::Lit(-1)));
+        let data = to_vec_fingerprinted::<CFG, _>(&expr).unwrap();
+        assert_eq!(from_slice_fingerprinted::<CFG, Expr>(&data).unwrap(), expr);
+
+        let point = v1::Point { x: 1, y: 2 };
+        let data = to_vec_fingerprinted::<CFG, _>(&point).unwrap();
+        assert_eq!(from_slice_fingerprinted::<CFG, v1::Point>(&data).unwrap(), point);
+        let res = from_slice_fingerprinted::<CFG, reordered::Point>(&data);
+        assert!(
+            matches!(res, Err(Error::FingerprintMismatch { expected, found })
+                if expected == fingerprint::<reordered::Point>() && found == fingerprint::<v1::Point>()),
+            "{:?}",
+            res.map(|_| ())
+        );
+
+        let res = from_slice_fingerprinted::<CFG, v1::Point>(&data[..7]);
+        assert!(matches!(res, Err(Error::Io(ref err)) if err.kind() == ErrorKind::UnexpectedEof));
+    }
+
+    check::<Full>();
+    check::<Slim>();
+
+    // Types requiring deserialize_any cannot be traced.
+    #[derive(Serialize, Deserialize, Debug)]
+    #[serde(untagged)]
+    enum Untagged {
+        Int(u32),
+        Text(String),
+    }
+
+    let res = to_vec_fingerprinted::<Slim, _>(&Untagged::Int(1));
+    assert!(matches!(&res, Err(Error::Untraceable(msg)) if msg.contains("Untagged")), "{res:?}");
+    let res = from_slice_fingerprinted::<Slim, Untagged>(&[0; 9]);
+    assert!(matches!(res, Err(Error::Untraceable(_))), "{res:?}");
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn numeric_variants() {
+    use postbag::{NumericVariants, numeric_variants};
+
+    #[numeric_variants]
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    #[serde(rename_all = "snake_case")]
+    enum Event {
+        PowerOn,
+        PowerOff { reason: String },
+        SetLevel(u8),
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    #[serde(rename_all = "snake_case")]
+    enum NamedEvent {
+        PowerOn,
+        PowerOff { reason: String },
+        SetLevel(u8),
+    }
+
+    #[numeric_variants]
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    #[serde(rename_all = "SCREAMING-KEBAB-CASE")]
+    enum Mode<T> {
+        Manual(T),
+        FullyAutomatic,
+    }
+
+    loopback(Event::PowerOn);
+    loopback(Event::PowerOff { reason: "idle".to_string() });
+    loopback(vec![Event::SetLevel(3), Event::PowerOn]);
+    loopback(Mode::Manual(5u32));
+    loopback(Mode::<u32>::FullyAutomatic);
+
+    // Encoded as a single byte in Full.
+    assert_eq!(postbag::to_full_vec(&Event::PowerOn).unwrap(), postbag::to_full_vec(&65u8).unwrap());
+    let named = postbag::to_full_vec(&NamedEvent::SetLevel(3)).unwrap();
+    let numeric = postbag::to_full_vec(&Event::SetLevel(3)).unwrap();
+    assert_eq!(numeric.len() + "set_level".len(), named.len());
+
+    // Identical to indices in Slim.
+    assert_eq!(
+        postbag::to_slim_vec(&Event::SetLevel(3)).unwrap(),
+        postbag::to_slim_vec(&NamedEvent::SetLevel(3)).unwrap()
+    );
+
+    // Data with variant names remains readable.
+    let data = postbag::to_full_vec(&NamedEvent::PowerOff { reason: "idle".to_string() }).unwrap();
+    assert_eq!(postbag::from_full_slice::<Event>(&data).unwrap(), Event::PowerOff { reason: "idle".to_string() });
+
+    // Names for diagnostics.
+    assert_eq!(Event::VARIANT_NAMES, ["power_on", "power_off", "set_level"]);
+    assert_eq!(Event::variant_name("_2"), Some("set_level"));
+    assert_eq!(Event::variant_name("_3"), None);
+    assert_eq!(Event::variant_name("set_level"), None);
+    assert_eq!(Event::variant_index("power_off"), Some(1));
+    assert_eq!(Mode::<u8>::VARIANT_NAMES, ["MANUAL", "FULLY-AUTOMATIC"]);
+    assert_eq!(format!("{:?}", Event::SetLevel(1)), "SetLevel(1)");
+}
+
+#[test]
+fn full_vec_bounded() {
+    use postbag::to_full_vec_bounded;
+    use std::cell::Cell;
+
+    /// Counts the elements serialized.
+    struct Counted<'a> {
+        len: usize,
+        serialized: &'a Cell<usize>,
+    }
+
+    impl Serialize for Counted<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq((0..self.len).map(|i| {
+                self.serialized.set(self.serialized.get() + 1);
+                i as u32
+            }))
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Message {
+        id: u32,
+        body: String,
+    }
+
+    // Fits, including exactly.
+    let message = Message { id: 1, body: "hello".to_string() };
+    let data = postbag::to_full_vec(&message).unwrap();
+    assert_eq!(to_full_vec_bounded(&message, 1500).unwrap(), data);
+    assert_eq!(to_full_vec_bounded(&message, data.len()).unwrap(), data);
+    let res = to_full_vec_bounded(&message, data.len() - 1);
+    assert!(matches!(res, Err(Error::SizeLimitExceeded)), "{res:?}");
+
+    // Does not fit and stops early.
+    let serialized = Cell::new(0);
+    let res = to_full_vec_bounded(&Counted { len: 1_000_000, serialized: &serialized }, 1500);
+    assert!(matches!(res, Err(Error::SizeLimitExceeded)), "{res:?}");
+    assert!(serialized.get() < 1500, "serialized {} elements", serialized.get());
+
+    let serialized = Cell::new(0);
+    let data = to_full_vec_bounded(&Counted { len: 100, serialized: &serialized }, 1500).unwrap();
+    assert_eq!(postbag::from_full_slice::<Vec<u32>>(&data).unwrap(), (0..100).collect::<Vec<_>>());
+    assert_eq!(serialized.get(), 100);
+
+    let res = to_full_vec_bounded(&(), 0);
+    assert_eq!(res.unwrap(), Vec::<u8>::new());
+}
+
+#[cfg(feature = "erased-serde")]
+#[test]
+fn serialize_erased() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    let values: Vec<Box<dyn erased_serde::Serialize>> =
+        vec![Box::new(Point { x: 1, y: -2 }), Box::new("text"), Box::new(Some(7u64)), Box::new(vec![1u8, 2])];
+
+    let mut full = Vec::new();
+    let mut slim = Vec::new();
+    for value in &values {
+        postbag::serialize_erased::<Full, _>(&mut full, &**value).unwrap();
+        postbag::serialize_erased::<Slim, _>(&mut slim, &**value).unwrap();
+    }
+
+    // Encoded identically to the typed values.
+    let typed = (Point { x: 1, y: -2 }, "text", Some(7u64), vec![1u8, 2]);
+    assert_eq!(full, postbag::to_full_vec(&typed).unwrap());
+    assert_eq!(slim, postbag::to_slim_vec(&typed).unwrap());
+
+    let (point, text, num, bytes): (Point, String, Option<u64>, Vec<u8>) =
+        postbag::from_full_slice(&full).unwrap();
+    assert_eq!(point, Point { x: 1, y: -2 });
+    assert_eq!((text.as_str(), num, bytes), ("text", Some(7), vec![1, 2]));
+}
+
+#[test]
+fn framing() {
+    use postbag::framing::{FrameReader, FrameWriter, PrefixFormat};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Message {
+        id: u32,
+        body: String,
+    }
+
+    let messages: Vec<_> = (0..10u32).map(|id| Message { id, body: "x".repeat(id as usize * 30) }).collect();
+
+    for format in [PrefixFormat::Varint, PrefixFormat::U16Be, PrefixFormat::U32Be, PrefixFormat::U32Le] {
+        let mut writer = FrameWriter::<_, Full>::new(Vec::new(), format);
+        for msg in &messages {
+            writer.write_frame(msg).unwrap();
+        }
+        let data = writer.into_inner();
+
+        let mut reader = FrameReader::<_, Full>::new(data.as_slice(), format);
+        for msg in &messages {
+            assert_eq!(&reader.read_frame::<Message>().unwrap(), msg);
+        }
+        assert!(reader.get_ref().is_empty());
+    }
+
+    // Prefix encodings.
+    let body = postbag::to_slim_vec(&"a".repeat(200)).unwrap();
+    for (format, prefix) in [
+        (PrefixFormat::Varint, vec![0xca, 0x01]),
+        (PrefixFormat::U16Be, vec![0, 202]),
+        (PrefixFormat::U32Be, vec![0, 0, 0, 202]),
+        (PrefixFormat::U32Le, vec![202, 0, 0, 0]),
+    ] {
+        let mut writer = FrameWriter::<_, Slim>::new(Vec::new(), format);
+        writer.write_frame(&"a".repeat(200)).unwrap();
+        assert_eq!(writer.get_ref()[..prefix.len()], prefix);
+        assert_eq!(writer.get_ref()[prefix.len()..], body);
+    }
+
+    // Body exceeding the range of the prefix.
+    let mut writer = FrameWriter::<_, Slim>::new(Vec::new(), PrefixFormat::U16Be);
+    let res = writer.write_frame(&vec![0u8; 70_000]);
+    assert!(matches!(res, Err(Error::FrameTooLarge)), "{res:?}");
+    assert!(writer.get_ref().is_empty());
+    writer.write_frame(&vec![0u8; 65_000]).unwrap();
+
+    // Body shorter than its value.
+    let mut data = vec![0, 0, 0, 2];
+    data.extend(postbag::to_slim_vec(&"abc").unwrap());
+    let mut reader = FrameReader::<_, Slim>::new(data.as_slice(), PrefixFormat::U32Be);
+    let res = reader.read_frame::<String>();
+    assert!(matches!(&res, Err(Error::Io(err)) if err.kind() == ErrorKind::UnexpectedEof), "{res:?}");
+
+    // Trailing data within the body is consumed.
+    let mut data = vec![3, 1, 2, 3];
+    let mut writer = FrameWriter::<_, Slim>::new(&mut data, PrefixFormat::Varint);
+    writer.write_frame(&7u32).unwrap();
+    let mut reader = FrameReader::<_, Slim>::new(data.as_slice(), PrefixFormat::Varint);
+    let res = reader.read_frame::<u8>();
+    assert!(matches!(res, Err(Error::TrailingData)), "{res:?}");
+    assert_eq!(reader.read_frame::<u32>().unwrap(), 7);
+
+    // Overlong varint prefix.
+    let mut reader = FrameReader::<_, Slim>::new([0xff; 11].as_slice(), PrefixFormat::Varint);
+    let res = reader.read_frame::<u8>();
+    assert!(matches!(res, Err(Error::BadVarint)), "{res:?}");
+}
+
+#[test]
+fn narrow() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Old {
+        offset: i64,
+        count: u64,
+        level: u16,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Saturating {
+        #[serde(with = "postbag::narrow::saturating")]
+        offset: i32,
+        #[serde(with = "postbag::narrow::saturating")]
+        count: u32,
+        #[serde(with = "postbag::narrow::saturating")]
+        level: u8,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct Checked {
+        #[serde(with = "postbag::narrow::checked")]
+        offset: i32,
+        #[serde(with = "postbag::narrow::checked")]
+        count: u32,
+        #[serde(with = "postbag::narrow::checked")]
+        level: u8,
+    }
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Strict {
+        offset: i32,
+        count: u32,
+        level: u16,
+    }
+
+    // In range.
+    let old = Old { offset: -5, count: 7, level: 200 };
+    let data = postbag::to_slim_vec(&old).unwrap();
+    assert_eq!(
+        postbag::from_slim_slice::<Saturating>(&data).unwrap(),
+        Saturating { offset: -5, count: 7, level: 200 }
+    );
+    assert_eq!(postbag::from_slim_slice::<Checked>(&data).unwrap(), Checked { offset: -5, count: 7, level: 200 });
+    loopback(Saturating { offset: i32::MIN, count: u32::MAX, level: u8::MAX });
+    loopback(Checked { offset: i32::MAX, count: 0, level: 0 });
+
+    // Narrowed values are encoded like the original type.
+    let narrowed = Saturating { offset: -5, count: 7, level: 200 };
+    assert_eq!(postbag::to_slim_vec(&narrowed).unwrap(), data);
+    assert_eq!(postbag::to_full_vec(&narrowed).unwrap(), postbag::to_full_vec(&old).unwrap());
+
+    // Out of range.
+    for (old, expected) in [
+        (
+            Old { offset: 1 << 40, count: u64::MAX, level: 256 },
+            Saturating { offset: i32::MAX, count: u32::MAX, level: 255 },
+        ),
+        (
+            Old { offset: i64::MIN, count: 1 << 32, level: u16::MAX },
+            Saturating { offset: i32::MIN, count: u32::MAX, level: 255 },
+        ),
+    ] {
+        let data = postbag::to_full_vec(&old).unwrap();
+        assert_eq!(postbag::from_full_slice::<Saturating>(&data).unwrap(), expected);
+
+        let res = postbag::from_full_slice::<Checked>(&data);
+        assert!(matches!(&res, Err(Error::Custom(msg)) if msg.contains("out of range")), "{res:?}");
+
+        // The default typed path is strict.
+        let res = postbag::from_full_slice::<Strict>(&data);
+        assert!(res.is_err(), "{res:?}");
+    }
+}
+
+#[test]
+fn serialize_to_vec() {
+    fn check<CFG: Cfg>(value: &(impl Serialize + ?Sized)) {
+        let mut expected = Vec::new();
+        serialize::<CFG, _, _>(&mut expected, value).unwrap();
+        assert_eq!(postbag::serialize_to_vec::<CFG, _>(value).unwrap(), expected);
+
+        let mut serializer = postbag::Serializer::<_, CFG>::new(Vec::new());
+        serializer.serialize_value(value).unwrap();
+        assert_eq!(serializer.into_vec().unwrap(), expected);
+    }
+
+    let value = DeeplyNestedStruct {
+        level1: OuterStruct {
+            inner: InnerStruct { id: 7, name: "seven".to_string() },
+            metadata: vec![0; 70_000],
+        },
+        extra: 1,
+    };
+    check::<Full>(&value);
+    check::<Slim>(&value);
+    check::<Full>("text");
+    check::<Slim>(&[1u128, u128::MAX]);
+
+    assert_eq!(postbag::serialize_to_vec::<Full, _>(&value).unwrap(), postbag::to_full_vec(&value).unwrap());
+    assert_eq!(postbag::serialize_to_vec::<Slim, _>(&value).unwrap(), postbag::to_slim_vec(&value).unwrap());
+}
+
+#[test]
+fn recursive_list() {
+    use postbag::recursive::{RightRecursive, deserialize_recursive};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    enum List {
+        Cons(u32, Box<List>),
+        Nil,
+    }
+
+    impl RightRecursive for List {
+        type Item = u32;
+        const NAME: &'static str = "List";
+        const VARIANTS: &'static [&'static str] = &["Cons", "Nil"];
+        const CONS: &'static str = "Cons";
+        const NIL: &'static str = "Nil";
+
+        fn nil() -> Self {
+            List::Nil
+        }
+
+        fn cons(item: u32, tail: Self) -> Self {
+            List::Cons(item, Box::new(tail))
+        }
+    }
+
+    impl List {
+        fn items(&self) -> Vec<u32> {
+            let mut items = Vec::new();
+            let mut node = self;
+            while let List::Cons(item, tail) = node {
+                items.push(*item);
+                node = tail;
+            }
+            items
+        }
+    }
+
+    impl Drop for List {
+        fn drop(&mut self) {
+            if let List::Cons(_, tail) = self {
+                let mut node = std::mem::replace(&mut **tail, List::Nil);
+                while let List::Cons(_, tail) = &mut node {
+                    node = std::mem::replace(&mut **tail, List::Nil);
+                }
+            }
+        }
+    }
+
+    /// Encodes a node like `List`, but without the rest of the list.
+    #[derive(Serialize)]
+    enum Node {
+        Cons(u32),
+        Nil,
+    }
+
+    fn encode<CFG: Cfg>(items: impl IntoIterator<Item = u32>) -> Vec<u8> {
+        let mut serializer = postbag::Serializer::<_, CFG>::new(Vec::new());
+        for item in items {
+            serializer.serialize_value(&Node::Cons(item)).unwrap();
+        }
+        serializer.serialize_value(&Node::Nil).unwrap();
+        serializer.into_vec().unwrap()
+    }
+
+    fn check<CFG: Cfg>() {
+        // Short lists are encoded like the derived implementation.
+        let list = List::cons(1, List::cons(2, List::cons(3, List::Nil)));
+        let mut data = Vec::new();
+        serialize::<CFG, _, _>(&mut data, &list).unwrap();
+        assert_eq!(data, encode::<CFG>([1, 2, 3]));
+        assert_eq!(deserialize_recursive::<CFG, _, List>(data.as_slice()).unwrap(), list);
+        assert_eq!(deserialize_recursive::<CFG, _, List>(encode::<CFG>([]).as_slice()).unwrap(), List::Nil);
+
+        // Long lists are decoded iteratively.
+        let data = encode::<CFG>(0..100_000);
+        let list: List = deserialize_recursive::<CFG, _, _>(data.as_slice()).unwrap();
+        assert_eq!(list.items(), (0..100_000).collect::<Vec<_>>());
+
+        // The derived implementation fails cleanly using a depth limit.
+        let res = postbag::DeserializerBuilder::new().max_depth(128).deserialize::<CFG, _, List>(data.as_slice());
+        assert!(matches!(res, Err(Error::DepthLimitExceeded)), "{res:?}");
+
+        // Truncated lists fail.
+        let res = deserialize_recursive::<CFG, _, List>(&data[..data.len() - 1]);
+        assert!(res.is_err(), "{res:?}");
+    }
+
+    check::<Full>();
+    check::<Slim>();
+}
+
+#[test]
+fn serialize_counted() {
+    /// Writer accepting at most 7 bytes per call.
+    struct Partial(Vec<u8>);
+
+    impl std::io::Write for Partial {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let n = buf.len().min(7);
+            self.0.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let value = DeeplyNestedStruct {
+        level1: OuterStruct {
+            inner: InnerStruct { id: 7, name: "seven".to_string() },
+            metadata: vec![0; 200_000],
+        },
+        extra: 1,
+    };
+    let expected = postbag::to_full_vec(&value).unwrap();
+
+    let mut buffer = vec![0xff];
+    assert_eq!(postbag::serialize_counted::<Full, _, _>(&mut buffer, &value).unwrap(), expected.len());
+    assert_eq!(buffer[1..], expected);
+
+    let mut writer = Partial(Vec::new());
+    assert_eq!(postbag::serialize_counted::<Full, _, _>(&mut writer, &value).unwrap(), expected.len());
+    assert_eq!(writer.0, expected);
+
+    let len = postbag::serialize_counted::<Slim, _, _>(std::io::sink(), &value).unwrap();
+    assert_eq!(len, postbag::to_slim_vec(&value).unwrap().len());
+    assert_eq!(postbag::serialize_counted::<Slim, _, _>(std::io::sink(), &()).unwrap(), 0);
+}
+
+#[test]
+fn serializer_checkpoint() {
+    fn element(i: u32) -> OuterStruct {
+        OuterStruct {
+            inner: InnerStruct { id: i, name: "x".repeat(i as usize * 10_000) },
+            metadata: vec![i as u8; i as usize],
+        }
+    }
+
+    fn check<CFG: Cfg>() {
+        let mut serializer = postbag::Serializer::<_, CFG>::new(Vec::new());
+        serializer.start_seq().unwrap();
+        let mut states = Vec::new();
+        for i in 0..5 {
+            serializer.serialize_element(&element(i)).unwrap();
+            states.push(serializer.checkpoint().unwrap());
+        }
+        serializer.end_seq().unwrap();
+        let complete = serializer.into_vec().unwrap();
+
+        let expected: Vec<_> = (0..5).map(element).collect();
+        assert_eq!(deserialize::<CFG, _, Vec<OuterStruct>>(complete.as_slice()).unwrap(), expected);
+
+        for (i, state) in states.iter().enumerate() {
+            assert_eq!(state.seq_len, Some(i as u64 + 1));
+
+            // Resume from the checkpoint after losing the data written afterwards.
+            let mut data = complete.clone();
+            data.truncate(state.position as usize);
+            let mut serializer = postbag::Serializer::<_, CFG>::restore(data, *state);
+            for i in i as u32 + 1..5 {
+                serializer.serialize_element(&element(i)).unwrap();
+            }
+            assert_eq!(serializer.checkpoint().unwrap(), *states.last().unwrap());
+            serializer.end_seq().unwrap();
+            assert_eq!(serializer.into_vec().unwrap(), complete);
+        }
+
+        // A value following the sequence.
+        let mut serializer = postbag::Serializer::<_, CFG>::restore(
+            Vec::new(),
+            postbag::SerializerState { position: 0, seq_len: None },
+        );
+        serializer.start_seq().unwrap();
+        serializer.end_seq().unwrap();
+        assert_eq!(serializer.checkpoint().unwrap().seq_len, None);
+        serializer.serialize_value(&7u8).unwrap();
+        let data = serializer.into_vec().unwrap();
+        let mut deserializer = postbag::Deserializer::<_, CFG>::new(data.as_slice());
+        assert!(deserializer.deserialize_value::<Vec<u8>>().unwrap().is_empty());
+        assert_eq!(deserializer.deserialize_value::<u8>().unwrap(), 7);
+    }
+
+    check::<Full>();
+    check::<Slim>();
+
+    let mut serializer = postbag::Serializer::<_, postbag::cfg::Canonical>::new(Vec::new());
+    assert!(matches!(
+        serializer.start_seq(),
+        Err(Error::SerializeSeqLengthUnknown { cause: postbag::UnknownLenCause::Canonical })
+    ));
 }
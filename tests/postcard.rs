@@ -0,0 +1,222 @@
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::{collections::BTreeMap, fmt::Debug};
+
+use postbag::{
+    Error,
+    cfg::{Cfg, Full, Postcard, Slim},
+    deserialize, serialize,
+};
+
+/// Checks that a value is encoded identically by postcard and postbag
+/// and that each decodes the encoding of the other.
+#[track_caller]
+fn interop<CFG: Cfg, T: Serialize + DeserializeOwned + Debug + PartialEq>(value: &T) {
+    let expected = postcard::to_stdvec(value).unwrap();
+
+    let mut data = Vec::new();
+    serialize::<CFG, _, _>(&mut data, value).unwrap();
+    assert_eq!(data, expected, "encoding differs from postcard");
+
+    let decoded: T = deserialize::<CFG, _, _>(expected.as_slice()).unwrap();
+    assert_eq!(&decoded, value);
+    let decoded: T = postcard::from_bytes(&data).unwrap();
+    assert_eq!(&decoded, value);
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Newtype(u64);
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct TupleStruct(i16, String);
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Point {
+    x: i32,
+    y: i32,
+    label: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+enum Shape {
+    Empty,
+    Circle(f32),
+    Line(Point, Point),
+    Rect { origin: Point, size: (u32, u32) },
+}
+
+/// Values encoded identically by postcard and by postbag's `Slim` configuration.
+fn common<CFG: Cfg>() {
+    interop::<CFG, _>(&true);
+    interop::<CFG, _>(&false);
+    interop::<CFG, _>(&0xabu8);
+    interop::<CFG, _>(&-5i8);
+    interop::<CFG, _>(&u16::MAX);
+    interop::<CFG, _>(&-300i16);
+    interop::<CFG, _>(&1_000_000u32);
+    interop::<CFG, _>(&i32::MIN);
+    interop::<CFG, _>(&u64::MAX);
+    interop::<CFG, _>(&-1i64);
+    interop::<CFG, _>(&u128::MAX);
+    interop::<CFG, _>(&i128::MIN);
+    interop::<CFG, _>(&1.5f32);
+    interop::<CFG, _>(&-0.1f64);
+    interop::<CFG, _>(&'€');
+    interop::<CFG, _>(&"text".to_string());
+    interop::<CFG, _>(&Some(7u32));
+    interop::<CFG, _>(&None::<u32>);
+    interop::<CFG, _>(&());
+    interop::<CFG, _>(&(1u8, 2u16, "three".to_string()));
+    interop::<CFG, _>(&[1u32, 2, 3]);
+    interop::<CFG, _>(&vec![1u32, 2, 3]);
+    interop::<CFG, _>(&BTreeMap::from([(1u8, "a".to_string()), (2, "b".to_string())]));
+    interop::<CFG, _>(&Newtype(300));
+    interop::<CFG, _>(&TupleStruct(-1, "x".to_string()));
+    interop::<CFG, _>(&Shape::Empty);
+    interop::<CFG, _>(&Shape::Circle(2.0));
+}
+
+#[test]
+fn slim_common_subset() {
+    common::<Slim>();
+
+    // Byte arrays.
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Bytes(#[serde(with = "postbag::serde_bytes")] Vec<u8>);
+    interop::<Slim, _>(&Bytes(vec![1, 2, 3]));
+}
+
+#[test]
+fn slim_differences() {
+    let point = Point { x: 1, y: -1, label: None };
+    let shape = Shape::Rect { origin: Point { x: 0, y: 0, label: None }, size: (2, 3) };
+    let len = vec![0u8; postbag::format::SPECIAL_LEN];
+
+    // Structs are preceded by their number of fields and enclosed in a skippable block.
+    assert_eq!(postbag::to_slim_vec(&point).unwrap(), [3, 3, 2, 1, 0]);
+    assert_eq!(postcard::to_stdvec(&point).unwrap(), [2, 1, 0]);
+    assert_ne!(postbag::to_slim_vec(&point).unwrap(), postcard::to_stdvec(&point).unwrap());
+    assert_ne!(postbag::to_slim_vec(&shape).unwrap(), postcard::to_stdvec(&shape).unwrap());
+
+    // The length `SPECIAL_LEN` is escaped.
+    assert_ne!(postbag::to_slim_vec(&len).unwrap(), postcard::to_stdvec(&len).unwrap());
+
+    // Identifiers are not understood by postcard.
+    assert_ne!(postbag::to_full_vec(&point).unwrap(), postcard::to_stdvec(&point).unwrap());
+}
+
+#[test]
+fn postcard_compat() {
+    common::<Postcard>();
+
+    interop::<Postcard, _>(&Point { x: 1, y: -1, label: Some("p".to_string()) });
+    interop::<Postcard, _>(&Shape::Line(Point { x: 0, y: 0, label: None }, Point { x: 5, y: 5, label: None }));
+    interop::<Postcard, _>(&Shape::Rect { origin: Point { x: -3, y: 4, label: None }, size: (2, 3) });
+    interop::<Postcard, _>(&vec![0u8; postbag::format::SPECIAL_LEN]);
+    interop::<Postcard, _>(&BTreeMap::from_iter((0..postbag::format::SPECIAL_LEN as u32).map(|i| (i, i))));
+    interop::<Postcard, _>(&vec![Point { x: 1, y: 2, label: None }; 3]);
+
+    // The fast path for integer sequences does not escape the length `SPECIAL_LEN` either.
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Varints(#[serde(with = "postbag::varint_slice")] Vec<u32>);
+    let varints = Varints((0..postbag::format::SPECIAL_LEN as u32).collect());
+    interop::<Postcard, _>(&varints);
+
+    // No field may be skipped, not even a trailing one.
+    #[derive(Serialize)]
+    struct Skipping {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        a: Option<u8>,
+        b: u8,
+    }
+
+    let res = postbag::serialize::<Postcard, _, _>(Vec::new(), &Skipping { a: None, b: 1 });
+    assert!(matches!(res, Err(Error::SkippedField)), "{res:?}");
+
+    #[derive(Serialize)]
+    struct TrailingSkip {
+        a: u8,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        b: Option<u8>,
+    }
+
+    #[derive(Serialize)]
+    struct Outer {
+        s: TrailingSkip,
+        c: Option<u8>,
+        d: Option<u8>,
+    }
+
+    let value = Outer { s: TrailingSkip { a: 1, b: None }, c: Some(7), d: None };
+    let res = postbag::serialize::<Postcard, _, _>(Vec::new(), &value);
+    assert!(matches!(res, Err(Error::SkippedField)), "{res:?}");
+    let res = postbag::serialize::<Postcard, _, _>(Vec::new(), &TrailingSkip { a: 1, b: Some(2) });
+    assert!(res.is_ok(), "{res:?}");
+
+    // Sequences of unknown length are unsupported, as in postcard.
+    struct Unknown;
+
+    impl Serialize for Unknown {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            use serde::ser::SerializeSeq;
+            serializer.serialize_seq(None)?.end()
+        }
+    }
+
+    let res = postbag::serialize::<Postcard, _, _>(Vec::new(), &Unknown);
+    assert!(
+        matches!(res, Err(Error::SerializeSeqLengthUnknown { cause: postbag::UnknownLenCause::PostcardCompat })),
+        "{res:?}"
+    );
+}
+
+#[test]
+fn postcard_compat_ignored_with_idents() {
+    struct PostcardFull;
+
+    impl Cfg for PostcardFull {
+        fn with_idents() -> bool {
+            true
+        }
+
+        fn postcard_compat() -> bool {
+            true
+        }
+    }
+
+    let point = Point { x: 1, y: -1, label: None };
+    let mut data = Vec::new();
+    serialize::<PostcardFull, _, _>(&mut data, &point).unwrap();
+    assert_eq!(data, postbag::to_full_vec(&point).unwrap());
+    let mut data = Vec::new();
+    serialize::<Full, _, _>(&mut data, &point).unwrap();
+    assert_eq!(deserialize::<PostcardFull, _, Point>(data.as_slice()).unwrap(), point);
+}
+
+/// Value prefixed by version `V`.
+#[derive(Debug, PartialEq)]
+struct Versioned<const V: u8>(u32);
+
+impl<const V: u8> Serialize for Versioned<V> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        postbag::versioned::serialize(V, &self.0, serializer)
+    }
+}
+
+impl<'de, const V: u8> Deserialize<'de> for Versioned<V> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        postbag::versioned::deserialize(V, deserializer).map(Self)
+    }
+}
+
+#[test]
+fn versioned() {
+    interop::<Postcard, _>(&Versioned::<0>(7));
+    interop::<Postcard, _>(&Versioned::<1>(7));
+    interop::<Postcard, _>(&Versioned::<255>(7));
+    assert_eq!(postcard::to_stdvec(&Versioned::<1>(2)).unwrap(), [1, 2]);
+
+    let data = postcard::to_stdvec(&Versioned::<1>(2)).unwrap();
+    assert!(postcard::from_bytes::<Versioned<2>>(&data).is_err());
+    let res = postbag::deserialize::<Postcard, _, Versioned<2>>(data.as_slice());
+    assert!(matches!(res, Err(Error::BadVersion { expected: 2, found: 1 })), "{res:?}");
+}